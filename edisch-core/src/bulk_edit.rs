@@ -0,0 +1,990 @@
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use std::{
+    borrow::Cow::{self, Borrowed, Owned},
+    env::{self, temp_dir},
+    fmt::Display,
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+};
+use unicode_width::UnicodeWidthStr;
+
+/// 一時ファイルのデフォルトの拡張子
+pub const DEFAULT_SUFFIX: &str = "edisch";
+
+/// テキストエディタを実行中かどうか
+static EDITING: AtomicBool = AtomicBool::new(false);
+
+/// テキストエディタを実行中かどうか。
+/// 実行中のCtrl-Cはエディタ自身が処理するため、呼び出し側は終了すべきでない
+pub fn is_editing() -> bool {
+    EDITING.load(Ordering::SeqCst)
+}
+
+/// [`EditOptions::keep_tempfile`] で残した一時ファイル
+static KEPT: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// [`EditOptions::keep_tempfile`] で残した一時ファイルのパスを取り出す。
+/// 表示するかどうかは呼び出し側が決める
+pub fn take_kept_tempfiles() -> Vec<PathBuf> {
+    std::mem::take(&mut *KEPT.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// テキストエディタの起動方法
+#[derive(Debug, Default)]
+pub struct EditOptions {
+    /// 編集後も一時ファイルを削除せずに残す
+    pub keep_tempfile: bool,
+    /// 一時ファイルを作成するディレクトリ。指定しない場合はシステムの一時ディレクトリ
+    pub tmpdir: Option<PathBuf>,
+    /// 一時ファイルの拡張子 (エディタが構文を判別できるように)。指定しない場合は [`DEFAULT_SUFFIX`]
+    pub suffix: Option<String>,
+    /// エディタのコマンド (シェルと同様に引用符で区切って引数を含められる)。
+    /// 指定しない場合は $VISUAL、$EDITOR、vi の順に使う
+    pub editor: Option<String>,
+}
+
+impl EditOptions {
+    /// 使用するエディタのコマンド
+    pub fn editor_command(&self) -> String {
+        self.editor
+            .clone()
+            .or_else(|| env::var("VISUAL").ok().filter(|v| !v.trim().is_empty()))
+            .or_else(|| env::var("EDITOR").ok().filter(|v| !v.trim().is_empty()))
+            .unwrap_or("vi".to_string())
+    }
+}
+
+/// エディタのコマンドを、シェルと同様に引用符とエスケープを解釈してプログラムと引数に分ける
+pub fn split_command(command: &str) -> Result<(String, Vec<String>)> {
+    let mut words = shell_words::split(command)
+        .map_err(|e| Error::EditorCommand(format!("{command:?}: {e}").into()))?
+        .into_iter();
+    let program = words
+        .next()
+        .ok_or(Error::EditorCommand(Borrowed("the command is empty")))?;
+    Ok((program, words.collect()))
+}
+
+/// 文字列のハッシュ値
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// テキストエディタを起動し、指定された内容を編集する。
+/// 内容が変更されなかった場合は None を返す
+fn edit(contents: &impl Display, options: &EditOptions) -> Result<Option<String>> {
+    let contents = format!("{contents}\n");
+    let before = hash(&contents);
+
+    // 同時に実行しても衝突しない名前で、所有者のみが読み書きできる一時ファイルを作成する
+    let suffix = options.suffix.as_deref().unwrap_or(DEFAULT_SUFFIX);
+    let mut file = tempfile::Builder::new()
+        .prefix("edisch-")
+        .suffix(&format!(".{}", suffix.trim_start_matches('.')))
+        .tempfile_in(options.tmpdir.clone().unwrap_or_else(temp_dir))?;
+    file.write_all(contents.as_bytes())?;
+    // エラー時も含め、サーバーの情報を含む一時ファイルは guard の破棄時に削除する
+    let (tempfile, _guard) = if options.keep_tempfile {
+        let path = file.into_temp_path().keep().map_err(io::Error::from)?;
+        KEPT.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(path.clone());
+        (path, None)
+    } else {
+        let path = file.into_temp_path();
+        (path.to_path_buf(), Some(path))
+    };
+
+    // コマンドの実行
+    let (program, args) = split_command(&options.editor_command())?;
+    EDITING.store(true, Ordering::SeqCst);
+    let status = Command::new(program).args(args).arg(&tempfile).status();
+    EDITING.store(false, Ordering::SeqCst);
+    let status = status?;
+    if !status.success() {
+        return Err(Error::Command(status.code()));
+    }
+
+    // 編集結果の取得
+    let contents = {
+        let mut contents = String::new();
+        File::open(&tempfile)?.read_to_string(&mut contents)?;
+        contents
+    };
+    if hash(&contents) == before {
+        return Ok(None);
+    }
+    Ok(Some(contents))
+}
+
+/// テキストエディタで、アイテムの一覧ではない任意の文書を編集する。
+/// 内容が変更されなかった場合は None を返す
+pub fn edit_document(contents: &str, options: &EditOptions) -> Result<Option<String>> {
+    edit(&contents, options)
+}
+
+/// 一括変更することができるアイテム。
+/// 種類の異なるアイテムを1つの [`Editor`] で編集できるよう、トレイトオブジェクトとして扱える。
+/// 実装には [`async_trait`](crate::async_trait) の `#[async_trait(?Send)]` を付ける
+#[async_trait(?Send)]
+pub trait TextEditableItem {
+    /// 適用・検証で返すエラー。一括編集自体のエラーも変換できる必要がある
+    type Error: From<Error>;
+    /// テキスト部分の抽出
+    fn content(&self) -> String;
+    /// テキストを適用する
+    async fn apply(&mut self, content: String) -> std::result::Result<(), Self::Error>;
+    /// コメント
+    fn comment(&self) -> String {
+        String::new()
+    }
+    /// バリデーション
+    fn validate(&self, _new: &str) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+    /// テキストを構成するタブ区切りの列の数。コメントはこれらの列の後に続く
+    fn columns(&self) -> usize {
+        1
+    }
+    /// テキストが複数行にわたりうるか。
+    /// そのようなアイテムを含む場合、バッファは区切り行で始まるブロックの並びになる
+    fn multiline(&self) -> bool {
+        false
+    }
+    /// バッファで見出しを付けてまとめる区切りの名前 (カテゴリなど)。デフォルトでは区切らない
+    fn section(&self) -> Option<String> {
+        None
+    }
+    /// 名前の重複を調べる範囲 (同じカテゴリなど)。同じ値を返すアイテム同士のテキストを比べる。
+    /// デフォルトでは重複を調べない
+    fn duplicate_scope(&self) -> Option<String> {
+        None
+    }
+    /// 追加された行から作成するアイテムの、名前の重複を調べる範囲と名前。
+    /// デフォルトでは重複を調べない
+    fn created_duplicate_scope(&self, _new: &str) -> Option<(String, String)> {
+        None
+    }
+    /// 適用はできるが、意図と異なる結果になりうる変更 (改名・作成) についての警告。デフォルトでは警告しない
+    fn lint(&self, _op: &Op, _new: &str) -> Vec<String> {
+        Vec::new()
+    }
+    /// テキストを規則に従うよう書き換える。デフォルトでは何もしない
+    fn fix(&self, new: &str) -> String {
+        new.to_string()
+    }
+    /// 変更を求める前に、同じ意味の書き方を1つの形に揃える (`y` を `yes` にするなど)。
+    /// 揃えた結果が現在のテキストと同じ行は変更とみなさない。デフォルトでは何もしない
+    fn normalize(&self, new: &str) -> String {
+        new.to_string()
+    }
+    /// 改名以外の操作のバリデーション。デフォルトでは改名以外の操作に対応しない
+    fn validate_op(&self, op: &Op) -> std::result::Result<(), Self::Error> {
+        Err(Error::UnsupportedOperation(op.to_string().into()).into())
+    }
+    /// アイテムを削除する
+    async fn delete(&mut self) -> std::result::Result<(), Self::Error> {
+        Err(Error::UnsupportedOperation(Borrowed("drop")).into())
+    }
+    /// アイテムを指定した先へ移動する
+    async fn move_to(&mut self, target: &str) -> std::result::Result<(), Self::Error> {
+        Err(Error::UnsupportedOperation(format!("move {target}").into()).into())
+    }
+    /// 追加された行のテキストのバリデーション。デフォルトではアイテムの作成に対応しない
+    fn validate_create(&self, _new: &str) -> std::result::Result<(), Self::Error> {
+        Err(Error::UnsupportedOperation(Borrowed("create")).into())
+    }
+    /// 追加された行からアイテムを作成するための、このアイテムの複製。
+    /// 作成は追加された行の直前の行のアイテムの複製に対して [`Self::create`] を呼んで行う
+    fn sibling(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+    /// 追加された行のテキストから、新しいアイテムを作成する
+    async fn create(&mut self, _content: String) -> std::result::Result<(), Self::Error> {
+        Err(Error::UnsupportedOperation(Borrowed("create")).into())
+    }
+    /// サーバー上の現在のテキストを取得し直す (適用後の検証に使う)。デフォルトでは取得できない (None)
+    async fn fetch_content(&self) -> std::result::Result<Option<String>, Self::Error> {
+        Ok(None)
+    }
+}
+
+/// `Box<dyn TextEditableItem<Error = E>>` を [`Editor`] に並べられるようにする
+#[async_trait(?Send)]
+impl<T: TextEditableItem + ?Sized> TextEditableItem for Box<T> {
+    type Error = T::Error;
+    fn content(&self) -> String {
+        (**self).content()
+    }
+    async fn apply(&mut self, content: String) -> std::result::Result<(), Self::Error> {
+        (**self).apply(content).await
+    }
+    fn comment(&self) -> String {
+        (**self).comment()
+    }
+    fn validate(&self, new: &str) -> std::result::Result<(), Self::Error> {
+        (**self).validate(new)
+    }
+    fn columns(&self) -> usize {
+        (**self).columns()
+    }
+    fn multiline(&self) -> bool {
+        (**self).multiline()
+    }
+    fn section(&self) -> Option<String> {
+        (**self).section()
+    }
+    fn duplicate_scope(&self) -> Option<String> {
+        (**self).duplicate_scope()
+    }
+    fn created_duplicate_scope(&self, new: &str) -> Option<(String, String)> {
+        (**self).created_duplicate_scope(new)
+    }
+    fn lint(&self, op: &Op, new: &str) -> Vec<String> {
+        (**self).lint(op, new)
+    }
+    fn fix(&self, new: &str) -> String {
+        (**self).fix(new)
+    }
+    fn normalize(&self, new: &str) -> String {
+        (**self).normalize(new)
+    }
+    fn validate_op(&self, op: &Op) -> std::result::Result<(), Self::Error> {
+        (**self).validate_op(op)
+    }
+    async fn delete(&mut self) -> std::result::Result<(), Self::Error> {
+        (**self).delete().await
+    }
+    async fn move_to(&mut self, target: &str) -> std::result::Result<(), Self::Error> {
+        (**self).move_to(target).await
+    }
+    fn validate_create(&self, new: &str) -> std::result::Result<(), Self::Error> {
+        (**self).validate_create(new)
+    }
+    async fn create(&mut self, content: String) -> std::result::Result<(), Self::Error> {
+        (**self).create(content).await
+    }
+    async fn fetch_content(&self) -> std::result::Result<Option<String>, Self::Error> {
+        (**self).fetch_content().await
+    }
+}
+
+/// 行頭の指示によって指定される操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// テキストを変更する
+    Rename,
+    /// アイテムを削除する
+    Drop,
+    /// アイテムを指定した先へ移動する
+    Move(String),
+    /// 追加された行からアイテムを作成する
+    Create,
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Rename => write!(f, "rename"),
+            Op::Drop => write!(f, "drop"),
+            Op::Move(target) => write!(f, "move to {target}"),
+            Op::Create => write!(f, "create"),
+        }
+    }
+}
+
+/// 行頭の指示の接頭辞。`drop zone` のような名前を指示と読み違えないよう、指示は `!` で始める
+const DIRECTIVE_PREFIX: char = '!';
+
+/// 行のテキスト部分から、git rebase 風の行頭の指示 (`!drop` など) を読み取る。
+/// 指示のない行は改名として扱い、`!keep` の場合は None を返す。
+/// `!` で始まる名前を書き出したまま読み込んでも指示にならないよう、
+/// アイテムの現在のテキスト `content` と同じ行は指示として読まない
+fn parse_directive<'a>(text: &'a str, content: &str) -> (Option<Op>, &'a str) {
+    if text == content || text == content.trim_end_matches(' ') {
+        return (Some(Op::Rename), text);
+    }
+    if let Some((word, rest)) = text
+        .strip_prefix(DIRECTIVE_PREFIX)
+        .and_then(|text| text.split_once(' '))
+    {
+        match word {
+            "keep" | "k" => return (None, rest),
+            "rename" | "r" => return (Some(Op::Rename), rest),
+            "drop" | "d" | "D" => return (Some(Op::Drop), rest),
+            "move" | "m" => return (Some(Op::Move(rest.to_string())), rest),
+            _ => {}
+        }
+    }
+    (Some(Op::Rename), text)
+}
+
+/// 行をテキスト部分 (`columns` 個のタブ区切りの列) とコメント部分 (先頭のタブを含む) に分ける。
+/// コメントの位置を揃えるための、テキスト末尾の空白は取り除く
+fn split_comment(line: &str, columns: usize) -> (&str, &str) {
+    match line.match_indices('\t').nth(columns.saturating_sub(1)) {
+        Some((pos, _)) => {
+            let (text, comment) = line.split_at(pos);
+            (text.trim_end_matches(' '), comment)
+        }
+        None => (line, ""),
+    }
+}
+
+/// 編集結果で無視する行か。空行と、`#` で始まりアイテムのコメントを持たない行が該当する。
+/// `#` で始まる名前のアイテムも、コメントを残しておけば行として扱われる
+fn is_comment_line(line: &str, columns: usize) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || (trimmed.starts_with('#') && split_comment(line, columns).1.is_empty())
+}
+
+/// コメントからアイテムを識別するキーを取り出す。
+/// コメント末尾の `(…)` の中身をキーとし、ない場合はコメント全体をキーとする
+pub fn comment_key(comment: &str) -> &str {
+    let comment = comment.trim_end();
+    comment
+        .strip_suffix(')')
+        .and_then(|rest| rest.rfind('(').map(|start| &rest[start + 1..]))
+        .unwrap_or(comment)
+}
+
+/// タブ区切りの列ごとに比べ、変更された列の番号と変更前後の値を返す
+pub fn changed_columns<'a>(old: &'a str, new: &'a str) -> Vec<(usize, &'a str, &'a str)> {
+    let old: Vec<_> = old.split('\t').collect();
+    let new: Vec<_> = new.split('\t').collect();
+    (0..old.len().max(new.len()))
+        .filter_map(|i| {
+            let old = old.get(i).copied().unwrap_or_default();
+            let new = new.get(i).copied().unwrap_or_default();
+            (old != new).then_some((i, old, new))
+        })
+        .collect()
+}
+
+/// 変更を表す
+pub struct Diff<T> {
+    /// 変更前のテキスト
+    pub old: String,
+    /// 変更後のテキスト
+    pub new: String,
+    /// 変更前のアイテム
+    pub item: T,
+    /// 操作
+    pub op: Op,
+    /// バッファ上の行番号 (0始まり)
+    pub index: usize,
+}
+
+impl<T> Diff<T> {
+    /// 複数の列からなるテキストの、変更された列の番号と変更前後の値
+    pub fn changed_columns(&self) -> Vec<(usize, &str, &str)> {
+        changed_columns(&self.old, &self.new)
+    }
+    /// 変更後の状態の表示
+    pub fn label(&self) -> Cow<'_, str> {
+        match &self.op {
+            Op::Rename | Op::Create => Borrowed(&self.new),
+            op => Owned(format!("({op})")),
+        }
+    }
+}
+
+impl<T: TextEditableItem> Diff<T> {
+    /// 変更を適用し、適用後のアイテムを返す
+    pub async fn apply(self) -> std::result::Result<T, T::Error> {
+        let Diff {
+            new, mut item, op, ..
+        } = self;
+        match op {
+            Op::Rename => item.apply(new).await,
+            Op::Drop => item.delete().await,
+            Op::Move(target) => item.move_to(&target).await,
+            Op::Create => item.create(new).await,
+        }?;
+        Ok(item)
+    }
+}
+
+/// 編集の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edited {
+    /// 内容が変更された
+    Changed,
+    /// 内容が変更されなかった
+    Unchanged,
+    /// バッファが空にされ、操作が中止された
+    Aborted,
+}
+
+/// 複数行のテキストを編集する際の、各ブロックの先頭を示す区切り行の接頭辞
+const BLOCK_DELIMITER: &str = "@@ ";
+
+/// アイテムの一覧をテキストエディタで一括編集するためのバッファ
+pub struct Editor<T> {
+    items: Vec<T>,
+    /// 各アイテムの行。ブロック形式の場合はコメントを含まないテキスト
+    lines: Vec<String>,
+    /// 区切り行で始まるブロックの並びとして編集するか
+    blocks: bool,
+    /// 行の追加・削除によるアイテムの作成・削除を許すか
+    structural: bool,
+    /// 追加された行と、その直前の行のアイテムの位置 (先頭に追加された場合は None)
+    created: Vec<(Option<usize>, String)>,
+    /// 行の位置ではなく、コメントのキーによって行とアイテムを対応づけるか
+    keyed: bool,
+    /// キーで対応づけた場合の、各アイテムに対応する行の位置
+    line_indices: Option<Vec<Option<usize>>>,
+    /// キーで対応づけた際に無視した行や、行のなかったアイテムについての警告
+    warnings: Vec<String>,
+    /// テキストの後ろを空白で埋め、コメントの位置を揃えて表示するか
+    aligned: bool,
+    /// 区切りが変わる位置に見出しのコメント行を挿入して表示するか
+    headers: bool,
+}
+
+impl<T: TextEditableItem> Editor<T> {
+    /// アイテムの一覧から、各アイテムのテキストとコメントを並べたバッファを作る
+    pub fn new(items: impl IntoIterator<Item = T>) -> Result<Self> {
+        let items: Vec<T> = items.into_iter().collect();
+        if items.iter().any(TextEditableItem::multiline) {
+            let lines = items.iter().map(TextEditableItem::content).collect();
+            return Ok(Self {
+                items,
+                lines,
+                blocks: true,
+                structural: false,
+                created: Vec::new(),
+                keyed: false,
+                line_indices: None,
+                warnings: Vec::new(),
+                aligned: false,
+                headers: false,
+            });
+        }
+        let mut lines = Vec::with_capacity(items.len());
+        for item in &items {
+            let mut line = item.content();
+            if line.matches('\t').count() + 1 != item.columns() {
+                return Err(Error::NotEditableItem(Borrowed(
+                    "tab character is not allowed in content except between columns",
+                )));
+            }
+            if !item.comment().is_empty() {
+                line.push_str(&format!("\t{}", item.comment()));
+            }
+            if line.contains('\n') {
+                return Err(Error::NotEditableItem(Borrowed(
+                    "newline character is not allowed in content",
+                )));
+            }
+            lines.push(line);
+        }
+        Ok(Self {
+            items,
+            lines,
+            blocks: false,
+            structural: false,
+            created: Vec::new(),
+            keyed: false,
+            line_indices: None,
+            warnings: Vec::new(),
+            aligned: false,
+            headers: false,
+        })
+    }
+    /// 行の追加・削除によるアイテムの作成・削除を許す。
+    /// 各行はコメントによってアイテムと対応づけるため、コメントが重複する場合は使えない。
+    /// コメントのない行は追加された行、なくなった行は `!drop` された行として扱う
+    pub fn structural(&mut self) -> Result<()> {
+        if self.blocks || !self.has_unique_keys() {
+            return Err(Error::NotEditableItem(Borrowed(
+                "lines cannot be added or removed unless every item has a unique key in its comment",
+            )));
+        }
+        self.structural = true;
+        Ok(())
+    }
+    /// 行の位置ではなく、コメントのキー ([`comment_key`]) によって行とアイテムを対応づける。
+    /// 並び替えられた行や、行の過不足を許し、対応するアイテムのない行とアイテムのない行は
+    /// 無視して [`Self::warnings`] で報告する。キーを持つ行が一つもない場合は位置で対応づける
+    pub fn keyed(&mut self) -> Result<()> {
+        if self.blocks || !self.has_unique_keys() {
+            return Err(Error::NotEditableItem(Borrowed(
+                "lines cannot be matched by key unless every item has a unique key in its comment",
+            )));
+        }
+        self.keyed = true;
+        Ok(())
+    }
+    /// テキストの後ろを空白で埋め、コメントの位置を揃えて表示する。
+    /// 幅は表示幅で数える。ブロック形式や、複数の列を持つアイテムの場合は揃えない
+    pub fn align(&mut self) {
+        self.aligned = true;
+    }
+    /// 区切り ([`TextEditableItem::section`]) が変わる位置に `# ── 名前 ──` の見出し行を挿入して表示する。
+    /// 見出し行はコメント行として読み飛ばされる。ブロック形式では挿入しない
+    pub fn headers(&mut self) {
+        self.headers = true;
+    }
+    /// 全てのアイテムが、重複しない空でないキーを持つか
+    fn has_unique_keys(&self) -> bool {
+        let comments: Vec<_> = self.items.iter().map(TextEditableItem::comment).collect();
+        let mut keys: Vec<_> = comments.iter().map(|c| comment_key(c)).collect();
+        keys.sort();
+        !keys.iter().any(|key| key.is_empty()) && !keys.windows(2).any(|w| w[0] == w[1])
+    }
+    /// 編集対象のアイテム
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+    /// index 番目のアイテムの行を編集前の状態に戻す
+    pub fn revert(&mut self, index: usize) {
+        let item = &self.items[index];
+        let mut line = item.content();
+        if !self.blocks && !item.comment().is_empty() {
+            line.push('\t');
+            line.push_str(&item.comment());
+        }
+        self.lines[index] = line;
+    }
+    /// index 番目のアイテムの行を、テキストを new に変更する行に置き換える
+    pub fn set_content(&mut self, index: usize, new: &str) {
+        let item = &self.items[index];
+        let mut line = new.to_string();
+        if !self.blocks && !item.comment().is_empty() {
+            line.push('\t');
+            line.push_str(&item.comment());
+        }
+        self.lines[index] = line;
+    }
+    /// [`Editor::diffs`] で求めた変更の一つを取り消す。
+    /// 作成の場合は追加された行を、それ以外の場合はアイテムの行を編集前の状態に戻す
+    pub fn discard(&mut self, index: usize, op: &Op, new: &str) {
+        if *op != Op::Create {
+            self.revert(index);
+            return;
+        }
+        let columns = self.items[index].columns();
+        if let Some(n) = self.created.iter().position(|(anchor, line)| {
+            anchor.unwrap_or(0) == index && split_comment(line, columns).0 == new
+        }) {
+            self.created.remove(n);
+        }
+    }
+    /// キーで対応づけた際に無視した行や、行のなかったアイテムについての警告
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+    /// キーで対応づけた場合や、コメント行・空行を読み飛ばした場合の、
+    /// 各アイテムに対応するテキスト上の行の位置 (0始まり)。行がそのままアイテムに対応する場合は None
+    pub fn line_indices(&self) -> Option<&[Option<usize>]> {
+        self.line_indices.as_deref()
+    }
+    /// テキストを編集結果として設定する。
+    /// 全ての行が削除されていた (コメント行と空行しかない) 場合は中止とみなし、何も設定しない
+    pub fn set_text(&mut self, mut text: String) -> Result<Edited> {
+        // git rebase の空のTODOファイルと同様、空のバッファは意図的な中止として扱う
+        if text.trim().is_empty() {
+            return Ok(Edited::Aborted);
+        }
+        if self.blocks {
+            return self.set_blocks(&text);
+        }
+        // 最後の文字が改行の場合削除
+        if text.ends_with('\n') {
+            text.pop();
+        }
+        let columns = self.items.first().map_or(1, TextEditableItem::columns);
+        if text.lines().all(|line| is_comment_line(line, columns)) {
+            return Ok(Edited::Aborted);
+        }
+        let has_keys = text
+            .lines()
+            .any(|line| !split_comment(line, columns).1.is_empty());
+        if self.structural || (self.keyed && has_keys) {
+            return self.set_lines(&text);
+        }
+        // コメント行と空行を除いた行を、順にアイテムと対応づける
+        let (indices, lines): (Vec<_>, Vec<_>) = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !is_comment_line(line, columns))
+            .unzip();
+        if self.items.len() != lines.len() {
+            return Err(Error::InvalidEditResult(Borrowed("item count mismatch")));
+        }
+        self.line_indices =
+            (lines.len() != text.lines().count()).then(|| indices.into_iter().map(Some).collect());
+        self.lines = lines.into_iter().map(str::to_string).collect();
+        Ok(Edited::Changed)
+    }
+    /// コメントのキーによって各行をアイテムと対応づけ、テキストを編集結果として設定する。
+    /// コメント行と空行は無視する。行の追加・削除を許す場合は、コメントのない行を追加された行、
+    /// なくなった行を削除された行として扱い、そうでない場合はそれらを無視して警告する
+    fn set_lines(&mut self, text: &str) -> Result<Edited> {
+        let comments: Vec<_> = self.items.iter().map(TextEditableItem::comment).collect();
+        let keys: Vec<_> = comments.iter().map(|c| comment_key(c)).collect();
+        let columns = self.items.first().map_or(1, TextEditableItem::columns);
+        let mut lines: Vec<Option<String>> = vec![None; self.items.len()];
+        let mut line_indices = vec![None; self.items.len()];
+        let mut created = Vec::new();
+        let mut warnings = Vec::new();
+        let mut anchor = None;
+        for (n, line) in text.lines().enumerate() {
+            if is_comment_line(line, columns) {
+                continue;
+            }
+            let (_, comment) = split_comment(line, columns);
+            let comment = comment.strip_prefix('\t').unwrap_or(comment);
+            if comment.is_empty() {
+                if self.structural {
+                    created.push((anchor, line.to_string()));
+                } else {
+                    warnings.push(format!("ignored a line without a key: {line:?}"));
+                }
+                continue;
+            }
+            let key = comment_key(comment);
+            let Some(i) = keys.iter().position(|k| *k == key) else {
+                if self.structural {
+                    return Err(Error::InvalidEditResult(
+                        format!("line with an unknown comment: {line:?}").into(),
+                    ));
+                }
+                warnings.push(format!("ignored a line for an unknown item: {line:?}"));
+                continue;
+            };
+            if lines[i].replace(line.to_string()).is_some() {
+                return Err(Error::InvalidEditResult(
+                    format!("duplicate line for {key:?}").into(),
+                ));
+            }
+            line_indices[i] = Some(n);
+            anchor = Some(i);
+        }
+        let mut unchanged = 0;
+        let lines: Vec<_> = lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| match line {
+                Some(line) => line,
+                // なくなった行は削除として扱い、再編集時にも分かるよう明示する
+                None if self.structural => {
+                    format!(
+                        "{DIRECTIVE_PREFIX}drop {}\t{}",
+                        self.items[i].content(),
+                        comments[i]
+                    )
+                }
+                None => {
+                    unchanged += 1;
+                    self.lines[i].clone()
+                }
+            })
+            .collect();
+        // 一部のアイテムのみの行を適用することもあるため、行のないアイテムはまとめて報告する
+        if unchanged > 0 {
+            warnings.push(format!(
+                "{unchanged} item(s) without a line were left unchanged"
+            ));
+        }
+        self.lines = lines;
+        self.created = created;
+        self.line_indices = Some(line_indices);
+        self.warnings = warnings;
+        Ok(Edited::Changed)
+    }
+    /// ブロック形式のテキストを編集結果として設定する。
+    /// 区切り行より前の行は無視し、各ブロック末尾の空行は取り除く
+    fn set_blocks(&mut self, text: &str) -> Result<Edited> {
+        let mut blocks: Vec<Vec<&str>> = Vec::new();
+        for line in text.lines() {
+            if line.starts_with(BLOCK_DELIMITER) {
+                blocks.push(Vec::new());
+            } else if let Some(block) = blocks.last_mut() {
+                block.push(line);
+            }
+        }
+        if self.items.len() != blocks.len() {
+            return Err(Error::InvalidEditResult(Borrowed("block count mismatch")));
+        }
+        self.lines = blocks
+            .into_iter()
+            .map(|block| block.join("\n").trim_end_matches('\n').to_string())
+            .collect();
+        Ok(Edited::Changed)
+    }
+    /// 改名される (または変更されていない) 行のテキストを、各アイテムの規則に従うよう書き換える。
+    /// 書き換えた行があれば true を返す
+    pub fn fix(&mut self) -> bool {
+        let mut fixed = false;
+        if self.blocks {
+            return fixed;
+        }
+        for (item, line) in self.items.iter().zip(&mut self.lines) {
+            let (text, comment) = split_comment(line, item.columns());
+            let (Some(Op::Rename), rest) = parse_directive(text, &item.content()) else {
+                continue;
+            };
+            let new = item.fix(rest);
+            if new != rest {
+                let directive = &text[..text.len() - rest.len()];
+                *line = format!("{directive}{new}{comment}");
+                fixed = true;
+            }
+        }
+        fixed
+    }
+    /// 変更されたテキストと追加された行を `f` で書き換える (Unicodeの正規化など)。書き換えた場合は true を返す
+    pub fn rewrite_changed(&mut self, f: impl Fn(&str) -> String) -> bool {
+        let mut rewritten = false;
+        if self.blocks {
+            return rewritten;
+        }
+        for (item, line) in self.items.iter().zip(&mut self.lines) {
+            let (text, comment) = split_comment(line, item.columns());
+            let (Some(Op::Rename), rest) = parse_directive(text, &item.content()) else {
+                continue;
+            };
+            if rest == item.content() {
+                continue;
+            }
+            let new = f(rest);
+            if new != rest {
+                let directive = &text[..text.len() - rest.len()];
+                *line = format!("{directive}{new}{comment}");
+                rewritten = true;
+            }
+        }
+        let columns = self.items.first().map_or(1, TextEditableItem::columns);
+        for (_, line) in &mut self.created {
+            let (text, comment) = split_comment(line, columns);
+            let new = f(text);
+            if new != text {
+                *line = format!("{new}{comment}");
+                rewritten = true;
+            }
+        }
+        rewritten
+    }
+    /// テキストエディタで編集する
+    pub fn edit(&mut self, options: &EditOptions) -> Result<Edited> {
+        let Some(text) = self.edit_text(options)? else {
+            return Ok(Edited::Unchanged);
+        };
+        self.set_text(text)
+    }
+    /// テキストエディタで編集したテキストを、編集結果として設定せずに返す。変更がなければ None。
+    /// 設定に失敗した場合にもテキストを残せるよう、[`Editor::set_text`] と組み合わせて使う
+    pub fn edit_text(&self, options: &EditOptions) -> Result<Option<String>> {
+        edit(self, options)
+    }
+}
+
+impl<T: TextEditableItem> Editor<T> {
+    /// i行目に変更があれば、その操作と変更後のテキストを求める
+    fn change(&self, i: usize) -> std::result::Result<Option<(Op, String)>, T::Error> {
+        let (item, line) = (&self.items[i], &self.lines[i]);
+        // ブロック形式では行頭の指示を使わず、全体をテキストとして扱う
+        if self.blocks {
+            if item.content() == *line {
+                return Ok(None);
+            }
+            item.validate(line)?;
+            return Ok(Some((Op::Rename, line.clone())));
+        }
+        let (text, _) = split_comment(line, item.columns());
+        match parse_directive(text, &item.content()) {
+            (None, _) => Ok(None),
+            (Some(Op::Rename), new) => {
+                let new = item.normalize(new);
+                // 変更のない行は検証しない。コメントの位置を揃えた際の空白は変更とみなさない
+                if item.content() == new || item.content().trim_end_matches(' ') == new {
+                    return Ok(None);
+                }
+                item.validate(&new)?;
+                Ok(Some((Op::Rename, new)))
+            }
+            (Some(op), _) => {
+                item.validate_op(&op)?;
+                Ok(Some((op, item.content())))
+            }
+        }
+    }
+
+    /// 追加された行のうち、直前のアイテムが i 番目であるものの作成を検証する
+    fn creations(&self, i: usize) -> std::result::Result<Vec<(Op, String)>, T::Error> {
+        let mut creations = Vec::new();
+        for (anchor, line) in &self.created {
+            if anchor.unwrap_or(0) != i {
+                continue;
+            }
+            let (text, _) = split_comment(line, self.items[i].columns());
+            self.items[i].validate_create(text)?;
+            creations.push((Op::Create, text.to_string()));
+        }
+        Ok(creations)
+    }
+
+    /// 変更のあった行の番号と操作、変更後のテキストを求める。
+    /// 追加された行は、直前の行のアイテムの番号で表す
+    fn changes(&self) -> std::result::Result<Vec<(usize, Op, String)>, T::Error> {
+        let mut changes = Vec::new();
+        for i in 0..self.items.len() {
+            if let Some((op, new)) = self.change(i)? {
+                changes.push((i, op, new));
+            }
+            for (op, new) in self.creations(i)? {
+                changes.push((i, op, new));
+            }
+        }
+        Ok(changes)
+    }
+
+    /// 検証に失敗した全ての行の番号とエラー
+    pub fn violations(&self) -> Vec<(usize, T::Error)> {
+        (0..self.items.len())
+            .flat_map(|i| {
+                [self.change(i).err(), self.creations(i).err()]
+                    .into_iter()
+                    .flatten()
+                    .map(move |e| (i, e))
+            })
+            .collect()
+    }
+
+    /// 現在の編集内容による変更を、アイテムを借用したまま求める
+    pub fn diffs(&self) -> std::result::Result<Vec<Diff<&T>>, T::Error> {
+        Ok(self
+            .changes()?
+            .into_iter()
+            .map(|(i, op, new)| {
+                let item = &self.items[i];
+                Diff {
+                    old: match op {
+                        Op::Create => String::new(),
+                        _ => item.content(),
+                    },
+                    new,
+                    item,
+                    op,
+                    index: i,
+                }
+            })
+            .collect())
+    }
+}
+
+impl<T: TextEditableItem> TryInto<Vec<Diff<T>>> for Editor<T> {
+    type Error = T::Error;
+    fn try_into(self) -> std::result::Result<Vec<Diff<T>>, T::Error> {
+        let changes = self.changes()?;
+        // 作成は直前の行のアイテムの複製で行うため、元のアイテムを取り出す前に複製しておく
+        let mut siblings = Vec::new();
+        for (i, op, _) in &changes {
+            siblings.push(match op {
+                Op::Create => Some(
+                    self.items[*i]
+                        .sibling()
+                        .ok_or(Error::UnsupportedOperation(Borrowed("create")))?,
+                ),
+                _ => None,
+            });
+        }
+        let mut items: Vec<_> = self.items.into_iter().map(Some).collect();
+        let mut diffs = Vec::new();
+        for ((i, op, new), sibling) in changes.into_iter().zip(siblings) {
+            let (old, item) = match sibling {
+                Some(sibling) => (String::new(), sibling),
+                None => {
+                    let item = items[i].take().expect("one change per item");
+                    (item.content(), item)
+                }
+            };
+            diffs.push(Diff {
+                old,
+                new,
+                item,
+                op,
+                index: i,
+            });
+        }
+        Ok(diffs)
+    }
+}
+
+impl<T: TextEditableItem> Display for Editor<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.blocks {
+            // ブロックの間に空行を入れ、最後のブロックの後には改行を入れない
+            for (i, (item, text)) in self.items.iter().zip(&self.lines).enumerate() {
+                if i > 0 {
+                    write!(f, "\n\n")?;
+                }
+                write!(f, "{BLOCK_DELIMITER}{}", item.comment())?;
+                if !text.is_empty() {
+                    write!(f, "\n{text}")?;
+                }
+            }
+            return Ok(());
+        }
+        // 追加された行は、直前の行のアイテムの後に置く
+        let created = |anchor| {
+            self.created
+                .iter()
+                .filter(move |(a, _)| *a == anchor)
+                .map(|(_, line)| line)
+        };
+        // 各行と、アイテムの行であればその位置
+        let lines =
+            created(None)
+                .map(|line| (None, line))
+                .chain(self.lines.iter().enumerate().flat_map(|(i, line)| {
+                    std::iter::once((Some(i), line))
+                        .chain(created(Some(i)).map(|line| (None, line)))
+                }));
+        let aligned = self.aligned && self.items.iter().all(|item| item.columns() == 1);
+        // コメントを持つ行のテキストの、最大の表示幅
+        let width = lines
+            .clone()
+            .map(|(_, line)| split_comment(line, 1))
+            .filter(|(_, comment)| aligned && !comment.is_empty())
+            .map(|(text, _)| text.width())
+            .max()
+            .unwrap_or(0);
+        let mut section = None;
+        for (n, (i, line)) in lines.enumerate() {
+            if n > 0 {
+                writeln!(f)?;
+            }
+            if let Some(i) = i.filter(|_| self.headers) {
+                let current = self.items[i].section();
+                if current.is_some() && current != section {
+                    writeln!(f, "# ── {} ──", current.as_deref().unwrap_or_default())?;
+                }
+                section = current;
+            }
+            match split_comment(line, 1) {
+                (text, comment) if aligned && !comment.is_empty() => {
+                    let padding = width - text.width();
+                    write!(f, "{text}{:padding$}{comment}", "")?;
+                }
+                _ => write!(f, "{}", line)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::borrow::Cow;
+use thiserror::Error;
+
+/// Short-hand for `Result<T, Error>`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// 一括編集のエラー型
+#[derive(Debug, Error)]
+pub enum Error {
+    /// 編集結果が不正な場合
+    #[error("Invalid edit result: {0}")]
+    InvalidEditResult(Cow<'static, str>),
+
+    /// アイテムが対応していない操作が指定された場合
+    #[error("Unsupported operation: {0}")]
+    UnsupportedOperation(Cow<'static, str>),
+
+    /// 編集できる対象ではなかった場合
+    #[error("Not an editable item names: {0}")]
+    NotEditableItem(Cow<'static, str>),
+
+    /// ファイルの読み書きに失敗した場合 (一時ファイルなど)
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// エディタのコマンドを解釈できない場合 (閉じられていない引用符など)
+    #[error("Invalid editor command: {0}")]
+    EditorCommand(Cow<'static, str>),
+
+    /// テキストエディタが正常に終了しなかった場合
+    #[error("EDITOR failed{}", if let Some(code) = .0 { format!(" with code {}", code) } else { String::new() })]
+    Command(Option<i32>),
+}
@@ -0,0 +1,68 @@
+//! Edit a list of items in bulk with your `$EDITOR`.
+//!
+//! Implement [`TextEditableItem`] for the things you want to edit, put them in an [`Editor`],
+//! let the user edit one line per item (or one block per item for multi-line text) in their
+//! editor, then turn the result into a list of [`Diff`]s to preview and apply.
+//!
+//! ```no_run
+//! use edisch_core::{async_trait, Diff, EditOptions, Edited, Editor, Error, TextEditableItem};
+//!
+//! struct Note {
+//!     id: u32,
+//!     title: String,
+//! }
+//!
+//! #[async_trait(?Send)]
+//! impl TextEditableItem for Note {
+//!     type Error = Error;
+//!
+//!     fn content(&self) -> String {
+//!         self.title.clone()
+//!     }
+//!     fn comment(&self) -> String {
+//!         format!("#{}", self.id)
+//!     }
+//!     async fn apply(&mut self, content: String) -> Result<(), Error> {
+//!         self.title = content;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # async fn run() -> Result<(), Error> {
+//! let notes = vec![Note { id: 1, title: "draft".into() }];
+//! let mut editor = Editor::new(notes)?;
+//! if editor.edit(&EditOptions::default())? == Edited::Changed {
+//!     let diffs: Vec<Diff<Note>> = editor.try_into()?;
+//!     for diff in diffs {
+//!         println!("{} -> {}", diff.old, diff.label());
+//!         diff.apply().await?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The trait is object-safe, so different kinds of items can share one editing session as
+//! `Editor<Box<dyn TextEditableItem<Error = E>>>`.
+//!
+//! An item can also span several tab-separated columns (see [`TextEditableItem::columns`]);
+//! [`Diff::changed_columns`] then tells which of them were edited.
+//!
+//! Lines may start with a directive like in `git rebase -i`: `!keep`/`!k`, `!rename`/`!r`,
+//! `!drop`/`!d` and `!move <target>`/`!m`, which call [`TextEditableItem::delete`] and
+//! [`TextEditableItem::move_to`]. A line equal to the item's current text is never read as a
+//! directive, so exported names starting with `!` round-trip unchanged. Blank lines and lines starting with `#` (without a comment
+//! column) are ignored, except in block mode; emptying the whole buffer aborts the edit.
+//! Spaces between the text and the comment are ignored, so [`Editor::align`] can line the
+//! comments up in a column, and [`Editor::headers`] inserts a `#` header line per
+//! [`TextEditableItem::section`].
+
+pub mod bulk_edit;
+pub mod error;
+
+pub use async_trait::async_trait;
+pub use bulk_edit::{
+    changed_columns, comment_key, edit_document, is_editing, split_command, take_kept_tempfiles,
+    Diff, EditOptions, Edited, Editor, Op, TextEditableItem,
+};
+pub use error::{Error, Result};
@@ -0,0 +1,342 @@
+//! バッファの解析と差分の計算を、メモリ上のアイテムで確かめる
+
+use edisch_core::{
+    async_trait, changed_columns, comment_key, edit_document, take_kept_tempfiles, Diff,
+    EditOptions, Edited, Editor, Error, Op, TextEditableItem,
+};
+use proptest::prelude::*;
+use std::{cell::RefCell, rc::Rc};
+
+/// 適用された操作を記録するアイテム
+#[derive(Clone)]
+struct Note {
+    id: u32,
+    title: String,
+    log: Rc<RefCell<Vec<String>>>,
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for Note {
+    type Error = Error;
+
+    fn content(&self) -> String {
+        self.title.clone()
+    }
+    fn comment(&self) -> String {
+        format!("note ({})", self.id)
+    }
+    fn validate(&self, new: &str) -> Result<(), Error> {
+        if new.trim().is_empty() {
+            return Err(Error::InvalidEditResult("empty title".into()));
+        }
+        Ok(())
+    }
+    fn validate_op(&self, _op: &Op) -> Result<(), Error> {
+        Ok(())
+    }
+    fn validate_create(&self, new: &str) -> Result<(), Error> {
+        self.validate(new)
+    }
+    fn sibling(&self) -> Option<Self> {
+        Some(self.clone())
+    }
+    async fn apply(&mut self, content: String) -> Result<(), Error> {
+        self.log
+            .borrow_mut()
+            .push(format!("rename {} {content}", self.id));
+        self.title = content;
+        Ok(())
+    }
+    async fn delete(&mut self) -> Result<(), Error> {
+        self.log.borrow_mut().push(format!("delete {}", self.id));
+        Ok(())
+    }
+    async fn move_to(&mut self, target: &str) -> Result<(), Error> {
+        self.log
+            .borrow_mut()
+            .push(format!("move {} {target}", self.id));
+        Ok(())
+    }
+    async fn create(&mut self, content: String) -> Result<(), Error> {
+        self.log
+            .borrow_mut()
+            .push(format!("create after {} {content}", self.id));
+        Ok(())
+    }
+}
+
+fn items(titles: &[&str]) -> (Vec<Note>, Rc<RefCell<Vec<String>>>) {
+    let log = Rc::default();
+    let notes = titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| Note {
+            id: i as u32 + 1,
+            title: title.to_string(),
+            log: Rc::clone(&log),
+        })
+        .collect();
+    (notes, log)
+}
+
+/// 差分を (行番号, 操作, 変更後) の組にする
+fn summary(diffs: &[Diff<&Note>]) -> Vec<(usize, Op, String)> {
+    diffs
+        .iter()
+        .map(|diff| (diff.index, diff.op.clone(), diff.new.clone()))
+        .collect()
+}
+
+fn apply_all(editor: Editor<Note>) -> Vec<String> {
+    let log = Rc::clone(&editor.items()[0].log);
+    let diffs: Vec<Diff<Note>> = editor.try_into().unwrap();
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(async {
+            for diff in diffs {
+                diff.apply().await.unwrap();
+            }
+        });
+    let log = log.borrow().clone();
+    log
+}
+
+#[test]
+fn buffer_lists_text_and_comment() {
+    let (notes, _) = items(&["alpha", "beta"]);
+    let editor = Editor::new(notes).unwrap();
+    assert_eq!(editor.to_string(), "alpha\tnote (1)\nbeta\tnote (2)");
+}
+
+#[test]
+fn aligned_buffer_pads_comments_by_display_width() {
+    let (notes, _) = items(&["日本", "abc"]);
+    let mut editor = Editor::new(notes).unwrap();
+    editor.align();
+    assert_eq!(editor.to_string(), "日本\tnote (1)\nabc \tnote (2)");
+    // 揃えるための空白は読み込む際に取り除かれる
+    let text = editor.to_string();
+    editor.set_text(text).unwrap();
+    assert!(editor.diffs().unwrap().is_empty());
+}
+
+#[test]
+fn tabs_and_newlines_in_content_are_rejected() {
+    let (notes, _) = items(&["a\tb"]);
+    assert!(matches!(Editor::new(notes), Err(Error::NotEditableItem(_))));
+    let (notes, _) = items(&["a\nb"]);
+    assert!(matches!(Editor::new(notes), Err(Error::NotEditableItem(_))));
+}
+
+#[test]
+fn changed_lines_become_renames() {
+    let (notes, log) = items(&["alpha", "beta", "gamma"]);
+    let mut editor = Editor::new(notes).unwrap();
+    let edited = editor
+        .set_text("alpha\tnote (1)\nBETA\tnote (2)\ngamma\tnote (3)\n".into())
+        .unwrap();
+    assert_eq!(edited, Edited::Changed);
+    assert_eq!(
+        summary(&editor.diffs().unwrap()),
+        [(1, Op::Rename, "BETA".to_string())]
+    );
+    assert_eq!(apply_all(editor), ["rename 2 BETA"]);
+    assert_eq!(log.borrow().len(), 1);
+}
+
+#[test]
+fn directives_select_the_operation() {
+    let (notes, _) = items(&["alpha", "beta", "gamma", "delta"]);
+    let mut editor = Editor::new(notes).unwrap();
+    let text =
+        "!d alpha\tnote (1)\n!move archive\tnote (2)\n!keep GAMMA\tnote (3)\n!r DELTA\tnote (4)";
+    editor.set_text(text.into()).unwrap();
+    assert_eq!(
+        summary(&editor.diffs().unwrap()),
+        [
+            (0, Op::Drop, "alpha".to_string()),
+            (1, Op::Move("archive".into()), "beta".to_string()),
+            (3, Op::Rename, "DELTA".to_string()),
+        ]
+    );
+    assert_eq!(
+        apply_all(editor),
+        ["delete 1", "move 2 archive", "rename 4 DELTA"]
+    );
+}
+
+#[test]
+fn names_that_look_like_directives_round_trip() {
+    let titles = [
+        "drop zone",
+        "d lounge",
+        "D 1",
+        "r and d",
+        "move night",
+        "keep out",
+        "!drop it",
+    ];
+    let (notes, _) = items(&titles);
+    let mut editor = Editor::new(notes).unwrap();
+    let text = editor.to_string();
+    editor.set_text(text).unwrap();
+    assert!(summary(&editor.diffs().unwrap()).is_empty());
+}
+
+#[test]
+fn comment_lines_and_blank_lines_are_skipped() {
+    let (notes, _) = items(&["alpha", "beta"]);
+    let mut editor = Editor::new(notes).unwrap();
+    let text = "# heading\n\nalpha\tnote (1)\n# another\nbeta!\tnote (2)";
+    editor.set_text(text.into()).unwrap();
+    assert_eq!(
+        summary(&editor.diffs().unwrap()),
+        [(1, Op::Rename, "beta!".to_string())]
+    );
+    assert_eq!(editor.line_indices(), Some(&[Some(2), Some(4)][..]));
+}
+
+#[test]
+fn empty_buffer_aborts() {
+    let (notes, _) = items(&["alpha"]);
+    let mut editor = Editor::new(notes).unwrap();
+    assert_eq!(editor.set_text("\n".into()).unwrap(), Edited::Aborted);
+    assert_eq!(
+        editor.set_text("# only comments\n".into()).unwrap(),
+        Edited::Aborted
+    );
+}
+
+#[test]
+fn line_count_must_match_unless_keyed() {
+    let (notes, _) = items(&["alpha", "beta"]);
+    let mut editor = Editor::new(notes).unwrap();
+    assert!(matches!(
+        editor.set_text("alpha\tnote (1)".into()),
+        Err(Error::InvalidEditResult(_))
+    ));
+}
+
+#[test]
+fn keyed_lines_may_be_reordered_or_missing() {
+    let (notes, _) = items(&["alpha", "beta", "gamma"]);
+    let mut editor = Editor::new(notes).unwrap();
+    editor.keyed().unwrap();
+    let text = "GAMMA\tnote (3)\nalpha\tnote (1)\nstray\tnote (9)";
+    editor.set_text(text.into()).unwrap();
+    assert_eq!(
+        summary(&editor.diffs().unwrap()),
+        [(2, Op::Rename, "GAMMA".to_string())]
+    );
+    assert_eq!(editor.warnings().len(), 2, "{:?}", editor.warnings());
+}
+
+#[test]
+fn structural_edits_create_and_drop() {
+    let (notes, _) = items(&["alpha", "beta"]);
+    let mut editor = Editor::new(notes).unwrap();
+    editor.structural().unwrap();
+    editor.set_text("alpha\tnote (1)\nfresh\n".into()).unwrap();
+    assert_eq!(
+        summary(&editor.diffs().unwrap()),
+        [
+            (0, Op::Create, "fresh".to_string()),
+            (1, Op::Drop, "beta".to_string()),
+        ]
+    );
+    assert_eq!(apply_all(editor), ["create after 1 fresh", "delete 2"]);
+}
+
+#[test]
+fn invalid_lines_are_reported() {
+    let (notes, _) = items(&["alpha", "beta"]);
+    let mut editor = Editor::new(notes).unwrap();
+    editor
+        .set_text(" \tnote (1)\nbeta\tnote (2)".into())
+        .unwrap();
+    assert!(editor.diffs().is_err());
+    let violations = editor.violations();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0, 0);
+}
+
+#[test]
+fn comment_keys_are_taken_from_the_last_parentheses() {
+    assert_eq!(comment_key("📝 in Stuff (12)"), "12");
+    assert_eq!(comment_key("no key"), "no key");
+    assert_eq!(comment_key("(a) b (c) "), "c");
+}
+
+#[cfg(unix)]
+#[test]
+fn kept_tempfiles_are_reported_to_the_caller() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = EditOptions {
+        keep_tempfile: true,
+        tmpdir: Some(dir.path().to_path_buf()),
+        editor: Some("true".into()),
+        ..Default::default()
+    };
+    assert_eq!(edit_document("alpha", &options).unwrap(), None);
+    let kept = take_kept_tempfiles();
+    assert_eq!(kept.len(), 1);
+    assert!(kept[0].starts_with(dir.path()));
+    assert_eq!(std::fs::read_to_string(&kept[0]).unwrap(), "alpha\n");
+    assert!(take_kept_tempfiles().is_empty());
+}
+
+/// 指示として解釈されない (`!` で始まらない)、前後に空白のない1行のテキスト
+fn title() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9_#-]([a-zA-Z0-9 _#-]{0,16}[a-zA-Z0-9_#-])?"
+}
+
+proptest! {
+    #[test]
+    fn unedited_buffer_has_no_diffs(titles in prop::collection::vec(title(), 1..8)) {
+        let titles: Vec<&str> = titles.iter().map(String::as_str).collect();
+        let (notes, _) = items(&titles);
+        let mut editor = Editor::new(notes).unwrap();
+        let text = editor.to_string();
+        prop_assert_eq!(editor.set_text(text).unwrap(), Edited::Changed);
+        prop_assert!(editor.diffs().unwrap().is_empty());
+    }
+
+    #[test]
+    fn diffs_are_exactly_the_changed_lines(
+        pairs in prop::collection::vec((title(), title()), 1..8),
+    ) {
+        let old: Vec<&str> = pairs.iter().map(|(old, _)| old.as_str()).collect();
+        let (notes, _) = items(&old);
+        let mut editor = Editor::new(notes).unwrap();
+        let text: Vec<String> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, (_, new))| format!("{new}\tnote ({})", i + 1))
+            .collect();
+        editor.set_text(text.join("\n")).unwrap();
+        let expected: Vec<_> = pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(i, (_, new))| (i, Op::Rename, new.clone()))
+            .collect();
+        prop_assert_eq!(summary(&editor.diffs().unwrap()), expected);
+    }
+
+    #[test]
+    fn changed_columns_lists_the_differing_columns(
+        columns in prop::collection::vec((title(), title()), 1..5),
+    ) {
+        let old: Vec<&str> = columns.iter().map(|(old, _)| old.as_str()).collect();
+        let new: Vec<&str> = columns.iter().map(|(_, new)| new.as_str()).collect();
+        let (old, new) = (old.join("\t"), new.join("\t"));
+        let expected: Vec<_> = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(i, (old, new))| (i, old.as_str(), new.as_str()))
+            .collect();
+        prop_assert_eq!(changed_columns(&old, &new), expected);
+    }
+}
@@ -0,0 +1,183 @@
+# Messages of edisch in English. Every message must also be in ja.ftl.
+
+## Prefixes of errors and warnings
+
+error-prefix = error:
+error-prefix-unknown = UNKNOWN ERROR
+warning-prefix = warning:
+
+## Errors
+
+error-missing-argument = Missing argument: { $detail }
+error-parse-argument = Failed to parse argument: { $detail }
+error-config = Invalid config file: { $detail }
+error-policy-violation = Policy violation: { $detail }
+error-invalid-edit-result = Invalid edit result: { $detail }
+error-unsupported-operation = Unsupported operation: { $detail }
+error-io = IO error: { $detail }
+error-keyring = Keyring error: { $detail }
+error-replay = Replay failed: { $detail }
+error-invalid-channel-name = Invalid channel name: { $name } ({ $detail })
+error-invalid-token = Invalid token: Discord rejected the bot token; check --token, $DISCORD_TOKEN or `edisch auth set`
+error-forbidden = Missing permission: { $detail }; check the bot's roles and the channel's permission overwrites
+error-not-found = Not found: { $detail }; check the ID and that the bot is a member of the guild
+error-rate-limited = Rate limited: { $detail }; wait a few minutes or raise --retries
+error-checks-failed = { $count ->
+    [one] 1 check failed
+   *[other] { $count } checks failed
+}
+error-conflicts = { $count ->
+    [one] 1 item was changed on the server since the export
+   *[other] { $count } items were changed on the server since the export
+}
+error-missing-permissions = the bot lacks the Manage Channels permission in { $count ->
+    [one] 1 channel
+   *[other] { $count } channels
+}
+error-duplicate-names = { $count ->
+    [one] 1 name would be duplicated
+   *[other] { $count } names would be duplicated
+}; rename them, or use --dedup to warn or add suffixes instead
+error-warnings = { $count ->
+    [one] 1 warning
+   *[other] { $count } warnings
+} about the changes; fix them or use --force to apply anyway
+error-pending-changes = { $count ->
+    [one] 1 change pending
+   *[other] { $count } changes pending
+}
+error-verification-failed = { $count ->
+    [one] 1 change did not take effect as written
+   *[other] { $count } changes did not take effect as written
+}
+error-partial-failure = { $failed } of { $total } changes failed
+error-interrupted = Interrupted by Ctrl-C
+
+## Prompts
+
+prompt-apply = Do you want to apply these changes?
+prompt-apply-count = Apply { $count ->
+    [one] 1 change
+   *[other] { $count } changes
+}?
+choice-no = No
+choice-yes = Yes
+choice-edit-again = Edit again
+prompt-delete = { $count ->
+    [one] 1 channel
+   *[other] { $count } channels
+} will be permanently deleted. Are you sure?
+prompt-pick = Select the items to edit (space to toggle, enter to confirm)
+prompt-review = ({ $n }/{ $total }) Apply this change? [y]es/[n]o/[a]ll/[q]uit/[e]dit
+review-invalid-answer = answer y, n, a, q or e
+review-single-line-only = Only changes of single-line values can be edited
+prompt-new-value = New value
+prompt-edit-again = Edit the document again?
+prompt-archive = Archive { $count ->
+    [one] 1 channel
+   *[other] { $count } channels
+} into { $category }?
+prompt-move = Move { $count ->
+    [one] 1 channel
+   *[other] { $count } channels
+}?
+prompt-token = Bot token
+prompt-overwrite-config = { $path } already exists. Overwrite it?
+prompt-token-storage = How should the bot token be provided?
+choice-token-config = Store it in the config file (readable only by you)
+choice-token-env = Read it from $DISCORD_TOKEN
+prompt-default-guild-id = Default guild ID (empty for none)
+invalid-guild-id = not a guild ID
+prompt-default-guild = Default guild
+choice-none = None
+prompt-editor = Editor command (empty to use $VISUAL or $EDITOR)
+prompt-default-kinds = Channel types to edit by default (none to ask each time)
+
+## Warnings
+
+no-category = (no category)
+duplicate-names-found = { $count } channels named { $name } in { $place }: { $items }
+duplicate-names-planned = { $count } channels would be named { $name } in { $place }: { $items }
+changed-since-export = { $value } ({ $item }) was changed on the server since the export{ $skipped ->
+    [yes] , skipped
+   *[no] {""}
+}
+missing-manage-channels = { $old } -> { $new } ({ $item }): the bot lacks the Manage Channels permission in this channel{ $skipped ->
+    [yes] , skipped
+   *[no] {""}
+}
+not-applied-as-written = { $value } ({ $item }) did not take effect as written: it is { $actual } on Discord
+not-verified = { $value } ({ $item }) could not be verified: { $detail }
+request-retrying = Request to { $path } failed ({ $detail }), retrying in { $delay } ({ $attempt }/{ $retries })
+
+## Progress
+
+no-channels = No channels found
+no-channels-in-range = No channels after --offset { $offset } ({ $total } found)
+channels-in-range = Listing channels { $first }-{ $last } of { $total }
+next-range = Pass --offset { $offset } for the next ones
+no-forum-tags = No forum tags found
+no-threads = No threads found
+no-webhooks = No webhooks found
+no-roles = No roles found
+no-emojis = No custom emojis found
+no-stickers = No stickers found
+no-sounds = No soundboard sounds found
+no-automod-rules = No AutoMod rules found
+no-onboarding-prompts = No onboarding prompts found
+no-items-selected = No items selected
+no-changes = No changes
+no-changes-to-apply = No changes to apply
+aborted-empty-buffer = Aborted: the buffer is empty
+aborted-empty-input = Aborted: the input is empty
+discarded-changes = Discarded the changes
+saved-edits = Saved the edits to { $path }
+rate-limit-estimate = { $count ->
+    [one] 1 rename
+   *[other] { $count } renames
+}, estimated ~{ $wait } due to rate limits
+rate-limit-none = { $count ->
+    [one] 1 rename
+   *[other] { $count } renames
+}, no wait for rate limits expected
+fetching-channels = Fetching channels...
+applying = Applying
+applying-prefix = Applying:
+failed-prefix = Failed:
+rolling-back-prefix = Rolling back:
+roll-back-failed = Failed to roll back { $item }: { $detail }
+changes-count = { $count ->
+    [one] 1 change
+   *[other] { $count } changes
+}
+verified = Verified { $count ->
+    [one] 1 change
+   *[other] { $count } changes
+}
+posted-summary = Posted a summary to channel { $channel }
+watching = Watching { $path } for changes (Ctrl-C to stop)
+file-changed = { $path } changed
+waiting-for-save = Waiting for the next save
+watch-polling = File system notifications are unavailable for { $path } ({ $detail }), checking it twice a second instead
+connected-as = Connected as { $user }
+token-env-unset = $DISCORD_TOKEN is not set, skipping the connectivity check
+wrote-file = Wrote { $path }
+tempfile-kept = Temporary file kept at { $path }
+saving-snapshot = Saving snapshot { $id }
+nothing-to-undo = nothing to undo: no renames applied yet
+rate-limit-waiting = Rate limited by Discord, waiting { $wait }
+using-cached-channels = Using the channel list of guild { $guild } cached { $age } ago
+
+## Summaries
+
+summary-heading = Summary:
+summary-ok = ok
+summary-failed = failed
+run-totals = { $applied } applied, { $unchanged } unchanged, { $skipped } skipped, { $failed } failed{ $pending ->
+    [0] {""}
+   *[other] , { $pending } not applied
+} in { $elapsed }
+review-kept = Keeping { $kept } of { $total ->
+    [one] 1 change
+   *[other] { $total } changes
+}
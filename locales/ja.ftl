@@ -0,0 +1,135 @@
+# edisch の日本語のメッセージ。en.ftl の全てのメッセージを定義する
+
+## エラーと警告の接頭辞
+
+error-prefix = エラー:
+error-prefix-unknown = 不明なエラー
+warning-prefix = 警告:
+
+## エラー
+
+error-missing-argument = 引数が足りません: { $detail }
+error-parse-argument = 引数を解釈できません: { $detail }
+error-config = 設定ファイルが不正です: { $detail }
+error-policy-violation = 命名ポリシーに違反しています: { $detail }
+error-invalid-edit-result = 編集結果が不正です: { $detail }
+error-unsupported-operation = 対応していない操作です: { $detail }
+error-io = 入出力エラー: { $detail }
+error-keyring = キーリングのエラー: { $detail }
+error-replay = 再生に失敗しました: { $detail }
+error-invalid-channel-name = チャンネル名が不正です: { $name } ({ $detail })
+error-invalid-token = トークンが不正です: Discordにトークンを拒否されました。--token、$DISCORD_TOKEN または `edisch auth set` を確認してください
+error-forbidden = 権限がありません: { $detail }。Botのロールとチャンネルの権限の上書きを確認してください
+error-not-found = 見つかりません: { $detail }。IDと、BotがGuildに参加していることを確認してください
+error-rate-limited = レートリミットに達しました: { $detail }。数分待つか、--retries を増やしてください
+error-checks-failed = { $count } 件の検査に失敗しました
+error-conflicts = { $count } 件のアイテムが、書き出した後にサーバー上で変更されています
+error-missing-permissions = { $count } 件のチャンネルで、BotにManage Channelsの権限がありません
+error-duplicate-names = { $count } 件の名前が重複します。名前を変えるか、--dedup で警告のみ・番号の付加を選んでください
+error-warnings = 変更に { $count } 件の警告があります。修正するか、--force で適用してください
+error-pending-changes = { $count } 件の変更が未適用です
+error-verification-failed = { $count } 件の変更が書いた通りに反映されませんでした
+error-partial-failure = { $total } 件中 { $failed } 件の変更に失敗しました
+error-interrupted = Ctrl-Cで中断しました
+
+## 確認
+
+prompt-apply = 変更を適用しますか?
+prompt-apply-count = { $count } 件の変更を適用しますか?
+choice-no = いいえ
+choice-yes = はい
+choice-edit-again = 編集し直す
+prompt-delete = { $count } 件のチャンネルが完全に削除されます。よろしいですか?
+prompt-pick = 編集するアイテムを選んでください (スペースで選択、Enterで確定)
+prompt-review = ({ $n }/{ $total }) この変更を適用しますか? [y]はい/[n]いいえ/[a]残り全て/[q]終了/[e]編集
+review-invalid-answer = y, n, a, q, e のいずれかで答えてください
+review-single-line-only = 編集できるのは1行の値の変更のみです
+prompt-new-value = 新しい値
+prompt-edit-again = 文書を編集し直しますか?
+prompt-archive = { $count } 件のチャンネルを { $category } にアーカイブしますか?
+prompt-move = { $count } 件のチャンネルを移動しますか?
+prompt-token = Botのトークン
+prompt-overwrite-config = { $path } は既にあります。上書きしますか?
+prompt-token-storage = Botのトークンをどのように渡しますか?
+choice-token-config = 設定ファイルに保存する (自分のみ読み取れます)
+choice-token-env = $DISCORD_TOKEN から読み取る
+prompt-default-guild-id = 既定のGuild ID (空の場合はなし)
+invalid-guild-id = Guild IDではありません
+prompt-default-guild = 既定のGuild
+choice-none = なし
+prompt-editor = エディタのコマンド (空の場合は $VISUAL または $EDITOR)
+prompt-default-kinds = 既定で編集するチャンネルの種類 (選ばない場合は毎回指定)
+
+## 警告
+
+no-category = (カテゴリなし)
+duplicate-names-found = { $place } に { $name } という名前のチャンネルが { $count } 件あります: { $items }
+duplicate-names-planned = { $place } で { $count } 件のチャンネルが { $name } という名前になります: { $items }
+changed-since-export = { $value } ({ $item }) は書き出した後にサーバー上で変更されています{ $skipped ->
+    [yes] 。スキップします
+   *[no] {""}
+}
+missing-manage-channels = { $old } -> { $new } ({ $item }): このチャンネルでBotにManage Channelsの権限がありません{ $skipped ->
+    [yes] 。スキップします
+   *[no] {""}
+}
+not-applied-as-written = { $value } ({ $item }) は書いた通りに反映されませんでした: Discord上では { $actual } です
+not-verified = { $value } ({ $item }) を確認できませんでした: { $detail }
+request-retrying = { $path } へのリクエストに失敗しました ({ $detail })。{ $delay } 後に再送します ({ $attempt }/{ $retries })
+
+## 進捗
+
+no-channels = チャンネルが見つかりません
+no-channels-in-range = --offset { $offset } より後にチャンネルはありません ({ $total } 件)
+channels-in-range = { $total } 件中 { $first }-{ $last } 件目のチャンネルを表示しています
+next-range = 続きは --offset { $offset } で編集できます
+no-forum-tags = フォーラムのタグが見つかりません
+no-threads = スレッドが見つかりません
+no-webhooks = Webhookが見つかりません
+no-roles = ロールが見つかりません
+no-emojis = カスタム絵文字が見つかりません
+no-stickers = スタンプが見つかりません
+no-sounds = サウンドボードのサウンドが見つかりません
+no-automod-rules = AutoModのルールが見つかりません
+no-onboarding-prompts = オンボーディングの質問が見つかりません
+no-items-selected = アイテムが選ばれていません
+no-changes = 変更はありません
+no-changes-to-apply = 適用する変更はありません
+aborted-empty-buffer = 中止しました: バッファが空です
+aborted-empty-input = 中止しました: 入力が空です
+discarded-changes = 変更を破棄しました
+saved-edits = 編集内容を { $path } に保存しました
+rate-limit-estimate = { $count } 件の変更は、レートリミットにより約 { $wait } かかる見込みです
+rate-limit-none = { $count } 件の変更は、レートリミットによる待ち時間なしで適用できる見込みです
+fetching-channels = チャンネルを取得しています...
+applying = 適用中
+applying-prefix = 適用中:
+failed-prefix = 失敗:
+rolling-back-prefix = 元に戻しています:
+roll-back-failed = { $item } を元に戻せませんでした: { $detail }
+changes-count = { $count } 件の変更
+verified = { $count } 件の変更を確認しました
+posted-summary = チャンネル { $channel } に変更の一覧を投稿しました
+watching = { $path } の変更を監視しています (Ctrl-Cで終了)
+file-changed = { $path } が変更されました
+waiting-for-save = 次の保存を待っています
+watch-polling = { $path } の変更の通知を受け取れないため ({ $detail })、代わりに1秒に2回調べます
+connected-as = { $user } として接続しました
+token-env-unset = $DISCORD_TOKEN が設定されていないため、接続の確認を省きます
+wrote-file = { $path } に書き出しました
+tempfile-kept = 一時ファイルを { $path } に残しました
+saving-snapshot = スナップショット { $id } を保存しています
+nothing-to-undo = 元に戻すものがありません: まだ名前の変更を適用していません
+rate-limit-waiting = Discordのレートリミットにより { $wait } 待っています
+using-cached-channels = { $age } 前にキャッシュしたGuild { $guild } のチャンネル一覧を使います
+
+## 結果
+
+summary-heading = 結果:
+summary-ok = 成功
+summary-failed = 失敗
+run-totals = 適用 { $applied } 件、変更なし { $unchanged } 件、スキップ { $skipped } 件、失敗 { $failed } 件{ $pending ->
+    [0] {""}
+   *[other] 、未適用 { $pending } 件
+} (所要時間 { $elapsed })
+review-kept = { $total } 件中 { $kept } 件の変更を残します
@@ -0,0 +1,66 @@
+use crate::args::AnnotateFormat;
+
+/// CIサービスが解釈できる形式でエラーを出力する
+pub struct Annotator {
+    format: Option<AnnotateFormat>,
+    /// 入力ファイルのパス
+    file: Option<String>,
+    /// バッファ上の各行に対応する入力上の行番号 (1始まり)
+    line_numbers: Vec<Option<usize>>,
+}
+
+impl Annotator {
+    /// `format` が None の場合は何も出力しない
+    pub fn new(format: Option<AnnotateFormat>, file: Option<String>) -> Self {
+        Self {
+            format,
+            file,
+            line_numbers: Vec::new(),
+        }
+    }
+
+    /// バッファ上の行と入力上の行番号の対応を設定する
+    pub fn set_line_numbers(&mut self, line_numbers: Vec<usize>) {
+        self.line_numbers = line_numbers.into_iter().map(Some).collect();
+    }
+
+    /// 行をIDで照合した場合に、各アイテムに対応するバッファ上の行の位置で行番号を並べ替える
+    pub fn reorder(&mut self, indices: &[Option<usize>]) {
+        self.line_numbers = indices
+            .iter()
+            .map(|index| index.and_then(|i| self.line_numbers.get(i).copied().flatten()))
+            .collect();
+    }
+
+    /// バッファ上の `index` 行目 (指定しない場合はファイル全体) に関するエラーを出力する
+    pub fn error(&self, index: Option<usize>, message: &str) {
+        let Some(AnnotateFormat::Github) = self.format else {
+            return;
+        };
+        let mut properties = Vec::new();
+        if let Some(file) = &self.file {
+            properties.push(format!("file={}", escape_property(file)));
+            if let Some(line) = index.and_then(|i| self.line_numbers.get(i).copied().flatten()) {
+                properties.push(format!("line={line}"));
+            }
+        }
+        let properties = if properties.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", properties.join(","))
+        };
+        println!("::error{properties}::{}", escape_data(message));
+    }
+}
+
+/// ワークフローコマンドのメッセージ部分のエスケープ
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// ワークフローコマンドのプロパティ値のエスケープ
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,128 @@
+use crate::{
+    args::{ArchiveOptions, ChannelFilterArgs},
+    channel::{kind_emoji, ChannelMatcher},
+    discord::Discord,
+    error::{Error, Result},
+    i18n::tr,
+};
+use dialoguer::Confirm;
+use serenity::all::{
+    ChannelType, EditChannel, GuildChannel, GuildId, PermissionOverwrite, PermissionOverwriteType,
+    Permissions, RoleId,
+};
+use std::sync::Arc;
+use tracing::info;
+
+/// 書き込みを禁止する際に、@everyone で拒否する権限
+const LOCKED: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::CREATE_PUBLIC_THREADS)
+    .union(Permissions::CREATE_PRIVATE_THREADS);
+
+/// 絞り込んだチャンネルを、接頭辞を付けた名前に変え、アーカイブ用のカテゴリへ移動する。
+/// 指定された場合は @everyone の書き込みも禁止する。カテゴリと、既にアーカイブ済みのチャンネルは対象外
+pub async fn archive(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    filter: &ChannelFilterArgs,
+    options: &ArchiveOptions,
+    color: bool,
+) -> Result<()> {
+    if filter.none() {
+        return Err(Error::MissingArgument(
+            "channel filters (use --all to archive every channel)".into(),
+        ));
+    }
+    let mut matcher = ChannelMatcher::new(filter)?;
+    let channels = discord.guild_channels(guild_id, |_| {}).await?;
+    matcher.resolve_categories(&channels)?;
+    let category = channels
+        .iter()
+        .find(|c| c.id == options.to_category && c.kind == ChannelType::Category)
+        .ok_or_else(|| {
+            Error::ParseArgument(format!("category not found: {}", options.to_category).into())
+        })?;
+
+    // 対象のチャンネルと、変更後の名前
+    let targets: Vec<(&GuildChannel, String)> = channels
+        .iter()
+        .filter(|c| c.kind != ChannelType::Category && c.parent_id != Some(category.id))
+        .filter(|c| matcher.matches(c))
+        .map(|c| {
+            let name = match &options.prefix {
+                Some(prefix) if !c.name.starts_with(prefix.as_str()) => {
+                    format!("{prefix}{}", c.name)
+                }
+                _ => c.name.clone(),
+            };
+            (c, name)
+        })
+        .collect();
+    if targets.is_empty() {
+        info!("No channels to archive");
+        return Ok(());
+    }
+    if let Some((_, name)) = targets.iter().find(|(_, name)| name.chars().count() > 100) {
+        return Err(Error::InvalidChannelName {
+            name: name.clone(),
+            message: "Channel name must be between 2 and 100 characters",
+        });
+    }
+
+    for (channel, name) in &targets {
+        let mut new = console::style(name);
+        if color {
+            new = new.green();
+        }
+        println!(
+            "{} {} -> {new} in {}{}  ({})",
+            kind_emoji(channel.kind),
+            channel.name,
+            category.name,
+            if options.lock { ", locked" } else { "" },
+            channel.id
+        );
+    }
+    if options.dry_run {
+        return Err(Error::PendingChanges(targets.len()));
+    }
+    if !options.yes
+        && !Confirm::new()
+            .with_prompt(tr!(
+                "prompt-archive",
+                count = targets.len(),
+                category = category.name.as_str()
+            ))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    let everyone = RoleId::new(guild_id.get());
+    for (channel, name) in &targets {
+        let mut edit = EditChannel::new().category(Some(category.id));
+        if *name != channel.name {
+            edit = edit.name(name);
+        }
+        discord.edit_channel(channel.id, &edit).await?;
+        if options.lock {
+            // 既存の @everyone の設定を残したまま、書き込みの権限のみ拒否する
+            let (allow, deny) = channel
+                .permission_overwrites
+                .iter()
+                .find(|o| o.kind == PermissionOverwriteType::Role(everyone))
+                .map_or((Permissions::empty(), Permissions::empty()), |o| {
+                    (o.allow, o.deny)
+                });
+            let overwrite = PermissionOverwrite {
+                allow: allow - LOCKED,
+                deny: deny | LOCKED,
+                kind: PermissionOverwriteType::Role(everyone),
+            };
+            discord.edit_permission(channel.id, &overwrite).await?;
+        }
+        info!("Archived {} ({})", channel.name, channel.id);
+    }
+    Ok(())
+}
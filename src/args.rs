@@ -1,7 +1,12 @@
 use clap::{Parser, Subcommand};
 use clap_complete::Shell;
-use serenity::all::ChannelType;
-use std::{ops::BitAnd, path::PathBuf};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, ChannelType};
+use std::{
+    ops::BitAnd,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 /// Tool to change Discord channel names in bulk with your $EDITOR
 #[derive(Parser, Debug)]
@@ -18,6 +23,208 @@ pub struct Args {
     /// Apply arguments
     #[clap(flatten)]
     apply: ApplyArgs,
+    /// Editor arguments
+    #[clap(flatten)]
+    editor: EditorArgs,
+    /// Transforms applied instead of opening the editor
+    #[clap(flatten)]
+    transform: TransformArgs,
+    /// Profile of the config file to connect with (its token and guild ID). Defaults to $EDISCH_PROFILE
+    #[clap(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// When to use colored output. With auto, NO_COLOR disables colors and CLICOLOR_FORCE forces them
+    #[clap(long, value_enum, global = true, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+    /// Language of the messages. Defaults to the one of $LC_ALL, $LC_MESSAGES or $LANG, or English
+    #[clap(long, value_enum, global = true)]
+    pub lang: Option<Lang>,
+    /// Which property of the channels to edit
+    #[clap(long, value_enum, global = true, default_value_t = ChannelField::Name)]
+    field: ChannelField,
+    /// Template of the comment after each channel. Placeholders: {kind} (emoji), {type}, {name}, {category}, {guild}, {topic}, {position} and {id} (the ID in parentheses, which apply uses to match lines)
+    #[clap(long, global = true, value_name = "TEMPLATE")]
+    comment_format: Option<String>,
+    /// Write no comment after each channel (lines are then matched by position)
+    #[clap(long, global = true, conflicts_with = "comment_format")]
+    no_comments: bool,
+    /// Order of the channels in the buffer and exported files
+    #[clap(long, value_enum, global = true, default_value_t = ChannelSort::Position)]
+    sort: ChannelSort,
+    /// Reverse the order of the channels
+    #[clap(long, global = true)]
+    reverse: bool,
+    /// Only list this many channels (after sorting and --offset), so that large guilds can be edited in chunks
+    #[clap(long, global = true, value_name = "N")]
+    limit: Option<usize>,
+    /// Skip this many channels (after sorting) before listing, e.g. --offset 100 --limit 100 for the second chunk
+    #[clap(long, global = true, value_name = "N", default_value_t = 0)]
+    offset: usize,
+    /// Print more detail to stderr: -v for debug messages, -vv also for HTTP requests and libraries
+    #[clap(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+    /// Print only warnings and errors to stderr
+    #[clap(short, long, global = true)]
+    pub quiet: bool,
+    /// Log metadata (status, rate-limit headers) of every Discord HTTP request to stderr, with the token redacted
+    #[clap(long, global = true, visible_alias = "debug-http")]
+    pub trace_http: bool,
+    /// Exit with status 4 instead of 0 when changes were applied
+    #[clap(long, global = true)]
+    pub detailed_exit_code: bool,
+}
+
+/// How the channels are listed
+#[derive(Debug, Clone, Default)]
+pub struct ChannelView {
+    /// Comment written after each channel
+    pub comments: CommentFormat,
+    /// Order of the channels
+    pub sort: ChannelSort,
+    /// Reverse the order
+    pub reverse: bool,
+    /// Maximum number of channels to list
+    pub limit: Option<usize>,
+    /// Number of channels to skip
+    pub offset: usize,
+}
+
+/// Order of the channels
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelSort {
+    /// Uncategorized channels first, then by category, with voice channels last in each category (as in Discord)
+    #[default]
+    Position,
+    /// Alphabetically by name, ignoring case
+    Name,
+    /// By channel ID
+    Id,
+    /// By creation time
+    Created,
+}
+
+/// Comment written after each channel
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CommentFormat {
+    /// Kind emoji, category and ID
+    #[default]
+    Default,
+    /// Template given by --comment-format
+    Template(String),
+    /// No comment (--no-comments)
+    Hidden,
+}
+
+/// Editable property of a channel
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelField {
+    /// Channel name
+    #[default]
+    Name,
+    /// Topic of text and announcement channels (0-1024 characters, newlines and tabs written as \n and \t)
+    Topic,
+    /// Temporary status text of voice channels (empty to clear)
+    VoiceStatus,
+    /// Post guidelines of forum channels (multi-line, edited as blocks)
+    Guidelines,
+    /// Slowmode in seconds of text, voice, stage and forum channels (0-21600)
+    Slowmode,
+    /// Default slowmode in seconds for new threads in text and forum channels (0-21600)
+    ThreadSlowmode,
+    /// NSFW flag of all channels except categories (true or false)
+    Nsfw,
+    /// Name, topic, slowmode and NSFW flag of text and announcement channels as tab-separated columns
+    Columns,
+    /// Bitrate in kbps and user limit (0 for none) of voice and stage channels as tab-separated columns
+    Voice,
+}
+
+impl std::fmt::Display for ChannelField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = clap::ValueEnum::to_possible_value(self).unwrap();
+        write!(f, "{}", value.get_name())
+    }
+}
+
+/// Editable property of a sticker
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StickerField {
+    /// Sticker name (2-30 characters)
+    #[default]
+    Name,
+    /// Comma-separated autocomplete tags (up to 200 characters)
+    Tags,
+    /// Description (empty or 2-100 characters)
+    Description,
+}
+
+impl ChannelField {
+    /// Whether channels of the kind have this property
+    pub fn supports(self, kind: ChannelType) -> bool {
+        match self {
+            ChannelField::Name => true,
+            ChannelField::Topic | ChannelField::Columns => {
+                matches!(kind, ChannelType::Text | ChannelType::News)
+            }
+            ChannelField::VoiceStatus => kind == ChannelType::Voice,
+            ChannelField::Voice => matches!(kind, ChannelType::Voice | ChannelType::Stage),
+            ChannelField::Guidelines => kind == ChannelType::Forum,
+            ChannelField::Slowmode => matches!(
+                kind,
+                ChannelType::Text | ChannelType::Voice | ChannelType::Stage | ChannelType::Forum
+            ),
+            ChannelField::Nsfw => kind != ChannelType::Category,
+            ChannelField::ThreadSlowmode => matches!(
+                kind,
+                ChannelType::Text | ChannelType::News | ChannelType::Forum
+            ),
+        }
+    }
+}
+
+/// Kind of the token sent in the Authorization header
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenType {
+    /// Bot token (`Authorization: Bot ...`)
+    Bot,
+    /// OAuth2 access token of a user (`Authorization: Bearer ...`)
+    Bearer,
+}
+
+/// Whether to use colored output
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default)]
+pub enum ColorChoice {
+    /// Use colors only if stderr is a terminal, unless NO_COLOR or CLICOLOR_FORCE is set
+    #[default]
+    Auto,
+    /// Always use colors
+    Always,
+    /// Never use colors
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether colors should be enabled, given whether stderr is a terminal.
+    /// auto では NO_COLOR (https://no-color.org) が CLICOLOR_FORCE より、CLICOLOR_FORCE が CLICOLOR=0 より優先される
+    pub fn enabled(self, is_tty: bool) -> bool {
+        let value = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        match self {
+            ColorChoice::Auto if value("NO_COLOR").is_some() => false,
+            ColorChoice::Auto if value("CLICOLOR_FORCE").is_some_and(|value| value != "0") => true,
+            ColorChoice::Auto if value("CLICOLOR").is_some_and(|value| value == "0") => false,
+            ColorChoice::Auto => is_tty,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Language of the messages
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    /// English
+    En,
+    /// Japanese
+    Ja,
 }
 
 #[derive(Debug, Subcommand)]
@@ -30,6 +237,12 @@ enum Commands {
         /// File to export to
         #[clap(short, long)]
         output: Option<PathBuf>,
+        /// Only export the channel with this ID (repeatable)
+        #[clap(long, value_name = "CHANNEL_ID")]
+        channel: Vec<ChannelId>,
+        /// Export arguments
+        #[clap(flatten)]
+        export: ExportArgs,
     },
     /// Apply all channel names from a file or stdin
     Apply {
@@ -39,6 +252,391 @@ enum Commands {
         /// File to apply from
         #[clap(short, long)]
         input: Option<PathBuf>,
+        /// Continue an interrupted apply from the file it left (only the changes not applied yet)
+        #[clap(long, value_name = "FILE", conflicts_with = "input")]
+        resume: Option<PathBuf>,
+        /// Only apply the lines of the channel with this ID (repeatable)
+        #[clap(long, value_name = "CHANNEL_ID")]
+        channel: Vec<ChannelId>,
+        /// Format of the input file: tsv, or a json/yaml/csv export. Detected from the extension by default
+        #[clap(long, value_enum, value_name = "FORMAT")]
+        format: Option<ExportFormat>,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+    },
+    /// Watch a file and apply it every time it is saved (with the usual preview and confirmation), until interrupted with Ctrl-C
+    Watch {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// File to watch, as written by export
+        input: PathBuf,
+        /// Format of the file: tsv, or a json/yaml/csv export. Detected from the extension by default
+        #[clap(long, value_enum, value_name = "FORMAT")]
+        format: Option<ExportFormat>,
+        /// Check the file twice a second instead of relying on file system notifications (e.g. on network drives)
+        #[clap(long)]
+        poll: bool,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+    },
+    /// Print the changes apply would make from a file, without applying them. Exits with status 2 if there are any
+    Diff {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// File to compare (stdin if omitted)
+        #[clap(short, long)]
+        input: Option<PathBuf>,
+        /// Format of the input file: tsv, or a json/yaml/csv export. Detected from the extension by default
+        #[clap(long, value_enum, value_name = "FORMAT")]
+        format: Option<ExportFormat>,
+        /// How to print the changes
+        #[clap(long, value_enum, value_name = "FORMAT", default_value_t)]
+        output_format: DiffFormat,
+    },
+    /// Edit guild settings (name, description, system/AFK channel, AFK timeout) with your $EDITOR
+    Guild {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Follow or unfollow announcement channels into a target channel with your $EDITOR
+    Follows {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Channel that receives the followed announcements
+        #[clap(long, value_name = "CHANNEL_ID")]
+        target: u64,
+        /// Only list the channels currently followed into the target channel
+        #[clap(long)]
+        list: bool,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Rename the available tags of all forum channels in bulk with your $EDITOR
+    ForumTags {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Rename threads in bulk with your $EDITOR
+    Threads {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Also list archived threads, which are unarchived and archived again around the rename
+        #[clap(long)]
+        archived: bool,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Rename webhooks in bulk with your $EDITOR
+    Webhooks {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Rename roles in bulk with your $EDITOR
+    Roles {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Also list roles managed by bots and integrations
+        #[clap(long)]
+        include_managed: bool,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Edit role icons (unicode emoji or PNG/JPEG file, empty to clear) with your $EDITOR
+    RoleIcons {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Rename custom emojis in bulk with your $EDITOR
+    Emojis {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Edit sticker names, tags or descriptions in bulk with your $EDITOR
+    Stickers {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Which property of the stickers to edit
+        #[clap(long, value_enum, default_value_t = StickerField::Name)]
+        property: StickerField,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Rename soundboard sounds in bulk with your $EDITOR
+    Sounds {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Rename AutoMod rules, or edit their keyword lists, in bulk with your $EDITOR
+    Automod {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Edit the keyword lists of keyword rules (one keyword per line) instead of the rule names
+        #[clap(long)]
+        keywords: bool,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Edit onboarding prompt titles and option labels with your $EDITOR
+    Onboarding {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Edit the welcome screen (description and each welcome channel's description and emoji) with your $EDITOR
+    Welcome {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+        /// Editor arguments
+        #[clap(flatten)]
+        editor: EditorArgs,
+    },
+    /// Make the guild match a file written by export: create missing categories and channels, rename or move mismatched ones, and report extras
+    Structure {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// File to apply from
+        #[clap(short, long)]
+        input: Option<PathBuf>,
+        /// Automatically confirm all changes
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Copy the categories and channels of one guild to another: create the missing ones and move the matched ones, after a preview
+    Copy {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Guild ID or alias to copy the structure from
+        #[clap(long, value_name = "GUILD")]
+        from: String,
+        /// Guild ID or alias to copy the structure to
+        #[clap(long, value_name = "GUILD")]
+        to: String,
+        /// Automatically confirm all changes
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Converge the guild to a desired-state TOML file: create, rename, move, retopic and reorder categories and channels (and delete the unlisted ones with --prune), after previewing the plan
+    Sync {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// TOML file describing the categories and channels (`[[channel]]` and `[[category]]` with nested `[[category.channel]]`)
+        file: PathBuf,
+        /// Delete the categories and channels that are not in the file
+        #[clap(long)]
+        prune: bool,
+        /// Automatically confirm all changes
+        #[clap(short, long)]
+        yes: bool,
+        /// Only print the plan, and exit with status 2 if there are changes
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Reorder the channels of each category alphabetically (text channels first, then voice channels) and apply the new positions at once
+    Sort {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Automatically confirm all changes
+        #[clap(short, long)]
+        yes: bool,
+        /// Only print the moves, and exit with status 2 if there are any
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Archive the filtered channels: add a prefix to their names, move them under an archive category, and optionally lock sending messages
+    Archive {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Filter channels arguments (required; categories and channels already in the archive category are skipped)
+        #[clap(flatten)]
+        filter: ChannelFilterArgs,
+        /// ID of the category to move the channels under
+        #[clap(long, value_name = "ID")]
+        to_category: u64,
+        /// Prefix added to the names (unless they already start with it)
+        #[clap(long)]
+        prefix: Option<String>,
+        /// Deny @everyone sending messages and creating threads in the archived channels
+        #[clap(long)]
+        lock: bool,
+        /// Automatically confirm all changes
+        #[clap(short, long)]
+        yes: bool,
+        /// Only print the channels to archive, and exit with status 2 if there are any
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Print the channels of the guild without editing them (`name<TAB>kind<TAB>id`, or a tree with --tree)
+    List {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Filter channels arguments (all channels if none is given)
+        #[clap(flatten)]
+        filter: ChannelFilterArgs,
+        /// Print the categories with their channels nested below them
+        #[clap(long)]
+        tree: bool,
+    },
+    /// Browse the channels in a full-screen terminal UI, rename them in place with live validation, and apply the changes
+    Tui {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Filter channels arguments (all channels if none is given)
+        #[clap(flatten)]
+        filter: ChannelFilterArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+    },
+    /// List the guilds the token has access to (ID, name and channel count)
+    Guilds {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Show the channels renamed, moved, deleted or created since a file was written by export, without applying anything
+    Status {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Exported file to compare with
+        #[clap(short, long)]
+        input: Option<PathBuf>,
+    },
+    /// Interactively set up the token, default guild, editor and filters, and write the config file
+    Init,
+    /// Check the token, the bot's permissions in the guild, the rate limits, the editor and the config file
+    Doctor {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+    },
+    /// Manage channel permission overwrites
+    Perms {
+        #[clap(subcommand)]
+        command: PermsCommand,
+    },
+    /// Store the bot token in the OS keyring (Keychain, Credential Manager or Secret Service), or remove it
+    Auth {
+        #[clap(subcommand)]
+        command: AuthCommand,
+    },
+    /// Export the channel layout of the guild as a portable TOML template, or import one
+    Template {
+        #[clap(subcommand)]
+        command: TemplateCommand,
+    },
+    /// Save, list and restore snapshots of the channel names (or another --field) of the guild
+    Snapshot {
+        #[clap(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Revert the channel renames of the last apply (running it again redoes them)
+    Undo {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+    },
+    /// Browse the local history of every change applied from this machine, newest last
+    History(HistoryArgs),
+    /// Apply all deterministic corrections required by the naming policy (.edisch-policy.toml)
+    Fix {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
         /// Apply arguments
         #[clap(flatten)]
         apply: ApplyArgs,
@@ -50,18 +648,211 @@ enum Commands {
     },
 }
 
-/// Token and Guild ID for Discord connection
+#[derive(Debug, Subcommand)]
+pub enum PermsCommand {
+    /// Apply the role overwrites declared in a template to all matching channels
+    Apply {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// TOML file declaring the allowed and denied permissions per role
+        #[clap(long, value_name = "FILE")]
+        template: PathBuf,
+        /// Only apply to channels whose name matches this regular expression
+        #[clap(long = "match", value_name = "REGEX")]
+        pattern: Option<String>,
+        /// Automatically confirm all changes
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Edit the permission overwrites of every channel as a TOML or YAML document in your $EDITOR, and apply the ones that changed
+    Edit(PermsEditArgs),
+    /// Compare the permission overwrites of two channels
+    Diff {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Channel name or ID
+        channel: String,
+        /// Channel name or ID to compare with
+        #[clap(required_unless_present = "against_category")]
+        other: Option<String>,
+        /// Compare with the category the channel belongs to
+        #[clap(long, conflicts_with = "other")]
+        against_category: bool,
+    },
+}
+
+#[derive(Debug, Subcommand, Clone, Copy)]
+pub enum AuthCommand {
+    /// Store a token in the keyring, prompting for it without echo (or reading it from stdin). It is used when no other token is given; each --profile has its own
+    Set,
+    /// Remove the token of the current profile from the keyring
+    Clear,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplateCommand {
+    /// Write the categories and channels of the guild (names, types, topics and order, without IDs) as a TOML template for `template import` and `sync`
+    Export {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Output file (stdout if omitted)
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Create, rename, move and reorder the categories and channels of the guild to match a template, after a preview
+    Import {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Template written by `template export`
+        file: PathBuf,
+        /// Delete the categories and channels that are not in the template
+        #[clap(long)]
+        prune: bool,
+        /// Automatically confirm all changes
+        #[clap(short, long)]
+        yes: bool,
+        /// Only print the plan, and exit with status 2 if there are changes
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+impl TemplateCommand {
+    /// Discord connection arguments of the command
+    pub fn discord(&self) -> &ConnectionArgs {
+        match self {
+            TemplateCommand::Export { discord, .. } | TemplateCommand::Import { discord, .. } => {
+                discord
+            }
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SnapshotCommand {
+    /// Save the current channel names (or the property given with --field) as a new snapshot
+    Save {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+    },
+    /// List the saved snapshots of the guild
+    List {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+    },
+    /// Restore a snapshot, showing the changes and asking for confirmation like apply
+    Restore {
+        /// Discord connection arguments
+        #[clap(flatten)]
+        discord: ConnectionArgs,
+        /// Snapshot ID shown by `snapshot list` (the latest snapshot if omitted)
+        id: Option<String>,
+        /// Apply arguments
+        #[clap(flatten)]
+        apply: ApplyArgs,
+    },
+}
+
+/// Arguments of `perms edit`
 #[derive(clap::Args, Debug)]
+pub struct PermsEditArgs {
+    /// Discord connection arguments
+    #[clap(flatten)]
+    pub discord: ConnectionArgs,
+    /// Only edit channels whose name matches this regular expression
+    #[clap(long = "match", value_name = "REGEX")]
+    pub pattern: Option<String>,
+    /// Format of the document
+    #[clap(long, value_enum, default_value_t = DocumentFormat::Toml)]
+    pub format: DocumentFormat,
+    /// Write the document to this file instead of opening the editor
+    #[clap(short, long, value_name = "FILE", conflicts_with = "input")]
+    pub output: Option<PathBuf>,
+    /// Apply a document edited beforehand instead of opening the editor (.yaml and .yml files are read as YAML)
+    #[clap(short, long, value_name = "FILE")]
+    pub input: Option<PathBuf>,
+    /// Editor command (defaults to the config file, $VISUAL or $EDITOR)
+    #[clap(long)]
+    pub editor: Option<String>,
+    /// Show the changes without applying them
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Automatically confirm all changes
+    #[clap(short, long)]
+    pub yes: bool,
+}
+
+impl PermsCommand {
+    /// Discord connection arguments of the command
+    pub fn discord(&self) -> &ConnectionArgs {
+        match self {
+            PermsCommand::Apply { discord, .. }
+            | PermsCommand::Edit(PermsEditArgs { discord, .. })
+            | PermsCommand::Diff { discord, .. } => discord,
+        }
+    }
+}
+
+/// Token and Guild ID for Discord connection
+#[derive(clap::Args, Debug, Clone)]
 pub struct ConnectionArgs {
-    /// Bot token. If not provided, it will be read from the $DISCORD_TOKEN environment variable
+    /// Bot token. If not provided, it will be read from the $DISCORD_TOKEN environment variable or the config file
     #[clap(short, long)]
     pub token: Option<String>,
-    /// Guild ID. If not provided, it will be read from the $GUILD_ID environment variable
-    #[clap(short, long)]
-    pub guild_id: Option<u64>,
+    /// Read the bot token from this file. On Unix it must not be readable by other users (e.g. chmod 600)
+    #[clap(long, value_name = "FILE", conflicts_with = "token")]
+    pub token_file: Option<PathBuf>,
+    /// Kind of the token: a bot token, or a user-authorized OAuth2 access token
+    #[clap(long, value_enum, value_name = "TYPE", default_value_t = TokenType::Bot)]
+    pub token_type: TokenType,
+    /// Guild ID or alias defined in the config file. If not provided, it will be read from the $GUILD_ID environment variable or the config file.
+    /// Channels can be edited in several guilds at once by passing it multiple times
+    #[clap(short, long, visible_alias = "guild", value_name = "GUILD")]
+    pub guild_id: Vec<String>,
+    /// Edit the channels of every guild the bot is in
+    #[clap(long, conflicts_with = "guild_id")]
+    pub all_guilds: bool,
+    /// Reason shown in the guild's audit log for every change
+    #[clap(long)]
+    pub reason: Option<String>,
+    /// Number of times to retry a request that failed with a rate limit, a server error or a connection error
+    #[clap(long, value_name = "N", default_value_t = 3)]
+    pub retries: u32,
+    /// Seconds to wait before the first retry; the wait doubles with each retry
+    #[clap(long, value_name = "SECONDS", default_value_t = 1.0)]
+    pub retry_delay: f64,
+    /// HTTP proxy for all requests, e.g. http://proxy.example:3128. Defaults to $HTTPS_PROXY / $ALL_PROXY
+    #[clap(long, value_name = "URL")]
+    pub proxy: Option<String>,
+    /// Send API requests to this Discord-compatible endpoint instead of https://discord.com
+    #[clap(long, value_name = "URL")]
+    pub api_base: Option<String>,
+    /// Seconds to wait for each request (including the channel fetch) before giving up; 0 waits forever
+    #[clap(long, value_name = "SECONDS", default_value_t = 30.0)]
+    pub timeout: f64,
+    /// Record all Discord API responses to a file
+    #[clap(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+    /// Replay Discord API responses from a file recorded with --record instead of connecting
+    #[clap(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+    /// Use the channel list cached by the last run instead of fetching it, for exports and dry runs.
+    /// The list is fetched (and cached) if there is no cache yet; changes computed from a cache are never applied
+    #[clap(long, conflicts_with_all = ["record", "replay"])]
+    pub cached: bool,
+    /// Fetch the channel list again and update the cache, even if --cached is given
+    #[clap(long)]
+    pub refresh: bool,
 }
 
-#[derive(clap::Args, Debug, Clone, Default)]
+#[derive(clap::Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ChannelFilterArgs {
     /// Edit Text Channels
     #[clap(long)]
@@ -84,17 +875,128 @@ pub struct ChannelFilterArgs {
     /// Edit All Channels
     #[clap(long)]
     all: bool,
+    /// Only edit the channels whose name matches the regex (repeatable; any of them may match). Implies --all if no kind is given
+    #[clap(long = "match", value_name = "REGEX")]
+    #[serde(rename = "match", skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<String>,
+    /// Skip the channels whose name matches the regex (repeatable)
+    #[clap(long, value_name = "REGEX")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Only edit the channels in the category with this name or ID, and the category itself (repeatable). Implies --all if no kind is given
+    #[clap(long, value_name = "CATEGORY")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub in_category: Vec<String>,
+    /// Only edit the channel with this ID (repeatable). Implies --all if no kind is given
+    #[clap(long, value_name = "CHANNEL_ID")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub channel: Vec<ChannelId>,
+    /// Only edit the channels without messages for this long, e.g. 90d (units: s, m, h, d, w), judged by their last message. Implies --all if no kind is given; categories are skipped
+    #[clap(long, value_name = "AGE", value_parser = check_age)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inactive_for: Option<String>,
+}
+
+/// `90d` のような期間をパースする。単位は s, m, h, d, w
+pub fn parse_age(text: &str) -> Result<Duration, String> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid age {text:?}, expected e.g. 90d"))?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid unit in {text:?}, expected s, m, h, d or w"
+            ))
+        }
+    };
+    Ok(Duration::from_secs(number.saturating_mul(unit)))
+}
+
+/// --inactive-for の値を検証する
+fn check_age(text: &str) -> Result<String, String> {
+    parse_age(text).map(|_| text.to_string())
 }
 
 impl ChannelFilterArgs {
+    /// 種類の名前 (`text` など) の一覧から作成する
+    pub fn from_kinds<'a>(kinds: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut filter = Self::default();
+        for kind in kinds {
+            match kind {
+                "text" => filter.text = true,
+                "voice" => filter.voice = true,
+                "forum" => filter.forum = true,
+                "stage" => filter.stage = true,
+                "news" => filter.news = true,
+                "category" => filter.category = true,
+                _ => filter.all = true,
+            }
+        }
+        filter
+    }
+
+    /// 種類の名前 (`text` など) が指定されているか
+    pub fn contains(&self, kind: &str) -> bool {
+        match kind {
+            "text" => self.text,
+            "voice" => self.voice,
+            "forum" => self.forum,
+            "stage" => self.stage,
+            "news" => self.news,
+            "category" => self.category,
+            _ => self.all,
+        }
+    }
+
+    /// 種類も名前・カテゴリ・ID・活動の条件も指定されていないか
     pub fn none(&self) -> bool {
-        !self.text
-            && !self.voice
-            && !self.forum
-            && !self.stage
-            && !self.news
-            && !self.category
-            && !self.all
+        !self.has_kinds()
+            && self.matches.is_empty()
+            && self.exclude.is_empty()
+            && self.in_category.is_empty()
+            && self.channel.is_empty()
+            && self.inactive_for.is_none()
+    }
+
+    /// 種類が指定されているか
+    pub fn has_kinds(&self) -> bool {
+        self.text
+            || self.voice
+            || self.forum
+            || self.stage
+            || self.news
+            || self.category
+            || self.all
+    }
+
+    /// 種類を default のものにする。名前・カテゴリ・ID・活動の条件は指定されていればこちらのものを使う
+    pub fn with_kinds_of(self, default: &Self) -> Self {
+        let mut filter = default.clone();
+        if !self.matches.is_empty() {
+            filter.matches = self.matches;
+        }
+        if !self.exclude.is_empty() {
+            filter.exclude = self.exclude;
+        }
+        if !self.in_category.is_empty() {
+            filter.in_category = self.in_category;
+        }
+        if !self.channel.is_empty() {
+            filter.channel = self.channel;
+        }
+        if self.inactive_for.is_some() {
+            filter.inactive_for = self.inactive_for;
+        }
+        filter
     }
 }
 
@@ -102,7 +1004,8 @@ impl BitAnd<ChannelType> for &ChannelFilterArgs {
     type Output = bool;
 
     fn bitand(self, rhs: ChannelType) -> bool {
-        if self.all {
+        // 名前・カテゴリ・ID・活動の条件のみ指定された場合は全ての種類を対象にする
+        if self.all || !self.has_kinds() {
             return true;
         }
         match rhs {
@@ -120,11 +1023,303 @@ impl BitAnd<ChannelType> for &ChannelFilterArgs {
     }
 }
 
-#[derive(clap::Args, Debug)]
+#[derive(clap::Args, Debug, Clone, Default)]
 pub struct ApplyArgs {
     /// Automatically confirm all changes
     #[clap(short, long)]
     pub yes: bool,
+    /// Keep applying the remaining changes when one fails, print a summary of the results, and exit with status 3 if any failed
+    #[clap(long, visible_alias = "continue-on-error")]
+    pub keep_going: bool,
+    /// When a change fails, revert the changes already applied in this run (only value changes, no deletions, moves or new channels)
+    #[clap(long, conflicts_with = "keep_going")]
+    pub atomic: bool,
+    /// Number of changes to apply at the same time
+    #[clap(short, long, value_name = "N", default_value_t = 1)]
+    pub jobs: usize,
+    /// Print each applied change to stdout as `old<TAB>new<TAB>id` (new is empty for deleted channels, old for created ones)
+    #[clap(long)]
+    pub print_mapping: bool,
+    /// Rewrite names to correct the naming policy violations that can be fixed mechanically
+    #[clap(long)]
+    pub fix: bool,
+    /// Create a channel for each added line and delete the channel of each removed line
+    #[clap(long)]
+    pub structural: bool,
+    /// Fail instead of skipping the lines whose channel was changed on the server since the export
+    #[clap(long)]
+    pub strict_conflicts: bool,
+    /// Fail instead of skipping the changes to channels where the bot lacks the Manage Channels permission
+    #[clap(long)]
+    pub strict: bool,
+    /// After applying, fetch the changed items again and report any whose value differs from the one written (e.g. a name Discord normalized)
+    #[clap(long)]
+    pub verify: bool,
+    /// Apply the changes even if there are warnings about them (e.g. names Discord will lowercase); errors still stop the apply
+    #[clap(long)]
+    pub force: bool,
+    /// Only print the changes to stdout instead of asking to apply them, and exit with status 2 if there are any
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Instead of refusing changes that would duplicate a name (ignoring case) within the same category: only warn about the duplicates, or append -2, -3, … to all but the first
+    #[clap(long, value_enum, value_name = "MODE")]
+    pub dedup: Option<DedupMode>,
+    /// Normalize the changed values to this Unicode normalization form before applying them (NFKC also turns full-width letters and ligatures into plain ones)
+    #[clap(long, value_enum, value_name = "FORM")]
+    pub normalize: Option<Normalization>,
+    /// Ask for each change whether to apply it ([y]es/[n]o/[a]ll/[q]uit/[e]dit) instead of confirming all of them at once
+    #[clap(long, conflicts_with_all = ["yes", "dry_run"])]
+    pub interactive: bool,
+    /// Overwrite the values changed on the server since the export instead of skipping them
+    #[clap(skip)]
+    pub overwrite: bool,
+    /// Format of the input file (detected from the extension by default)
+    #[clap(skip)]
+    pub input_format: Option<ExportFormat>,
+    /// With --dry-run, how to print the changes
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t,
+        requires = "dry_run"
+    )]
+    pub output_format: DiffFormat,
+    /// With --dry-run, save the edited lines to a file that `apply -i` accepts
+    #[clap(long, value_name = "FILE", requires = "dry_run")]
+    pub save_edits: Option<PathBuf>,
+    /// Report progress as JSON Lines events on stdout (fetch_started, diff_computed, apply_started, apply_ok, apply_failed, done) instead of a progress bar
+    #[clap(long, value_enum, value_name = "FORMAT")]
+    pub progress: Option<OutputFormat>,
+    /// After every change has been applied, post a summary of the changes to this channel
+    #[clap(long, value_name = "CHANNEL_ID")]
+    pub notify_channel: Option<ChannelId>,
+    /// Write a JSON record of the run (inputs, filters, fetched state, diffs, per-item results and timings) to a file
+    #[clap(long, visible_alias = "report", value_name = "FILE")]
+    pub manifest: Option<PathBuf>,
+    /// Also report validation errors and apply failures as CI annotations
+    #[clap(long, value_enum, value_name = "FORMAT")]
+    pub annotate: Option<AnnotateFormat>,
+}
+
+/// Format of CI annotations
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum AnnotateFormat {
+    /// GitHub Actions workflow commands (`::error file=…,line=…::`)
+    Github,
+}
+
+/// Format of listings
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// JSON
+    Json,
+}
+
+/// How to handle duplicate names
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupMode {
+    /// Only print a warning for each duplicate name
+    Warn,
+    /// Append -2, -3, … to the duplicates
+    Suffix,
+}
+
+/// Format of a document edited as a whole
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Toml,
+    Yaml,
+}
+
+/// Unicode normalization form
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    /// Canonical composition (e.g. e + combining acute accent -> é)
+    Nfc,
+    /// Compatibility composition (also e.g. full-width Ａ -> A, ﬁ -> fi)
+    Nfkc,
+}
+
+/// Format of the changes printed by diff and --dry-run
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffFormat {
+    /// `old -> new  (id)` table
+    #[default]
+    Table,
+    /// Unified diff with one hunk per change, headed by the ID of the item
+    Unified,
+}
+
+/// Options for the exported file
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// Append the ID of each item's guild as a `guild:<ID>` column so the file can be applied to several guilds at once
+    #[clap(long)]
+    pub guild_column: bool,
+    /// Format of the exported file. The formats other than tsv write one record (id, name, kind, parent, position) per channel and cannot be applied back
+    #[clap(long, value_enum, value_name = "FORMAT", default_value_t = ExportFormat::Tsv)]
+    pub format: ExportFormat,
+}
+
+/// Format of the exported file
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The tab-separated format that the editor shows and apply reads
+    Tsv,
+    /// JSON array of channel records
+    Json,
+    /// YAML sequence of channel records
+    Yaml,
+    /// CSV with a header row
+    Csv,
+}
+
+impl ExportFormat {
+    /// 拡張子から形式を判定する。圧縮形式の拡張子は除いて判定する
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let path = match path.extension().and_then(|e| e.to_str()) {
+            Some("gz" | "zst") => Path::new(path.file_stem()?),
+            _ => path,
+        };
+        match path.extension()?.to_str()? {
+            "json" => Some(ExportFormat::Json),
+            "yaml" | "yml" => Some(ExportFormat::Yaml),
+            "csv" => Some(ExportFormat::Csv),
+            "tsv" | "txt" => Some(ExportFormat::Tsv),
+            _ => None,
+        }
+    }
+}
+
+/// Options for the editor session
+#[derive(clap::Args, Debug)]
+pub struct EditorArgs {
+    /// Editor command, with arguments quoted as in a shell (e.g. "code --wait"). Defaults to `editor` in the config file, $VISUAL, $EDITOR, then vi
+    #[clap(long, value_name = "COMMAND")]
+    pub editor: Option<String>,
+    /// Keep the temporary file passed to the editor instead of deleting it (for debugging)
+    #[clap(long)]
+    pub keep_tempfile: bool,
+    /// Directory to create the temporary file in. Defaults to $EDISCH_TMPDIR, `tmpdir` in the config file, or the system temp directory
+    #[clap(long, value_name = "DIR")]
+    pub tmpdir: Option<PathBuf>,
+    /// Extension of the temporary file, so that the editor can pick a syntax (e.g. tsv). Defaults to `tempfile_suffix` in the config file, or edisch
+    #[clap(long, value_name = "EXT")]
+    pub tempfile_suffix: Option<String>,
+    /// Also save the edited buffer to the recovery file when declining to apply the changes
+    #[clap(long)]
+    pub save_on_decline: bool,
+    /// Choose the items to put in the buffer from a checklist before opening the editor
+    #[clap(long)]
+    pub pick: bool,
+}
+
+/// Filters of the change history
+#[derive(clap::Args, Debug)]
+pub struct HistoryArgs {
+    /// Only show the changes in the guild with this ID or alias
+    #[clap(long, value_name = "GUILD")]
+    pub guild: Option<String>,
+    /// Only show the changes to the item with this ID (repeatable)
+    #[clap(long, value_name = "ID")]
+    pub channel: Vec<ChannelId>,
+    /// Only show the changes applied within this long, e.g. 7d (units: s, m, h, d, w)
+    #[clap(long, value_name = "AGE", value_parser = check_age)]
+    pub since: Option<String>,
+    /// Only show the changes whose old or new value matches the regex
+    #[clap(long, value_name = "REGEX")]
+    pub grep: Option<String>,
+    /// Only show the last N matching changes
+    #[clap(long, value_name = "N")]
+    pub last: Option<usize>,
+    /// Output format (json prints one record per line)
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Rewrite the names without an editor
+#[derive(clap::Args, Debug, Default)]
+pub struct TransformArgs {
+    /// Rewrite every listed name with a sed-style substitution instead of opening the editor, e.g. `s/^temp-/archived-/` (flags: g, i; `\1` and `&` in the replacement). Repeatable, applied in order
+    #[clap(long, value_name = "s/REGEX/REPLACEMENT/FLAGS", value_parser = check_replace)]
+    pub replace: Vec<String>,
+    /// Remove this prefix from the names that start with it
+    #[clap(long, value_name = "PREFIX")]
+    pub strip_prefix: Option<String>,
+    /// Change the case of the names
+    #[clap(long, value_enum, value_name = "CASE")]
+    pub case: Option<NameCase>,
+    /// Add this prefix to the names that do not start with it yet
+    #[clap(long, value_name = "PREFIX")]
+    pub prefix: Option<String>,
+    /// Add this suffix to the names that do not end with it yet
+    #[clap(long, value_name = "SUFFIX")]
+    pub suffix: Option<String>,
+    /// Manage the emoji at the start of the names: `set EMOJI` replaces it (or adds it), `add EMOJI` only adds it to the names without one, `strip` removes it
+    #[clap(long, num_args = 1..=2, value_names = ["ACTION", "EMOJI"])]
+    pub emoji_prefix: Vec<String>,
+    /// Separator written between the emoji prefix and the name (the usual separators are removed with the old emoji)
+    #[clap(
+        long,
+        value_name = "SEP",
+        default_value = "",
+        requires = "emoji_prefix"
+    )]
+    pub emoji_separator: String,
+    /// Send the names to this shell command on stdin, one per line, and use the lines it prints as the new names, e.g. `tr A-Z a-z`
+    #[clap(
+        long,
+        value_name = "COMMAND",
+        conflicts_with_all = ["replace", "strip_prefix", "case", "prefix", "suffix", "emoji_prefix"]
+    )]
+    pub pipe: Option<String>,
+}
+
+impl TransformArgs {
+    /// 変換が一つも指定されていないか
+    pub fn none(&self) -> bool {
+        self.replace.is_empty()
+            && self.strip_prefix.is_none()
+            && self.case.is_none()
+            && self.prefix.is_none()
+            && self.suffix.is_none()
+            && self.emoji_prefix.is_empty()
+            && self.pipe.is_none()
+    }
+}
+
+/// アーカイブの設定
+#[derive(Debug, Clone)]
+pub struct ArchiveOptions {
+    /// 移動先のカテゴリ
+    pub to_category: ChannelId,
+    /// 名前に付ける接頭辞
+    pub prefix: Option<String>,
+    /// @everyone の書き込みを禁止するか
+    pub lock: bool,
+    /// 確認せずに適用するか
+    pub yes: bool,
+    /// 表示のみ行うか
+    pub dry_run: bool,
+}
+
+/// Case to convert the names to
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameCase {
+    /// `words-joined-by-hyphens` (also splits camelCase and snake_case)
+    Kebab,
+    /// All lowercase
+    Lower,
+    /// `Each Word Capitalized`, joined by spaces
+    Title,
+}
+
+/// --replace の値を検証する
+fn check_replace(text: &str) -> Result<String, String> {
+    crate::transform::Transform::parse_replace(text).map(|_| text.to_string())
 }
 
 /// Parsed arguments for program execution
@@ -139,50 +1334,367 @@ pub enum Work {
         io: IOMode,
         /// Apply confirmation arguments
         apply: Option<ApplyArgs>,
+        /// What to edit
+        target: Target,
+    },
+    /// Apply a file every time it is saved
+    Watch {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// File to watch
+        input: PathBuf,
+        /// Poll the file instead of using file system notifications
+        poll: bool,
+        /// Apply confirmation arguments
+        apply: ApplyArgs,
+        /// Which property of the channels the file contains
+        field: ChannelField,
+        /// How the channels are listed
+        view: ChannelView,
+    },
+    /// Make the guild match an exported structure
+    Structure {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Input file or Stdin
+        input: Option<PathBuf>,
+        /// Automatically confirm all changes
+        yes: bool,
+    },
+    /// Copy the structure of a guild to another
+    Copy {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Guild to copy from
+        from: String,
+        /// Guild to copy to
+        to: String,
+        /// Automatically confirm all changes
+        yes: bool,
+    },
+    /// Converge the guild to a desired-state file
+    Sync {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Desired-state file
+        file: PathBuf,
+        /// Delete what is not in the file
+        prune: bool,
+        /// Automatically confirm all changes
+        yes: bool,
+        /// Only print the plan
+        dry_run: bool,
+    },
+    /// Sort the channels of each category by name
+    Sort {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Automatically confirm all changes
+        yes: bool,
+        /// Only print the moves
+        dry_run: bool,
+    },
+    /// Archive the filtered channels
+    Archive {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Filter channels arguments
+        filter: ChannelFilterArgs,
+        /// Archive options
+        options: ArchiveOptions,
+    },
+    /// Print the channels of the guild
+    List {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Filter channels arguments
+        filter: ChannelFilterArgs,
+        /// Print as a tree
+        tree: bool,
+    },
+    /// List the guilds the token has access to
+    Guilds {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Output format
+        format: OutputFormat,
+    },
+    /// Compare an exported file with the guild
+    Status {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Input file or Stdin
+        input: Option<PathBuf>,
+    },
+    /// Set up the config file interactively
+    Init,
+    /// Check the setup
+    Doctor(ConnectionArgs),
+    /// Manage channel permission overwrites
+    Perms(PermsCommand),
+    /// Store or remove the token in the OS keyring
+    Auth(AuthCommand),
+    /// Export or import a template of the channel layout
+    Template(TemplateCommand),
+    /// Manage snapshots of the guild
+    Snapshot {
+        /// Snapshot command
+        command: SnapshotCommand,
+        /// Which property of the channels to save
+        field: ChannelField,
+    },
+    /// Browse the history of applied changes
+    History(HistoryArgs),
+    /// Revert the renames of the last apply
+    Undo {
+        /// Discord connection arguments
+        discord: ConnectionArgs,
+        /// Apply confirmation arguments
+        apply: ApplyArgs,
     },
     /// Generate shell completion
     Completion(Shell),
 }
 
+/// What to edit
+pub enum Target {
+    /// A property of the channels of the guild, and how they are listed
+    Channels(ChannelField, ChannelView),
+    /// Settings of the guild itself
+    Guild,
+    /// Follows of announcement channels into a channel
+    Follows {
+        /// Channel that receives the followed announcements
+        target: u64,
+        /// Only list the current follows
+        list: bool,
+    },
+    /// Available tags of the forum channels of the guild
+    ForumTags,
+    /// Names of the threads of the guild
+    Threads {
+        /// Also list the archived threads
+        archived: bool,
+    },
+    /// Names of the webhooks of the guild
+    Webhooks,
+    /// Names of the roles of the guild
+    Roles {
+        /// Also list the roles managed by bots and integrations
+        include_managed: bool,
+    },
+    /// Icons of the roles of the guild
+    RoleIcons,
+    /// Custom emojis of the guild
+    Emojis,
+    /// A property of the stickers of the guild
+    Stickers(StickerField),
+    /// Soundboard sounds of the guild
+    Sounds,
+    /// Onboarding prompts and options of the guild
+    Onboarding,
+    /// Welcome screen of the guild
+    Welcome,
+    /// AutoMod rules of the guild
+    Automod {
+        /// Edit the keyword lists instead of the names
+        keywords: bool,
+    },
+}
+
 /// Input/Output files or Editor mode
 pub enum IOMode {
     /// Some Input file or Stdin
     Input(Option<PathBuf>),
     /// Some Output file or Stdout
-    Output(Option<PathBuf>),
+    Output(Option<PathBuf>, ExportArgs),
     /// Editor mode
-    Editor,
+    Editor(EditorArgs),
+    /// Full-screen terminal UI
+    Tui,
+    /// Rewrite the texts with transforms without an editor
+    Transform(Box<TransformArgs>),
+    /// Rewrite names by the naming policy without an editor
+    Fix,
 }
 
 impl From<Args> for Work {
     fn from(val: Args) -> Self {
+        let comments = match (&val.comment_format, val.no_comments) {
+            (_, true) => CommentFormat::Hidden,
+            (Some(template), false) => CommentFormat::Template(template.clone()),
+            (None, false) => CommentFormat::Default,
+        };
+        let view = ChannelView {
+            comments,
+            sort: val.sort,
+            reverse: val.reverse,
+            limit: val.limit,
+            offset: val.offset,
+        };
         match val {
             Args {
                 subcommand: None,
                 discord,
                 filter,
                 apply,
+                editor,
+                transform,
+                field,
+                ..
             } => Work::Edit {
                 discord,
                 filter,
-                io: IOMode::Editor,
+                io: if transform.none() {
+                    IOMode::Editor(editor)
+                } else {
+                    IOMode::Transform(Box::new(transform))
+                },
                 apply: Some(apply),
+                target: Target::Channels(field, view),
             },
-            args => match args.subcommand.unwrap() {
+            Args {
+                subcommand: Some(subcommand),
+                field,
+                ..
+            } => match subcommand {
                 Commands::Completion { shell } => Work::Completion(shell),
-                Commands::Export { discord, output } => Work::Edit {
+                Commands::Perms { command } => Work::Perms(command),
+                Commands::Auth { command } => Work::Auth(command),
+                Commands::Template { command } => Work::Template(command),
+                Commands::List {
+                    discord,
+                    filter,
+                    tree,
+                } => Work::List {
+                    discord,
+                    filter,
+                    tree,
+                },
+                Commands::Guilds { discord, format } => Work::Guilds { discord, format },
+                Commands::Status { discord, input } => Work::Status { discord, input },
+                Commands::Init => Work::Init,
+                Commands::Doctor { discord } => Work::Doctor(discord),
+                Commands::Copy {
+                    discord,
+                    from,
+                    to,
+                    yes,
+                } => Work::Copy {
+                    discord,
+                    from,
+                    to,
+                    yes,
+                },
+                Commands::Sync {
+                    discord,
+                    file,
+                    prune,
+                    yes,
+                    dry_run,
+                } => Work::Sync {
+                    discord,
+                    file,
+                    prune,
+                    yes,
+                    dry_run,
+                },
+                Commands::Sort {
+                    discord,
+                    yes,
+                    dry_run,
+                } => Work::Sort {
+                    discord,
+                    yes,
+                    dry_run,
+                },
+                Commands::Archive {
+                    discord,
+                    filter,
+                    to_category,
+                    prefix,
+                    lock,
+                    yes,
+                    dry_run,
+                } => Work::Archive {
+                    discord,
+                    filter,
+                    options: ArchiveOptions {
+                        to_category: ChannelId::new(to_category),
+                        prefix,
+                        lock,
+                        yes,
+                        dry_run,
+                    },
+                },
+                Commands::Structure {
+                    discord,
+                    input,
+                    yes,
+                } => Work::Structure {
+                    discord,
+                    input,
+                    yes,
+                },
+                Commands::Export {
+                    discord,
+                    output,
+                    channel,
+                    export,
+                } => Work::Edit {
                     discord,
                     filter: ChannelFilterArgs {
                         all: true,
+                        channel,
                         ..Default::default()
                     },
-                    io: IOMode::Output(output),
+                    io: IOMode::Output(output, export),
                     apply: None,
+                    target: Target::Channels(field, view),
                 },
                 Commands::Apply {
                     discord,
                     input,
-                    apply,
+                    resume,
+                    channel,
+                    format,
+                    mut apply,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs {
+                        all: true,
+                        channel,
+                        ..Default::default()
+                    },
+                    io: IOMode::Input(resume.or(input)),
+                    apply: Some({
+                        apply.input_format = format;
+                        apply
+                    }),
+                    target: Target::Channels(field, view),
+                },
+                Commands::Watch {
+                    discord,
+                    input,
+                    format,
+                    poll,
+                    mut apply,
+                } => Work::Watch {
+                    discord,
+                    input,
+                    poll,
+                    apply: {
+                        apply.input_format = format;
+                        apply
+                    },
+                    field,
+                    view,
+                },
+                Commands::Diff {
+                    discord,
+                    input,
+                    format,
+                    output_format,
                 } => Work::Edit {
                     discord,
                     filter: ChannelFilterArgs {
@@ -190,7 +1702,193 @@ impl From<Args> for Work {
                         ..Default::default()
                     },
                     io: IOMode::Input(input),
+                    apply: Some(ApplyArgs {
+                        dry_run: true,
+                        input_format: format,
+                        output_format,
+                        ..Default::default()
+                    }),
+                    target: Target::Channels(field, view),
+                },
+                Commands::Guild {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Guild,
+                },
+                Commands::Follows {
+                    discord,
+                    target,
+                    list,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Follows { target, list },
+                },
+                Commands::ForumTags {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::ForumTags,
+                },
+                Commands::Threads {
+                    discord,
+                    archived,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Threads { archived },
+                },
+                Commands::Webhooks {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Webhooks,
+                },
+                Commands::Roles {
+                    discord,
+                    include_managed,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Roles { include_managed },
+                },
+                Commands::RoleIcons {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::RoleIcons,
+                },
+                Commands::Emojis {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Emojis,
+                },
+                Commands::Stickers {
+                    discord,
+                    property,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Stickers(property),
+                },
+                Commands::Sounds {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Sounds,
+                },
+                Commands::Automod {
+                    discord,
+                    keywords,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Automod { keywords },
+                },
+                Commands::Onboarding {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Onboarding,
+                },
+                Commands::Welcome {
+                    discord,
+                    apply,
+                    editor,
+                } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs::default(),
+                    io: IOMode::Editor(editor),
+                    apply: Some(apply),
+                    target: Target::Welcome,
+                },
+                Commands::Snapshot { command } => Work::Snapshot { command, field },
+                Commands::Undo { discord, apply } => Work::Undo { discord, apply },
+                Commands::History(args) => Work::History(args),
+                Commands::Tui {
+                    discord,
+                    filter,
+                    apply,
+                } => Work::Edit {
+                    discord,
+                    filter: if filter.none() {
+                        ChannelFilterArgs {
+                            all: true,
+                            ..filter
+                        }
+                    } else {
+                        filter
+                    },
+                    io: IOMode::Tui,
+                    apply: Some(apply),
+                    target: Target::Channels(field, view),
+                },
+                Commands::Fix { discord, apply } => Work::Edit {
+                    discord,
+                    filter: ChannelFilterArgs {
+                        all: true,
+                        ..Default::default()
+                    },
+                    io: IOMode::Fix,
                     apply: Some(apply),
+                    target: Target::Channels(field, view),
                 },
             },
         }
@@ -0,0 +1,103 @@
+use crate::{
+    config,
+    error::{Error, Result},
+    i18n::tr,
+};
+use console::Term;
+use dialoguer::Password;
+use keyring::Entry;
+use std::{
+    fs,
+    io::{stdin, IsTerminal},
+    path::Path,
+};
+use tracing::info;
+
+/// キーリングに保存する際のサービス名
+const SERVICE: &str = "edisch";
+
+/// 選ばれたプロファイル (なければ default) のキーリングの項目
+fn entry() -> Result<Entry> {
+    let account = config::profile_name().unwrap_or_else(|| "default".to_string());
+    Entry::new(SERVICE, &account).map_err(keyring_error)
+}
+
+fn keyring_error(e: keyring::Error) -> Error {
+    Error::Keyring(e.to_string().into())
+}
+
+/// キーリングに保存されたトークン。保存されていない場合や、キーリングを使えない環境では None
+pub fn stored_token() -> Option<String> {
+    entry()
+        .ok()?
+        .get_password()
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+/// トークンをキーリングに保存する。端末では入力を表示せずに尋ね、そうでなければ標準入力の1行目を使う
+pub fn set() -> Result<()> {
+    let token = if stdin().is_terminal() {
+        Password::new()
+            .with_prompt(tr!("prompt-token"))
+            .interact()?
+    } else {
+        let mut line = String::new();
+        stdin().read_line(&mut line)?;
+        line.trim().to_string()
+    };
+    if token.is_empty() {
+        return Err(Error::MissingArgument("token".into()));
+    }
+    entry()?.set_password(&token).map_err(keyring_error)?;
+    info!("Stored the token in the keyring");
+    Ok(())
+}
+
+/// キーリングからトークンを削除する
+pub fn clear() -> Result<()> {
+    match entry()?.delete_credential() {
+        Ok(()) => info!("Removed the token from the keyring"),
+        Err(keyring::Error::NoEntry) => info!("No token is stored in the keyring"),
+        Err(e) => return Err(keyring_error(e)),
+    }
+    Ok(())
+}
+
+/// --token-file のトークンを読み込む。Unixでは、所有者以外が読めるファイルは拒否する
+pub fn read_token_file(path: &Path) -> Result<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Err(Error::ParseArgument(
+                format!(
+                    "--token-file {} is accessible by other users (mode {:o}); run `chmod 600` on it",
+                    path.display(),
+                    mode & 0o777
+                )
+                .into(),
+            ));
+        }
+    }
+    let token = fs::read_to_string(path)?.trim().to_string();
+    if token.is_empty() {
+        return Err(Error::ParseArgument(
+            format!("--token-file {} is empty", path.display()).into(),
+        ));
+    }
+    Ok(token)
+}
+
+/// トークンがどこからも得られない場合に、端末であれば入力を表示せずに尋ねる
+pub fn prompt_token() -> Result<Option<String>> {
+    if !stdin().is_terminal() || !Term::stderr().is_term() {
+        return Ok(None);
+    }
+    let token = Password::new()
+        .with_prompt(tr!("prompt-token"))
+        .allow_empty_password(true)
+        .interact()?;
+    Ok(Some(token).filter(|token| !token.is_empty()))
+}
@@ -0,0 +1,139 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::{GuildId, Rule, Trigger};
+use std::{fmt::Display, sync::Arc};
+
+/// キーワードの最大数
+const MAX_KEYWORDS: usize = 1000;
+
+/// キーワード1つの最大文字数
+const MAX_KEYWORD_LENGTH: usize = 60;
+
+/// AutoModルールの名前またはキーワード一覧
+pub struct AutomodItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// ルール
+    rule: Rule,
+    /// 名前ではなくキーワード一覧を編集するか
+    keywords: bool,
+}
+
+/// 1行1キーワードのテキストを読み取る。空行は無視する
+fn parse_keywords(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl Display for AutomodItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rule.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for AutomodItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let edit = if self.keywords {
+            let Trigger::Keyword {
+                regex_patterns,
+                allow_list,
+                ..
+            } = &self.rule.trigger
+            else {
+                return Err(Error::UnsupportedOperation("not a keyword rule".into()));
+            };
+            // メタデータは丸ごと置き換わるため、他の項目も送る
+            serde_json::json!({
+                "trigger_metadata": {
+                    "keyword_filter": parse_keywords(&content),
+                    "regex_patterns": regex_patterns,
+                    "allow_list": allow_list,
+                }
+            })
+        } else {
+            serde_json::json!({ "name": content })
+        };
+        self.rule = self
+            .discord
+            .edit_automod_rule(self.rule.guild_id, self.rule.id, &edit)
+            .await?;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        match (&self.rule.trigger, self.keywords) {
+            (Trigger::Keyword { strings, .. }, true) => strings.join("\n"),
+            _ => self.rule.name.clone(),
+        }
+    }
+    fn comment(&self) -> String {
+        let state = if self.rule.enabled { "" } else { " (disabled)" };
+        if self.keywords {
+            format!("🛡️ {}{state} ({})", self.rule.name, self.rule.id)
+        } else {
+            format!("🛡️{state} ({})", self.rule.id)
+        }
+    }
+    fn multiline(&self) -> bool {
+        self.keywords
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if !self.keywords {
+            if !(1..=100).contains(&new.chars().count()) {
+                return Err(Error::InvalidEditResult(
+                    format!("rule name must be between 1 and 100 characters: {new:?}").into(),
+                ));
+            }
+            return Ok(());
+        }
+        let keywords = parse_keywords(new);
+        if keywords.len() > MAX_KEYWORDS {
+            return Err(Error::InvalidEditResult(
+                format!(
+                    "{}: at most {MAX_KEYWORDS} keywords are allowed, got {}",
+                    self.rule.name,
+                    keywords.len()
+                )
+                .into(),
+            ));
+        }
+        if let Some(keyword) = keywords
+            .iter()
+            .find(|keyword| keyword.chars().count() > MAX_KEYWORD_LENGTH)
+        {
+            return Err(Error::InvalidEditResult(
+                format!("keyword must be at most {MAX_KEYWORD_LENGTH} characters: {keyword:?}")
+                    .into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのAutoModルールを取得する。
+/// キーワード一覧を編集する場合は、キーワードで判定するルールのみを対象にする
+pub async fn fetch_items(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    keywords: bool,
+) -> Result<Vec<AutomodItem>> {
+    Ok(discord
+        .guild_automod_rules(guild_id)
+        .await?
+        .into_iter()
+        .filter(|rule| !keywords || matches!(rule.trigger, Trigger::Keyword { .. }))
+        .map(|rule| AutomodItem {
+            discord: discord.clone(),
+            rule,
+            keywords,
+        })
+        .collect())
+}
@@ -0,0 +1,108 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serenity::all::{GuildChannel, GuildId};
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, warn};
+
+/// 取得したチャンネル一覧を保存するディレクトリ。$EDISCH_CACHE_DIR で上書きできる
+pub fn dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("EDISCH_CACHE_DIR") {
+        return Some(dir.into());
+    }
+    dirs::cache_dir().map(|dir| dir.join("edisch").join("channels"))
+}
+
+/// キャッシュファイルの内容
+#[derive(Serialize, Deserialize)]
+struct Cache {
+    /// 取得した時刻 (UNIX時間の秒)
+    fetched_at: u64,
+    channels: Vec<GuildChannel>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Guildのチャンネル一覧のキャッシュを、取得してからの経過時間とともに読み込む。
+/// キャッシュがない、または読み込めない場合は None
+pub fn load(guild_id: GuildId) -> Result<Option<(Duration, Vec<GuildChannel>)>> {
+    let Some(path) = dir().map(|dir| dir.join(format!("{guild_id}.json"))) else {
+        return Ok(None);
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    match serde_json::from_str::<Cache>(&text) {
+        Ok(cache) => {
+            let age = Duration::from_secs(now().saturating_sub(cache.fetched_at));
+            Ok(Some((age, cache.channels)))
+        }
+        Err(e) => {
+            // 古い形式などで読めないキャッシュは、取得し直して上書きする
+            warn!(
+                "Ignoring the unreadable channel cache {}: {e}",
+                path.display()
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// キャッシュの古さを `3d 4h` のように、大きい方から2つの単位で表示する
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let units = [
+        (secs / 86400, "d"),
+        (secs / 3600 % 24, "h"),
+        (secs / 60 % 60, "m"),
+        (secs % 60, "s"),
+    ];
+    let Some(first) = units.iter().position(|(n, _)| *n > 0) else {
+        return "0s".to_string();
+    };
+    units[first..]
+        .iter()
+        .take(2)
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{n}{unit}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Guildのチャンネル一覧をキャッシュに書き込む。
+/// 失敗しても取得した一覧は使えるため、警告のみ表示する
+pub fn store(guild_id: GuildId, channels: &[GuildChannel]) {
+    let Some(dir) = dir() else {
+        return;
+    };
+    let path = dir.join(format!("{guild_id}.json"));
+    let cache = Cache {
+        fetched_at: now(),
+        channels: channels.to_vec(),
+    };
+    let result = fs::create_dir_all(&dir).and_then(|()| {
+        let json = serde_json::to_string(&cache)?;
+        fs::write(&path, json)?;
+        // チャンネルの権限設定などを含むため、他のユーザーから読めないようにする
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => debug!("Cached {} channels in {}", channels.len(), path.display()),
+        Err(e) => warn!("Failed to cache the channel list: {e}"),
+    }
+}
@@ -0,0 +1,1417 @@
+use crate::{
+    args::{parse_age, ChannelField, ChannelFilterArgs, ChannelSort, CommentFormat},
+    discord::{ChannelApi, Discord},
+    error::{Error, Result},
+    format::GUILD_PREFIX,
+    i18n::tr,
+    policy::Policy,
+    progress::{self, Progress},
+    unicode,
+};
+use edisch_core::{async_trait, changed_columns, Op, TextEditableItem};
+use futures::{future::try_join_all, try_join};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use serenity::all::{
+    ChannelId, ChannelType, CreateChannel, EditChannel, GuildChannel, GuildId, PartialGuild,
+    PremiumTier,
+};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::{Arc, LazyLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::info;
+
+/// カテゴリ名として使用できる文字列
+static CATEGORY_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\-\w]*|[^\x00-\x7F ]*$").unwrap());
+/// カテゴリ以外のチャンネル名として使用できる文字列
+static CHANNEL_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\-\w]*|[^\x00-\x7F]*$").unwrap());
+
+/// 上限 (100文字) に近いとして警告するチャンネル名の長さ
+const NEAR_NAME_LIMIT: usize = 90;
+
+/// コメント列でチャンネルの種類を表す絵文字
+const KIND_EMOJIS: [(ChannelType, char); 6] = [
+    (ChannelType::Text, '📝'),
+    (ChannelType::Voice, '🔊'),
+    (ChannelType::Category, '📁'),
+    (ChannelType::News, '📣'),
+    (ChannelType::Forum, '💬'),
+    (ChannelType::Stage, '🎭'),
+];
+
+/// チャンネルの種類を表す絵文字
+pub fn kind_emoji(kind: ChannelType) -> char {
+    KIND_EMOJIS
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map_or('❓', |(_, emoji)| *emoji)
+}
+
+/// 絵文字が表すチャンネルの種類
+pub fn kind_from_emoji(emoji: char) -> Option<ChannelType> {
+    KIND_EMOJIS
+        .iter()
+        .find(|(_, e)| *e == emoji)
+        .map(|(kind, _)| *kind)
+}
+
+/// `--comment-format` で使えるプレースホルダ
+const PLACEHOLDERS: [&str; 8] = [
+    "kind", "type", "name", "category", "guild", "topic", "position", "id",
+];
+
+/// テンプレートの `{name}` などのプレースホルダを値で置き換える。
+/// 値が空の場合は、空白が重ならないよう直後の空白を詰める
+fn expand_template(template: &str, value: impl Fn(&str) -> String) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            return Err(Error::ParseArgument(
+                "unclosed \"{\" in --comment-format".into(),
+            ));
+        };
+        let key = &rest[start + 1..start + len];
+        if !PLACEHOLDERS.contains(&key) {
+            return Err(Error::ParseArgument(
+                format!(
+                    "unknown placeholder {{{key}}} in --comment-format (available: {})",
+                    PLACEHOLDERS.map(|key| format!("{{{key}}}")).join(", ")
+                )
+                .into(),
+            ));
+        }
+        let value = value(key);
+        rest = &rest[start + len + 1..];
+        if value.is_empty() && (expanded.is_empty() || expanded.ends_with(' ')) {
+            rest = rest.trim_start_matches(' ');
+        }
+        expanded.push_str(&value);
+    }
+    expanded.push_str(rest);
+    Ok(expanded.trim_end().to_string())
+}
+
+/// `--comment-format` のテンプレートを検証する
+pub fn check_comment_format(template: &str) -> Result<()> {
+    expand_template(template, |_| String::new()).map(|_| ())
+}
+
+/// 種類と名前の条件 (--match, --exclude)、カテゴリ (--in-category)、ID (--channel) によるチャンネルの絞り込み
+pub struct ChannelMatcher<'a> {
+    filter: &'a ChannelFilterArgs,
+    matches: Vec<Regex>,
+    exclude: Vec<Regex>,
+    /// --in-category で指定したカテゴリのID。指定がなければ None
+    categories: Option<HashSet<ChannelId>>,
+    /// --inactive-for で、これより後 (UNIX時間) に活動のあったチャンネルを除く
+    inactive_since: Option<i64>,
+}
+
+impl<'a> ChannelMatcher<'a> {
+    pub fn new(filter: &'a ChannelFilterArgs) -> Result<Self> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Regex::new(pattern).map_err(|e| {
+                        Error::ParseArgument(format!("invalid regex {pattern:?}: {e}").into())
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            filter,
+            matches: compile(&filter.matches)?,
+            exclude: compile(&filter.exclude)?,
+            categories: None,
+            inactive_since: match &filter.inactive_for {
+                Some(age) => {
+                    let age = parse_age(age).map_err(|e| Error::ParseArgument(e.into()))?;
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default();
+                    Some(now.saturating_sub(age).as_secs() as i64)
+                }
+                None => None,
+            },
+        })
+    }
+
+    /// --in-category で名前またはIDを指定したカテゴリを、Guildのチャンネルから探す
+    pub fn resolve_categories(&mut self, channels: &[GuildChannel]) -> Result<()> {
+        if self.filter.in_category.is_empty() {
+            return Ok(());
+        }
+        let mut categories = HashSet::new();
+        for target in &self.filter.in_category {
+            let found: Vec<_> = channels
+                .iter()
+                .filter(|channel| channel.kind == ChannelType::Category)
+                .filter(|channel| channel.name == *target || channel.id.to_string() == *target)
+                .map(|channel| channel.id)
+                .collect();
+            if found.is_empty() {
+                return Err(Error::ParseArgument(
+                    format!("category not found: {target:?}").into(),
+                ));
+            }
+            categories.extend(found);
+        }
+        self.categories = Some(categories);
+        Ok(())
+    }
+
+    /// 最後のメッセージの時刻 (UNIX時間)。メッセージがなければ作成された時刻とする。
+    /// 最後のメッセージのIDから求めるため、メッセージを取得する必要はない
+    fn last_activity(channel: &GuildChannel) -> i64 {
+        match channel.last_message_id {
+            Some(id) => id.created_at().unix_timestamp(),
+            None => channel.id.created_at().unix_timestamp(),
+        }
+    }
+
+    /// 条件に合うチャンネルか。条件が何も指定されていなければ全て合う
+    pub fn matches(&self, channel: &GuildChannel) -> bool {
+        // カテゴリにはメッセージがない
+        if let Some(since) = self.inactive_since {
+            if channel.kind == ChannelType::Category || Self::last_activity(channel) >= since {
+                return false;
+            }
+        }
+        let in_category = self.categories.as_ref().is_none_or(|categories| {
+            categories.contains(&channel.id)
+                || channel.parent_id.is_some_and(|id| categories.contains(&id))
+        });
+        in_category
+            && (self.filter.channel.is_empty() || self.filter.channel.contains(&channel.id))
+            && (self.filter.none() || self.filter & channel.kind)
+            && (self.matches.is_empty() || self.matches.iter().any(|re| re.is_match(&channel.name)))
+            && !self.exclude.iter().any(|re| re.is_match(&channel.name))
+    }
+}
+
+/// `--sort` の順にチャンネルを並べ替える。position の順は [`fetch_items`] で並べた順のまま
+pub fn sort_items(items: &mut [ChannelItem], sort: ChannelSort) {
+    match sort {
+        ChannelSort::Position => {}
+        ChannelSort::Name => {
+            items.sort_by_cached_key(|item| (item.channel.name.to_lowercase(), item.channel_id))
+        }
+        ChannelSort::Id => items.sort_by_key(|item| item.channel_id),
+        ChannelSort::Created => {
+            items.sort_by_key(|item| (*item.channel_id.created_at(), item.channel_id))
+        }
+    }
+}
+
+/// `--field columns` で編集する列
+const COLUMNS: [&str; 4] = ["name", "topic", "slowmode", "nsfw"];
+
+/// `--field voice` で編集する列
+const VOICE_COLUMNS: [&str; 2] = ["bitrate", "user limit"];
+
+/// ステージチャンネルの最大ビットレート (kbps)
+const STAGE_MAX_BITRATE: u32 = 64;
+
+#[derive(Clone)]
+pub struct ChannelItem {
+    /// Discord APIクライアント
+    discord: Arc<dyn ChannelApi>,
+
+    /// チャンネル情報
+    channel: GuildChannel,
+    /// チャンネルID
+    channel_id: ChannelId,
+    /// 編集する項目
+    field: ChannelField,
+
+    /// 親チャンネルの名前。同じカテゴリのチャンネルで共有する
+    parent_name: Option<Arc<str>>,
+    /// 所属するカテゴリのposition
+    category_position: u16,
+    /// Guildの全カテゴリの索引 (名前, position)
+    categories: Arc<HashMap<ChannelId, (Arc<str>, u16)>>,
+    /// 命名ポリシー
+    policy: Arc<Policy>,
+    /// Guildのブーストレベルで使えるボイスチャンネルの最大ビットレート (kbps)
+    max_bitrate: u32,
+    /// 複数のGuildをまとめて編集する場合の、所属するGuildの名前
+    guild_name: Option<String>,
+    /// コメントの書式
+    comment_format: Arc<CommentFormat>,
+}
+
+/// `export --format` で書き出すチャンネルの情報
+#[derive(Serialize, Debug)]
+pub struct ChannelRecord {
+    id: String,
+    name: String,
+    kind: String,
+    /// 親カテゴリの名前
+    parent: Option<String>,
+    parent_id: Option<String>,
+    position: u16,
+    guild_id: String,
+    /// --field で名前以外の項目を指定した場合の、その値
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+impl ChannelRecord {
+    /// CSVなどの列名
+    pub fn columns(with_value: bool) -> Vec<&'static str> {
+        let mut columns = vec![
+            "id",
+            "name",
+            "kind",
+            "parent",
+            "parent_id",
+            "position",
+            "guild_id",
+        ];
+        if with_value {
+            columns.push("value");
+        }
+        columns
+    }
+
+    /// 列の順に並べた値
+    pub fn cells(&self) -> Vec<serde_json::Value> {
+        let mut cells = vec![
+            self.id.clone().into(),
+            self.name.clone().into(),
+            self.kind.clone().into(),
+            self.parent.clone().into(),
+            self.parent_id.clone().into(),
+            self.position.into(),
+            self.guild_id.clone().into(),
+        ];
+        if let Some(value) = &self.value {
+            cells.push(value.clone().into());
+        }
+        cells
+    }
+}
+
+/// `export --format` で書き出したレコードを、IDをコメントに持つ `apply` の行に変換する
+pub fn lines_from_records(
+    records: &[serde_json::Map<String, serde_json::Value>],
+    field: ChannelField,
+) -> Result<String> {
+    let key = if field == ChannelField::Name {
+        "name"
+    } else {
+        "value"
+    };
+    // 文字列・数値・真偽値のいずれで書かれていても読めるようにする
+    let text = |record: &serde_json::Map<_, _>, key: &str| match record.get(key) {
+        Some(serde_json::Value::String(s)) => Some(s.clone()),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        // YAMLでは引用符のない true などが真偽値になる
+        Some(serde_json::Value::Bool(b)) => Some(b.to_string()),
+        _ => None,
+    };
+    let mut lines = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        let missing = |key: &str| {
+            Error::InvalidEditResult(format!("record {}: missing {key:?}", i + 1).into())
+        };
+        let id = text(record, "id").ok_or_else(|| missing("id"))?;
+        let value = text(record, key).ok_or_else(|| missing(key))?;
+        let mut line = format!("{value}\t({id})");
+        if let Some(guild_id) = text(record, "guild_id") {
+            // 書き出しと同じGuild列にし、適用時にGuildごとに振り分けられるようにする
+            line.push_str(&format!("\t{GUILD_PREFIX}{guild_id}"));
+        }
+        lines.push(line);
+    }
+    Ok(lines.join("\n"))
+}
+
+impl ChannelItem {
+    /// 構造化した形式で書き出すための情報
+    pub fn record(&self) -> ChannelRecord {
+        ChannelRecord {
+            id: self.channel_id.to_string(),
+            name: self.channel.name.clone(),
+            kind: self.channel.kind.name().to_string(),
+            parent: self.parent_name.as_deref().map(str::to_string),
+            parent_id: self.channel.parent_id.map(|id| id.to_string()),
+            position: self.channel.position,
+            guild_id: self.channel.guild_id.to_string(),
+            value: (self.field != ChannelField::Name).then(|| self.content()),
+        }
+    }
+
+    /// 所属するGuildのID
+    pub fn guild_id(&self) -> GuildId {
+        self.channel.guild_id
+    }
+    /// チャンネルのID
+    pub fn channel_id(&self) -> ChannelId {
+        self.channel_id
+    }
+    /// コメントに所属するGuildの名前を添える
+    pub fn with_guild_name(self, name: &str) -> Self {
+        Self {
+            guild_name: Some(name.to_string()),
+            ..self
+        }
+    }
+    /// コメントの書式を指定する
+    pub fn with_comment_format(self, format: &Arc<CommentFormat>) -> Self {
+        Self {
+            comment_format: format.clone(),
+            ..self
+        }
+    }
+    /// `--comment-format` のプレースホルダの値
+    fn placeholder(&self, key: &str) -> String {
+        match key {
+            "kind" => kind_emoji(self.channel.kind).to_string(),
+            "type" => self.channel.kind.name().to_string(),
+            "name" => self.channel.name.clone(),
+            "category" => self.parent_name.as_deref().unwrap_or_default().to_string(),
+            "guild" => self.guild_name.clone().unwrap_or_default(),
+            "topic" => escape_line(self.channel.topic.as_deref().unwrap_or_default()),
+            "position" => self.channel.position.to_string(),
+            // 行とチャンネルを対応づけるキーの形式で書く
+            "id" => format!("({})", self.channel_id),
+            _ => String::new(),
+        }
+    }
+    /// 名前またはIDからカテゴリを探す
+    fn find_category(&self, target: &str) -> Result<ChannelId> {
+        let found: Vec<_> = self
+            .categories
+            .iter()
+            .filter(|(id, (name, _))| &**name == target || id.to_string() == target)
+            .map(|(id, _)| *id)
+            .collect();
+        match found[..] {
+            [id] => Ok(id),
+            [] => Err(Error::InvalidEditResult(
+                format!("category not found: {target:?}").into(),
+            )),
+            _ => Err(Error::InvalidEditResult(
+                format!("ambiguous category name: {target:?}").into(),
+            )),
+        }
+    }
+    /// 親カテゴリの名前とID
+    fn parent(&self) -> Option<(&str, u64)> {
+        Some((self.parent_name.as_deref()?, self.channel.parent_id?.get()))
+    }
+    fn is_no_categoryzed_channel(&self) -> bool {
+        self.channel.kind != ChannelType::Category && self.parent_name.is_none()
+    }
+    fn is_voice_like_channel(&self) -> bool {
+        self.channel.kind == ChannelType::Voice || self.channel.kind == ChannelType::Stage
+    }
+    /// 編集する列の名前。1列の項目の場合は空
+    fn column_names(&self) -> &'static [&'static str] {
+        match self.field {
+            ChannelField::Columns => &COLUMNS,
+            ChannelField::Voice => &VOICE_COLUMNS,
+            _ => &[],
+        }
+    }
+    /// 編集結果を列に分ける。列の数が合わない場合はエラー
+    fn split_columns<'a>(&self, new: &'a str) -> Result<Vec<&'a str>> {
+        let names = self.column_names();
+        let values: Vec<_> = new.split('\t').collect();
+        if values.len() != names.len() {
+            return Err(Error::InvalidEditResult(
+                format!(
+                    "expected {} tab-separated columns ({}): {new:?}",
+                    names.len(),
+                    names.join(", ")
+                )
+                .into(),
+            ));
+        }
+        Ok(values)
+    }
+    /// ビットレート (kbps) を読み取る
+    fn parse_bitrate(&self, text: &str) -> Result<u32> {
+        let max = if self.channel.kind == ChannelType::Stage {
+            STAGE_MAX_BITRATE
+        } else {
+            self.max_bitrate
+        };
+        match text.trim().parse() {
+            Ok(kbps) if (8..=max).contains(&kbps) => Ok(kbps),
+            _ => Err(Error::InvalidEditResult(
+                format!("bitrate must be between 8 and {max} kbps: {text:?}").into(),
+            )),
+        }
+    }
+    /// 人数制限を読み取る。0は制限なし
+    fn parse_user_limit(&self, text: &str) -> Result<u32> {
+        let max = if self.channel.kind == ChannelType::Stage {
+            10000
+        } else {
+            99
+        };
+        match text.trim().parse() {
+            Ok(limit) if limit <= max => Ok(limit),
+            _ => Err(Error::InvalidEditResult(
+                format!("user limit must be between 0 and {max}: {text:?}").into(),
+            )),
+        }
+    }
+    /// チャンネル名を検証する
+    fn validate_name(&self, new: &str) -> Result<()> {
+        self.policy
+            .check_protected(&self.channel.name, &self.channel_id.to_string())?;
+        self.check_name(new, self.channel.kind, self.parent())
+    }
+    /// 指定した種類と親カテゴリのチャンネルの名前として検証する
+    fn check_name(&self, new: &str, kind: ChannelType, parent: Option<(&str, u64)>) -> Result<()> {
+        let len = new.chars().count();
+        if !(2..=100).contains(&len) {
+            return Err(Error::InvalidChannelName {
+                name: new.to_string(),
+                message: "Channel name must be between 2 and 100 characters",
+            });
+        }
+
+        // TODO: 文字種やルールの制限が不十分。
+        let re = if kind == ChannelType::Category {
+            &*CATEGORY_NAME_RE
+        } else {
+            &*CHANNEL_NAME_RE
+        };
+        if !re.is_match(new) || new.contains("--") {
+            return Err(Error::InvalidChannelName {
+                name: new.to_string(),
+                message: "Contains characters or patterns that cannot be used",
+            });
+        }
+
+        self.policy
+            .check_name(new, kind == ChannelType::Category, parent)
+    }
+    /// このチャンネルの直後の行に追加した、指定した種類のチャンネルが所属するカテゴリの名前とID
+    fn sibling_parent(&self, kind: ChannelType) -> Option<(&str, u64)> {
+        if kind == ChannelType::Category {
+            return None;
+        }
+        if self.channel.kind == ChannelType::Category {
+            return Some((&self.channel.name, self.channel_id.get()));
+        }
+        self.parent()
+    }
+    /// 名前の重複を調べる範囲。カテゴリ同士、同じカテゴリに属するチャンネル同士で比べる
+    fn scope_in(&self, kind: ChannelType, parent_id: Option<u64>) -> String {
+        let parent = match kind {
+            ChannelType::Category => "categories".to_string(),
+            _ => parent_id.map_or("top".to_string(), |id| id.to_string()),
+        };
+        format!("{}/{parent}", self.channel.guild_id)
+    }
+    /// 追加された行から、作成するチャンネルの種類と名前を求める
+    fn parse_create<'a>(&self, new: &'a str) -> Result<(ChannelType, &'a str)> {
+        if self.field != ChannelField::Name {
+            return Err(Error::UnsupportedOperation(
+                format!("create while editing {}", self.field).into(),
+            ));
+        }
+        Ok(parse_kind_prefix(new))
+    }
+    /// チャンネル名を命名ポリシーに従うよう書き換える
+    fn fix_name(&self, new: &str) -> String {
+        self.policy.fix_name(
+            new,
+            self.channel.kind == ChannelType::Category,
+            self.parent(),
+        )
+    }
+}
+
+impl PartialEq for ChannelItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.channel_id == other.channel_id
+    }
+}
+
+impl Eq for ChannelItem {}
+
+impl PartialOrd for ChannelItem {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ChannelItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // 無カテゴリチャンネルを一番上にする
+        if self.is_no_categoryzed_channel() && !other.is_no_categoryzed_channel() {
+            return Ordering::Less;
+        } else if !self.is_no_categoryzed_channel() && other.is_no_categoryzed_channel() {
+            return Ordering::Greater;
+        }
+
+        // 同一カテゴリのチャンネルをまとめる
+        match self.category_position.cmp(&other.category_position) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+
+        // 同一カテゴリ内なら、カテゴリを表すチャンネルを一番上にする
+        if self.parent_name.is_some() && other.parent_name.is_none() {
+            return Ordering::Greater;
+        } else if self.parent_name.is_none() && other.parent_name.is_some() {
+            return Ordering::Less;
+        }
+
+        // 同一カテゴリ内なら、ボイス系チャンネルを下にする
+        if self.is_voice_like_channel() && !other.is_voice_like_channel() {
+            return Ordering::Greater;
+        } else if !self.is_voice_like_channel() && other.is_voice_like_channel() {
+            return Ordering::Less;
+        }
+
+        // 同一カテゴリ内なら、positionでソート
+        match self.channel.parent_id.cmp(&other.channel.parent_id) {
+            Ordering::Equal => self.channel.position.cmp(&other.channel.position),
+            other => other,
+        }
+    }
+}
+
+impl Display for ChannelItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.channel_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for ChannelItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let editchannel = match self.field {
+            ChannelField::Name => EditChannel::new().name(content),
+            ChannelField::Topic => EditChannel::new().topic(unescape_line(&content)),
+            ChannelField::VoiceStatus => {
+                return self
+                    .discord
+                    .set_voice_status(self.channel_id, &content)
+                    .await;
+            }
+            ChannelField::Guidelines => EditChannel::new().topic(content),
+            ChannelField::Slowmode => {
+                EditChannel::new().rate_limit_per_user(parse_slowmode(&content)?)
+            }
+            ChannelField::ThreadSlowmode => {
+                EditChannel::new().default_thread_rate_limit_per_user(parse_slowmode(&content)?)
+            }
+            ChannelField::Nsfw => EditChannel::new().nsfw(parse_nsfw(&content)?),
+            ChannelField::Columns => {
+                // 変更された列の項目のみを送る
+                let mut edit = EditChannel::new();
+                for (column, _, new) in changed_columns(&self.content(), &content) {
+                    edit = match column {
+                        0 => edit.name(new),
+                        1 => edit.topic(unescape_line(new)),
+                        2 => edit.rate_limit_per_user(parse_slowmode(new)?),
+                        _ => edit.nsfw(parse_nsfw(new)?),
+                    };
+                }
+                edit
+            }
+            ChannelField::Voice => {
+                let mut edit = EditChannel::new();
+                for (column, _, new) in changed_columns(&self.content(), &content) {
+                    edit = match column {
+                        0 => edit.bitrate(self.parse_bitrate(new)? * 1000),
+                        _ => edit.user_limit(self.parse_user_limit(new)?),
+                    };
+                }
+                edit
+            }
+        };
+        self.discord
+            .edit_channel(self.channel_id, &editchannel)
+            .await?;
+        Ok(())
+    }
+    async fn delete(&mut self) -> Result<()> {
+        self.discord.delete_channel(self.channel_id).await?;
+        Ok(())
+    }
+    fn validate_create(&self, new: &str) -> Result<()> {
+        let (kind, name) = self.parse_create(new)?;
+        self.check_name(name, kind, self.sibling_parent(kind))
+    }
+    fn sibling(&self) -> Option<Self> {
+        Some(self.clone())
+    }
+    async fn create(&mut self, content: String) -> Result<()> {
+        let (kind, name) = self.parse_create(&content)?;
+        let mut create = CreateChannel::new(name).kind(kind);
+        if let Some((_, parent_id)) = self.sibling_parent(kind) {
+            create = create.category(parent_id);
+        }
+        let channel = self
+            .discord
+            .create_channel(self.channel.guild_id, &create)
+            .await?;
+        // 作成結果を表示できるよう、作成したチャンネルを指すようにする
+        self.channel_id = channel.id;
+        self.channel = channel;
+        Ok(())
+    }
+    async fn fetch_content(&self) -> Result<Option<String>> {
+        // ボイスチャンネルのステータスはチャンネルの取得結果に含まれない
+        if self.field == ChannelField::VoiceStatus {
+            return Ok(None);
+        }
+        let mut fetched = self.clone();
+        fetched.channel = self.discord.channel(self.channel_id).await?;
+        Ok(Some(fetched.content()))
+    }
+    async fn move_to(&mut self, target: &str) -> Result<()> {
+        let editchannel = EditChannel::new().category(Some(self.find_category(target)?));
+        self.discord
+            .edit_channel(self.channel_id, &editchannel)
+            .await?;
+        Ok(())
+    }
+    fn validate_op(&self, op: &Op) -> Result<()> {
+        // 名前以外の項目の編集中に、チャンネル自体を操作させない
+        if self.field != ChannelField::Name {
+            return Err(Error::UnsupportedOperation(
+                format!("{op} while editing {}", self.field).into(),
+            ));
+        }
+        self.policy
+            .check_protected(&self.channel.name, &self.channel_id.to_string())?;
+        match op {
+            Op::Move(_) if self.channel.kind == ChannelType::Category => Err(
+                Error::UnsupportedOperation("categories cannot be moved into a category".into()),
+            ),
+            Op::Move(target) => self.find_category(target).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+    fn content(&self) -> String {
+        match self.field {
+            ChannelField::Name => self.channel.name.clone(),
+            ChannelField::Topic => escape_line(self.channel.topic.as_deref().unwrap_or_default()),
+            ChannelField::VoiceStatus => self.channel.status.clone().unwrap_or_default(),
+            ChannelField::Guidelines => self.channel.topic.clone().unwrap_or_default(),
+            ChannelField::Slowmode => self.channel.rate_limit_per_user.unwrap_or(0).to_string(),
+            ChannelField::Nsfw => self.channel.nsfw.to_string(),
+            ChannelField::ThreadSlowmode => self
+                .channel
+                .default_thread_rate_limit_per_user
+                .unwrap_or(0)
+                .to_string(),
+            ChannelField::Columns => format!(
+                "{}\t{}\t{}\t{}",
+                self.channel.name,
+                escape_line(self.channel.topic.as_deref().unwrap_or_default()),
+                self.channel.rate_limit_per_user.unwrap_or(0),
+                self.channel.nsfw,
+            ),
+            ChannelField::Voice => format!(
+                "{}\t{}",
+                self.channel.bitrate.unwrap_or(0) / 1000,
+                self.channel.user_limit.unwrap_or(0),
+            ),
+        }
+    }
+    fn columns(&self) -> usize {
+        self.column_names().len().max(1)
+    }
+    fn multiline(&self) -> bool {
+        self.field == ChannelField::Guidelines
+    }
+    fn section(&self) -> Option<String> {
+        // カテゴリ自身も、そのカテゴリの見出しの下に置く
+        let category = match self.channel.kind {
+            ChannelType::Category => &self.channel.name,
+            _ => self.parent_name.as_deref()?,
+        };
+        Some(match &self.guild_name {
+            Some(guild_name) => format!("{category} @ {guild_name}"),
+            None => category.to_string(),
+        })
+    }
+    fn duplicate_scope(&self) -> Option<String> {
+        if self.field != ChannelField::Name {
+            return None;
+        }
+        Some(self.scope_in(self.channel.kind, self.channel.parent_id.map(|id| id.get())))
+    }
+    fn lint(&self, op: &Op, new: &str) -> Vec<String> {
+        if self.field != ChannelField::Name {
+            return Vec::new();
+        }
+        let (kind, name) = match op {
+            Op::Rename => (self.channel.kind, new),
+            Op::Create => match self.parse_create(new) {
+                Ok(created) => created,
+                Err(_) => return Vec::new(),
+            },
+            _ => return Vec::new(),
+        };
+        let mut warnings = unicode::suspicious(name);
+        // テキストチャンネルなどの名前は、Discordが小文字にし、空白をハイフンに置き換える
+        if matches!(
+            kind,
+            ChannelType::Text | ChannelType::News | ChannelType::Forum
+        ) {
+            if name.chars().any(char::is_uppercase) {
+                warnings.push("will be lowercased by Discord".to_string());
+            }
+            if name.contains(' ') {
+                warnings.push("spaces will be replaced with hyphens by Discord".to_string());
+            }
+        }
+        let len = name.chars().count();
+        if (NEAR_NAME_LIMIT..=100).contains(&len) {
+            warnings.push(format!(
+                "is {len} characters long, close to the limit of 100"
+            ));
+        }
+        warnings
+    }
+    fn created_duplicate_scope(&self, new: &str) -> Option<(String, String)> {
+        let (kind, name) = self.parse_create(new).ok()?;
+        let parent_id = self.sibling_parent(kind).map(|(_, id)| id);
+        Some((self.scope_in(kind, parent_id), name.to_string()))
+    }
+    fn comment(&self) -> String {
+        match &*self.comment_format {
+            CommentFormat::Default => {}
+            CommentFormat::Template(template) => {
+                return expand_template(template, |key| self.placeholder(key)).unwrap_or_default();
+            }
+            CommentFormat::Hidden => return String::new(),
+        }
+        let mut comment = kind_emoji(self.channel.kind).to_string();
+        // 名前以外の項目を編集する場合は、どのチャンネルか分かるよう名前を添える
+        if !matches!(self.field, ChannelField::Name | ChannelField::Columns) {
+            comment.push(' ');
+            comment.push_str(&self.channel.name);
+        }
+        if let Some(parent_name) = &self.parent_name {
+            comment.push_str(" in ");
+            comment.push_str(parent_name);
+        }
+        if let Some(guild_name) = &self.guild_name {
+            comment.push_str(" @ ");
+            comment.push_str(guild_name);
+        }
+        comment.push_str(" (");
+        comment.push_str(&self.channel_id.to_string());
+        comment.push(')');
+        comment
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if self.field == ChannelField::Voice {
+            self.split_columns(new)?;
+            // 変更されていない値は、ブーストレベルが下がって上限を超えていても検証しない
+            for (column, _, new) in changed_columns(&self.content(), new) {
+                match column {
+                    0 => self.parse_bitrate(new).map(|_| ())?,
+                    _ => self.parse_user_limit(new).map(|_| ())?,
+                }
+            }
+            return Ok(());
+        }
+        if self.field == ChannelField::Columns {
+            let values = self.split_columns(new)?;
+            let (name, topic, slowmode, nsfw) = (values[0], values[1], values[2], values[3]);
+            // 変更されていない名前は、ポリシーに反していても検証しない
+            if name != self.channel.name {
+                self.validate_name(name)?;
+            }
+            validate_topic(topic)?;
+            parse_slowmode(slowmode)?;
+            parse_nsfw(nsfw)?;
+            return Ok(());
+        }
+        if matches!(
+            self.field,
+            ChannelField::Slowmode | ChannelField::ThreadSlowmode
+        ) {
+            return parse_slowmode(new).map(|_| ());
+        }
+        if self.field == ChannelField::Nsfw {
+            return parse_nsfw(new).map(|_| ());
+        }
+        if self.field == ChannelField::Topic {
+            return validate_topic(new);
+        }
+        // 名前以外の項目は長さのみを検証する
+        let max = match self.field {
+            ChannelField::Name
+            | ChannelField::Topic
+            | ChannelField::Slowmode
+            | ChannelField::ThreadSlowmode
+            | ChannelField::Nsfw
+            | ChannelField::Columns
+            | ChannelField::Voice => None,
+            ChannelField::VoiceStatus => Some(500),
+            ChannelField::Guidelines => Some(4096),
+        };
+        if let Some(max) = max {
+            if new.chars().count() > max {
+                return Err(Error::InvalidEditResult(
+                    format!("{} must be at most {max} characters", self.field).into(),
+                ));
+            }
+            return Ok(());
+        }
+        self.validate_name(new)
+    }
+    fn fix(&self, new: &str) -> String {
+        match self.field {
+            ChannelField::Name => self.fix_name(new),
+            ChannelField::Columns => match new.split_once('\t') {
+                Some((name, rest)) => format!("{}\t{rest}", self.fix_name(name)),
+                None => self.fix_name(new),
+            },
+            _ => new.to_string(),
+        }
+    }
+}
+
+/// 追加された行の `voice:` などの接頭辞から、作成するチャンネルの種類と名前を求める。
+/// 接頭辞がない場合はテキストチャンネルとする
+fn parse_kind_prefix(new: &str) -> (ChannelType, &str) {
+    const PREFIXES: [(&str, ChannelType); 7] = [
+        ("text:", ChannelType::Text),
+        ("voice:", ChannelType::Voice),
+        ("category:", ChannelType::Category),
+        ("news:", ChannelType::News),
+        ("announcement:", ChannelType::News),
+        ("forum:", ChannelType::Forum),
+        ("stage:", ChannelType::Stage),
+    ];
+    PREFIXES
+        .iter()
+        .find_map(|(prefix, kind)| Some((*kind, new.strip_prefix(prefix)?)))
+        .unwrap_or((ChannelType::Text, new))
+}
+
+/// 1行の列として編集できるよう、改行を `\n` に、タブを `\t` に、バックスラッシュを `\\` に置き換える
+pub fn escape_line(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// [`escape_line`] で置き換えた文字を元に戻す。
+/// それ以外のバックスラッシュはそのまま残す
+pub fn unescape_line(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// トピックの長さを検証する
+fn validate_topic(text: &str) -> Result<()> {
+    if unescape_line(text).chars().count() > 1024 {
+        return Err(Error::InvalidEditResult(
+            format!("topic must be at most 1024 characters: {text:?}").into(),
+        ));
+    }
+    Ok(())
+}
+
+/// NSFWフラグを読み取る
+fn parse_nsfw(text: &str) -> Result<bool> {
+    match text.trim() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(Error::InvalidEditResult(
+            format!("NSFW flag must be true or false: {text:?}").into(),
+        )),
+    }
+}
+
+/// スローモードの秒数を読み取る
+fn parse_slowmode(text: &str) -> Result<u16> {
+    match text.trim().parse() {
+        Ok(secs) if secs <= 21600 => Ok(secs),
+        _ => Err(Error::InvalidEditResult(
+            format!("slowmode must be a number of seconds between 0 and 21600: {text:?}").into(),
+        )),
+    }
+}
+
+/// Guildのボイスチャンネルで使える最大ビットレート (kbps)
+fn max_bitrate(guild: &PartialGuild) -> u32 {
+    if guild
+        .features
+        .iter()
+        .any(|feature| feature == "VIP_REGIONS")
+    {
+        return 384;
+    }
+    match guild.premium_tier {
+        PremiumTier::Tier1 => 128,
+        PremiumTier::Tier2 => 256,
+        PremiumTier::Tier3 => 384,
+        _ => 96,
+    }
+}
+
+/// BotがManage Channelsの権限を持たず、編集できないチャンネルのID。
+/// Botのロールとチャンネルの権限の上書きから、チャンネルごとの実際の権限を求める
+pub async fn uneditable_ids(
+    discord: &Arc<Discord>,
+    items: &[ChannelItem],
+) -> Result<HashSet<String>> {
+    let mut guild_ids: Vec<_> = items.iter().map(ChannelItem::guild_id).collect();
+    guild_ids.sort();
+    guild_ids.dedup();
+    let mut ids = HashSet::new();
+    if guild_ids.is_empty() {
+        return Ok(ids);
+    }
+    let user_id = discord.current_user().await?.id;
+    // Guildごとの情報とBotのメンバー情報を、全てのGuildについて同時に取得する
+    let guilds = try_join_all(guild_ids.into_iter().map(|guild_id| async move {
+        try_join!(discord.guild(guild_id), discord.member(guild_id, user_id))
+    }))
+    .await?;
+    for (guild, member) in guilds {
+        for item in items.iter().filter(|item| item.guild_id() == guild.id) {
+            let permissions = guild.partial_member_permissions_in(&item.channel, user_id, &member);
+            if !permissions.manage_channels() {
+                ids.insert(item.to_string());
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// 指定したGuildのチャンネル一覧を取得し、フィルタリングとソートを行う
+pub async fn fetch_items<A: ChannelApi + 'static>(
+    discord: &Arc<A>,
+    guild_id: GuildId,
+    filter: &ChannelFilterArgs,
+    field: ChannelField,
+    policy: &Arc<Policy>,
+    color: bool,
+    is_tty: bool,
+) -> Result<Vec<ChannelItem>> {
+    let mut matcher = ChannelMatcher::new(filter)?;
+    // チャンネル一覧取得中の表示。端末の場合は受信済みのチャンネル数で更新し、取得後に消す
+    if !is_tty {
+        let mut msg = console::style("Fetching channels...");
+        if color {
+            msg = msg.dim();
+        }
+        info!("{msg}");
+    }
+    let mut progress = Progress::new(tr!("fetching-channels"), None, is_tty, color);
+    progress::event("fetch_started", json!({ "guild_id": guild_id.to_string() }));
+
+    // 指定したGuildのチャンネル一覧を取得
+    let channels = async {
+        // 名前以外の項目は、その項目を持つチャンネルのみを対象にできる
+        if filter.none() && field == ChannelField::Name {
+            Ok(Vec::new())
+        } else {
+            discord
+                .guild_channels(guild_id, &mut |count| progress.set_done(count))
+                .await
+        }
+    };
+    // ビットレートの上限はGuildのブーストレベルで決まる。チャンネル一覧と同時に取得する
+    let bitrate = async {
+        if field == ChannelField::Voice {
+            Ok(max_bitrate(&discord.guild(guild_id).await?))
+        } else {
+            Ok(0)
+        }
+    };
+    let (channels, max_bitrate) = try_join!(channels, bitrate)?;
+    drop(progress);
+
+    matcher.resolve_categories(&channels)?;
+    Ok(build_items(
+        discord.clone(),
+        channels,
+        &matcher,
+        field,
+        policy,
+        max_bitrate,
+    ))
+}
+
+/// 取得したチャンネル一覧を絞り込み、アイテムにしてソートする。
+/// アイテムは変更の適用に `api` を使う
+pub fn build_items(
+    api: Arc<dyn ChannelApi>,
+    channels: Vec<GuildChannel>,
+    matcher: &ChannelMatcher,
+    field: ChannelField,
+    policy: &Arc<Policy>,
+    max_bitrate: u32,
+) -> Vec<ChannelItem> {
+    // 親カテゴリの索引 (名前, position) を一度だけ作成する。名前は各アイテムで共有する
+    let parents: Arc<HashMap<ChannelId, (Arc<str>, u16)>> = Arc::new(
+        channels
+            .iter()
+            .filter(|channel| channel.kind == ChannelType::Category)
+            .map(|channel| {
+                (
+                    channel.id,
+                    (Arc::from(channel.name.as_str()), channel.position),
+                )
+            })
+            .collect(),
+    );
+    let mut items: Vec<_> = channels
+        .into_iter()
+        .filter(|channel| matcher.matches(channel) && field.supports(channel.kind))
+        .map(|channel| {
+            let parent = channel.parent_id.and_then(|id| parents.get(&id));
+            let parent_name = parent.map(|(name, _)| name.clone());
+            let category_position = parent
+                .map(|(_, position)| *position)
+                .unwrap_or(channel.position);
+            ChannelItem {
+                discord: api.clone(),
+                channel_id: channel.id,
+                field,
+                channel,
+                parent_name,
+                category_position,
+                categories: parents.clone(),
+                policy: policy.clone(),
+                max_bitrate,
+                guild_name: None,
+                comment_format: Arc::default(),
+            }
+        })
+        .collect();
+    items.sort();
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use edisch_core::{Diff, Editor};
+    use serde_json::Value;
+    use std::sync::Mutex;
+
+    /// 受け取った変更をチャンネル一覧に反映し、記録するメモリ上のDiscord API
+    #[derive(Default)]
+    struct FakeApi {
+        channels: Mutex<Vec<GuildChannel>>,
+        /// 送られたリクエスト (メソッド, チャンネルID, 本文)
+        requests: Mutex<Vec<(&'static str, ChannelId, Value)>>,
+    }
+
+    impl FakeApi {
+        fn new(channels: Vec<GuildChannel>) -> Arc<Self> {
+            Arc::new(Self {
+                channels: Mutex::new(channels),
+                requests: Mutex::default(),
+            })
+        }
+
+        fn requests(&self) -> Vec<(&'static str, ChannelId, Value)> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl ChannelApi for FakeApi {
+        async fn guild_channels(
+            &self,
+            _guild_id: GuildId,
+            progress: &mut dyn FnMut(usize),
+        ) -> Result<Vec<GuildChannel>> {
+            let channels = self.channels.lock().unwrap().clone();
+            progress(channels.len());
+            Ok(channels)
+        }
+        async fn guild(&self, _guild_id: GuildId) -> Result<PartialGuild> {
+            Err(Error::UnsupportedOperation("guild".into()))
+        }
+        async fn channel(&self, channel_id: ChannelId) -> Result<GuildChannel> {
+            let channels = self.channels.lock().unwrap();
+            channels
+                .iter()
+                .find(|c| c.id == channel_id)
+                .cloned()
+                .ok_or_else(|| Error::UnsupportedOperation("unknown channel".into()))
+        }
+        async fn edit_channel(
+            &self,
+            channel_id: ChannelId,
+            edit: &EditChannel<'_>,
+        ) -> Result<GuildChannel> {
+            let body = serde_json::to_value(edit).unwrap();
+            self.requests
+                .lock()
+                .unwrap()
+                .push(("PATCH", channel_id, body.clone()));
+            let mut channels = self.channels.lock().unwrap();
+            let channel = channels.iter_mut().find(|c| c.id == channel_id).unwrap();
+            if let Some(name) = body["name"].as_str() {
+                channel.name = name.to_string();
+            }
+            Ok(channel.clone())
+        }
+        async fn create_channel(
+            &self,
+            guild_id: GuildId,
+            create: &CreateChannel<'_>,
+        ) -> Result<GuildChannel> {
+            let body = serde_json::to_value(create).unwrap();
+            let mut channels = self.channels.lock().unwrap();
+            let id = ChannelId::new(100 + channels.len() as u64);
+            self.requests
+                .lock()
+                .unwrap()
+                .push(("POST", id, body.clone()));
+            let mut channel = guild_channel(id.get(), 0, body["name"].as_str().unwrap(), None, 0);
+            channel.guild_id = guild_id;
+            channel.parent_id = body["parent_id"]
+                .as_str()
+                .map(|id| ChannelId::new(id.parse().unwrap()));
+            channels.push(channel.clone());
+            Ok(channel)
+        }
+        async fn delete_channel(&self, channel_id: ChannelId) -> Result<GuildChannel> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(("DELETE", channel_id, Value::Null));
+            let mut channels = self.channels.lock().unwrap();
+            let index = channels.iter().position(|c| c.id == channel_id).unwrap();
+            Ok(channels.remove(index))
+        }
+        async fn set_voice_status(&self, channel_id: ChannelId, status: &str) -> Result<()> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(("PUT", channel_id, json!({ "status": status })));
+            Ok(())
+        }
+    }
+
+    fn guild_channel(
+        id: u64,
+        kind: u8,
+        name: &str,
+        parent_id: Option<u64>,
+        position: u16,
+    ) -> GuildChannel {
+        serde_json::from_value(json!({
+            "id": id.to_string(),
+            "type": kind,
+            "guild_id": "1",
+            "name": name,
+            "position": position,
+            "parent_id": parent_id.map(|id| id.to_string()),
+            "permission_overwrites": [],
+        }))
+        .unwrap()
+    }
+
+    /// カテゴリなしのテキストチャンネルと、カテゴリ内のボイス・テキストチャンネル
+    fn guild() -> Vec<GuildChannel> {
+        vec![
+            guild_channel(13, 2, "Lounge", Some(11), 0),
+            guild_channel(12, 0, "chat", Some(11), 2),
+            guild_channel(11, 4, "Stuff", None, 1),
+            guild_channel(14, 0, "memes", Some(11), 1),
+            guild_channel(10, 0, "general", None, 0),
+        ]
+    }
+
+    async fn items(api: &Arc<FakeApi>) -> Vec<ChannelItem> {
+        let filter: ChannelFilterArgs = serde_json::from_value(json!({ "all": true })).unwrap();
+        let policy = Arc::new(Policy::default());
+        fetch_items(
+            api,
+            GuildId::new(1),
+            &filter,
+            ChannelField::Name,
+            &policy,
+            false,
+            false,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn names(items: &[ChannelItem]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| item.channel.name.as_str())
+            .collect()
+    }
+
+    /// エディタのバッファの、`from` で始まる行のテキストを `to` に置き換える
+    fn edit(editor: &Editor<ChannelItem>, from: &str, to: &str) -> String {
+        editor
+            .to_string()
+            .lines()
+            .map(|line| match line.strip_prefix(from) {
+                Some(rest) if rest.starts_with('\t') => format!("{to}{rest}"),
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    async fn apply_all(editor: Editor<ChannelItem>) -> Result<()> {
+        let diffs: Vec<Diff<ChannelItem>> = editor.try_into()?;
+        for diff in diffs {
+            diff.apply().await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn items_are_grouped_by_category() {
+        let api = FakeApi::new(guild());
+        assert_eq!(
+            names(&items(&api).await),
+            ["general", "Stuff", "memes", "chat", "Lounge"]
+        );
+    }
+
+    #[tokio::test]
+    async fn items_can_be_sorted_by_name_and_id() {
+        let api = FakeApi::new(guild());
+        let mut items = items(&api).await;
+        sort_items(&mut items, ChannelSort::Name);
+        assert_eq!(
+            names(&items),
+            ["chat", "general", "Lounge", "memes", "Stuff"]
+        );
+        sort_items(&mut items, ChannelSort::Id);
+        assert_eq!(
+            names(&items),
+            ["general", "Stuff", "chat", "Lounge", "memes"]
+        );
+    }
+
+    #[tokio::test]
+    async fn renames_are_sent_only_for_changed_lines() {
+        let api = FakeApi::new(guild());
+        let mut editor = Editor::new(items(&api).await).unwrap();
+        let text = edit(&editor, "chat", "lobby");
+        editor.set_text(text).unwrap();
+        apply_all(editor).await.unwrap();
+        assert_eq!(
+            api.requests(),
+            [("PATCH", ChannelId::new(12), json!({ "name": "lobby" }))]
+        );
+        assert_eq!(
+            names(&items(&api).await),
+            ["general", "Stuff", "memes", "lobby", "Lounge"]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_names_are_rejected_before_any_request() {
+        let api = FakeApi::new(guild());
+        let mut editor = Editor::new(items(&api).await).unwrap();
+        let text = edit(&editor, "memes", &"m".repeat(101));
+        editor.set_text(text).unwrap();
+        assert!(matches!(
+            editor.diffs(),
+            Err(Error::InvalidChannelName { .. })
+        ));
+        assert_eq!(editor.violations().len(), 1);
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn structural_edits_drop_and_create_channels() {
+        let api = FakeApi::new(guild());
+        let mut editor = Editor::new(items(&api).await).unwrap();
+        editor.structural().unwrap();
+        let text = edit(&editor, "general", "!drop general")
+            .replace("Stuff\t📁 (11)", "Stuff\t📁 (11)\nannouncements");
+        editor.set_text(text).unwrap();
+        apply_all(editor).await.unwrap();
+        let requests = api.requests();
+        assert_eq!(requests.len(), 2, "{requests:?}");
+        assert!(requests.contains(&("DELETE", ChannelId::new(10), Value::Null)));
+        let (_, id, body) = requests.iter().find(|(m, ..)| *m == "POST").unwrap();
+        assert_eq!(body["name"], "announcements");
+        assert_eq!(body["parent_id"], "11");
+        assert_eq!(
+            api.channel(*id).await.unwrap().parent_id,
+            Some(ChannelId::new(11))
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_content_reflects_the_applied_change() {
+        let api = FakeApi::new(guild());
+        let mut items = items(&api).await;
+        let item = items.iter_mut().find(|i| i.channel_id.get() == 14).unwrap();
+        item.apply("dank-memes".to_string()).await.unwrap();
+        assert_eq!(
+            item.fetch_content().await.unwrap().as_deref(),
+            Some("dank-memes")
+        );
+    }
+
+    #[test]
+    fn records_with_a_guild_are_grouped_by_it() {
+        let records: Vec<serde_json::Map<_, _>> = serde_json::from_value(serde_json::json!([
+            { "id": "10", "name": "general", "guild_id": "1" },
+            { "id": "20", "name": "chat", "guild_id": 2 },
+            { "id": "11", "name": "random" },
+        ]))
+        .unwrap();
+        let text = lines_from_records(&records, ChannelField::Name).unwrap();
+        let groups = crate::format::group_by_guild(&text);
+        let groups: Vec<_> = groups
+            .iter()
+            .map(|(guild_id, lines, _)| (guild_id.map(GuildId::get), lines.as_str()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (Some(1), "general\t(10)\n"),
+                (Some(2), "chat\t(20)\n"),
+                (None, "random\t(11)\n"),
+            ]
+        );
+    }
+}
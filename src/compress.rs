@@ -0,0 +1,99 @@
+use crate::error::Result;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// ファイルの圧縮形式
+enum Format {
+    /// 圧縮なし
+    Plain,
+    /// gzip (`.gz`)
+    Gzip,
+    /// zstd (`.zst`)
+    Zstd,
+}
+
+/// 拡張子から圧縮形式を判定する
+fn format_of(path: &Path) -> Format {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => Format::Gzip,
+        Some("zst") => Format::Zstd,
+        _ => Format::Plain,
+    }
+}
+
+/// ファイルを読み込む。拡張子が圧縮形式を表す場合は展開する
+pub fn read_to_string(path: &Path) -> Result<String> {
+    let mut text = String::new();
+    match format_of(path) {
+        Format::Plain => return Ok(fs::read_to_string(path)?),
+        Format::Gzip => {
+            GzDecoder::new(BufReader::new(File::open(path)?)).read_to_string(&mut text)?;
+        }
+        Format::Zstd => {
+            zstd::Decoder::new(File::open(path)?)?.read_to_string(&mut text)?;
+        }
+    }
+    Ok(text)
+}
+
+/// ファイルに書き出す。拡張子が圧縮形式を表す場合は圧縮する
+pub fn write(path: &Path, text: &str) -> Result<()> {
+    let format = format_of(path);
+    let file = BufWriter::new(File::create(path)?);
+    match format {
+        Format::Plain => {
+            let mut file = file;
+            file.write_all(text.as_bytes())?;
+            file.flush()?;
+        }
+        Format::Gzip => {
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            encoder.write_all(text.as_bytes())?;
+            encoder.finish()?.flush()?;
+        }
+        Format::Zstd => {
+            let mut encoder = zstd::Encoder::new(file, 0)?;
+            encoder.write_all(text.as_bytes())?;
+            encoder.finish()?.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::ExportFormat;
+
+    #[test]
+    fn compressed_files_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = "general\t📝 (10)\nchat\t📝 in Stuff (12)\n".repeat(100);
+        for name in ["channels.txt", "channels.txt.gz", "channels.txt.zst"] {
+            let path = dir.path().join(name);
+            write(&path, &text).unwrap();
+            assert_eq!(read_to_string(&path).unwrap(), text, "{name}");
+        }
+        // 圧縮したファイルは元の内容より小さく、そのままでは読めない
+        let zst = fs::read(dir.path().join("channels.txt.zst")).unwrap();
+        assert!(zst.len() < text.len());
+        assert!(String::from_utf8(zst).is_err());
+    }
+
+    #[test]
+    fn export_format_is_detected_under_compression() {
+        for (name, format) in [
+            ("channels.json.gz", Some(ExportFormat::Json)),
+            ("channels.json.zst", Some(ExportFormat::Json)),
+            ("channels.yaml.zst", Some(ExportFormat::Yaml)),
+            ("channels.txt.zst", Some(ExportFormat::Tsv)),
+            ("channels.zst", None),
+        ] {
+            assert_eq!(ExportFormat::from_path(Path::new(name)), format, "{name}");
+        }
+    }
+}
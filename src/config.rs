@@ -0,0 +1,188 @@
+use crate::{
+    args::ChannelFilterArgs,
+    auth,
+    error::{Error, Result},
+    redact,
+};
+use serde::{Deserialize, Serialize};
+use serenity::all::GuildId;
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf, sync::OnceLock};
+use tracing::debug;
+
+/// --profile で選ばれたプロファイルの名前
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// 以降の設定ファイルの読み込みで使うプロファイルを選ぶ。$EDISCH_PROFILE より優先する
+pub fn set_profile(name: &str) {
+    PROFILE.set(name.to_string()).ok();
+}
+
+/// --profile または $EDISCH_PROFILE で選ばれたプロファイルの名前
+pub fn profile_name() -> Option<String> {
+    PROFILE
+        .get()
+        .cloned()
+        .or_else(|| env::var("EDISCH_PROFILE").ok())
+        .filter(|name| !name.is_empty())
+}
+
+/// Botやサーバーごとの接続先
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct Profile {
+    /// Botのトークン
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// トークンを読み取る環境変数の名前。トークンを設定ファイルに書かずに済む
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    /// 操作対象のGuild ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<u64>,
+}
+
+impl Profile {
+    /// プロファイルのトークン。token_env の環境変数が空または未設定の場合は token を使う
+    pub fn token(&self) -> Option<String> {
+        self.token_env
+            .as_ref()
+            .and_then(|name| env::var(name).ok())
+            .filter(|token| !token.is_empty())
+            .or(self.token.clone())
+    }
+}
+
+/// 設定ファイルの内容
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct Config {
+    /// Botのトークン。--token や $DISCORD_TOKEN がない場合に使う
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// 操作対象のGuild ID。--guild-id や $GUILD_ID がない場合に使う
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<u64>,
+    /// エディタのコマンド。$VISUAL・$EDITOR より優先する
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// エディタに渡す一時ファイルを作成するディレクトリ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmpdir: Option<PathBuf>,
+    /// エディタに渡す一時ファイルの拡張子
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tempfile_suffix: Option<String>,
+    /// エディタのモードでチャンネルの種類の指定がない場合に編集する種類
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ChannelFilterArgs>,
+    /// Guild IDの別名
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub guilds: BTreeMap<String, u64>,
+    /// --profile や $EDISCH_PROFILE で選べる接続先
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// 選ばれたプロファイル。トークンとGuild IDは、コマンドラインの指定の次にこちらを優先する
+    #[serde(skip)]
+    pub profile: Option<Profile>,
+}
+
+impl Config {
+    /// 設定ファイルのパス。$EDISCH_CONFIG で上書きできる
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("EDISCH_CONFIG") {
+            return Some(path.into());
+        }
+        dirs::config_dir().map(|dir| dir.join("edisch").join("config.toml"))
+    }
+
+    /// 設定ファイルを読み込む。存在しない場合はデフォルト値を返す。
+    /// プロファイルが選ばれていれば、それも読み込む
+    pub fn load() -> Result<Self> {
+        let mut config = Self::load_file()?;
+        // 設定ファイルのトークンは、使わない場合も表示から伏せる
+        for token in config
+            .token
+            .iter()
+            .chain(config.profiles.values().filter_map(|p| p.token.as_ref()))
+        {
+            redact::register(token);
+        }
+        if let Some(name) = profile_name() {
+            let profile = config.profiles.get(&name).cloned().ok_or_else(|| {
+                Error::Config(format!("profile {name:?} is not defined in [profiles]").into())
+            })?;
+            config.profile = Some(profile);
+        }
+        Ok(config)
+    }
+
+    /// 設定ファイルを読み込む
+    fn load_file() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        debug!("Loading the config file {}", path.display());
+        toml::from_str(&text)
+            .map_err(|e| Error::Config(format!("{}: {}", path.display(), e.message()).into()))
+    }
+
+    /// コマンドラインで指定されなかった場合のトークン。
+    /// プロファイル、$DISCORD_TOKEN、設定ファイル、OSのキーリング (`edisch auth set`) の順に探す
+    pub fn token(&self) -> Option<String> {
+        self.profile
+            .as_ref()
+            .and_then(Profile::token)
+            .or(env::var("DISCORD_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()))
+            .or(self.token.clone())
+            .or_else(auth::stored_token)
+    }
+
+    /// コマンドラインで指定されなかった場合のGuild ID。プロファイル、$GUILD_ID、設定ファイルの順に探す
+    pub fn default_guild(&self) -> Result<GuildId> {
+        if let Some(id) = self.profile.as_ref().and_then(|profile| profile.guild_id) {
+            return Ok(GuildId::new(id));
+        }
+        match env::var("GUILD_ID") {
+            Ok(guild) => self.resolve_guild(&guild),
+            Err(_) => match self.guild_id {
+                Some(id) => Ok(GuildId::new(id)),
+                None => Err(Error::MissingArgument("GUILD_ID".into())),
+            },
+        }
+    }
+
+    /// 別名またはIDからGuild IDを得る
+    pub fn resolve_guild(&self, guild: &str) -> Result<GuildId> {
+        if let Some(&id) = self.guilds.get(guild) {
+            return Ok(GuildId::new(id));
+        }
+        match guild.parse() {
+            Ok(id) if id != 0 => Ok(GuildId::new(id)),
+            _ => Err(Error::ParseArgument(
+                format!("unknown guild alias or ID: {guild:?}").into(),
+            )),
+        }
+    }
+
+    /// 設定ファイルに書き出す。トークンを含みうるため、Unixでは所有者のみ読めるようにする
+    pub fn save(&self) -> Result<PathBuf> {
+        let Some(path) = Self::path() else {
+            return Err(Error::Config("config directory not found".into()));
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string(self).map_err(|e| Error::Config(e.to_string().into()))?;
+        fs::write(&path, text)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(path)
+    }
+}
@@ -0,0 +1,1522 @@
+use crate::{
+    args::TokenType,
+    cache,
+    error::{Error, Result},
+    i18n::tr,
+    logging,
+    redact::{self, Redacted},
+    schedule,
+};
+use edisch_core::async_trait;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serenity::{
+    all::{
+        ChannelId, CreateChannel, CurrentApplicationInfo, CurrentUser, EditChannel, EditGuild,
+        EditRole, EditSticker, EditThread, EditWebhook, Emoji, EmojiId, FollowedChannel, ForumTag,
+        GenericId, GuildChannel, GuildId, GuildInfo, Http, HttpBuilder, LightMethod, PartialGuild,
+        PartialMember, PermissionOverwrite, PermissionOverwriteType, Request, Role, RoleId, Route,
+        Rule, RuleId, Sticker, StickerId, TargetId, ThreadsData, UserId, Webhook, WebhookId,
+    },
+    http::{ErrorResponse, HttpError, StatusCode},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+use tracing::{debug, info, warn, Level};
+
+/// Discord APIのオリジン。--api-base を指定した場合はこの部分を置き換える
+const API_ORIGIN: &str = "https://discord.com";
+
+/// Discord APIのベースURL
+const API_BASE: &str = "https://discord.com/api/v10";
+
+/// 監査ログに表示される理由のヘッダ
+const AUDIT_LOG_REASON: &str = "X-Audit-Log-Reason";
+
+/// レートリミットで待つことを知らせる待ち時間
+const LONG_WAIT: Duration = Duration::from_secs(5);
+
+/// 429のレスポンスで指示されても、1回に待つ最長の時間
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// ログに出力するレートリミット関連のヘッダ
+const RATELIMIT_HEADERS: [&str; 6] = [
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset-after",
+    "x-ratelimit-bucket",
+    "x-ratelimit-scope",
+    "retry-after",
+];
+
+/// 変更を伴うリクエスト (GET以外) が成功したか。終了ステータスで変更の有無を伝えるのに使う
+static CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// このプロセスでDiscord上の何かを変更したか
+pub fn changed() -> bool {
+    CHANGED.load(Ordering::Relaxed)
+}
+
+/// 記録された1回分のAPI通信
+#[derive(Serialize, Deserialize, Clone)]
+struct Interaction {
+    /// HTTPメソッド
+    method: String,
+    /// リクエスト先のURL
+    path: String,
+    /// ステータスコード
+    status: u16,
+    /// レスポンスボディ
+    body: serde_json::Value,
+}
+
+/// 記録ファイルの形式
+#[derive(Serialize, Deserialize, Default)]
+struct Recording {
+    interactions: Vec<Interaction>,
+}
+
+/// serenityにルートが定義されていないエンドポイントへのリクエスト
+#[derive(Clone)]
+struct RawRequest {
+    method: LightMethod,
+    /// リクエスト先のURL
+    path: String,
+    body: Option<Vec<u8>>,
+}
+
+/// レスポンスヘッダから読み取ったレートリミットの状態
+pub struct RateLimitStatus {
+    /// バケットで許されるリクエスト数
+    pub limit: Option<u32>,
+    /// バケットの残りのリクエスト数
+    pub remaining: Option<u32>,
+    /// バケットがリセットされるまでの秒数
+    pub reset_after: Option<f64>,
+    /// 429で拒否された場合の、再送できるまでの秒数
+    pub retry_after: Option<f64>,
+    /// 全てのリクエストに対するレートリミットか
+    pub global: bool,
+}
+
+/// Guildのサウンドボードのサウンド (serenityに型がないため必要な項目のみ)
+#[derive(Deserialize, Debug, Clone)]
+pub struct SoundboardSound {
+    /// サウンドID
+    pub sound_id: GenericId,
+    /// サウンド名
+    pub name: String,
+    /// カスタム絵文字のID
+    pub emoji_id: Option<EmojiId>,
+    /// Unicode絵文字 (カスタム絵文字の場合はその名前)
+    pub emoji_name: Option<String>,
+}
+
+/// サウンドボードのサウンド一覧のレスポンス
+#[derive(Deserialize)]
+struct SoundboardSounds {
+    items: Vec<SoundboardSound>,
+}
+
+/// チャンネルの権限の上書き。serenityは知らない権限のビットを読み捨てるため、値を文字列のまま読む
+#[derive(Deserialize)]
+struct RawChannelOverwrites {
+    id: ChannelId,
+    #[serde(default)]
+    permission_overwrites: Vec<RawOverwrite>,
+}
+
+/// 1つの権限の上書き
+#[derive(Deserialize)]
+struct RawOverwrite {
+    /// ロールまたはメンバーのID
+    id: TargetId,
+    allow: String,
+    deny: String,
+}
+
+/// Guildのメンバーのうち、必要な項目のみ
+#[derive(Deserialize)]
+struct MemberRoles {
+    roles: Vec<RoleId>,
+}
+
+/// APIへのリクエスト
+#[derive(Clone)]
+enum ApiRequest<'a> {
+    /// serenityのルートを使うリクエスト
+    Serenity(Box<Request<'a>>),
+    /// URLを直接指定するリクエスト
+    Raw(RawRequest),
+}
+
+impl ApiRequest<'_> {
+    fn method(&self) -> LightMethod {
+        match self {
+            ApiRequest::Serenity(req) => *req.method_ref(),
+            ApiRequest::Raw(req) => req.method,
+        }
+    }
+
+    fn path(&self) -> String {
+        match self {
+            ApiRequest::Serenity(req) => req.route_ref().path().into_owned(),
+            ApiRequest::Raw(req) => req.path.clone(),
+        }
+    }
+}
+
+impl<'a> From<Request<'a>> for ApiRequest<'a> {
+    fn from(req: Request<'a>) -> Self {
+        ApiRequest::Serenity(Box::new(req))
+    }
+}
+
+impl From<RawRequest> for ApiRequest<'_> {
+    fn from(req: RawRequest) -> Self {
+        ApiRequest::Raw(req)
+    }
+}
+
+/// API通信の記録・再生モード
+enum Session {
+    /// 通常通り通信する
+    Live,
+    /// 通信内容をファイルに記録する
+    Record {
+        path: PathBuf,
+        recording: Mutex<Recording>,
+    },
+    /// 記録された通信内容を再生し、実際には通信しない
+    Replay {
+        interactions: Mutex<Vec<Interaction>>,
+    },
+}
+
+/// チャンネルの編集に使うDiscord APIの操作。
+/// 適用の処理をDiscordに接続せずに試せるよう、[`Discord`] の代わりにメモリ上の実装を渡せる
+#[async_trait(?Send)]
+pub trait ChannelApi: Send + Sync {
+    /// Guildのチャンネル一覧を、受信済みの件数を通知しながら取得する
+    async fn guild_channels(
+        &self,
+        guild_id: GuildId,
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<Vec<GuildChannel>>;
+    /// Guildの情報を取得する
+    async fn guild(&self, guild_id: GuildId) -> Result<PartialGuild>;
+    /// チャンネルを取得する
+    async fn channel(&self, channel_id: ChannelId) -> Result<GuildChannel>;
+    /// チャンネルを編集する
+    async fn edit_channel(
+        &self,
+        channel_id: ChannelId,
+        edit: &EditChannel<'_>,
+    ) -> Result<GuildChannel>;
+    /// チャンネルを作成する
+    async fn create_channel(
+        &self,
+        guild_id: GuildId,
+        create: &CreateChannel<'_>,
+    ) -> Result<GuildChannel>;
+    /// チャンネルを削除する
+    async fn delete_channel(&self, channel_id: ChannelId) -> Result<GuildChannel>;
+    /// ボイスチャンネルのステータスを設定する。空文字列の場合は消去する
+    async fn set_voice_status(&self, channel_id: ChannelId, status: &str) -> Result<()>;
+}
+
+#[async_trait(?Send)]
+impl ChannelApi for Discord {
+    async fn guild_channels(
+        &self,
+        guild_id: GuildId,
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<Vec<GuildChannel>> {
+        Discord::guild_channels(self, guild_id, progress).await
+    }
+    async fn guild(&self, guild_id: GuildId) -> Result<PartialGuild> {
+        Discord::guild(self, guild_id).await
+    }
+    async fn channel(&self, channel_id: ChannelId) -> Result<GuildChannel> {
+        Discord::channel(self, channel_id).await
+    }
+    async fn edit_channel(
+        &self,
+        channel_id: ChannelId,
+        edit: &EditChannel<'_>,
+    ) -> Result<GuildChannel> {
+        Discord::edit_channel(self, channel_id, edit).await
+    }
+    async fn create_channel(
+        &self,
+        guild_id: GuildId,
+        create: &CreateChannel<'_>,
+    ) -> Result<GuildChannel> {
+        Discord::create_channel(self, guild_id, create).await
+    }
+    async fn delete_channel(&self, channel_id: ChannelId) -> Result<GuildChannel> {
+        Discord::delete_channel(self, channel_id).await
+    }
+    async fn set_voice_status(&self, channel_id: ChannelId, status: &str) -> Result<()> {
+        Discord::set_voice_status(self, channel_id, status).await
+    }
+}
+
+/// Discord APIクライアント
+pub struct Discord {
+    http: Http,
+    /// トークンの種類。ユーザーのトークンでは取得できるメンバー情報が自身のものに限られる
+    token_type: TokenType,
+    /// serenityにルートのないエンドポイント用のクライアント
+    client: reqwest::Client,
+    /// 記録・再生モード
+    session: Session,
+    /// 変更を伴うリクエストに付ける監査ログの理由 (URLエンコード済み)
+    reason: Option<HeaderValue>,
+    /// 一時的なエラーで失敗したリクエストを再送する回数
+    retries: u32,
+    /// 最初の再送までの待ち時間。再送するたびに倍になる
+    retry_delay: Duration,
+    /// Discord APIの代わりに使うオリジン (--api-base)
+    api_base: Option<String>,
+    /// キャッシュがあればチャンネル一覧を取得せずに使う (--cached)
+    cached: bool,
+    /// キャッシュしたチャンネル一覧を使ったか
+    from_cache: AtomicBool,
+}
+
+impl Discord {
+    pub fn new(token: &str, token_type: TokenType) -> Self {
+        redact::register(token);
+        // serenityは Bot または Bearer で始まらないトークンに Bot を付けて送る
+        let authorization = match token_type {
+            TokenType::Bot => token.to_string(),
+            TokenType::Bearer => format!("Bearer {}", token.trim()),
+        };
+        Self {
+            http: build_http(HttpBuilder::new(authorization)),
+            token_type,
+            client: reqwest::Client::new(),
+            session: Session::Live,
+            reason: None,
+            retries: 0,
+            retry_delay: Duration::ZERO,
+            api_base: None,
+            cached: false,
+            from_cache: AtomicBool::new(false),
+        }
+    }
+
+    /// HTTPプロキシ、Discord APIの代わりに使うDiscord互換のエンドポイントと、リクエストのタイムアウトを設定する。
+    /// プロキシを指定しない場合は $HTTPS_PROXY などの環境変数に従う。タイムアウトが None なら待ち続ける
+    pub fn with_endpoint(
+        mut self,
+        proxy: Option<&str>,
+        api_base: Option<&str>,
+        timeout: Option<Duration>,
+    ) -> Result<Self> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = timeout {
+            // 応答の受信が終わるまでの時間。途中で止まった接続はこの時間で失敗し、再送の対象になる
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| Error::ParseArgument(format!("--proxy: {e}").into()))?;
+            builder = builder.proxy(proxy);
+        }
+        self.client = builder.build().map_err(serenity::Error::from)?;
+        let mut http = HttpBuilder::new(self.http.token()).client(self.client.clone());
+        if let Some(api_base) = api_base {
+            let url = reqwest::Url::parse(api_base)
+                .map_err(|e| Error::ParseArgument(format!("--api-base: {e}").into()))?;
+            if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+                return Err(Error::ParseArgument(
+                    "--api-base: expected an http(s) URL such as http://localhost:8080".into(),
+                ));
+            }
+            let api_base = api_base.trim_end_matches('/').to_string();
+            // serenityのレートリミッタはプロキシ先を無視してDiscordに送信するため使わない。
+            // レートリミットはエンドポイントの返す429を再送して扱う
+            http = http.proxy(&api_base).ratelimiter_disabled(true);
+            self.api_base = Some(api_base);
+        }
+        self.http = build_http(http);
+        Ok(self)
+    }
+
+    /// 一時的なエラー (429, 5xx, 接続の失敗) で失敗したリクエストを、間隔を倍にしながら再送する
+    pub fn with_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// キャッシュしたチャンネル一覧があれば、取得せずにそれを使う
+    pub fn with_cache(mut self, cached: bool) -> Self {
+        self.cached = cached;
+        self
+    }
+
+    /// キャッシュしたチャンネル一覧を使ったか。
+    /// キャッシュはDiscord上の現在の状態と異なりうるため、変更の適用には使わない
+    pub fn used_cache(&self) -> bool {
+        self.from_cache.load(Ordering::Relaxed)
+    }
+
+    /// 変更を伴う全てのリクエストに、監査ログに表示される理由を付ける
+    pub fn with_reason(mut self, reason: &str) -> Result<Self> {
+        // 監査ログの理由は1〜512文字
+        if reason.is_empty() || reason.chars().count() > 512 {
+            return Err(Error::ParseArgument(
+                "the audit log reason must be 1 to 512 characters long".into(),
+            ));
+        }
+        let encoded = utf8_percent_encode(reason, NON_ALPHANUMERIC).to_string();
+        self.reason = Some(
+            HeaderValue::try_from(encoded)
+                .map_err(|e| Error::ParseArgument(e.to_string().into()))?,
+        );
+        Ok(self)
+    }
+
+    /// 全てのAPIレスポンスを指定したファイルに記録する
+    pub fn record(mut self, path: PathBuf) -> Self {
+        self.session = Session::Record {
+            path,
+            recording: Mutex::new(Recording::default()),
+        };
+        self
+    }
+
+    /// 指定したファイルに記録されたAPIレスポンスを再生する
+    pub fn replay(mut self, path: PathBuf) -> Result<Self> {
+        let recording: Recording = serde_json::from_reader(File::open(path)?)
+            .map_err(|e| Error::Replay(e.to_string().into()))?;
+        self.session = Session::Replay {
+            interactions: Mutex::new(recording.interactions),
+        };
+        Ok(self)
+    }
+
+    /// 送信先のURL。--api-base が指定されていれば、Discordのオリジンを置き換える
+    fn endpoint(&self, path: &str) -> String {
+        match &self.api_base {
+            Some(api_base) => path.replacen(API_ORIGIN, api_base, 1),
+            None => path.to_string(),
+        }
+    }
+
+    /// HTTP通信のログを出力する (--trace-http または -vv の場合のみ)
+    fn log(&self, line: &str) {
+        debug!(target: logging::HTTP, "{line}");
+    }
+
+    /// リクエストを送信し、レスポンスをデシリアライズする。よくあるAPIのエラーは対処法を示すエラーにする
+    async fn request<'a, T: DeserializeOwned>(&self, req: impl Into<ApiRequest<'a>>) -> Result<T> {
+        let req = req.into();
+        let (method, path) = (req.method(), req.path());
+        let result = self
+            .exchange(req)
+            .await
+            .map_err(|e| friendly_error(e, method, &path));
+        if result.is_ok() && !matches!(method, LightMethod::Get) {
+            CHANGED.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// リクエストを送信するか、記録から再生し、レスポンスをデシリアライズする
+    async fn exchange<T: DeserializeOwned>(&self, req: ApiRequest<'_>) -> Result<T> {
+        let method = req.method();
+        let path = req.path();
+        let interaction = match &self.session {
+            Session::Live => return self.send(req).await,
+            Session::Replay { interactions } => {
+                let mut interactions = interactions.lock().unwrap();
+                let Some(pos) = interactions
+                    .iter()
+                    .position(|i| i.method == method.reqwest_method().as_str() && i.path == path)
+                else {
+                    return Err(Error::Replay(
+                        format!("no recorded response for {method:?} {path}").into(),
+                    ));
+                };
+                interactions.remove(pos)
+            }
+            Session::Record {
+                path: file,
+                recording,
+            } => {
+                let result = self.send::<serde_json::Value>(req).await;
+                let (status, body) = match &result {
+                    Ok(body) => (StatusCode::OK.as_u16(), body.clone()),
+                    Err(Error::Serenity(e)) => match &**e {
+                        serenity::Error::Http(HttpError::UnsuccessfulRequest(res)) => (
+                            res.status_code.as_u16(),
+                            serde_json::to_value(&res.error).map_err(serenity::Error::from)?,
+                        ),
+                        _ => return Err(result.unwrap_err()),
+                    },
+                    Err(_) => return Err(result.unwrap_err()),
+                };
+                let mut recording = recording.lock().unwrap();
+                recording.interactions.push(Interaction {
+                    method: method.reqwest_method().as_str().to_string(),
+                    path: path.to_string(),
+                    status,
+                    body,
+                });
+                // 途中で異常終了しても記録が残るよう、毎回書き出す
+                serde_json::to_writer_pretty(BufWriter::new(File::create(file)?), &*recording)
+                    .map_err(serenity::Error::from)?;
+                return Ok(serde_json::from_value(result?).map_err(serenity::Error::from)?);
+            }
+        };
+        self.log(&format!(
+            "{method:?} {path} -> {} (replayed)",
+            interaction.status
+        ));
+        if StatusCode::from_u16(interaction.status).is_ok_and(|s| s.is_success()) {
+            Ok(serde_json::from_value(interaction.body).map_err(serenity::Error::from)?)
+        } else {
+            let res = http::Response::builder()
+                .status(interaction.status)
+                .body(interaction.body.to_string())
+                .map_err(|e| Error::Replay(e.to_string().into()))?;
+            let res = ErrorResponse::from_response(res.into(), method.reqwest_method()).await;
+            Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(res)).into())
+        }
+    }
+
+    /// 実際にリクエストを送信する
+    async fn send<T: DeserializeOwned>(&self, req: ApiRequest<'_>) -> Result<T> {
+        let res = self.send_raw(req).await?;
+        let mut bytes = res.bytes().await.map_err(serenity::Error::from)?.to_vec();
+        if bytes.is_empty() {
+            // 204 No Content など
+            bytes = b"null".to_vec();
+        }
+        Ok(serde_json::from_slice(&bytes).map_err(serenity::Error::from)?)
+    }
+
+    /// 実際にリクエストを送信し、ボディを読む前のレスポンスを返す。
+    /// 一時的なエラーの場合は指定された回数まで再送する
+    async fn send_raw(&self, req: ApiRequest<'_>) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let result = self.send_once(req.clone()).await;
+            let Err(Error::Serenity(e)) = &result else {
+                return result;
+            };
+            if attempt >= self.retries || !is_transient(e, req.method()) {
+                return result;
+            }
+            // URLを直接指定するリクエストの429は、send_unrouted が retry-after に従って再送済み
+            if matches!(req, ApiRequest::Raw(_)) && is_rate_limited(e) {
+                return result;
+            }
+            // 複数のリクエストが同時に再送しないよう、待ち時間をばらつかせる
+            let delay = self.retry_delay * 2u32.saturating_pow(attempt);
+            let delay = delay + delay.mul_f64(jitter() / 2.0);
+            attempt += 1;
+            warn!(
+                "{}",
+                tr!(
+                    "request-retrying",
+                    path = req.path(),
+                    detail = Redacted(e).to_string(),
+                    delay = schedule::format_duration(delay),
+                    attempt = attempt,
+                    retries = self.retries
+                )
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// リクエストを1回送信し、ボディを読む前のレスポンスを返す
+    async fn send_once(&self, req: ApiRequest<'_>) -> Result<reqwest::Response> {
+        let method = req.method();
+        let path = req.path();
+        let reason = self
+            .reason
+            .clone()
+            .filter(|_| !matches!(method, LightMethod::Get));
+        let result = match req {
+            ApiRequest::Serenity(mut req) => {
+                if let Some(reason) = reason {
+                    req.headers_mut()
+                        .get_or_insert_with(HeaderMap::new)
+                        .insert(AUDIT_LOG_REASON, reason);
+                }
+                self.http.request(*req).await
+            }
+            ApiRequest::Raw(req) => self.send_unrouted(req, reason).await,
+        };
+        match result {
+            Ok(res) => {
+                if http_logging() {
+                    let mut line = format!("{method:?} {path} -> {}", res.status());
+                    for name in RATELIMIT_HEADERS {
+                        if let Some(value) = res.headers().get(name) {
+                            line.push_str(&format!(" {name}={}", value.to_str().unwrap_or("?")));
+                        }
+                    }
+                    self.log(&line);
+                }
+                Ok(res)
+            }
+            Err(e) => {
+                if let serenity::Error::Http(HttpError::UnsuccessfulRequest(res)) = &e {
+                    self.log(&format!(
+                        "{method:?} {path} -> {} ({})",
+                        res.status_code, res.error.message
+                    ));
+                } else {
+                    self.log(&format!("{method:?} {path} -> {e}"));
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// serenityのルートを使わずにリクエストを送信する。
+    /// 事前のレートリミットの計算はせず、429が返された場合に retry-after だけ待って
+    /// --retries の回数まで再送する
+    async fn send_unrouted(
+        &self,
+        req: RawRequest,
+        reason: Option<HeaderValue>,
+    ) -> serenity::Result<reqwest::Response> {
+        let reqwest_method = req.method.reqwest_method();
+        let mut attempt = 0;
+        let res = loop {
+            let mut builder = self
+                .client
+                .request(reqwest_method.clone(), self.endpoint(&req.path))
+                .header(reqwest::header::AUTHORIZATION, self.http.token());
+            if let Some(reason) = reason.clone() {
+                builder = builder.header(AUDIT_LOG_REASON, reason);
+            }
+            if let Some(body) = req.body.clone() {
+                builder = builder
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body);
+            }
+            let res = builder.send().await?;
+            if res.status() != StatusCode::TOO_MANY_REQUESTS || attempt >= self.retries {
+                break res;
+            }
+            attempt += 1;
+            let wait = retry_after(res.headers()).unwrap_or(self.retry_delay);
+            self.log(&format!(
+                "{:?} {} -> rate limited, retrying after {:.3}s ({attempt}/{})",
+                req.method,
+                req.path,
+                wait.as_secs_f64(),
+                self.retries
+            ));
+            if !http_logging() && wait >= LONG_WAIT {
+                info!(
+                    "{}",
+                    tr!("rate-limit-waiting", wait = schedule::format_duration(wait))
+                );
+            }
+            tokio::time::sleep(wait).await;
+        };
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(
+                ErrorResponse::from_response(res, reqwest_method).await,
+            )))
+        }
+    }
+
+    /// JSON配列を返すリクエストを送信し、要素を受信するたびにその件数をコールバックに渡す
+    async fn request_list<T: DeserializeOwned>(
+        &self,
+        req: Request<'_>,
+        mut progress: impl FnMut(usize),
+    ) -> Result<Vec<T>> {
+        if !matches!(self.session, Session::Live) {
+            let list: Vec<T> = self.request(req).await?;
+            progress(list.len());
+            return Ok(list);
+        }
+        let req = ApiRequest::from(req);
+        let (method, path) = (req.method(), req.path());
+        let mut res = self
+            .send_raw(req)
+            .await
+            .map_err(|e| friendly_error(e, method, &path))?;
+        let mut splitter = ArraySplitter::default();
+        let mut list = Vec::new();
+        while let Some(chunk) = res.chunk().await.map_err(serenity::Error::from)? {
+            for element in splitter.feed(&chunk) {
+                list.push(serde_json::from_slice(&element).map_err(serenity::Error::from)?);
+            }
+            progress(list.len());
+        }
+        debug!("Received {} items from {path}", list.len());
+        Ok(list)
+    }
+
+    /// Botユーザー自身の情報を取得する
+    pub async fn current_user(&self) -> Result<CurrentUser> {
+        self.request(Request::new(Route::UserMe, LightMethod::Get))
+            .await
+    }
+
+    /// Botのアプリケーションの情報を取得する
+    pub async fn current_application(&self) -> Result<CurrentApplicationInfo> {
+        self.request(Request::new(
+            Route::Oauth2ApplicationCurrent,
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// Guildのメンバーが持つロールの一覧を取得する
+    pub async fn member_roles(&self, guild_id: GuildId, user_id: UserId) -> Result<Vec<RoleId>> {
+        let member: MemberRoles = self.request(self.member_request(guild_id, user_id)).await?;
+        Ok(member.roles)
+    }
+
+    /// Guildのメンバーを取得するリクエスト。
+    /// ユーザーのトークンではGuildのメンバーのエンドポイントを使えないため、自身のメンバー情報を取得する
+    fn member_request(&self, guild_id: GuildId, user_id: UserId) -> ApiRequest<'static> {
+        match self.token_type {
+            TokenType::Bot => {
+                Request::new(Route::GuildMember { guild_id, user_id }, LightMethod::Get).into()
+            }
+            TokenType::Bearer => RawRequest {
+                method: LightMethod::Get,
+                path: format!("{API_BASE}/users/@me/guilds/{guild_id}/member"),
+                body: None,
+            }
+            .into(),
+        }
+    }
+
+    /// 現在のユーザーを取得するリクエストを1回だけ送り、レスポンスヘッダからレートリミットの状態を得る。
+    /// 429でも再送しない。記録・再生モードでは通信しないため None
+    pub async fn rate_limit_status(&self) -> Result<Option<RateLimitStatus>> {
+        if !matches!(self.session, Session::Live) {
+            return Ok(None);
+        }
+        let res = self
+            .client
+            .get(self.endpoint(&format!("{API_BASE}/users/@me")))
+            .header(reqwest::header::AUTHORIZATION, self.http.token())
+            .send()
+            .await
+            .map_err(serenity::Error::from)?;
+        fn header<T: std::str::FromStr>(res: &reqwest::Response, name: &str) -> Option<T> {
+            res.headers().get(name)?.to_str().ok()?.parse().ok()
+        }
+        let scope: Option<String> = header(&res, "x-ratelimit-scope");
+        Ok(Some(RateLimitStatus {
+            limit: header(&res, "x-ratelimit-limit"),
+            remaining: header(&res, "x-ratelimit-remaining"),
+            reset_after: header(&res, "x-ratelimit-reset-after"),
+            retry_after: if res.status() == StatusCode::TOO_MANY_REQUESTS {
+                header(&res, "retry-after")
+            } else {
+                None
+            },
+            global: res.headers().contains_key("x-ratelimit-global")
+                || scope.as_deref() == Some("global"),
+        }))
+    }
+
+    /// Guildのメンバーを取得する
+    pub async fn member(&self, guild_id: GuildId, user_id: UserId) -> Result<PartialMember> {
+        self.request(self.member_request(guild_id, user_id)).await
+    }
+
+    /// Botが参加しているGuildの一覧を取得する
+    pub async fn current_user_guilds(&self) -> Result<Vec<GuildInfo>> {
+        self.request(Request::new(Route::UserMeGuilds, LightMethod::Get))
+            .await
+    }
+
+    /// Guildのチャンネル一覧を、受信済みの件数を通知しながら取得する。
+    /// 取得した一覧はキャッシュに保存し、--cached の場合はキャッシュがあればそれを返す
+    pub async fn guild_channels(
+        &self,
+        guild_id: GuildId,
+        mut progress: impl FnMut(usize),
+    ) -> Result<Vec<GuildChannel>> {
+        let live = matches!(self.session, Session::Live);
+        if self.cached && live {
+            if let Some((age, channels)) = cache::load(guild_id)? {
+                info!(
+                    "{}",
+                    tr!(
+                        "using-cached-channels",
+                        guild = guild_id.to_string(),
+                        age = cache::format_age(age)
+                    )
+                );
+                self.from_cache.store(true, Ordering::Relaxed);
+                progress(channels.len());
+                return Ok(channels);
+            }
+        }
+        let channels = self
+            .request_list(
+                Request::new(Route::GuildChannels { guild_id }, LightMethod::Get),
+                progress,
+            )
+            .await?;
+        if live {
+            cache::store(guild_id, &channels);
+        }
+        Ok(channels)
+    }
+
+    /// Guildの全チャンネルの権限の上書きを、serenityが知らない権限のビットも含めて取得する。
+    /// キーは (チャンネル, 上書きの対象のID)、値は (許可, 拒否) のビット
+    pub async fn guild_overwrite_bits(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<(ChannelId, u64), (u64, u64)>> {
+        let channels: Vec<RawChannelOverwrites> = self
+            .request(Request::new(
+                Route::GuildChannels { guild_id },
+                LightMethod::Get,
+            ))
+            .await?;
+        Ok(channels
+            .into_iter()
+            .flat_map(|channel| {
+                channel
+                    .permission_overwrites
+                    .into_iter()
+                    .filter_map(move |o| {
+                        let bits = (o.allow.parse().ok()?, o.deny.parse().ok()?);
+                        Some(((channel.id, o.id.get()), bits))
+                    })
+            })
+            .collect())
+    }
+
+    /// Guildのアクティブなスレッド一覧を取得する
+    pub async fn guild_active_threads(&self, guild_id: GuildId) -> Result<Vec<GuildChannel>> {
+        let data: ThreadsData = self
+            .request(Request::new(
+                Route::GuildThreadsActive { guild_id },
+                LightMethod::Get,
+            ))
+            .await?;
+        Ok(data.threads)
+    }
+
+    /// チャンネルのアーカイブされたスレッドを全て取得する。
+    /// `private` の場合は非公開スレッドを取得する (スレッドの管理権限が必要)
+    pub async fn archived_threads(
+        &self,
+        channel_id: ChannelId,
+        private: bool,
+    ) -> Result<Vec<GuildChannel>> {
+        let mut threads = Vec::new();
+        let mut before = None;
+        loop {
+            let route = if private {
+                Route::ChannelArchivedPrivateThreads { channel_id }
+            } else {
+                Route::ChannelArchivedPublicThreads { channel_id }
+            };
+            let mut params = vec![("limit", "100".to_string())];
+            if let Some(before) = before {
+                params.push(("before", before));
+            }
+            let data: ThreadsData = self
+                .request(Request::new(route, LightMethod::Get).params(Some(params)))
+                .await?;
+            // アーカイブされた日時の新しい順に返るため、最後のスレッドの日時から続きを取得する
+            before = data
+                .threads
+                .last()
+                .and_then(|thread| thread.thread_metadata)
+                .and_then(|metadata| metadata.archive_timestamp)
+                .map(|timestamp| timestamp.to_string());
+            threads.extend(data.threads);
+            if !data.has_more || before.is_none() {
+                break;
+            }
+        }
+        Ok(threads)
+    }
+
+    /// フォーラムチャンネルのタグ一覧を置き換える。
+    /// serenityのビルダーはタグのIDを送らず、タグが作り直されて投稿から外れてしまうため、IDごと渡す
+    pub async fn edit_forum_tags(
+        &self,
+        channel_id: ChannelId,
+        tags: &[ForumTag],
+    ) -> Result<GuildChannel> {
+        let body = serde_json::to_vec(&serde_json::json!({ "available_tags": tags }))
+            .map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::Channel { channel_id }, LightMethod::Patch).body(Some(body)),
+        )
+        .await
+    }
+
+    /// スレッドを編集する
+    pub async fn edit_thread(
+        &self,
+        channel_id: ChannelId,
+        edit: &EditThread<'_>,
+    ) -> Result<GuildChannel> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::Channel { channel_id }, LightMethod::Patch).body(Some(body)),
+        )
+        .await
+    }
+
+    /// Guildの情報を取得する
+    pub async fn guild(&self, guild_id: GuildId) -> Result<PartialGuild> {
+        self.request(Request::new(Route::Guild { guild_id }, LightMethod::Get))
+            .await
+    }
+
+    /// Guildの設定を編集する
+    pub async fn edit_guild(
+        &self,
+        guild_id: GuildId,
+        edit: &EditGuild<'_>,
+    ) -> Result<PartialGuild> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(Request::new(Route::Guild { guild_id }, LightMethod::Patch).body(Some(body)))
+            .await
+    }
+
+    /// ボイスチャンネルのステータスを設定する。空文字列の場合は消去する
+    pub async fn set_voice_status(&self, channel_id: ChannelId, status: &str) -> Result<()> {
+        let status = (!status.is_empty()).then_some(status);
+        let body = serde_json::to_vec(&serde_json::json!({ "status": status }))
+            .map_err(serenity::Error::from)?;
+        self.request(RawRequest {
+            method: LightMethod::Put,
+            path: format!("{API_BASE}/channels/{channel_id}/voice-status"),
+            body: Some(body),
+        })
+        .await
+    }
+
+    /// Guildのロール一覧を取得する
+    pub async fn guild_roles(&self, guild_id: GuildId) -> Result<Vec<Role>> {
+        self.request(Request::new(
+            Route::GuildRoles { guild_id },
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// Guildのカスタム絵文字一覧を取得する
+    pub async fn guild_emojis(&self, guild_id: GuildId) -> Result<Vec<Emoji>> {
+        self.request(Request::new(
+            Route::GuildEmojis { guild_id },
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// カスタム絵文字の名前を変更する
+    pub async fn rename_emoji(
+        &self,
+        guild_id: GuildId,
+        emoji_id: EmojiId,
+        name: &str,
+    ) -> Result<Emoji> {
+        let body = serde_json::to_vec(&serde_json::json!({ "name": name }))
+            .map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::GuildEmoji { guild_id, emoji_id }, LightMethod::Patch)
+                .body(Some(body)),
+        )
+        .await
+    }
+
+    /// Guildのスタンプ一覧を取得する
+    pub async fn guild_stickers(&self, guild_id: GuildId) -> Result<Vec<Sticker>> {
+        self.request(Request::new(
+            Route::GuildStickers { guild_id },
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// スタンプを編集する
+    pub async fn edit_sticker(
+        &self,
+        guild_id: GuildId,
+        sticker_id: StickerId,
+        edit: &EditSticker<'_>,
+    ) -> Result<Sticker> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(
+                Route::GuildSticker {
+                    guild_id,
+                    sticker_id,
+                },
+                LightMethod::Patch,
+            )
+            .body(Some(body)),
+        )
+        .await
+    }
+
+    /// Guildのサウンドボードのサウンド一覧を取得する
+    pub async fn guild_soundboard_sounds(&self, guild_id: GuildId) -> Result<Vec<SoundboardSound>> {
+        let sounds: SoundboardSounds = self
+            .request(RawRequest {
+                method: LightMethod::Get,
+                path: format!("{API_BASE}/guilds/{guild_id}/soundboard-sounds"),
+                body: None,
+            })
+            .await?;
+        Ok(sounds.items)
+    }
+
+    /// サウンドボードのサウンドの名前を変更する
+    pub async fn rename_soundboard_sound(
+        &self,
+        guild_id: GuildId,
+        sound_id: GenericId,
+        name: &str,
+    ) -> Result<SoundboardSound> {
+        let body = serde_json::to_vec(&serde_json::json!({ "name": name }))
+            .map_err(serenity::Error::from)?;
+        self.request(RawRequest {
+            method: LightMethod::Patch,
+            path: format!("{API_BASE}/guilds/{guild_id}/soundboard-sounds/{sound_id}"),
+            body: Some(body),
+        })
+        .await
+    }
+
+    /// Guildのオンボーディングの設定を取得する。
+    /// 書き戻す際に未知の項目を失わないよう、JSONのまま扱う
+    pub async fn guild_onboarding(&self, guild_id: GuildId) -> Result<serde_json::Value> {
+        self.request(RawRequest {
+            method: LightMethod::Get,
+            path: format!("{API_BASE}/guilds/{guild_id}/onboarding"),
+            body: None,
+        })
+        .await
+    }
+
+    /// Guildのオンボーディングの設定を置き換える
+    pub async fn edit_guild_onboarding(
+        &self,
+        guild_id: GuildId,
+        onboarding: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let body = serde_json::to_vec(onboarding).map_err(serenity::Error::from)?;
+        self.request(RawRequest {
+            method: LightMethod::Put,
+            path: format!("{API_BASE}/guilds/{guild_id}/onboarding"),
+            body: Some(body),
+        })
+        .await
+    }
+
+    /// Guildのウェルカム画面の設定を取得する
+    pub async fn guild_welcome_screen(&self, guild_id: GuildId) -> Result<serde_json::Value> {
+        self.request(RawRequest {
+            method: LightMethod::Get,
+            path: format!("{API_BASE}/guilds/{guild_id}/welcome-screen"),
+            body: None,
+        })
+        .await
+    }
+
+    /// Guildのウェルカム画面の設定を編集する
+    pub async fn edit_guild_welcome_screen(
+        &self,
+        guild_id: GuildId,
+        edit: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(RawRequest {
+            method: LightMethod::Patch,
+            path: format!("{API_BASE}/guilds/{guild_id}/welcome-screen"),
+            body: Some(body),
+        })
+        .await
+    }
+
+    /// GuildのAutoModルール一覧を取得する
+    pub async fn guild_automod_rules(&self, guild_id: GuildId) -> Result<Vec<Rule>> {
+        self.request(Request::new(
+            Route::GuildAutomodRules { guild_id },
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// AutoModルールを編集する。
+    /// serenityのビルダーはトリガーの種類も送ってしまうため、JSONを直接渡す
+    pub async fn edit_automod_rule(
+        &self,
+        guild_id: GuildId,
+        rule_id: RuleId,
+        edit: &serde_json::Value,
+    ) -> Result<Rule> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(
+                Route::GuildAutomodRule { guild_id, rule_id },
+                LightMethod::Patch,
+            )
+            .body(Some(body)),
+        )
+        .await
+    }
+
+    /// ロールを編集する
+    pub async fn edit_role(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+        edit: &EditRole<'_>,
+    ) -> Result<Role> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::GuildRole { guild_id, role_id }, LightMethod::Patch)
+                .body(Some(body)),
+        )
+        .await
+    }
+
+    /// チャンネルの権限の上書きを作成・置換する
+    pub async fn edit_permission(
+        &self,
+        channel_id: ChannelId,
+        overwrite: &PermissionOverwrite,
+    ) -> Result<()> {
+        let target_id = match overwrite.kind {
+            PermissionOverwriteType::Role(id) => TargetId::new(id.get()),
+            PermissionOverwriteType::Member(id) => TargetId::new(id.get()),
+            _ => {
+                return Err(Error::UnsupportedOperation(
+                    "unknown permission overwrite type".into(),
+                ))
+            }
+        };
+        let body = serde_json::to_vec(overwrite).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(
+                Route::ChannelPermission {
+                    channel_id,
+                    target_id,
+                },
+                LightMethod::Put,
+            )
+            .body(Some(body)),
+        )
+        .await
+    }
+
+    /// チャンネルの権限の上書きを削除する
+    pub async fn delete_permission(
+        &self,
+        channel_id: ChannelId,
+        kind: PermissionOverwriteType,
+    ) -> Result<()> {
+        let target_id = match kind {
+            PermissionOverwriteType::Role(id) => TargetId::new(id.get()),
+            PermissionOverwriteType::Member(id) => TargetId::new(id.get()),
+            _ => {
+                return Err(Error::UnsupportedOperation(
+                    "unknown permission overwrite type".into(),
+                ))
+            }
+        };
+        self.request(Request::new(
+            Route::ChannelPermission {
+                channel_id,
+                target_id,
+            },
+            LightMethod::Delete,
+        ))
+        .await
+    }
+
+    /// チャンネルのWebhook一覧を取得する
+    pub async fn channel_webhooks(&self, channel_id: ChannelId) -> Result<Vec<Webhook>> {
+        self.request(Request::new(
+            Route::ChannelWebhooks { channel_id },
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// GuildのWebhook一覧を取得する
+    pub async fn guild_webhooks(&self, guild_id: GuildId) -> Result<Vec<Webhook>> {
+        self.request(Request::new(
+            Route::GuildWebhooks { guild_id },
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// Webhookを編集する
+    pub async fn edit_webhook(
+        &self,
+        webhook_id: WebhookId,
+        edit: &EditWebhook<'_>,
+    ) -> Result<Webhook> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::Webhook { webhook_id }, LightMethod::Patch).body(Some(body)),
+        )
+        .await
+    }
+
+    /// アナウンスチャンネルを指定したチャンネルへフォローする
+    pub async fn follow_channel(
+        &self,
+        channel_id: ChannelId,
+        target: ChannelId,
+    ) -> Result<FollowedChannel> {
+        let body = serde_json::to_vec(&serde_json::json!({ "webhook_channel_id": target }))
+            .map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::ChannelFollowNews { channel_id }, LightMethod::Post)
+                .body(Some(body)),
+        )
+        .await
+    }
+
+    /// チャンネルにメッセージを送信する
+    pub async fn send_message(&self, channel_id: ChannelId, content: &str) -> Result<()> {
+        let body = serde_json::to_vec(&serde_json::json!({ "content": content }))
+            .map_err(serenity::Error::from)?;
+        let _: serde_json::Value = self
+            .request(
+                Request::new(Route::ChannelMessages { channel_id }, LightMethod::Post)
+                    .body(Some(body)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Webhookを削除する
+    pub async fn delete_webhook(&self, webhook_id: WebhookId) -> Result<()> {
+        self.request(Request::new(
+            Route::Webhook { webhook_id },
+            LightMethod::Delete,
+        ))
+        .await
+    }
+
+    /// チャンネルを作成する
+    pub async fn create_channel(
+        &self,
+        guild_id: GuildId,
+        create: &CreateChannel<'_>,
+    ) -> Result<GuildChannel> {
+        let body = serde_json::to_vec(create).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::GuildChannels { guild_id }, LightMethod::Post).body(Some(body)),
+        )
+        .await
+    }
+
+    /// チャンネルを取得する
+    pub async fn channel(&self, channel_id: ChannelId) -> Result<GuildChannel> {
+        self.request(Request::new(
+            Route::Channel { channel_id },
+            LightMethod::Get,
+        ))
+        .await
+    }
+
+    /// チャンネルを削除する
+    pub async fn delete_channel(&self, channel_id: ChannelId) -> Result<GuildChannel> {
+        self.request(Request::new(
+            Route::Channel { channel_id },
+            LightMethod::Delete,
+        ))
+        .await
+    }
+
+    /// Guildのチャンネルの position をまとめて変更する
+    pub async fn edit_channel_positions(
+        &self,
+        guild_id: GuildId,
+        positions: &[(ChannelId, u16)],
+    ) -> Result<()> {
+        let body: Vec<_> = positions
+            .iter()
+            .map(|(id, position)| serde_json::json!({ "id": id, "position": position }))
+            .collect();
+        let body = serde_json::to_vec(&body).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::GuildChannels { guild_id }, LightMethod::Patch).body(Some(body)),
+        )
+        .await
+    }
+
+    /// チャンネルを編集する
+    pub async fn edit_channel(
+        &self,
+        channel_id: ChannelId,
+        edit: &EditChannel<'_>,
+    ) -> Result<GuildChannel> {
+        let body = serde_json::to_vec(edit).map_err(serenity::Error::from)?;
+        self.request(
+            Request::new(Route::Channel { channel_id }, LightMethod::Patch).body(Some(body)),
+        )
+        .await
+    }
+}
+
+/// Discord APIのよくあるエラー (401, 403, 404, 429) を、原因と対処法の分かるエラーに変換する
+fn friendly_error(e: Error, method: LightMethod, path: &str) -> Error {
+    let Error::Serenity(inner) = &e else {
+        return e;
+    };
+    let serenity::Error::Http(HttpError::UnsuccessfulRequest(res)) = &**inner else {
+        return e;
+    };
+    // Discordのエラーメッセージ (Unknown Channel など) と、失敗したリクエスト
+    let detail = || {
+        format!(
+            "{} ({} {})",
+            res.error.message,
+            method.reqwest_method(),
+            path.strip_prefix(API_BASE).unwrap_or(path)
+        )
+        .into()
+    };
+    match res.status_code {
+        StatusCode::UNAUTHORIZED => Error::InvalidToken,
+        StatusCode::FORBIDDEN => Error::Forbidden(detail()),
+        StatusCode::NOT_FOUND => Error::NotFound(detail()),
+        StatusCode::TOO_MANY_REQUESTS => Error::RateLimited(detail()),
+        _ => e,
+    }
+}
+
+/// HTTP通信のログを出力するか
+fn http_logging() -> bool {
+    tracing::enabled!(target: logging::HTTP, Level::DEBUG)
+}
+
+/// serenityのHTTPクライアントを作成する。レートリミットで待つ場合はログで知らせる
+fn build_http(builder: HttpBuilder) -> Http {
+    let mut http = builder.build();
+    if let Some(ratelimiter) = http.ratelimiter.as_mut() {
+        ratelimiter.set_ratelimit_callback(Box::new(move |info| {
+            if http_logging() {
+                let global = if info.global { " (global)" } else { "" };
+                let line = format!(
+                    "{:?} {} -> rate limited{global}, retrying after {:.3}s",
+                    info.method,
+                    info.path,
+                    info.timeout.as_secs_f64(),
+                );
+                debug!(target: logging::HTTP, "{line}");
+            } else if info.timeout >= LONG_WAIT {
+                // 長く待つ場合は、止まっているように見えないよう知らせる
+                info!(
+                    "{}",
+                    tr!(
+                        "rate-limit-waiting",
+                        wait = schedule::format_duration(info.timeout)
+                    )
+                );
+            }
+        }));
+    }
+    http
+}
+
+/// 再送すれば成功する見込みのあるエラーか。
+/// 作成 (POST) は処理されたかどうか分からない場合に再送すると重複するため、届かなかったことが確かな場合のみ
+fn is_transient(e: &serenity::Error, method: LightMethod) -> bool {
+    match e {
+        serenity::Error::Http(HttpError::UnsuccessfulRequest(res)) => {
+            res.status_code == StatusCode::TOO_MANY_REQUESTS
+                || (res.status_code.is_server_error() && !matches!(method, LightMethod::Post))
+        }
+        serenity::Error::Http(HttpError::Request(e)) => {
+            e.is_connect() || (e.is_timeout() && !matches!(method, LightMethod::Post))
+        }
+        _ => false,
+    }
+}
+
+/// 429で再送する前に待つ時間。retry-after、なければバケットの x-ratelimit-reset-after の秒数を使う。
+/// 負の値・無限大・NaN の場合は None とし、長すぎる値は [`MAX_RETRY_AFTER`] に切り詰める
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    ["retry-after", "x-ratelimit-reset-after"]
+        .into_iter()
+        .find_map(|name| {
+            let secs: f64 = headers.get(name)?.to_str().ok()?.parse().ok()?;
+            Duration::try_from_secs_f64(secs).ok()
+        })
+        .map(|wait| wait.min(MAX_RETRY_AFTER))
+}
+
+/// レートリミットによる失敗か
+fn is_rate_limited(e: &serenity::Error) -> bool {
+    matches!(e, serenity::Error::Http(HttpError::UnsuccessfulRequest(res)) if res.status_code == StatusCode::TOO_MANY_REQUESTS)
+}
+
+/// 0以上1未満の擬似乱数
+fn jitter() -> f64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    f64::from(nanos % 1000) / 1000.0
+}
+
+/// 受信途中のJSON配列から、完結した要素を順に切り出す
+#[derive(Default)]
+struct ArraySplitter {
+    /// 現在読んでいる要素のバイト列
+    current: Vec<u8>,
+    /// 括弧のネストの深さ (最外の配列が1)
+    depth: usize,
+    /// 文字列リテラルの中にいるか
+    in_string: bool,
+    /// 直前がバックスラッシュか
+    escaped: bool,
+}
+
+impl ArraySplitter {
+    fn feed(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        let mut elements = Vec::new();
+        for &b in chunk {
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                }
+                self.current.push(b);
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'[' | b'{' => {
+                    self.depth += 1;
+                    if self.depth == 1 {
+                        continue;
+                    }
+                }
+                b']' | b'}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        self.flush(&mut elements);
+                        continue;
+                    }
+                }
+                b',' if self.depth == 1 => {
+                    self.flush(&mut elements);
+                    continue;
+                }
+                _ if self.depth == 0 || b.is_ascii_whitespace() && self.current.is_empty() => {
+                    continue
+                }
+                _ => {}
+            }
+            self.current.push(b);
+        }
+        elements
+    }
+
+    fn flush(&mut self, elements: &mut Vec<Vec<u8>>) {
+        if !self.current.is_empty() {
+            elements.push(std::mem::take(&mut self.current));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `chunks` を順に与え、切り出した要素を文字列で返す
+    fn split(chunks: &[&[u8]]) -> Vec<String> {
+        let mut splitter = ArraySplitter::default();
+        chunks
+            .iter()
+            .flat_map(|chunk| splitter.feed(chunk))
+            .map(|element| String::from_utf8(element).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn brackets_commas_and_escaped_quotes_in_strings_are_kept() {
+        let json = br#"["a,b", "[x]", "{y}", "say \"hi\", [ok]", "back\\"]"#;
+        assert_eq!(
+            split(&[json]),
+            [
+                r#""a,b""#,
+                r#""[x]""#,
+                r#""{y}""#,
+                r#""say \"hi\", [ok]""#,
+                r#""back\\""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_objects_and_arrays_are_one_element() {
+        let json = br#"[{"id": "1", "tags": [{"name": "a"}, {"name": "b"}]}, {"id": "2", "parent": {"id": "1"}}]"#;
+        assert_eq!(
+            split(&[json]),
+            [
+                r#"{"id": "1", "tags": [{"name": "a"}, {"name": "b"}]}"#,
+                r#"{"id": "2", "parent": {"id": "1"}}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_array_has_no_elements() {
+        assert!(split(&[b"[]"]).is_empty());
+        assert!(split(&[b" [ \n ] "]).is_empty());
+    }
+
+    #[test]
+    fn elements_split_across_chunks_are_joined() {
+        let json = br#"[{"name": "a,\"[b]\""}, {"tags": [1, 2]}, "c"]"#;
+        let whole = split(&[json]);
+        assert_eq!(whole.len(), 3);
+        // 全ての位置で2つに分けても、1バイトずつ与えても同じ要素になる
+        for at in 0..=json.len() {
+            let (head, tail) = json.split_at(at);
+            assert_eq!(split(&[head, tail]), whole, "split at {at}");
+        }
+        let bytes: Vec<&[u8]> = json.chunks(1).collect();
+        assert_eq!(split(&bytes), whole);
+        for element in &whole {
+            serde_json::from_str::<serde_json::Value>(element).unwrap();
+        }
+    }
+}
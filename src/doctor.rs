@@ -0,0 +1,296 @@
+use crate::{
+    config::Config,
+    discord::Discord,
+    error::{Error, Result},
+    policy::Policy,
+    redact::redact,
+    schedule::{self, RenameScheduler},
+};
+use edisch_core::{split_command, EditOptions};
+use serenity::all::{ApplicationFlags, GuildId, Permissions};
+use std::{env, path::Path, sync::Arc, time::Duration};
+
+/// 検査結果の表示と集計
+struct Report {
+    color: bool,
+    failed: usize,
+}
+
+impl Report {
+    fn ok(&self, message: impl AsRef<str>) {
+        let mut mark = console::style("✔");
+        if self.color {
+            mark = mark.green();
+        }
+        eprintln!("{mark} {}", redact(message.as_ref()));
+    }
+
+    fn warn(&self, message: impl AsRef<str>, fix: impl AsRef<str>) {
+        let mut mark = console::style("!");
+        if self.color {
+            mark = mark.yellow();
+        }
+        eprintln!("{mark} {}", redact(message.as_ref()));
+        eprintln!("  → {}", fix.as_ref());
+    }
+
+    fn fail(&mut self, message: impl AsRef<str>, fix: impl AsRef<str>) {
+        self.failed += 1;
+        let mut mark = console::style("✘");
+        if self.color {
+            mark = mark.red();
+        }
+        eprintln!("{mark} {}", redact(message.as_ref()));
+        eprintln!("  → {}", fix.as_ref());
+    }
+}
+
+/// コマンドがPATH上 (またはパスとして) 存在するか
+fn command_exists(command: &str) -> bool {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| {
+            let candidate = dir.join(command);
+            candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+        })
+    })
+}
+
+/// 設定・トークン・権限・エディタを検査し、失敗した項目の対処法を表示する
+pub async fn run(
+    config: Result<Config>,
+    discord: Result<Arc<Discord>>,
+    guild_id: Result<GuildId>,
+    color: bool,
+) -> Result<()> {
+    let mut report = Report { color, failed: 0 };
+
+    // 設定ファイル
+    let path = Config::path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    let config = match config {
+        Ok(config) => {
+            report.ok(format!("Config file is valid ({path})"));
+            config
+        }
+        Err(e) => {
+            report.fail(
+                e.to_string(),
+                format!("Fix or remove {path}, or run `edisch init`"),
+            );
+            Config::default()
+        }
+    };
+    match Policy::load() {
+        Ok(_) => report.ok("Naming policy is valid (or absent)"),
+        Err(e) => report.fail(e.to_string(), "Fix the naming policy file"),
+    }
+
+    // エディタ
+    let editor = EditOptions {
+        editor: config.editor.clone(),
+        ..Default::default()
+    }
+    .editor_command();
+    match split_command(&editor) {
+        Ok((program, _)) if command_exists(&program) => {
+            report.ok(format!("Editor found ({editor})"))
+        }
+        Ok((program, _)) => report.fail(
+            format!("Editor not found: {program:?}"),
+            "Set $VISUAL, $EDITOR or `editor` in the config file to an installed editor",
+        ),
+        Err(e) => report.fail(
+            e.to_string(),
+            "Quote the arguments of the editor command as in a shell",
+        ),
+    }
+    if let Some(tmpdir) = &config.tmpdir {
+        if tmpdir.is_dir() {
+            report.ok(format!("Temporary directory exists ({})", tmpdir.display()));
+        } else {
+            report.fail(
+                format!("Temporary directory not found: {}", tmpdir.display()),
+                "Create it or change `tmpdir` in the config file",
+            );
+        }
+    }
+
+    // トークン
+    let discord = match discord {
+        Ok(discord) => discord,
+        Err(e) => {
+            report.fail(
+                e.to_string(),
+                "Pass --token, set $DISCORD_TOKEN, run `edisch auth set`, or run `edisch init`",
+            );
+            return finish(report);
+        }
+    };
+    let user = match discord.current_user().await {
+        Ok(user) => {
+            report.ok(format!("Token is valid (logged in as {})", user.name));
+            user
+        }
+        Err(e) => {
+            report.fail(
+                e.to_string(),
+                "Reset the token on the Bot page of the Discord Developer Portal and update it",
+            );
+            return finish(report);
+        }
+    };
+
+    // レートリミット
+    match discord.rate_limit_status().await {
+        Ok(Some(status)) => match status.retry_after {
+            Some(secs) => report.warn(
+                format!(
+                    "Rate limited by Discord{} for {}",
+                    if status.global { " (global)" } else { "" },
+                    schedule::format_duration(Duration::from_secs_f64(secs))
+                ),
+                "Wait before starting a bulk edit; edisch waits automatically, but the run will stall",
+            ),
+            None => {
+                let bucket = match (status.remaining, status.limit, status.reset_after) {
+                    (Some(remaining), Some(limit), Some(reset)) => format!(
+                        " ({remaining}/{limit} requests left in the bucket, resets in {})",
+                        schedule::format_duration(Duration::from_secs_f64(reset))
+                    ),
+                    _ => String::new(),
+                };
+                report.ok(format!("Not rate limited by Discord{bucket}"));
+            }
+        },
+        Ok(None) => {}
+        Err(e) => report.warn(
+            format!("Cannot check the rate limit status: {e}"),
+            "Check the network connection and --proxy / $HTTPS_PROXY",
+        ),
+    }
+    match RenameScheduler::load().blocked() {
+        (0, _) => report.ok("No channels are waiting for the rename limit (2 per 10 minutes)"),
+        (count, wait) => report.warn(
+            format!(
+                "{count} channel(s) reached the rename limit (2 per 10 minutes) in recent runs",
+            ),
+            format!(
+                "Renaming them again waits up to {}; other channels are not affected",
+                schedule::format_duration(wait)
+            ),
+        ),
+    }
+
+    // 特権インテント (edisch自体はゲートウェイに接続しないため参考情報)
+    if let Ok(application) = discord.current_application().await {
+        let flags = application.flags.unwrap_or(ApplicationFlags::empty());
+        let intents: Vec<_> = [
+            (
+                ApplicationFlags::GATEWAY_GUILD_MEMBERS
+                    | ApplicationFlags::GATEWAY_GUILD_MEMBERS_LIMITED,
+                "Server Members",
+            ),
+            (
+                ApplicationFlags::GATEWAY_PRESENCE | ApplicationFlags::GATEWAY_PRESENCE_LIMITED,
+                "Presence",
+            ),
+            (
+                ApplicationFlags::GATEWAY_MESSAGE_CONTENT
+                    | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED,
+                "Message Content",
+            ),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| flags.intersects(*flag))
+        .map(|(_, name)| name)
+        .collect();
+        let intents = if intents.is_empty() {
+            "none".to_string()
+        } else {
+            intents.join(", ")
+        };
+        report.ok(format!(
+            "Privileged intents enabled: {intents} (edisch does not need any)"
+        ));
+    }
+
+    // Guildでの権限
+    let guild_id = match guild_id {
+        Ok(guild_id) => guild_id,
+        Err(e) => {
+            report.fail(
+                e.to_string(),
+                "Pass --guild-id, set $GUILD_ID, or set `guild_id` in the config file",
+            );
+            return finish(report);
+        }
+    };
+    let guild = match discord.guild(guild_id).await {
+        Ok(guild) => guild,
+        Err(e) => {
+            report.fail(
+                format!("Cannot access guild {guild_id}: {e}"),
+                "Check the guild ID and invite the bot to the guild",
+            );
+            return finish(report);
+        }
+    };
+    report.ok(format!("Bot is a member of {} ({guild_id})", guild.name));
+    let permissions = if guild.owner_id == user.id {
+        Permissions::all()
+    } else {
+        let roles = discord.member_roles(guild_id, user.id).await?;
+        guild
+            .roles
+            .values()
+            .filter(|role| role.id.get() == guild_id.get() || roles.contains(&role.id))
+            .fold(Permissions::empty(), |acc, role| acc | role.permissions)
+    };
+    let has = |permission| {
+        permissions.contains(permission) || permissions.contains(Permissions::ADMINISTRATOR)
+    };
+    if has(Permissions::MANAGE_CHANNELS) {
+        report.ok("Manage Channels is granted");
+    } else {
+        report.fail(
+            "Manage Channels is not granted, so channels cannot be edited",
+            "Give one of the bot's roles the Manage Channels permission in the server settings",
+        );
+    }
+    for (permission, name, used_by) in [
+        (
+            Permissions::MANAGE_ROLES,
+            "Manage Roles",
+            "role-icons and perms",
+        ),
+        (
+            Permissions::MANAGE_GUILD,
+            "Manage Server",
+            "guild, automod, onboarding and welcome",
+        ),
+        (Permissions::MANAGE_WEBHOOKS, "Manage Webhooks", "follows"),
+    ] {
+        if has(permission) {
+            report.ok(format!("{name} is granted"));
+        } else {
+            report.warn(
+                format!("{name} is not granted (needed by {used_by})"),
+                format!("Give one of the bot's roles the {name} permission if you use them"),
+            );
+        }
+    }
+    finish(report)
+}
+
+/// 失敗した項目があればエラーにする
+fn finish(report: Report) -> Result<()> {
+    match report.failed {
+        0 => Ok(()),
+        failed => Err(Error::ChecksFailed(failed)),
+    }
+}
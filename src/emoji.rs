@@ -0,0 +1,77 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::{Emoji, GuildId};
+use std::{fmt::Display, sync::Arc};
+
+/// Guildのカスタム絵文字
+pub struct EmojiItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// 絵文字
+    emoji: Emoji,
+}
+
+impl Display for EmojiItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.emoji.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for EmojiItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        self.emoji = self
+            .discord
+            .rename_emoji(self.guild_id, self.emoji.id, &content)
+            .await?;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.emoji.name.clone()
+    }
+    fn comment(&self) -> String {
+        let kind = if self.emoji.animated {
+            "animated"
+        } else {
+            "static"
+        };
+        format!("{kind} ({})", self.emoji.id)
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if !(2..=32).contains(&new.chars().count()) {
+            return Err(Error::InvalidEditResult(
+                format!("emoji name must be between 2 and 32 characters: {new:?}").into(),
+            ));
+        }
+        if !new.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(Error::InvalidEditResult(
+                format!("emoji name may only contain alphanumerics and underscores: {new:?}")
+                    .into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのカスタム絵文字を取得する。
+/// 連携サービスが管理する絵文字は改名できないため除く
+pub async fn fetch_items(discord: &Arc<Discord>, guild_id: GuildId) -> Result<Vec<EmojiItem>> {
+    Ok(discord
+        .guild_emojis(guild_id)
+        .await?
+        .into_iter()
+        .filter(|emoji| !emoji.managed)
+        .map(|emoji| EmojiItem {
+            discord: discord.clone(),
+            guild_id,
+            emoji,
+        })
+        .collect())
+}
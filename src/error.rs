@@ -1,4 +1,5 @@
-use std::borrow::Cow;
+use crate::i18n::tr;
+use std::{borrow::Cow, io};
 use thiserror::Error;
 
 /// Short-hand for `Result<T, Error>`
@@ -8,38 +9,106 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug, Error)]
 pub enum Error {
     /// 必要な引数が足りない場合
-    #[error("Missing argument: {0}")]
+    #[error("{}", tr!("error-missing-argument", detail = .0.to_string()))]
     MissingArgument(Cow<'static, str>),
     /// 引数のパースに失敗した場合
-    #[error("Failed to parse argument: {0}")]
+    #[error("{}", tr!("error-parse-argument", detail = .0.to_string()))]
     ParseArgument(Cow<'static, str>),
 
+    /// 設定ファイルが不正な場合
+    #[error("{}", tr!("error-config", detail = .0.to_string()))]
+    Config(Cow<'static, str>),
+
+    /// 命名ポリシーに違反した場合
+    #[error("{}", tr!("error-policy-violation", detail = .0.to_string()))]
+    PolicyViolation(Cow<'static, str>),
+
     /// 編集結果が不正な場合
-    #[error("Invalid edit result: {0}")]
+    #[error("{}", tr!("error-invalid-edit-result", detail = .0.to_string()))]
     InvalidEditResult(Cow<'static, str>),
 
-    /// 編集できる対象ではなかった場合
-    #[error("Not an editable item names: {0}")]
-    NotEditableItem(Cow<'static, str>),
+    /// アイテムが対応していない操作が指定された場合
+    #[error("{}", tr!("error-unsupported-operation", detail = .0.to_string()))]
+    UnsupportedOperation(Cow<'static, str>),
 
     /// ファイルの読み書きに失敗した場合 (一時ファイルなど)
-    #[error("IO error: {0}")]
+    #[error("{}", tr!("error-io", detail = .0.to_string()))]
     Io(#[from] std::io::Error),
 
-    /// テキストエディタが正常に終了しなかった場合
-    #[error("EDITOR failed{}", if let Some(code) = .0 { format!(" with code {}", code) } else { String::new() })]
-    Command(Option<i32>),
+    /// 一括編集 (エディタの起動や編集結果の解釈) に失敗した場合
+    #[error(transparent)]
+    Core(#[from] edisch_core::Error),
+
+    /// OSのキーリングを使えない場合
+    #[error("{}", tr!("error-keyring", detail = .0.to_string()))]
+    Keyring(Cow<'static, str>),
+
+    /// 記録ファイルからの再生に失敗した場合
+    #[error("{}", tr!("error-replay", detail = .0.to_string()))]
+    Replay(Cow<'static, str>),
 
     /// チャンネル名が不正な場合
-    #[error("Invalid channel name: {:?} ({})", name, message)]
+    #[error("{}", tr!("error-invalid-channel-name", name = format!("{:?}", .name), detail = *.message))]
     InvalidChannelName { name: String, message: &'static str },
 
+    /// Discordにトークンを拒否された場合 (401)
+    #[error("{}", tr!("error-invalid-token"))]
+    InvalidToken,
+
+    /// 権限不足でDiscordにリクエストを拒否された場合 (403)
+    #[error("{}", tr!("error-forbidden", detail = .0.to_string()))]
+    Forbidden(Cow<'static, str>),
+
+    /// Guild・チャンネルなどが見つからない場合 (404)
+    #[error("{}", tr!("error-not-found", detail = .0.to_string()))]
+    NotFound(Cow<'static, str>),
+
+    /// 再送してもレートリミットが解けなかった場合 (429)
+    #[error("{}", tr!("error-rate-limited", detail = .0.to_string()))]
+    RateLimited(Cow<'static, str>),
+
+    /// doctor の検査項目に失敗したものがある場合
+    #[error("{}", tr!("error-checks-failed", count = .0))]
+    ChecksFailed(usize),
+
+    /// --strict-conflicts で、書き出した後にサーバー上で変更されたアイテムがあった場合
+    #[error("{}", tr!("error-conflicts", count = .0))]
+    Conflicts(usize),
+
+    /// --strict で、Botが編集する権限を持たないチャンネルへの変更があった場合
+    #[error("{}", tr!("error-missing-permissions", count = .0))]
+    MissingPermissions(usize),
+
+    /// 変更後の名前が、同じカテゴリの他のチャンネルと重複する場合
+    #[error("{}", tr!("error-duplicate-names", count = .0))]
+    DuplicateNames(usize),
+
+    /// --force なしで、警告のある変更を適用しようとした場合
+    #[error("{}", tr!("error-warnings", count = .0))]
+    Warnings(usize),
+
+    /// diff で、適用すると変更される項目があった場合
+    #[error("{}", tr!("error-pending-changes", count = .0))]
+    PendingChanges(usize),
+
+    /// --verify で、適用後の値が変更後のテキストと一致しなかった場合
+    #[error("{}", tr!("error-verification-failed", count = .0))]
+    VerificationFailed(usize),
+
+    /// --keep-going で一部の変更の適用に失敗した場合
+    #[error("{}", tr!("error-partial-failure", failed = .failed, total = .total))]
+    PartialFailure { failed: usize, total: usize },
+
+    /// Ctrl-Cで中断された場合
+    #[error("{}", tr!("error-interrupted"))]
+    Interrupted,
+
     // 以下はキャッチされていないかもしれないエラー
     #[error("{0}")]
-    Serenity(#[from] serenity::Error),
+    Serenity(Box<serenity::Error>),
 
     #[error("{0}")]
-    Dialoguer(#[from] dialoguer::Error),
+    Dialoguer(dialoguer::Error),
 }
 
 impl Error {
@@ -47,9 +116,86 @@ impl Error {
     pub fn unknown(&self) -> bool {
         use Error::*;
         // SerenityのHTTPエラーはオフラインなどなのでキャッチしている扱い
-        if let Serenity(serenity::Error::Http(_)) = self {
-            return false;
+        if let Serenity(e) = self {
+            if let serenity::Error::Http(_) = **e {
+                return false;
+            }
         }
         matches!(self, Serenity(_) | Dialoguer(_))
     }
+
+    /// 権限不足でDiscord APIへのリクエストが拒否されたか
+    pub fn is_forbidden(&self) -> bool {
+        matches!(self, Error::Forbidden(_))
+    }
+
+    /// プロセスの終了ステータス。スクリプトが原因を区別できるよう、種類ごとに値を分ける
+    /// (一覧は [`exit_code`])
+    pub fn exit_code(&self) -> i32 {
+        use Error::*;
+        match self {
+            PendingChanges(_) => exit_code::PENDING_CHANGES,
+            PartialFailure { .. } => exit_code::PARTIAL_FAILURE,
+            Interrupted => exit_code::INTERRUPTED,
+            MissingArgument(_)
+            | ParseArgument(_)
+            | Config(_)
+            | PolicyViolation(_)
+            | InvalidEditResult(_)
+            | UnsupportedOperation(_)
+            | InvalidChannelName { .. }
+            | DuplicateNames(_)
+            | Warnings(_) => exit_code::INVALID_INPUT,
+            Core(e)
+                if !matches!(
+                    e,
+                    edisch_core::Error::Io(_) | edisch_core::Error::Command(_)
+                ) =>
+            {
+                exit_code::INVALID_INPUT
+            }
+            InvalidToken | Forbidden(_) | MissingPermissions(_) => exit_code::PERMISSION,
+            NotFound(_) | RateLimited(_) | VerificationFailed(_) => exit_code::API,
+            Serenity(_) if !self.unknown() => exit_code::API,
+            _ => exit_code::ERROR,
+        }
+    }
+}
+
+impl From<serenity::Error> for Error {
+    fn from(e: serenity::Error) -> Self {
+        Error::Serenity(Box::new(e))
+    }
+}
+
+impl From<dialoguer::Error> for Error {
+    fn from(e: dialoguer::Error) -> Self {
+        match e {
+            // 確認中は端末を生のモードで読むため、Ctrl-Cはシグナルではなく読み取りのエラーになる
+            dialoguer::Error::IO(e) if e.kind() == io::ErrorKind::Interrupted => Error::Interrupted,
+            e => Error::Dialoguer(e),
+        }
+    }
+}
+
+/// 終了ステータスの一覧
+pub mod exit_code {
+    /// 成功 (適用する変更がなかった場合も含む)
+    pub const SUCCESS: i32 = 0;
+    /// 他に分類されないエラー
+    pub const ERROR: i32 = 1;
+    /// diff・--dry-run で、適用すると変更される項目があった
+    pub const PENDING_CHANGES: i32 = 2;
+    /// --keep-going で一部の変更の適用に失敗した
+    pub const PARTIAL_FAILURE: i32 = 3;
+    /// --detailed-exit-code で、変更を適用した
+    pub const CHANGES_APPLIED: i32 = 4;
+    /// 引数・入力・設定ファイルが不正、またはポリシーに違反した
+    pub const INVALID_INPUT: i32 = 5;
+    /// トークンが拒否された、または権限が足りない
+    pub const PERMISSION: i32 = 6;
+    /// その他のDiscord APIのエラー (見つからない、レートリミット、接続できないなど)
+    pub const API: i32 = 7;
+    /// Ctrl-Cで中断された
+    pub const INTERRUPTED: i32 = 130;
 }
@@ -0,0 +1,134 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::{ChannelId, ChannelType, GuildId, WebhookId, WebhookType};
+use std::{fmt::Display, sync::Arc};
+
+/// アナウンスチャンネルから対象チャンネルへのフォローの有無
+pub struct FollowItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// フォロー元のアナウンスチャンネル
+    source: ChannelId,
+    /// フォロー元の表示名
+    source_name: String,
+    /// フォロー先のチャンネル
+    target: ChannelId,
+    /// フォローしている場合、そのWebhook
+    webhook: Option<WebhookId>,
+}
+
+impl FollowItem {
+    /// 現在フォローしている場合、フォロー元を表す行
+    pub fn describe(&self) -> Option<String> {
+        self.webhook
+            .map(|_| format!("{}\t{}", self.source, self.source_name))
+    }
+}
+
+/// `yes`/`no` (`y`/`n`、大文字も可) を読み取る
+fn parse_follow(text: &str) -> Result<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "yes" | "y" => Ok(true),
+        "no" | "n" => Ok(false),
+        _ => Err(Error::InvalidEditResult(
+            format!("expected yes or no: {text:?}").into(),
+        )),
+    }
+}
+
+/// フォローの有無の表示
+fn follow_text(followed: bool) -> String {
+    if followed { "yes" } else { "no" }.to_string()
+}
+
+impl Display for FollowItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for FollowItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        match (parse_follow(&content)?, self.webhook) {
+            (true, None) => {
+                let followed = self
+                    .discord
+                    .follow_channel(self.source, self.target)
+                    .await?;
+                self.webhook = Some(followed.webhook_id);
+            }
+            (false, Some(webhook_id)) => {
+                self.discord.delete_webhook(webhook_id).await?;
+                self.webhook = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    fn content(&self) -> String {
+        follow_text(self.webhook.is_some())
+    }
+    fn comment(&self) -> String {
+        format!("📣 {} ({})", self.source_name, self.source)
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        parse_follow(new).map(|_| ())
+    }
+    /// 答えを `yes`/`no` に揃え、現在の状態と同じ答えは変更としない
+    fn normalize(&self, new: &str) -> String {
+        parse_follow(new).map_or_else(|_| new.to_string(), follow_text)
+    }
+}
+
+/// Guildのアナウンスチャンネルと、対象チャンネルへの既存のフォローを取得する。
+/// 他のGuildのチャンネルからのフォローも、解除できるよう含める
+pub async fn fetch_items(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    target: ChannelId,
+) -> Result<Vec<FollowItem>> {
+    let webhooks: Vec<_> = discord
+        .channel_webhooks(target)
+        .await?
+        .into_iter()
+        .filter(|webhook| webhook.kind == WebhookType::ChannelFollower)
+        .collect();
+    let mut items: Vec<_> = discord
+        .guild_channels(guild_id, |_| {})
+        .await?
+        .into_iter()
+        .filter(|channel| channel.kind == ChannelType::News)
+        .map(|channel| FollowItem {
+            discord: discord.clone(),
+            source: channel.id,
+            source_name: channel.name,
+            target,
+            webhook: None,
+        })
+        .collect();
+    for webhook in webhooks {
+        let Some(source) = webhook.source_channel else {
+            continue;
+        };
+        match items.iter_mut().find(|item| item.source == source.id) {
+            Some(item) => item.webhook = Some(webhook.id),
+            None => items.push(FollowItem {
+                discord: discord.clone(),
+                source: source.id,
+                source_name: match webhook.source_guild {
+                    Some(guild) => format!("{} in {}", source.name, guild.name),
+                    None => source.name,
+                },
+                target,
+                webhook: Some(webhook.id),
+            }),
+        }
+    }
+    Ok(items)
+}
@@ -0,0 +1,366 @@
+use crate::{
+    args::ExportFormat,
+    error::{Error, Result},
+};
+use serde_json::{Map, Value};
+use serenity::all::GuildId;
+
+/// 書き出し時の値を記録する列の接頭辞
+const BASE_PREFIX: &str = "base:";
+
+/// Guild IDの列の接頭辞。数値のコメントや値の列と区別できるようにする
+pub const GUILD_PREFIX: &str = "guild:";
+
+/// 行のコメントにある `(…)` のID。コメントのない行は None
+fn line_id(line: &str) -> Option<&str> {
+    line.split('\t')
+        .skip(1)
+        .filter(|cell| cell.trim_end().ends_with(')'))
+        .map(edisch_core::comment_key)
+        .find(|key| !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// IDが `ids` にない行を取り除く。IDを持たない行は残す
+pub fn select_lines(text: &str, ids: &[String]) -> String {
+    text.lines()
+        .filter(|line| line_id(line).is_none_or(|id| ids.iter().any(|selected| selected == id)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 書き出し時の値と適用時の値を比べるための短いハッシュ (FNV-1a 32bit)
+pub fn fingerprint(text: &str) -> String {
+    let hash = text.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    format!("{hash:08x}")
+}
+
+/// 各行の末尾に、書き出し時の値のハッシュの列を付加する
+pub fn with_base_column(text: &str, bases: &[String]) -> String {
+    text.lines()
+        .zip(bases)
+        .map(|(line, base)| {
+            // コメント列がない行も列の位置を揃える
+            let sep = if line.contains('\t') { "\t" } else { "\t\t" };
+            format!("{line}{sep}{BASE_PREFIX}{base}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 行末の書き出し時の値のハッシュの列を取り除き、各行のハッシュとともに返す
+pub fn strip_base_column(text: &str) -> (String, Vec<Option<String>>) {
+    let mut stripped = String::new();
+    let mut bases = Vec::new();
+    for line in text.lines() {
+        let (line, base) = match line.rsplit_once(&format!("\t{BASE_PREFIX}")) {
+            Some((rest, base)) => {
+                // コメント列がない行に揃えのため付加した空の列も取り除く
+                let rest = rest
+                    .strip_suffix('\t')
+                    .filter(|rest| !rest.contains('\t'))
+                    .unwrap_or(rest);
+                (rest, Some(base.trim().to_string()))
+            }
+            None => (line, None),
+        };
+        stripped.push_str(line);
+        stripped.push('\n');
+        bases.push(base);
+    }
+    (stripped, bases)
+}
+
+/// 各行の末尾に、その行のアイテムが属するGuildのIDの列を付加する
+pub fn with_guild_column(text: &str, guilds: &[GuildId]) -> String {
+    text.lines()
+        .zip(guilds)
+        .map(|(line, guild_id)| {
+            // コメント列がない行も列の位置を揃える
+            let sep = if line.contains('\t') { "\t" } else { "\t\t" };
+            format!("{line}{sep}{GUILD_PREFIX}{guild_id}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 行末のGuild列 (`guild:<id>`) を読み取る
+fn guild_column(line: &str) -> Option<GuildId> {
+    let (_, last) = line.rsplit_once('\t')?;
+    last.trim()
+        .strip_prefix(GUILD_PREFIX)?
+        .parse()
+        .ok()
+        .filter(|id| *id != 0)
+        .map(GuildId::new)
+}
+
+/// 入力の行をGuild列ごとにまとめ、各行の入力上の行番号 (1始まり) を添える。
+/// Guild列は取り除き、Guild列のない行は None にまとめる。
+/// 各Guildの行の順序と、Guildが最初に現れた順序は保たれる。
+/// どのGuildの行でもない空行と `#` のコメント行は取り除く
+pub fn group_by_guild(text: &str) -> Vec<(Option<GuildId>, String, Vec<usize>)> {
+    let mut groups: Vec<(Option<GuildId>, String, Vec<usize>)> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || (trimmed.starts_with('#') && !line.contains('\t')) {
+            continue;
+        }
+        let guild_id = guild_column(line);
+        let (lines, numbers) = match groups.iter_mut().find(|(id, _, _)| *id == guild_id) {
+            Some((_, lines, numbers)) => (lines, numbers),
+            None => {
+                groups.push((guild_id, String::new(), Vec::new()));
+                let (_, lines, numbers) = groups.last_mut().unwrap();
+                (lines, numbers)
+            }
+        };
+        // コメントのIDで行を照合できるよう、Guild列を取り除く
+        let line = match guild_id {
+            Some(_) => {
+                let rest = line.rsplit_once('\t').map_or(line, |(rest, _)| rest);
+                // コメント列のない行に揃えのため付加した空の列も取り除く
+                rest.strip_suffix('\t')
+                    .filter(|rest| !rest.contains('\t'))
+                    .unwrap_or(rest)
+            }
+            None => line,
+        };
+        lines.push_str(line);
+        lines.push('\n');
+        numbers.push(n + 1);
+    }
+    groups
+}
+
+/// 表をCSVで書き出す。文字列は必要な場合のみ引用符で囲み、null は空欄とする
+pub fn to_csv(columns: &[&str], rows: &[Vec<serde_json::Value>]) -> String {
+    let quote = |cell: &str| {
+        if cell.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.to_string()
+        }
+    };
+    let mut text = columns.join(",");
+    text.push('\n');
+    for row in rows {
+        let cells: Vec<_> = row
+            .iter()
+            .map(|cell| match cell {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(s) => quote(s),
+                other => quote(&other.to_string()),
+            })
+            .collect();
+        text.push_str(&cells.join(","));
+        text.push('\n');
+    }
+    text
+}
+
+/// 表をYAMLのマッピングのシーケンスで書き出す。列の順序は `columns` のとおりとする
+pub fn to_yaml(columns: &[&str], rows: &[Vec<serde_json::Value>]) -> Result<String> {
+    let records = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .zip(row)
+                .map(|(column, cell)| Ok(((*column).into(), serde_yaml::to_value(cell)?)))
+                .collect::<std::result::Result<serde_yaml::Mapping, serde_yaml::Error>>()
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .and_then(|records| serde_yaml::to_string(&records));
+    records.map_err(|e| Error::InvalidEditResult(format!("invalid YAML records: {e}").into()))
+}
+
+/// `export --format` で書き出したレコードを読み込む
+pub fn parse_records(text: &str, format: ExportFormat) -> Result<Vec<Map<String, Value>>> {
+    match format {
+        ExportFormat::Json => serde_json::from_str(text)
+            .map_err(|e| Error::InvalidEditResult(format!("invalid JSON records: {e}").into())),
+        ExportFormat::Yaml => from_yaml(text),
+        ExportFormat::Csv => from_csv(text),
+        ExportFormat::Tsv => unreachable!("tsv is read as lines, not records"),
+    }
+}
+
+/// 1行目を列名とするCSVを読み込む。空欄は null とする
+fn from_csv(text: &str) -> Result<Vec<Map<String, Value>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                // 引用符の中の "" は " を表す
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut cell)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err(Error::InvalidEditResult(
+            "invalid CSV records: unterminated quoted cell".into(),
+        ));
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    let mut rows = rows
+        .into_iter()
+        .filter(|row| !(row.len() == 1 && row[0].is_empty()));
+    let Some(header) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    rows.enumerate()
+        .map(|(i, row)| {
+            if row.len() != header.len() {
+                return Err(Error::InvalidEditResult(
+                    format!(
+                        "invalid CSV records: row {} has {} cells, expected {}",
+                        i + 2,
+                        row.len(),
+                        header.len()
+                    )
+                    .into(),
+                ));
+            }
+            Ok(header
+                .iter()
+                .zip(row)
+                .map(|(column, cell)| {
+                    let value = if cell.is_empty() {
+                        Value::Null
+                    } else {
+                        Value::String(cell)
+                    };
+                    (column.clone(), value)
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// マッピングのシーケンスのYAMLを読み込む。空の文書はレコードなしとする
+fn from_yaml(text: &str) -> Result<Vec<Map<String, Value>>> {
+    serde_yaml::from_str::<Option<Vec<Map<String, Value>>>>(text)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| Error::InvalidEditResult(format!("invalid YAML records: {e}").into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn yaml_records_round_trip() {
+        let columns = ["id", "name", "parent", "position"];
+        let rows = vec![
+            vec![json!("10"), json!("a: b # c"), Value::Null, json!(0)],
+            vec![
+                json!("12"),
+                json!("- \"quoted\"\nnext"),
+                json!("true"),
+                json!(3),
+            ],
+        ];
+        let text = to_yaml(&columns, &rows).unwrap();
+        assert!(text.starts_with("- id: '10'\n  name:"), "{text}");
+        let records = from_yaml(&text).unwrap();
+        assert_eq!(records.len(), 2);
+        for (record, row) in records.iter().zip(&rows) {
+            for (column, cell) in columns.iter().zip(row) {
+                assert_eq!(&record[*column], cell, "{column} in {text}");
+            }
+        }
+        assert_eq!(to_yaml(&columns, &[]).unwrap(), "[]\n");
+        assert!(from_yaml("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn hand_written_yaml_records_are_read() {
+        let text = "\
+# edited by hand
+-   id: 10
+    name: >-
+      folded
+      name
+- {id: '11', name: \"flow: mapping\"}
+-
+  id: '12'
+  name: |-
+    block
+";
+        let records = from_yaml(text).unwrap();
+        let names: Vec<_> = records.iter().map(|record| &record["name"]).collect();
+        assert_eq!(
+            names,
+            [
+                &json!("folded name"),
+                &json!("flow: mapping"),
+                &json!("block")
+            ]
+        );
+        assert_eq!(records[0]["id"], json!(10));
+        assert!(from_yaml("id: 10").is_err());
+    }
+
+    #[test]
+    fn guild_column_round_trips_mixed_guilds() {
+        let text = "general\t📝 (10)\nStuff\t📁 (11)\ngeneral\t📝 (20)\nbare";
+        let guilds = [1, 1, 2, 3].map(GuildId::new);
+        let with = with_guild_column(text, &guilds);
+        assert_eq!(
+            with,
+            "general\t📝 (10)\tguild:1\nStuff\t📁 (11)\tguild:1\ngeneral\t📝 (20)\tguild:2\nbare\t\tguild:3"
+        );
+        assert_eq!(
+            group_by_guild(&with),
+            [
+                (
+                    Some(GuildId::new(1)),
+                    "general\t📝 (10)\nStuff\t📁 (11)\n".to_string(),
+                    vec![1, 2]
+                ),
+                (
+                    Some(GuildId::new(2)),
+                    "general\t📝 (20)\n".to_string(),
+                    vec![3]
+                ),
+                (Some(GuildId::new(3)), "bare\n".to_string(), vec![4]),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_without_a_guild_column_are_grouped_apart() {
+        let text = "general\t📝 (10)\tguild:1\nchat\t📝 (12)\n# note\n\nlobby\t📝 (20)\tguild:2";
+        let groups: Vec<_> = group_by_guild(text)
+            .into_iter()
+            .map(|(guild_id, _, numbers)| (guild_id.map(GuildId::get), numbers))
+            .collect();
+        assert_eq!(
+            groups,
+            [(Some(1), vec![1]), (None, vec![2]), (Some(2), vec![5])]
+        );
+    }
+}
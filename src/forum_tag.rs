@@ -0,0 +1,100 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::{ChannelId, ChannelType, ForumEmoji, ForumTag, ForumTagId, GuildId};
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+/// フォーラムチャンネルのタグ
+pub struct ForumTagItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// フォーラムチャンネルのID
+    forum: ChannelId,
+    /// フォーラムチャンネルの名前
+    forum_name: String,
+    /// フォーラムのタグ一覧。更新は一覧全体の置き換えになるため、同じフォーラムのアイテムで共有する
+    tags: Arc<Mutex<Vec<ForumTag>>>,
+    /// タグID
+    id: ForumTagId,
+    /// 現在のタグ名
+    name: String,
+    /// タグの絵文字
+    emoji: Option<ForumEmoji>,
+}
+
+impl Display for ForumTagItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for ForumTagItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let tags = {
+            let mut tags = self.tags.lock().unwrap();
+            let Some(tag) = tags.iter_mut().find(|tag| tag.id == self.id) else {
+                return Err(Error::InvalidEditResult(
+                    format!("tag no longer exists: {}", self.name).into(),
+                ));
+            };
+            tag.name.clone_from(&content);
+            tags.clone()
+        };
+        let forum = self.discord.edit_forum_tags(self.forum, &tags).await?;
+        *self.tags.lock().unwrap() = forum.available_tags;
+        self.name = content;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.name.clone()
+    }
+    fn comment(&self) -> String {
+        let emoji = match &self.emoji {
+            Some(ForumEmoji::Name(name)) => format!("{name} "),
+            Some(ForumEmoji::Id(id)) => format!("<:_:{id}> "),
+            _ => String::new(),
+        };
+        format!("{emoji}in #{} ({})", self.forum_name, self.id)
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if !(1..=20).contains(&new.chars().count()) {
+            return Err(Error::InvalidEditResult(
+                format!("tag name must be between 1 and 20 characters: {new:?}").into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildの全てのフォーラムチャンネルのタグを、チャンネルの並び順に取得する
+pub async fn fetch_items(discord: &Arc<Discord>, guild_id: GuildId) -> Result<Vec<ForumTagItem>> {
+    let mut forums: Vec<_> = discord
+        .guild_channels(guild_id, |_| {})
+        .await?
+        .into_iter()
+        .filter(|channel| channel.kind == ChannelType::Forum)
+        .collect();
+    forums.sort_by_key(|forum| forum.position);
+    let mut items = Vec::new();
+    for forum in forums {
+        let tags = Arc::new(Mutex::new(forum.available_tags.clone()));
+        items.extend(forum.available_tags.into_iter().map(|tag| ForumTagItem {
+            discord: discord.clone(),
+            forum: forum.id,
+            forum_name: forum.name.clone(),
+            tags: tags.clone(),
+            id: tag.id,
+            name: tag.name,
+            emoji: tag.emoji,
+        }));
+    }
+    Ok(items)
+}
@@ -0,0 +1,191 @@
+use crate::{
+    channel::{escape_line, unescape_line},
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::{AfkTimeout, ChannelId, ChannelType, EditGuild, GuildChannel, GuildId};
+use std::{fmt::Display, sync::Arc};
+
+/// 編集できるGuildの設定項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GuildField {
+    /// サーバー名
+    Name,
+    /// サーバーの説明
+    Description,
+    /// システムメッセージを送信するチャンネル
+    SystemChannel,
+    /// AFKチャンネル
+    AfkChannel,
+    /// AFKチャンネルへ移動するまでの秒数
+    AfkTimeout,
+}
+
+impl GuildField {
+    const ALL: [GuildField; 5] = [
+        GuildField::Name,
+        GuildField::Description,
+        GuildField::SystemChannel,
+        GuildField::AfkChannel,
+        GuildField::AfkTimeout,
+    ];
+
+    /// プレビューなどで項目を識別するキー
+    fn key(self) -> &'static str {
+        match self {
+            GuildField::Name => "name",
+            GuildField::Description => "description",
+            GuildField::SystemChannel => "system_channel",
+            GuildField::AfkChannel => "afk_channel",
+            GuildField::AfkTimeout => "afk_timeout",
+        }
+    }
+}
+
+/// AFKタイムアウトとして指定できる秒数
+const AFK_TIMEOUTS: [u16; 5] = [60, 300, 900, 1800, 3600];
+
+/// Guildの設定項目の1つ
+pub struct GuildFieldItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// 設定項目
+    field: GuildField,
+    /// 現在の値
+    value: String,
+    /// Guildの全チャンネル。チャンネルを指す項目の名前解決に使う
+    channels: Arc<Vec<GuildChannel>>,
+}
+
+impl GuildFieldItem {
+    /// 名前またはIDから、項目に指定できる種類のチャンネルを探す。空の場合は未設定を表す
+    fn find_channel(&self, target: &str) -> Result<Option<ChannelId>> {
+        if target.is_empty() {
+            return Ok(None);
+        }
+        let kind = match self.field {
+            GuildField::AfkChannel => ChannelType::Voice,
+            _ => ChannelType::Text,
+        };
+        let found: Vec<_> = self
+            .channels
+            .iter()
+            .filter(|c| c.kind == kind && (c.name == target || c.id.to_string() == target))
+            .map(|c| c.id)
+            .collect();
+        match found[..] {
+            [id] => Ok(Some(id)),
+            [] => Err(Error::InvalidEditResult(
+                format!("channel not found: {target:?}").into(),
+            )),
+            _ => Err(Error::InvalidEditResult(
+                format!("ambiguous channel name: {target:?} (use the ID instead)").into(),
+            )),
+        }
+    }
+
+    fn afk_timeout(value: &str) -> Result<AfkTimeout> {
+        match value.parse() {
+            Ok(secs) if AFK_TIMEOUTS.contains(&secs) => Ok(AfkTimeout::from(secs)),
+            _ => Err(Error::InvalidEditResult(
+                format!("AFK timeout must be one of {AFK_TIMEOUTS:?} seconds: {value:?}").into(),
+            )),
+        }
+    }
+}
+
+impl Display for GuildFieldItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.field.key())
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for GuildFieldItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let edit = match self.field {
+            GuildField::Name => EditGuild::new().name(content),
+            GuildField::Description => EditGuild::new().description(unescape_line(&content)),
+            GuildField::SystemChannel => {
+                EditGuild::new().system_channel_id(self.find_channel(&content)?)
+            }
+            GuildField::AfkChannel => EditGuild::new().afk_channel(self.find_channel(&content)?),
+            GuildField::AfkTimeout => EditGuild::new().afk_timeout(Self::afk_timeout(&content)?),
+        };
+        self.discord.edit_guild(self.guild_id, &edit).await?;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.value.clone()
+    }
+    fn comment(&self) -> String {
+        match self.field {
+            GuildField::Name => "server name",
+            GuildField::Description => "description (newlines and tabs written as \\n and \\t)",
+            GuildField::SystemChannel => "system messages channel (name or ID, empty for none)",
+            GuildField::AfkChannel => "AFK channel (name or ID, empty for none)",
+            GuildField::AfkTimeout => "AFK timeout in seconds",
+        }
+        .to_string()
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        match self.field {
+            GuildField::Name => {
+                if !(2..=100).contains(&new.chars().count()) {
+                    return Err(Error::InvalidEditResult(
+                        "server name must be between 2 and 100 characters".into(),
+                    ));
+                }
+            }
+            GuildField::Description => {}
+            GuildField::SystemChannel | GuildField::AfkChannel => {
+                self.find_channel(new)?;
+            }
+            GuildField::AfkTimeout => {
+                Self::afk_timeout(new)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildの設定項目を取得する
+pub async fn fetch_items(discord: &Arc<Discord>, guild_id: GuildId) -> Result<Vec<GuildFieldItem>> {
+    let guild = discord.guild(guild_id).await?;
+    let channels = Arc::new(discord.guild_channels(guild_id, |_| {}).await?);
+    let channel_name = |id: Option<ChannelId>| {
+        id.and_then(|id| channels.iter().find(|c| c.id == id))
+            .map(|c| c.name.clone())
+            .unwrap_or_default()
+    };
+    let afk = guild.afk_metadata.as_ref();
+    Ok(GuildField::ALL
+        .into_iter()
+        .map(|field| {
+            let value = match field {
+                GuildField::Name => guild.name.clone(),
+                // 改行を含む説明は、チャンネルのトピックと同様にエスケープして1行に収める
+                GuildField::Description => {
+                    escape_line(guild.description.as_deref().unwrap_or_default())
+                }
+                GuildField::SystemChannel => channel_name(guild.system_channel_id),
+                GuildField::AfkChannel => channel_name(afk.map(|afk| afk.afk_channel_id)),
+                GuildField::AfkTimeout => afk
+                    .map(|afk| u16::from(afk.afk_timeout).to_string())
+                    .unwrap_or_default(),
+            };
+            GuildFieldItem {
+                discord: discord.clone(),
+                guild_id,
+                field,
+                value,
+                channels: channels.clone(),
+            }
+        })
+        .collect())
+}
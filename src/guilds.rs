@@ -0,0 +1,59 @@
+use crate::{args::OutputFormat, discord::Discord, error::Result};
+use serde::Serialize;
+use serenity::all::GuildId;
+use std::io::{stdout, Write};
+use unicode_width::UnicodeWidthStr;
+
+/// 一覧に表示するGuildの情報
+#[derive(Serialize)]
+struct GuildSummary {
+    id: GuildId,
+    name: String,
+    channels: usize,
+}
+
+/// トークンでアクセスできるGuildの一覧を、チャンネル数とともに出力する
+pub async fn list(discord: &Discord, format: OutputFormat) -> Result<()> {
+    let mut guilds = Vec::new();
+    for guild in discord.current_user_guilds().await? {
+        let channels = discord.guild_channels(guild.id, |_| {}).await?.len();
+        guilds.push(GuildSummary {
+            id: guild.id,
+            name: guild.name,
+            channels,
+        });
+    }
+
+    let mut stdout = stdout().lock();
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout, &guilds).map_err(serenity::Error::from)?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Text => {
+            // IDと名前の幅を揃えて表示する
+            let id_width = guilds
+                .iter()
+                .map(|guild| guild.id.to_string().len())
+                .max()
+                .unwrap_or(0);
+            let name_width = guilds
+                .iter()
+                .map(|guild| guild.name.width())
+                .max()
+                .unwrap_or(0);
+            for guild in &guilds {
+                let padding = name_width - guild.name.width();
+                writeln!(
+                    stdout,
+                    "{:<id_width$}  {}{:padding$}  {} channel(s)",
+                    guild.id.to_string(),
+                    guild.name,
+                    "",
+                    guild.channels,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
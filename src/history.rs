@@ -0,0 +1,215 @@
+use crate::{
+    args::{parse_age, HistoryArgs, OutputFormat},
+    config::Config,
+    error::{Error, Result},
+};
+use edisch_core::Op;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::{all::GuildId, model::Timestamp};
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, stdout, BufWriter, Write},
+    path::PathBuf,
+};
+use tracing::warn;
+
+/// 適用した全ての変更を記録するファイルのパス。$EDISCH_HISTORY_FILE で上書きできる
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("EDISCH_HISTORY_FILE") {
+        return Some(path.into());
+    }
+    dirs::data_local_dir().map(|dir| dir.join("edisch").join("history.jsonl"))
+}
+
+/// 適用した1つの変更の記録
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Record {
+    /// 適用した日時 (RFC 3339)
+    pub time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild: Option<String>,
+    /// 変更したアイテム (作成した場合は作成したアイテム) のID
+    pub id: String,
+    pub op: String,
+    pub old: String,
+    pub new: String,
+    /// 変更したBotのユーザー (`name (id)`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+}
+
+/// 適用した変更を、1行に1つのJSONとして履歴ファイルに追記する。
+/// 中断しても適用済みの変更が残るよう、変更ごとに書き込む
+pub struct HistoryLog {
+    path: Option<PathBuf>,
+    /// アイテムのIDと所属するGuildの対応。対応がなければ default_guild とする
+    guilds: HashMap<String, GuildId>,
+    /// 1つのGuildのみを編集する場合のGuild
+    default_guild: Option<GuildId>,
+    actor: Option<String>,
+}
+
+impl HistoryLog {
+    pub fn new(default_guild: Option<GuildId>) -> Self {
+        Self {
+            path: path(),
+            guilds: HashMap::new(),
+            default_guild,
+            actor: None,
+        }
+    }
+
+    /// アイテムのID (アイテムの表示) と所属するGuildの対応を設定する
+    pub fn set_guilds(&mut self, guilds: HashMap<String, GuildId>) {
+        self.guilds = guilds;
+    }
+
+    /// アイテムのID (アイテムの表示) が所属するGuild
+    pub fn guild(&self, id: &str) -> Option<GuildId> {
+        self.guilds.get(id).copied().or(self.default_guild)
+    }
+
+    /// 変更したBotのユーザーを設定する
+    pub fn set_actor(&mut self, actor: String) {
+        self.actor = Some(actor);
+    }
+
+    /// 適用した変更を追記する。`id` は変更前のアイテム、`result_id` は適用後のアイテムのID
+    pub fn record(&mut self, id: &str, result_id: &str, op: &Op, old: &str, new: &str) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let record = Record {
+            time: Timestamp::now().to_string(),
+            guild: self.guild(id).map(|guild_id| guild_id.to_string()),
+            id: result_id.to_string(),
+            op: op.to_string(),
+            old: old.to_string(),
+            new: match op {
+                Op::Drop => String::new(),
+                _ => new.to_string(),
+            },
+            actor: self.actor.clone(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                let line = serde_json::to_string(&record).map_err(io::Error::from)?;
+                writeln!(file, "{line}")
+            });
+        if let Err(e) = result {
+            warn!("Failed to write history file {}: {e}", path.display());
+            // 以降の変更で警告を繰り返さない
+            self.path = None;
+        }
+    }
+}
+
+/// 履歴ファイルの記録のうち、条件に合うものを古い順に表示する
+pub fn print(args: &HistoryArgs) -> Result<()> {
+    let Some(path) = path() else {
+        return Err(Error::MissingArgument("EDISCH_HISTORY_FILE".into()));
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let guild = match &args.guild {
+        Some(guild) => Some(Config::load()?.resolve_guild(guild)?.to_string()),
+        None => None,
+    };
+    let channels: Vec<_> = args.channel.iter().map(ToString::to_string).collect();
+    let since = match &args.since {
+        Some(age) => {
+            let age = parse_age(age).map_err(|e| Error::ParseArgument(e.into()))?;
+            Some(Timestamp::now().unix_timestamp() - age.as_secs() as i64)
+        }
+        None => None,
+    };
+    let pattern = match &args.grep {
+        Some(pattern) => Some(
+            Regex::new(pattern).map_err(|e| Error::ParseArgument(format!("--grep: {e}").into()))?,
+        ),
+        None => None,
+    };
+    let mut records = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = match serde_json::from_str(line) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!(
+                    "{}:{}: skipped an unreadable record: {e}",
+                    path.display(),
+                    n + 1
+                );
+                continue;
+            }
+        };
+        if guild.is_some() && record.guild != guild {
+            continue;
+        }
+        if !channels.is_empty() && !channels.contains(&record.id) {
+            continue;
+        }
+        if let Some(since) = since {
+            let time = Timestamp::parse(&record.time).map(|time| time.unix_timestamp());
+            if time.map_or(true, |time| time < since) {
+                continue;
+            }
+        }
+        if let Some(pattern) = &pattern {
+            if !pattern.is_match(&record.old) && !pattern.is_match(&record.new) {
+                continue;
+            }
+        }
+        records.push(record);
+    }
+    if let Some(last) = args.last {
+        records.drain(..records.len().saturating_sub(last));
+    }
+
+    let mut output = BufWriter::new(stdout());
+    for record in &records {
+        match args.format {
+            OutputFormat::Json => writeln!(
+                output,
+                "{}",
+                serde_json::to_string(record).map_err(io::Error::from)?
+            )?,
+            OutputFormat::Text => {
+                let change = match record.op.as_str() {
+                    "rename" => format!("{} -> {}", record.old, record.new),
+                    "create" => format!("(new) -> {}", record.new),
+                    _ => format!("{} -> ({})", record.old, record.op),
+                };
+                writeln!(
+                    output,
+                    "{}  {}  ({}){}{}",
+                    record.time,
+                    change.replace('\n', "⏎"),
+                    record.id,
+                    record
+                        .guild
+                        .as_ref()
+                        .map(|guild| format!(" in {guild}"))
+                        .unwrap_or_default(),
+                    record
+                        .actor
+                        .as_ref()
+                        .map(|actor| format!(" by {actor}"))
+                        .unwrap_or_default(),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::args::Lang;
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use std::{env, sync::OnceLock};
+use unic_langid::LanguageIdentifier;
+
+/// 英語のメッセージ。他の言語のカタログにないメッセージもこれで表示する
+const EN: &str = include_str!("../locales/en.ftl");
+/// 日本語のメッセージ
+const JA: &str = include_str!("../locales/ja.ftl");
+
+/// 選ばれた言語のカタログと、英語のカタログ
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// 以降のメッセージの言語を選ぶ。指定がなければ $LC_ALL, $LC_MESSAGES, $LANG の順に決める
+pub fn set_lang(lang: Option<Lang>) {
+    BUNDLES.set(bundles(lang.unwrap_or_else(detect))).ok();
+}
+
+/// 環境変数のロケールに対応する言語。対応していない言語や未設定の場合は英語
+fn detect() -> Lang {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    if locale.starts_with("ja") {
+        Lang::Ja
+    } else {
+        Lang::En
+    }
+}
+
+fn bundle(locale: &str, source: &str) -> FluentBundle<FluentResource> {
+    let id: LanguageIdentifier = locale.parse().expect("valid language identifier");
+    let mut bundle = FluentBundle::new_concurrent(vec![id]);
+    // 端末にそのまま表示するため、埋め込んだ値を方向制御文字で囲まない
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(source.to_string()).expect("valid message catalog");
+    bundle
+        .add_resource(resource)
+        .expect("no duplicate messages in the catalog");
+    bundle
+}
+
+fn bundles(lang: Lang) -> Vec<FluentBundle<FluentResource>> {
+    match lang {
+        Lang::En => vec![bundle("en", EN)],
+        Lang::Ja => vec![bundle("ja", JA), bundle("en", EN)],
+    }
+}
+
+/// メッセージ `id` に引数を埋め込んだ文字列。カタログにない場合は `id` をそのまま返す
+pub fn message(id: &str, args: &[(&str, FluentValue)]) -> String {
+    let bundles = BUNDLES.get_or_init(|| bundles(detect()));
+    let args: FluentArgs = args.iter().cloned().collect();
+    for bundle in bundles {
+        let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+            continue;
+        };
+        let mut errors = Vec::new();
+        return bundle
+            .format_pattern(pattern, Some(&args), &mut errors)
+            .into_owned();
+    }
+    id.to_string()
+}
+
+/// 現在の言語のメッセージを得る。`tr!("id", name = value, ...)` の値は [`FluentValue`] に変換できるもの
+macro_rules! tr {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::message(
+            $id,
+            &[$((stringify!($name), ::fluent_bundle::FluentValue::from($value))),*],
+        )
+    };
+}
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// カタログに定義されたメッセージのID (行頭の `id =`)
+    fn ids(source: &str) -> Vec<&str> {
+        source
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|line| Some(line.split_once(" =")?.0))
+            .collect()
+    }
+
+    #[test]
+    fn catalogs_define_the_same_messages() {
+        let (en, ja) = (bundle("en", EN), bundle("ja", JA));
+        for id in ids(EN) {
+            assert!(ja.has_message(id), "{id} is missing in ja.ftl");
+        }
+        for id in ids(JA) {
+            assert!(en.has_message(id), "{id} is missing in en.ftl");
+        }
+    }
+
+    #[test]
+    fn plurals_and_arguments_are_formatted() {
+        let en = bundles(Lang::En);
+        let format = |bundles: &[FluentBundle<FluentResource>], count: usize| {
+            let bundle = &bundles[0];
+            let pattern = bundle
+                .get_message("changes-count")
+                .unwrap()
+                .value()
+                .unwrap();
+            let args: FluentArgs = [("count", FluentValue::from(count))].into_iter().collect();
+            bundle
+                .format_pattern(pattern, Some(&args), &mut Vec::new())
+                .into_owned()
+        };
+        assert_eq!(format(&en, 1), "1 change");
+        assert_eq!(format(&en, 3), "3 changes");
+        assert_eq!(format(&bundles(Lang::Ja), 3), "3 件の変更");
+    }
+}
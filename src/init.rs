@@ -0,0 +1,113 @@
+use crate::{
+    args::{ChannelFilterArgs, TokenType},
+    config::Config,
+    discord::Discord,
+    error::{Error, Result},
+    i18n::tr,
+};
+use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
+use std::env;
+
+/// 対話的に設定を尋ね、接続を確認して設定ファイルを書き出す
+pub async fn run(color: bool) -> Result<()> {
+    let path = Config::path().ok_or_else(|| Error::Config("config directory not found".into()))?;
+    let mut config = Config::load()?;
+    if path.exists()
+        && !Confirm::new()
+            .with_prompt(tr!(
+                "prompt-overwrite-config",
+                path = path.display().to_string()
+            ))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    // トークンの保存方法
+    let storage = Select::new()
+        .with_prompt(tr!("prompt-token-storage"))
+        .items(&[tr!("choice-token-config"), tr!("choice-token-env")])
+        .default(if config.token.is_some() { 0 } else { 1 })
+        .interact()?;
+    let token = if storage == 0 {
+        let token = Password::new()
+            .with_prompt(tr!("prompt-token"))
+            .interact()?;
+        config.token = Some(token.clone());
+        Some(token)
+    } else {
+        config.token = None;
+        env::var("DISCORD_TOKEN").ok().filter(|t| !t.is_empty())
+    };
+
+    // 接続の確認と、既定のGuildの選択
+    let mut guilds = Vec::new();
+    match &token {
+        Some(token) => {
+            let discord = Discord::new(token, TokenType::Bot);
+            let user = discord.current_user().await?;
+            let mut ok = console::style("✔");
+            if color {
+                ok = ok.green();
+            }
+            eprintln!("{ok} {}", tr!("connected-as", user = user.name.as_str()));
+            guilds = discord.current_user_guilds().await?;
+        }
+        None => eprintln!("{}", tr!("token-env-unset")),
+    }
+    config.guild_id = if guilds.is_empty() {
+        let id: String = Input::new()
+            .with_prompt(tr!("prompt-default-guild-id"))
+            .allow_empty(true)
+            .with_initial_text(config.guild_id.map(|id| id.to_string()).unwrap_or_default())
+            .validate_with(|id: &String| {
+                if id.is_empty() || id.parse::<u64>().is_ok() {
+                    Ok(())
+                } else {
+                    Err(tr!("invalid-guild-id"))
+                }
+            })
+            .interact_text()?;
+        id.parse().ok()
+    } else {
+        let mut items: Vec<_> = guilds
+            .iter()
+            .map(|guild| format!("{} ({})", guild.name, guild.id))
+            .collect();
+        items.push(tr!("choice-none"));
+        let default = guilds
+            .iter()
+            .position(|guild| Some(guild.id.get()) == config.guild_id)
+            .unwrap_or(0);
+        let selected = Select::new()
+            .with_prompt(tr!("prompt-default-guild"))
+            .items(&items)
+            .default(default)
+            .interact()?;
+        guilds.get(selected).map(|guild| guild.id.get())
+    };
+
+    // エディタ
+    let editor: String = Input::new()
+        .with_prompt(tr!("prompt-editor"))
+        .allow_empty(true)
+        .with_initial_text(config.editor.clone().unwrap_or_default())
+        .interact_text()?;
+    config.editor = (!editor.trim().is_empty()).then_some(editor);
+
+    // 既定のフィルタ
+    const KINDS: [&str; 6] = ["text", "voice", "forum", "stage", "news", "category"];
+    let current = config.filter.clone().unwrap_or_default();
+    let selected = MultiSelect::new()
+        .with_prompt(tr!("prompt-default-kinds"))
+        .items(&KINDS)
+        .defaults(&KINDS.map(|kind| current.contains(kind)))
+        .interact()?;
+    config.filter = (!selected.is_empty())
+        .then(|| ChannelFilterArgs::from_kinds(selected.iter().map(|&i| KINDS[i])));
+
+    let path = config.save()?;
+    eprintln!("{}", tr!("wrote-file", path = path.display().to_string()));
+    Ok(())
+}
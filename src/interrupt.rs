@@ -0,0 +1,73 @@
+use crate::error::exit_code;
+use console::Term;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::Notify;
+
+/// Ctrl-Cが押されたか
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+/// 中断を保留している処理の数 ([`defer`])
+static DEFERRED: AtomicUsize = AtomicUsize::new(0);
+/// 中断の要求と保留の解除を待っている処理を起こす
+static CHANGED: Notify = Notify::const_new();
+
+/// Ctrl-Cの処理を始める。エディタ実行中はエディタに任せ、終了後に一時ファイルを片付けられるようにする。
+/// それ以外では中断を要求し、[`cancelled`] を待つ処理を止める。
+/// 2回目のCtrl-Cでは、保留中の処理を待たずに終了する
+pub fn install() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if edisch_core::is_editing() {
+                continue;
+            }
+            if REQUESTED.swap(true, Ordering::SeqCst) {
+                Term::stderr().show_cursor().ok();
+                std::process::exit(exit_code::INTERRUPTED);
+            }
+            CHANGED.notify_waiters();
+        }
+    });
+}
+
+/// Ctrl-Cで中断が要求されたか
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// `ready` が真になるまで待つ
+async fn wait(ready: impl Fn() -> bool) {
+    loop {
+        // 条件を確かめた直後の通知も取りこぼさないよう、確かめる前に待ち始める
+        let changed = CHANGED.notified();
+        if ready() {
+            return;
+        }
+        changed.await;
+    }
+}
+
+/// 中断が要求されるまで待つ
+pub async fn wait_requested() {
+    wait(requested).await;
+}
+
+/// 中断が要求され、保留している処理がなくなるまで待つ
+pub async fn cancelled() {
+    wait(|| requested() && DEFERRED.load(Ordering::SeqCst) == 0).await;
+}
+
+/// 破棄されるまで [`cancelled`] による中断を保留するガード
+pub struct Deferred(());
+
+/// 適用中など、途中で止めると結果が分からなくなる処理の間、中断を保留する。
+/// 保留中の処理は [`requested`] を確かめ、区切りの良いところで自ら止まる
+pub fn defer() -> Deferred {
+    DEFERRED.fetch_add(1, Ordering::SeqCst);
+    Deferred(())
+}
+
+impl Drop for Deferred {
+    fn drop(&mut self) {
+        DEFERRED.fetch_sub(1, Ordering::SeqCst);
+        CHANGED.notify_waiters();
+    }
+}
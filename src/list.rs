@@ -0,0 +1,87 @@
+use crate::{
+    args::ChannelFilterArgs,
+    channel::{kind_emoji, ChannelMatcher},
+    discord::Discord,
+    error::Result,
+};
+use serenity::all::{ChannelType, GuildChannel, GuildId};
+use std::io::{stdout, Write};
+
+/// チャンネル一覧の1行
+fn describe(channel: &GuildChannel) -> String {
+    format!(
+        "{} {} ({})",
+        kind_emoji(channel.kind),
+        channel.name,
+        channel.id
+    )
+}
+
+/// Discordクライアントと同じ順に並べるための整列キー。テキスト系のチャンネルをボイス系より上にする
+fn sort_key(channel: &GuildChannel) -> (bool, u16, u64) {
+    let voice_like = matches!(channel.kind, ChannelType::Voice | ChannelType::Stage);
+    (voice_like, channel.position, channel.id.get())
+}
+
+/// Guildのチャンネルを編集せずに出力する。
+/// tree が真の場合は、カテゴリの下に所属するチャンネルを字下げして表示する
+pub async fn print(
+    discord: &Discord,
+    guild_id: GuildId,
+    filter: &ChannelFilterArgs,
+    tree: bool,
+) -> Result<()> {
+    let mut matcher = ChannelMatcher::new(filter)?;
+    let mut channels = discord.guild_channels(guild_id, |_| {}).await?;
+    matcher.resolve_categories(&channels)?;
+    channels.sort_by_key(sort_key);
+    let matches = |channel: &GuildChannel| matcher.matches(channel);
+
+    // 無カテゴリのチャンネルを先頭に、カテゴリとその中のチャンネルをその後に並べる
+    let (categories, channels): (Vec<_>, Vec<_>) = channels
+        .into_iter()
+        .partition(|channel| channel.kind == ChannelType::Category);
+    let mut entries = Vec::new();
+    for channel in channels
+        .iter()
+        .filter(|channel| channel.parent_id.is_none())
+    {
+        if matches(channel) {
+            entries.push(("", channel));
+        }
+    }
+    for category in &categories {
+        let children: Vec<_> = channels
+            .iter()
+            .filter(|channel| channel.parent_id == Some(category.id) && matches(channel))
+            .collect();
+        // 木構造では、該当するチャンネルを含むカテゴリはカテゴリ自体が対象外でも見出しとして表示する
+        if matches(category) || (tree && !children.is_empty()) {
+            entries.push(("", category));
+        }
+        for (i, channel) in children.iter().enumerate() {
+            let branch = if i + 1 == children.len() {
+                "└─ "
+            } else {
+                "├─ "
+            };
+            entries.push((branch, channel));
+        }
+    }
+
+    let mut stdout = stdout().lock();
+    for (branch, channel) in entries {
+        if tree {
+            writeln!(stdout, "{branch}{}", describe(channel))?;
+        } else {
+            writeln!(
+                stdout,
+                "{}\t{}\t{}",
+                channel.name,
+                channel.kind.name(),
+                channel.id
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use crate::{i18n::tr, progress, redact::redact};
+use std::fmt::{self, Write as _};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Metadata, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    Layer, Registry,
+};
+
+/// HTTP通信のログのターゲット
+pub const HTTP: &str = "edisch::http";
+
+/// ログを stderr に出力するレイヤー。プログレスバーを崩さないよう [`progress::println`] で出力し、トークンは伏せる
+struct Stderr {
+    /// edisch自身のログで出力する最も詳しいレベル
+    level: Level,
+    /// HTTP通信のログを出力するか
+    http: bool,
+    color: bool,
+}
+
+impl<S: Subscriber> Layer<S> for Stderr {
+    fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+        let target = metadata.target();
+        if target == HTTP {
+            self.http
+        } else if target.starts_with("edisch") {
+            *metadata.level() <= self.level
+        } else {
+            // 依存ライブラリ (serenityなど) のログは -vv の場合のみ
+            self.level == Level::TRACE && *metadata.level() <= Level::DEBUG
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        let metadata = event.metadata();
+        let style = |text: String| {
+            let styled = console::style(text);
+            if !self.color {
+                return styled;
+            }
+            match *metadata.level() {
+                Level::ERROR => styled.red().bold(),
+                Level::WARN => styled.yellow().bold(),
+                _ => styled.dim(),
+            }
+        };
+        let line = match *metadata.level() {
+            Level::INFO => message.0,
+            Level::WARN => format!("{} {}", style(tr!("warning-prefix")), message.0),
+            Level::ERROR => format!("{} {}", style(tr!("error-prefix")), message.0),
+            _ if metadata.target() == HTTP => format!("{} {}", style("[http]".into()), message.0),
+            level => {
+                let prefix = format!("[{}]", level.as_str().to_lowercase());
+                format!("{} {}", style(prefix), message.0)
+            }
+        };
+        progress::println(redact(&line));
+    }
+}
+
+/// イベントのメッセージと、その後に `key=value` で続けるフィールド
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            write!(self.0, "{value:?}").ok();
+        } else {
+            write!(self.0, " {}={value:?}", field.name()).ok();
+        }
+    }
+}
+
+/// ログの出力を設定する。verbosity は -v の数 (-q の場合は -1)。
+/// -q では警告とエラーのみ、-v ではデバッグ用の詳細、-vv では依存ライブラリとHTTP通信のログも出力する
+pub fn init(verbosity: i8, trace_http: bool, color: bool) {
+    let level = match verbosity {
+        ..=-1 => Level::WARN,
+        0 => Level::INFO,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let layer = Stderr {
+        level,
+        http: trace_http || level == Level::TRACE,
+        color,
+    };
+    tracing::subscriber::set_global_default(Registry::default().with(layer)).ok();
+}
@@ -1,448 +1,2468 @@
+mod annotate;
+mod archive;
 mod args;
-mod bulk_edit;
+mod auth;
+mod automod;
+mod cache;
+mod channel;
+mod compress;
+mod config;
+mod discord;
+mod doctor;
+mod emoji;
 mod error;
+mod follow;
+mod format;
+mod forum_tag;
+mod guild;
+mod guilds;
+mod history;
+mod i18n;
+mod init;
+mod interrupt;
+mod list;
+mod logging;
+mod manifest;
+mod notify;
+mod onboarding;
+mod perms;
+mod policy;
+mod progress;
+mod recovery;
+mod redact;
+mod reorder;
+mod resume;
+mod role;
+mod schedule;
+mod snapshot;
+mod sound;
+mod sticker;
+mod structure;
+mod sync;
+mod thread;
+mod transform;
+mod tui;
+mod undo;
+mod unicode;
+mod watch;
+mod webhook;
+mod welcome;
 
-use args::{ApplyArgs, Args, IOMode, Work};
-use atty::Stream;
-use bulk_edit::{Editor, TextEditableItem};
-use clap::{CommandFactory, Parser};
-use console::pad_str;
-use dialoguer::Confirm;
-use error::{Error, Result};
-use regex::Regex;
-use scopeguard::defer;
-use serenity::{
-    all::{ChannelId, ChannelType, EditChannel, GuildChannel, Http},
-    model::id::GuildId,
+use annotate::Annotator;
+use args::{
+    ApplyArgs, Args, AuthCommand, ChannelField, ChannelFilterArgs, ChannelSort, ChannelView,
+    CommentFormat, ConnectionArgs, DedupMode, DiffFormat, EditorArgs, ExportArgs, ExportFormat,
+    IOMode, OutputFormat, PermsCommand, SnapshotCommand, Target, TemplateCommand, Work,
 };
+use channel::{fetch_items, ChannelItem};
+use clap::{CommandFactory, Parser};
+use config::Config;
+use console::{pad_str, StyledObject, Term};
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+use discord::Discord;
+use edisch_core::{comment_key, Diff, EditOptions, Edited, Editor, Op, TextEditableItem};
+use error::{exit_code, Error, Result};
+use futures::{future::try_join_all, stream::FuturesOrdered, StreamExt};
+use history::HistoryLog;
+use i18n::tr;
+use manifest::{Manifest, Outcome, Totals};
+use policy::Policy;
+use progress::Progress;
+use recovery::Recovery;
+use redact::{redact, Redacted};
+use resume::ResumeState;
+use schedule::RenameScheduler;
+use serde_json::json;
+use serenity::model::id::{ChannelId, GuildId};
+use similar::{DiffTag, TextDiff};
 use std::{
-    cmp::Ordering,
-    collections::HashMap,
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     env,
     fmt::Display,
-    fs::File,
     io::{self, stdin, stdout, BufReader, BufWriter, Read, Write},
+    process::{Command, Stdio},
     sync::Arc,
+    time::{Duration, Instant},
 };
+use tracing::{debug, error, info, warn};
+use undo::UndoLog;
 use unicode_width::UnicodeWidthStr;
 
-#[derive(Clone)]
-struct ChannelItem {
-    /// Discord HTTPクライアント
-    http: Arc<Http>,
+#[tokio::main]
+async fn main() {
+    // clap のエラーには誤った引数の値がそのまま含まれるため、トークンを伏せてから表示する
+    let args = Args::try_parse().unwrap_or_else(|e| {
+        if !e.use_stderr() {
+            e.exit();
+        }
+        let rendered = e.render();
+        let text = if Term::stderr().is_term() {
+            rendered.ansi().to_string()
+        } else {
+            rendered.to_string()
+        };
+        eprint!("{}", redact(&text));
+        std::process::exit(exit_code::INVALID_INPUT);
+    });
+    let term = Term::stderr();
+    let is_tty = term.is_term();
+    // WindowsではここでVirtual Terminal Processingが有効化される。
+    // 有効化できない古いコンソールでは色を付けない
+    let color = args.color.enabled(term.features().colors_supported());
+    console::set_colors_enabled(color);
+    console::set_colors_enabled_stderr(color);
+    let verbosity = if args.quiet { -1 } else { args.verbose as i8 };
+    i18n::set_lang(args.lang);
+    logging::init(verbosity, args.trace_http, color);
+    if let Some(profile) = &args.profile {
+        config::set_profile(profile);
+    }
 
-    /// チャンネル情報
-    channel: GuildChannel,
-    /// チャンネルID
-    channel_id: ChannelId,
+    interrupt::install();
 
-    /// 親チャンネルの名前
-    parent_name: Option<String>,
-    /// 所属するカテゴリのposition
-    category_position: u16,
+    let detailed_exit_code = args.detailed_exit_code;
+    // Ctrl-Cでは run を止め、途中の処理のガードを破棄して片付ける。
+    // 適用中は中断が保留され、適用の処理が適用済みの変更を記録してから自ら止まる
+    let result = tokio::select! {
+        biased;
+        result = run(args.into(), color, is_tty) => result,
+        () = interrupt::cancelled() => Err(Error::Interrupted),
+    };
+    if let Err(Error::Interrupted) = result {
+        // 確認の途中で中断した場合も、隠したカーソルを元に戻す
+        Term::stderr().show_cursor().ok();
+    }
+    for path in edisch_core::take_kept_tempfiles() {
+        info!(
+            "{}",
+            tr!("tempfile-kept", path = path.display().to_string())
+        );
+    }
+    let code = match &result {
+        Ok(()) if detailed_exit_code && discord::changed() => exit_code::CHANGES_APPLIED,
+        Ok(()) => exit_code::SUCCESS,
+        Err(e) => e.exit_code(),
+    };
+    progress::event(
+        "done",
+        json!({
+            "ok": result.is_ok(),
+            "exit_code": code,
+            "error": result.as_ref().err().map(|e| Redacted(e).to_string()),
+        }),
+    );
+    if let Err(e) = result {
+        // 変更があることは終了ステータスのみで伝える
+        if let Error::PendingChanges(_) = e {
+            std::process::exit(code);
+        }
+        let prompt = if e.unknown() {
+            let mut p = console::style(tr!("error-prefix-unknown"));
+            if color {
+                p = p.on_red().bold();
+            }
+            p
+        } else {
+            let mut p = console::style(tr!("error-prefix"));
+            if color {
+                p = p.red().bold();
+            }
+            p
+        };
+        eprint!("{} ", prompt);
+        eprintln!("{}", Redacted(&e));
+    }
+    if code != exit_code::SUCCESS {
+        std::process::exit(code);
+    }
 }
 
-impl ChannelItem {
-    fn is_no_categoryzed_channel(&self) -> bool {
-        self.channel.kind != ChannelType::Category && self.parent_name.is_none()
+async fn run(work: Work, color: bool, is_tty: bool) -> Result<()> {
+    let (discord, filter, io, apply, target) = match work {
+        Work::Completion(shell) => {
+            shell_completion(shell);
+            return Ok(());
+        }
+        Work::Structure {
+            discord: discord_args,
+            input,
+            yes,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            let guild_id = guild_id_from(&discord_args, &config)?;
+            let text = match input {
+                Some(path) => compress::read_to_string(&path)?,
+                None => io::read_to_string(stdin())?,
+            };
+            return structure::apply_structure(&discord, guild_id, &text, yes, color).await;
+        }
+        Work::Copy {
+            discord: discord_args,
+            from,
+            to,
+            yes,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            let (from, to) = (config.resolve_guild(&from)?, config.resolve_guild(&to)?);
+            return structure::copy_structure(&discord, from, to, yes, color).await;
+        }
+        Work::Sync {
+            discord: discord_args,
+            file,
+            prune,
+            yes,
+            dry_run,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            let guild_id = guild_id_from(&discord_args, &config)?;
+            let text = compress::read_to_string(&file)?;
+            return sync::sync(&discord, guild_id, &text, prune, yes, dry_run, color).await;
+        }
+        Work::Sort {
+            discord: discord_args,
+            yes,
+            dry_run,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            let guild_id = guild_id_from(&discord_args, &config)?;
+            return reorder::sort_channels(&discord, guild_id, yes, dry_run, color).await;
+        }
+        Work::Archive {
+            discord: discord_args,
+            filter,
+            options,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            let guild_id = guild_id_from(&discord_args, &config)?;
+            return archive::archive(&discord, guild_id, &filter, &options, color).await;
+        }
+        Work::List {
+            discord: discord_args,
+            filter,
+            tree,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            let (guilds, names) = guild_ids_from(&discord, &discord_args, &config).await?;
+            for guild_id in guilds {
+                // 複数のGuildを表示する場合は、Guildごとに見出しを付ける
+                if let Some(name) = names.get(&guild_id) {
+                    println!("# {name} ({guild_id})");
+                }
+                list::print(&discord, guild_id, &filter, tree).await?;
+            }
+            return Ok(());
+        }
+        Work::Guilds {
+            discord: discord_args,
+            format,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            return guilds::list(&discord, format).await;
+        }
+        Work::Status {
+            discord: discord_args,
+            input,
+        } => {
+            let config = Config::load()?;
+            let discord = connect(&discord_args, &config)?;
+            let guild_id = guild_id_from(&discord_args, &config)?;
+            let text = match input {
+                Some(path) => compress::read_to_string(&path)?,
+                None => io::read_to_string(stdin())?,
+            };
+            return structure::status(&discord, guild_id, &text, color).await;
+        }
+        Work::Init => return init::run(color).await,
+        Work::Doctor(discord_args) => {
+            // 設定ファイルが不正でも他の項目を検査できるよう、エラーは doctor に渡す
+            let config = Config::load();
+            let fallback = Config::default();
+            let loaded = config.as_ref().unwrap_or(&fallback);
+            let discord = connect(&discord_args, loaded);
+            let guild_id = guild_id_from(&discord_args, loaded);
+            return doctor::run(config, discord, guild_id, color).await;
+        }
+        Work::Watch {
+            discord,
+            input,
+            poll,
+            apply,
+            field,
+            view,
+        } => {
+            info!("{}", tr!("watching", path = input.display().to_string()));
+            let mut watcher = watch::FileWatcher::new(&input, poll);
+            loop {
+                watcher.changed().await;
+                info!(
+                    "{}",
+                    tr!("file-changed", path = input.display().to_string())
+                );
+                let work = Work::Edit {
+                    discord: discord.clone(),
+                    filter: ChannelFilterArgs::from_kinds(["all"]),
+                    io: IOMode::Input(Some(input.clone())),
+                    apply: Some(apply.clone()),
+                    target: Target::Channels(field, view.clone()),
+                };
+                // 失敗しても、次の保存を待ち続ける
+                match Box::pin(run(work, color, is_tty)).await {
+                    Ok(()) | Err(Error::PendingChanges(_)) => {}
+                    Err(e) => {
+                        error!("{}", Redacted(e));
+                    }
+                }
+                info!("{}", tr!("waiting-for-save"));
+            }
+        }
+        Work::History(args) => return history::print(&args),
+        Work::Auth(command) => {
+            return match command {
+                AuthCommand::Set => auth::set(),
+                AuthCommand::Clear => auth::clear(),
+            };
+        }
+        Work::Template(command) => {
+            let discord_args = command.discord();
+            let config = Config::load()?;
+            let discord = connect(discord_args, &config)?;
+            let guild_id = guild_id_from(discord_args, &config)?;
+            return match command {
+                TemplateCommand::Export { output, .. } => {
+                    let text = sync::export_template(&discord, guild_id).await?;
+                    match output {
+                        Some(path) => compress::write(&path, &text),
+                        None => {
+                            print!("{text}");
+                            Ok(())
+                        }
+                    }
+                }
+                TemplateCommand::Import {
+                    file,
+                    prune,
+                    yes,
+                    dry_run,
+                    ..
+                } => {
+                    let text = compress::read_to_string(&file)?;
+                    sync::sync(&discord, guild_id, &text, prune, yes, dry_run, color).await
+                }
+            };
+        }
+        Work::Perms(command) => {
+            let discord_args = command.discord();
+            let config = Config::load()?;
+            let discord = connect(discord_args, &config)?;
+            let guild_id = guild_id_from(discord_args, &config)?;
+            return match command {
+                PermsCommand::Apply {
+                    ref template,
+                    ref pattern,
+                    yes,
+                    ..
+                } => {
+                    perms::apply_template(
+                        &discord,
+                        guild_id,
+                        template,
+                        pattern.as_deref(),
+                        yes,
+                        color,
+                    )
+                    .await
+                }
+                PermsCommand::Edit(ref args) => {
+                    let options = EditOptions {
+                        keep_tempfile: false,
+                        tmpdir: env::var_os("EDISCH_TMPDIR")
+                            .map(Into::into)
+                            .or(config.tmpdir.clone()),
+                        suffix: None,
+                        editor: args.editor.clone().or(config.editor.clone()),
+                    };
+                    perms::edit(&discord, guild_id, args, options, color).await
+                }
+                PermsCommand::Diff {
+                    ref channel,
+                    ref other,
+                    ..
+                } => perms::diff(&discord, guild_id, channel, other.as_deref(), color).await,
+            };
+        }
+        Work::Edit {
+            discord,
+            filter,
+            io,
+            apply,
+            target,
+        } => (discord, filter, io, apply, target),
+        Work::Snapshot { command, field } => match command {
+            SnapshotCommand::Save { discord } => {
+                let config = Config::load()?;
+                let guild_id = guild_id_from(&discord, &config)?;
+                let (id, path) = snapshot::new_path(guild_id, field)?;
+                info!("{}", tr!("saving-snapshot", id = id.to_string()));
+                (
+                    discord,
+                    ChannelFilterArgs::from_kinds(["all"]),
+                    IOMode::Output(
+                        Some(path),
+                        ExportArgs {
+                            guild_column: true,
+                            format: ExportFormat::Tsv,
+                        },
+                    ),
+                    None,
+                    Target::Channels(field, ChannelView::default()),
+                )
+            }
+            SnapshotCommand::List { discord } => {
+                let config = Config::load()?;
+                return snapshot::print_list(guild_id_from(&discord, &config)?);
+            }
+            SnapshotCommand::Restore {
+                discord,
+                id,
+                mut apply,
+            } => {
+                let config = Config::load()?;
+                let guild_id = guild_id_from(&discord, &config)?;
+                let snapshot = snapshot::find(guild_id, id.as_deref())?;
+                // スナップショット以降の変更を戻すため、変更されたチャンネルも上書きする
+                apply.overwrite = true;
+                (
+                    discord,
+                    ChannelFilterArgs::from_kinds(["all"]),
+                    IOMode::Input(Some(snapshot.path)),
+                    Some(apply),
+                    Target::Channels(snapshot.field, ChannelView::default()),
+                )
+            }
+        },
+        Work::Undo { discord, apply } => {
+            // 直前の適用で書き出した、名前を元に戻すファイルを適用する
+            let path = undo::path()
+                .filter(|path| path.exists())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, tr!("nothing-to-undo")))?;
+            (
+                discord,
+                ChannelFilterArgs::from_kinds(["all"]),
+                IOMode::Input(Some(path)),
+                Some(apply),
+                Target::Channels(ChannelField::Name, ChannelView::default()),
+            )
+        }
+    };
+    if apply
+        .as_ref()
+        .is_some_and(|apply| apply.progress == Some(OutputFormat::Json))
+    {
+        progress::set_json();
     }
-    fn is_voice_like_channel(&self) -> bool {
-        self.channel.kind == ChannelType::Voice || self.channel.kind == ChannelType::Stage
+    let config = Config::load()?;
+    // エディタで名前を編集する際、種類の指定がなければ設定ファイルの既定値を使う
+    let filter = match (&config.filter, &io, &target) {
+        (
+            Some(default),
+            IOMode::Editor(_) | IOMode::Tui | IOMode::Transform(_),
+            Target::Channels(ChannelField::Name, _),
+        ) if !filter.has_kinds() => filter.with_kinds_of(default),
+        _ => filter,
+    };
+    let policy = Arc::new(Policy::load()?);
+    // 変換の指定の誤りは、チャンネルを取得する前に報告する
+    if let IOMode::Transform(args) = &io {
+        transform::transforms(args)?;
     }
-}
 
-impl PartialEq for ChannelItem {
-    fn eq(&self, other: &Self) -> bool {
-        self.channel_id == other.channel_id
+    // 実行内容の記録。エラーで中断した場合も含め、関数を抜ける際に書き出される
+    let mut manifest = Manifest::new(
+        apply.as_ref().and_then(|apply| apply.manifest.clone()),
+        match io {
+            IOMode::Editor(_) => "editor",
+            IOMode::Fix => "fix",
+            IOMode::Tui => "tui",
+            IOMode::Transform(_) => "transform",
+            _ => "apply",
+        },
+        match &io {
+            IOMode::Input(Some(path)) => Some(path.display().to_string()),
+            IOMode::Input(None) => Some("-".to_string()),
+            _ => None,
+        },
+        filter.clone(),
+    );
+    let mut annotator = Annotator::new(
+        apply.as_ref().and_then(|apply| apply.annotate),
+        match &io {
+            IOMode::Input(Some(path)) => Some(path.display().to_string()),
+            _ => None,
+        },
+    );
+
+    let discord_args = discord;
+    let discord = connect(&discord_args, &config)?;
+
+    // 入力ファイルはGuild列によって取得するチャンネルが変わるため、先に読み込む
+    let input = match io {
+        IOMode::Input(ref input) => {
+            let mut text = String::new();
+            match input {
+                Some(p) => {
+                    text = compress::read_to_string(p)?;
+                }
+                None => {
+                    BufReader::new(stdin()).read_to_string(&mut text)?;
+                }
+            }
+            // 構造化した形式は、IDをコメントに持つ行に変換して照合する
+            let format = apply
+                .as_ref()
+                .and_then(|apply| apply.input_format)
+                .or_else(|| input.as_deref().and_then(ExportFormat::from_path))
+                .unwrap_or(ExportFormat::Tsv);
+            if format != ExportFormat::Tsv {
+                let Target::Channels(field, _) = target else {
+                    return Err(Error::UnsupportedOperation(
+                        "structured input formats are only supported for channels".into(),
+                    ));
+                };
+                let records = format::parse_records(&text, format)?;
+                text = channel::lines_from_records(&records, field)?;
+            }
+            // --channel で指定していないチャンネルの行は、警告せずに読み飛ばす
+            if !filter.channel.is_empty() {
+                let ids: Vec<_> = filter.channel.iter().map(ToString::to_string).collect();
+                text = format::select_lines(&text, &ids);
+            }
+            Some(text)
+        }
+        _ => None,
+    };
+
+    // 入力の行は、Guild列ごとにまとめて対応するGuildのアイテムと照合する
+    let (guilds, guild_names, input) = match input {
+        Some(input) => {
+            let mut guilds = Vec::new();
+            let mut text = String::new();
+            let mut line_numbers = Vec::new();
+            for (guild_id, lines, numbers) in format::group_by_guild(&input) {
+                guilds.push(match guild_id {
+                    Some(id) => id,
+                    None => guild_id_from(&discord_args, &config)?,
+                });
+                text.push_str(&lines);
+                line_numbers.extend(numbers);
+            }
+            annotator.set_line_numbers(line_numbers);
+            (guilds, HashMap::new(), Some(text))
+        }
+        None => {
+            let (guilds, names) = guild_ids_from(&discord, &discord_args, &config).await?;
+            (guilds, names, None)
+        }
+    };
+    for &guild_id in &guilds {
+        manifest.add_guild(guild_id);
     }
-}
 
-impl Eq for ChannelItem {}
+    let mut ctx = Context {
+        discord: discord.clone(),
+        discord_args,
+        io,
+        apply,
+        config,
+        manifest,
+        annotator,
+        undo: None,
+        resume: None,
+        scheduler: None,
+        history: HistoryLog::new((guilds.len() == 1).then(|| guilds[0])),
+        uneditable: HashSet::new(),
+        color,
+        is_tty,
+        headers: true,
+    };
+    match target {
+        Target::Channels(field, view) => {
+            if let CommentFormat::Template(template) = &view.comments {
+                channel::check_comment_format(template)?;
+            }
+            let comments = Arc::new(view.comments);
+            // 指定したGuildのチャンネル一覧を同時に取得する。
+            // 複数のGuildの場合は、プログレスバーが重ならないよう1行ずつの表示にする
+            let progress = is_tty && guilds.len() == 1;
+            let fetched = try_join_all(guilds.iter().map(|&guild_id| {
+                fetch_items(&discord, guild_id, &filter, field, &policy, color, progress)
+            }))
+            .await?;
+            let mut items = Vec::with_capacity(fetched.iter().map(Vec::len).sum());
+            for (guild_id, fetched) in guilds.into_iter().zip(fetched) {
+                // 複数のGuildをまとめて編集する場合は、どのGuildのチャンネルか分かるよう名前を添える
+                items.extend(
+                    fetched
+                        .into_iter()
+                        .map(|item| match guild_names.get(&guild_id) {
+                            Some(name) => item.with_guild_name(name),
+                            None => item,
+                        })
+                        .map(|item| item.with_comment_format(&comments)),
+                );
+            }
+            // IDで指定したチャンネルは、いずれかのGuildで見つかる必要がある
+            let found: HashSet<_> = items.iter().map(ChannelItem::channel_id).collect();
+            if let Some(id) = filter.channel.iter().find(|id| !found.contains(id)) {
+                return Err(Error::ParseArgument(
+                    format!("channel not found or without the {field} property: {id}").into(),
+                ));
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-channels"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            channel::sort_items(&mut items, view.sort);
+            if view.reverse {
+                items.reverse();
+            }
+            // 大きなGuildを分けて編集できるよう、並べた後で範囲を絞る
+            if view.offset > 0 || view.limit.is_some() {
+                let found = items.len();
+                items.drain(..view.offset.min(found));
+                if let Some(limit) = view.limit {
+                    items.truncate(limit);
+                }
+                if items.is_empty() {
+                    info!(
+                        "{}",
+                        tr!("no-channels-in-range", offset = view.offset, total = found)
+                    );
+                    let mut manifest = ctx.manifest;
+                    manifest.finish(Outcome::NoChanges);
+                    return Ok(());
+                }
+                let end = view.offset + items.len();
+                info!(
+                    "{}",
+                    tr!(
+                        "channels-in-range",
+                        first = view.offset + 1,
+                        last = end,
+                        total = found
+                    )
+                );
+                if end < found {
+                    info!("{}", tr!("next-range", offset = end));
+                }
+            }
+            // カテゴリ順でなければ、カテゴリの見出しで区切れない
+            ctx.headers = view.sort == ChannelSort::Position;
+            // 構造化した形式では、エディタの形式を経由せずに書き出す
+            if let IOMode::Output(output, export) = &ctx.io {
+                if export.format != ExportFormat::Tsv {
+                    return export_records(&items, output.as_deref(), export.format, field);
+                }
+            }
+            let guilds: HashMap<_, _> = items
+                .iter()
+                .map(|item| (item.to_string(), item.guild_id()))
+                .collect();
+            if field == ChannelField::Name {
+                ctx.undo = Some(UndoLog::new(guilds.clone()));
+            }
+            // 名前とトピックの変更はチャンネルごとに10分間に2回まで
+            if matches!(field, ChannelField::Name | ChannelField::Topic) {
+                ctx.scheduler = Some(RenameScheduler::load());
+            }
+            ctx.history.set_guilds(guilds.clone());
+            ctx.resume = Some(ResumeState::new(guilds));
+            // 適用の途中で権限不足により失敗しないよう、編集できないチャンネルを先に調べる
+            if ctx.apply.is_some() && !discord.used_cache() {
+                ctx.uneditable = channel::uneditable_ids(&discord, &items).await?;
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Guild => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(guild::fetch_items(&discord, guild_id).await?);
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::ForumTags => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(forum_tag::fetch_items(&discord, guild_id).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-forum-tags"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Threads { archived } => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(thread::fetch_items(&discord, guild_id, archived).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-threads"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Webhooks => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(webhook::fetch_items(&discord, guild_id).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-webhooks"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Roles { include_managed } => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(role::fetch_items(&discord, guild_id, include_managed).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-roles"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::RoleIcons => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(role::fetch_icon_items(&discord, guild_id).await?);
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Emojis => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(emoji::fetch_items(&discord, guild_id).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-emojis"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Stickers(field) => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(sticker::fetch_items(&discord, guild_id, field).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-stickers"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Sounds => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(sound::fetch_items(&discord, guild_id).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-sounds"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Automod { keywords } => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(automod::fetch_items(&discord, guild_id, keywords).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-automod-rules"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Onboarding => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(onboarding::fetch_items(&discord, guild_id).await?);
+            }
+            if items.is_empty() {
+                info!("{}", tr!("no-onboarding-prompts"));
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Welcome => {
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(welcome::fetch_items(&discord, guild_id).await?);
+            }
+            edit_items(items, input, ctx).await
+        }
+        Target::Follows { target, list } => {
+            let target = ChannelId::new(target);
+            let mut items = Vec::new();
+            for guild_id in guilds {
+                items.extend(follow::fetch_items(&discord, guild_id, target).await?);
+            }
+            if list {
+                let mut output = BufWriter::new(stdout());
+                for line in items.iter().filter_map(|item| item.describe()) {
+                    writeln!(output, "{line}")?;
+                }
+                let mut manifest = ctx.manifest;
+                manifest.finish(Outcome::NoChanges);
+                return Ok(());
+            }
+            edit_items(items, input, ctx).await
+        }
+    }
+}
 
-impl PartialOrd for ChannelItem {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+/// チャンネルの情報を、ツールで読み込みやすい形式で書き出す
+fn export_records(
+    items: &[channel::ChannelItem],
+    output: Option<&std::path::Path>,
+    format: ExportFormat,
+    field: ChannelField,
+) -> Result<()> {
+    let records: Vec<_> = items.iter().map(|item| item.record()).collect();
+    let columns = channel::ChannelRecord::columns(field != ChannelField::Name);
+    let rows: Vec<_> = records.iter().map(|record| record.cells()).collect();
+    let text = match format {
+        ExportFormat::Json => {
+            let mut text = serde_json::to_string_pretty(&records).map_err(serenity::Error::from)?;
+            text.push('\n');
+            text
+        }
+        ExportFormat::Yaml => format::to_yaml(&columns, &rows)?,
+        ExportFormat::Csv => format::to_csv(&columns, &rows),
+        ExportFormat::Tsv => unreachable!("the tsv format is written by edit_items"),
+    };
+    match output {
+        Some(file) => compress::write(file, &text)?,
+        None => stdout().lock().write_all(text.as_bytes())?,
     }
+    Ok(())
+}
+
+/// アイテムの取得後、編集と適用に必要な状態
+struct Context {
+    discord: Arc<Discord>,
+    discord_args: ConnectionArgs,
+    io: IOMode,
+    apply: Option<ApplyArgs>,
+    config: Config,
+    manifest: Manifest,
+    annotator: Annotator,
+    /// 適用した変更を元に戻すための記録。対応する対象のみ記録する
+    undo: Option<UndoLog>,
+    /// 中断した適用を再開するための記録。対応する対象のみ記録する
+    resume: Option<ResumeState>,
+    /// レートリミットに合わせて変更の間隔を空ける。対応する対象のみ使う
+    scheduler: Option<RenameScheduler>,
+    /// 適用した変更を追記する履歴
+    history: HistoryLog,
+    /// Botが編集する権限を持たないアイテムのID。対応する対象のみ調べる
+    uneditable: HashSet<String>,
+    color: bool,
+    /// stderr が端末か
+    is_tty: bool,
+    /// エディタのバッファに区切りの見出し行を挿入するか
+    headers: bool,
 }
 
-impl Ord for ChannelItem {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // 無カテゴリチャンネルを一番上にする
-        if self.is_no_categoryzed_channel() && !other.is_no_categoryzed_channel() {
-            return Ordering::Less;
-        } else if !self.is_no_categoryzed_channel() && other.is_no_categoryzed_channel() {
-            return Ordering::Greater;
+/// 取得したアイテムを書き出す、またはエディタ・入力で編集して適用する
+async fn edit_items<T: TextEditableItem<Error = Error> + Display>(
+    items: Vec<T>,
+    input: Option<String>,
+    ctx: Context,
+) -> Result<()> {
+    let Context {
+        discord,
+        discord_args,
+        io,
+        apply,
+        config,
+        mut manifest,
+        mut annotator,
+        mut undo,
+        mut resume,
+        mut scheduler,
+        mut history,
+        uneditable,
+        color,
+        is_tty,
+        headers,
+    } = ctx;
+    manifest.set_state(&items);
+
+    // 編集するアイテムのみをバッファに入れる
+    let items = match &io {
+        IOMode::Editor(EditorArgs { pick: true, .. }) => {
+            let picked = pick_items(items)?;
+            if picked.is_empty() {
+                info!("{}", tr!("no-items-selected"));
+                manifest.finish(Outcome::Cancelled);
+                return Ok(());
+            }
+            picked
         }
+        _ => items,
+    };
 
-        // 同一カテゴリのチャンネルをまとめる
-        match self.category_position.cmp(&other.category_position) {
-            Ordering::Equal => {}
-            other => return other,
+    // 一括編集
+    debug!("Editing {} items", items.len());
+    let mut editor = Editor::new(items)?;
+    if apply.as_ref().is_some_and(|apply| apply.structural) {
+        editor.structural()?;
+    }
+
+    // ポリシーに従うよう名前を書き換えるか
+    let fix = apply.as_ref().is_some_and(|apply| apply.fix);
+    // 重複する名前をどう扱うか
+    let dedup = apply.as_ref().and_then(|apply| apply.dedup);
+    // 変更後の値を正規化する形式
+    let normalize = apply.as_ref().and_then(|apply| apply.normalize);
+
+    // 確認時に再編集するためのエディタの起動方法
+    let mut edit_options = None;
+    // 適用せずに終了した場合に、編集したバッファを保存する
+    let mut recovery = Recovery::default();
+    // 変更を適用しないと答えた場合もバッファを保存するか
+    let mut save_on_decline = false;
+    // 適用の確認が済んでいるか
+    let mut confirmed = false;
+    // 適用しなかった変更 (サーバー上で変更されていた・権限がない・確認で取り消した) の数
+    let mut skipped = 0;
+    match io {
+        IOMode::Output(
+            output,
+            ExportArgs {
+                guild_column,
+                format,
+            },
+        ) => {
+            if format != ExportFormat::Tsv {
+                return Err(Error::UnsupportedOperation(
+                    "--format other than tsv is only supported for channels".into(),
+                ));
+            }
+            let mut text = editor.to_string();
+            // 適用時に、書き出した後でサーバー上の値が変更されていないか確かめられるようにする
+            if !editor.items().iter().any(TextEditableItem::multiline) {
+                let bases: Vec<_> = editor
+                    .items()
+                    .iter()
+                    .map(|item| format::fingerprint(&item.content()))
+                    .collect();
+                text = format::with_base_column(&text, &bases);
+            }
+            // 複数のGuildから取得した場合も、各行にそのアイテムのGuildを記す
+            if guild_column {
+                let guilds = editor
+                    .items()
+                    .iter()
+                    .map(|item| {
+                        history.guild(&item.to_string()).ok_or_else(|| {
+                            Error::UnsupportedOperation(
+                                format!("--guild-column: the guild of {item} is unknown").into(),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                text = format::with_guild_column(&text, &guilds);
+            }
+            match output {
+                Some(file) => compress::write(&file, &format!("{text}\n"))?,
+                None => {
+                    let mut output = BufWriter::new(stdout());
+                    writeln!(output, "{}", text)?;
+                }
+            }
+            return Ok(());
+        }
+        IOMode::Editor(EditorArgs {
+            editor: editor_command,
+            keep_tempfile,
+            tmpdir,
+            tempfile_suffix,
+            save_on_decline: save,
+            pick: _,
+        }) => {
+            let tmpdir = tmpdir
+                .or(env::var_os("EDISCH_TMPDIR").map(Into::into))
+                .or(config.tmpdir);
+            let options = EditOptions {
+                keep_tempfile,
+                tmpdir,
+                suffix: tempfile_suffix.or(config.tempfile_suffix.clone()),
+                editor: editor_command.or(config.editor.clone()),
+            };
+            // 書き換えた名前をエディタで確認できるよう、起動前に書き換える
+            let mut fixed = fix && editor.fix();
+            if dedup == Some(DedupMode::Suffix) {
+                fixed |= dedup_names(&mut editor, DedupMode::Suffix)?;
+            }
+            editor.align();
+            if headers {
+                editor.headers();
+            }
+            match edit_buffer(&mut editor, &options, &mut recovery)? {
+                Edited::Changed => {}
+                Edited::Unchanged if fixed => {}
+                Edited::Unchanged => {
+                    // 変更がなくても、既にある重複は報告する
+                    if dedup == Some(DedupMode::Warn) {
+                        dedup_names(&mut editor, DedupMode::Warn)?;
+                    }
+                    info!("{}", tr!("no-changes"));
+                    manifest.finish(Outcome::NoChanges);
+                    return Ok(());
+                }
+                Edited::Aborted => {
+                    recovery.disarm();
+                    info!("{}", tr!("aborted-empty-buffer"));
+                    manifest.finish(Outcome::Cancelled);
+                    return Ok(());
+                }
+            }
+            if fixed {
+                recovery.set(editor.to_string());
+            }
+            save_on_decline = save;
+            edit_options = Some(options);
+        }
+        IOMode::Input(_) => {
+            let (text, bases) = format::strip_base_column(&input.unwrap_or_default());
+            // 書き出した後にアイテムが増減していても対応づけられるよう、IDで行とアイテムを照合する。
+            // IDを持たないアイテムは従来通り行の位置で照合する
+            editor.keyed().ok();
+            let edited = editor.set_text(text).inspect_err(|e| {
+                annotator.error(None, &e.to_string());
+            })?;
+            if edited == Edited::Aborted {
+                info!("{}", tr!("aborted-empty-input"));
+                manifest.finish(Outcome::Cancelled);
+                return Ok(());
+            }
+            for warning in editor.warnings() {
+                warn!("{warning}");
+            }
+            if let Some(indices) = editor.line_indices() {
+                annotator.reorder(indices);
+            }
+            if !apply.as_ref().is_some_and(|apply| apply.overwrite) {
+                let strict = apply.as_ref().is_some_and(|apply| apply.strict_conflicts);
+                skipped += skip_conflicts(&mut editor, &bases, strict)?;
+            }
+            if fix {
+                editor.fix();
+            }
+        }
+        IOMode::Fix => {
+            editor.fix();
         }
+        IOMode::Transform(args) => {
+            match &args.pipe {
+                Some(command) => transform::pipe_items(&mut editor, command)?,
+                None => transform::transform_items(&mut editor, &transform::transforms(&args)?)?,
+            }
+            if fix {
+                editor.fix();
+            }
+        }
+        IOMode::Tui => {
+            if fix {
+                editor.fix();
+            }
+            match tui::run(&mut editor, color)? {
+                Edited::Changed => {}
+                Edited::Unchanged => {
+                    info!("{}", tr!("no-changes"));
+                    manifest.finish(Outcome::NoChanges);
+                    return Ok(());
+                }
+                Edited::Aborted => {
+                    info!("{}", tr!("discarded-changes"));
+                    manifest.finish(Outcome::Cancelled);
+                    return Ok(());
+                }
+            }
+            // 変更の適用は TUI 上で確認済み
+            confirmed = true;
+        }
+    }
 
-        // 同一カテゴリ内なら、カテゴリを表すチャンネルを一番上にする
-        if self.parent_name.is_some() && other.parent_name.is_none() {
-            return Ordering::Greater;
-        } else if self.parent_name.is_none() && other.parent_name.is_some() {
-            return Ordering::Less;
+    let Some(ApplyArgs {
+        yes,
+        keep_going,
+        atomic,
+        jobs,
+        print_mapping,
+        dry_run,
+        output_format,
+        interactive,
+        save_edits,
+        notify_channel,
+        strict,
+        force,
+        verify,
+        ..
+    }) = apply
+    else {
+        return Ok(());
+    };
+
+    loop {
+        if let Some(form) = normalize {
+            editor.rewrite_changed(|new| unicode::normalize(new, form));
+        }
+        if let Some(mode) = dedup {
+            dedup_names(&mut editor, mode)?;
+        }
+        let diffs = editor.diffs().inspect_err(|_| {
+            for (i, e) in editor.violations() {
+                annotator.error(Some(i), &e.to_string());
+            }
+        })?;
+        if dedup.is_none() {
+            check_duplicates(&editor, &diffs)?;
+        }
+        let warnings = lint_diffs(&diffs);
+        // 権限がなく失敗する変更は、適用前に取り除く
+        let denied = uneditable_diffs(&diffs, &uneditable, strict)?;
+        if !denied.is_empty() {
+            skipped += denied.len();
+            for (index, op, new) in denied {
+                editor.discard(index, &op, &new);
+            }
+            continue;
+        }
+        manifest.set_diffs(&diffs);
+        progress::event(
+            "diff_computed",
+            json!({
+                "count": diffs.len(),
+                "diffs": diffs.iter().map(diff_json).collect::<Vec<_>>(),
+            }),
+        );
+        if diffs.is_empty() {
+            recovery.disarm();
+            info!("{}", tr!("no-changes-to-apply"));
+            manifest.finish(Outcome::NoChanges);
+            return Ok(());
+        }
+        // --atomic で元に戻せるのは値の変更のみ
+        if let Some(diff) = diffs.iter().find(|diff| atomic && diff.op != Op::Rename) {
+            return Err(Error::UnsupportedOperation(
+                format!("--atomic cannot roll back {} ({})", diff.op, diff.item).into(),
+            ));
+        }
+        // 名前の変更のレートリミットによる待ち時間の見込み。分けて適用するか決められるよう、確認の前と --dry-run で示す
+        let estimate = scheduler.as_ref().and_then(|scheduler| {
+            let ids: Vec<_> = diffs
+                .iter()
+                .filter(|diff| diff.op == Op::Rename)
+                .map(|diff| diff.item.to_string())
+                .collect();
+            if ids.is_empty() {
+                return None;
+            }
+            let wait = scheduler.estimate(ids.iter().map(String::as_str));
+            Some(if wait.is_zero() {
+                tr!("rate-limit-none", count = ids.len())
+            } else {
+                tr!(
+                    "rate-limit-estimate",
+                    count = ids.len(),
+                    wait = schedule::format_duration(wait)
+                )
+            })
+        });
+        // 変更を表示するのみで、適用しない
+        if dry_run {
+            let mut stdout = stdout().lock();
+            match output_format {
+                DiffFormat::Table => {
+                    let widths = column_widths(&diffs);
+                    for diff in &diffs {
+                        writeln!(stdout, "{}", format_diff(diff, widths, color))?;
+                    }
+                }
+                DiffFormat::Unified => write!(stdout, "{}", unified_diff(&diffs, color))?,
+            }
+            if let Some(estimate) = &estimate {
+                info!("{estimate}");
+            }
+            // 編集内容を失わないよう、後で apply できる形式で保存する
+            if let Some(path) = &save_edits {
+                compress::write(path, &format!("{editor}\n"))?;
+                info!("{}", tr!("saved-edits", path = path.display().to_string()));
+            }
+            recovery.disarm();
+            manifest.finish(Outcome::DryRun);
+            return Err(Error::PendingChanges(diffs.len()));
+        }
+        // キャッシュは古い可能性があり、その差分を適用すると他の変更を上書きしかねない
+        if discord.used_cache() {
+            return Err(Error::UnsupportedOperation(
+                "changes computed from the cached channel list cannot be applied; run again without --cached or with --refresh".into(),
+            ));
+        }
+        // 警告は --force で確認済みとしない限り適用しない
+        if warnings > 0 && !force {
+            return Err(Error::Warnings(warnings));
+        }
+        if yes || confirmed {
+            if let Some(estimate) = &estimate {
+                info!("{estimate}");
+            }
+            break;
+        }
+        // 変更ごとに確認し、残った変更を適用する
+        if interactive {
+            skipped += review_diffs(&mut editor, color)?;
+            confirmed = true;
+            continue;
         }
 
-        // 同一カテゴリ内なら、ボイス系チャンネルを下にする
-        if self.is_voice_like_channel() && !other.is_voice_like_channel() {
-            return Ordering::Greater;
-        } else if !self.is_voice_like_channel() && other.is_voice_like_channel() {
-            return Ordering::Less;
+        // 変更予定表の表示
+        let widths = column_widths(&diffs);
+        let preview: Vec<_> = diffs
+            .iter()
+            .map(|diff| format_diff(diff, widths, color))
+            .collect();
+        show_preview(&preview, is_tty);
+        if let Some(estimate) = &estimate {
+            info!("{estimate}");
         }
 
-        // 同一カテゴリ内なら、positionでソート
-        match self.channel.parent_id.cmp(&other.channel.parent_id) {
-            Ordering::Equal => self.channel.position.cmp(&other.channel.position),
-            other => other,
+        let prompt = tr!("prompt-apply");
+        match edit_options {
+            // エディタで編集した場合は再編集を選べる
+            Some(ref options) => {
+                match Select::new()
+                    .with_prompt(&prompt)
+                    .items(&[
+                        tr!("choice-no"),
+                        tr!("choice-yes"),
+                        tr!("choice-edit-again"),
+                    ])
+                    .default(0)
+                    .interact()?
+                {
+                    1 => break,
+                    2 => {
+                        if edit_buffer(&mut editor, options, &mut recovery)? == Edited::Aborted {
+                            recovery.disarm();
+                            info!("{}", tr!("aborted-empty-buffer"));
+                            manifest.finish(Outcome::Cancelled);
+                            return Ok(());
+                        }
+                    }
+                    _ => {
+                        if !save_on_decline {
+                            recovery.disarm();
+                        }
+                        manifest.finish(Outcome::Cancelled);
+                        return Ok(());
+                    }
+                }
+            }
+            None => {
+                if !Confirm::new()
+                    .with_prompt(&prompt)
+                    .default(false)
+                    .interact()?
+                {
+                    if !save_on_decline {
+                        recovery.disarm();
+                    }
+                    manifest.finish(Outcome::Cancelled);
+                    return Ok(());
+                }
+                break;
+            }
         }
     }
-}
 
-impl Display for ChannelItem {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.channel_id)
+    // 削除は取り消せないため、追加で確認する
+    let drops = editor
+        .diffs()?
+        .iter()
+        .filter(|diff| diff.op == Op::Drop)
+        .count();
+    if drops > 0
+        && !yes
+        && !interactive
+        && !Confirm::new()
+            .with_prompt(tr!("prompt-delete", count = drops))
+            .default(false)
+            .interact()?
+    {
+        if !save_on_decline {
+            recovery.disarm();
+        }
+        manifest.finish(Outcome::Cancelled);
+        return Ok(());
     }
-}
 
-impl TextEditableItem for ChannelItem {
-    async fn apply(&mut self, content: String) -> Result<()> {
-        let editchannel = EditChannel::new().name(content);
-        self.channel_id
-            .edit(self.http.clone(), editchannel)
-            .await
-            .or(Err(io::Error::new(
-                io::ErrorKind::Other,
-                "failed to edit channel",
-            )))?;
-        Ok(())
+    // 変更のないアイテムの数。取り消した変更のアイテムは skipped に数える
+    let unchanged = {
+        let changed: HashSet<_> = editor
+            .diffs()?
+            .iter()
+            .filter(|diff| diff.op != Op::Create)
+            .map(|diff| diff.index)
+            .collect();
+        editor.items().len().saturating_sub(changed.len() + skipped)
+    };
+    let diffs: Vec<_> = editor.try_into()?;
+    let widths = column_widths(&diffs);
+    // --atomic では失敗時に全て元に戻すため、途中から再開することはない
+    if atomic {
+        resume = None;
+    }
+    if let Some(resume) = &mut resume {
+        resume.start(
+            diffs
+                .iter()
+                .map(|diff| (diff.item.to_string(), resume_line(diff))),
+        );
+        // 以降に中断した場合は、残りの変更を再開ファイルから適用できる
+        recovery.disarm();
     }
-    fn content(&self) -> String {
-        self.channel.name.clone()
+
+    // 誰が変更したか分かるよう、Botのユーザーを履歴に残す
+    if let Ok(user) = discord.current_user().await {
+        history.set_actor(format!("{} ({})", user.name, user.id));
     }
-    fn comment(&self) -> String {
-        let mut comment = match self.channel.kind {
-            ChannelType::Text => '📝',
-            ChannelType::Voice => '🔊',
-            ChannelType::Category => '📁',
-            ChannelType::News => '📣',
-            ChannelType::Forum => '💬',
-            ChannelType::Stage => '🎭',
-            _ => '❓',
+
+    // 変更状況の表示と適用
+    let total = diffs.len();
+    let mut succeeded = 0;
+    let mut failed = 0;
+    // --keep-going の場合に最後に表示する、各変更の結果
+    let mut summary = Vec::new();
+    // --atomic で失敗時に元に戻し、--verify で検証するための、適用済みのアイテムと変更前後の値
+    let mut applied = Vec::new();
+    // --atomic では全て適用できるまで、取り消しの記録を保留する
+    let mut pending_undo = Vec::new();
+    // --keep-going でない場合に、最初に失敗した変更のエラー
+    let mut error = None;
+    let mut queue = diffs.into_iter().enumerate();
+    // 適用中の変更。結果は開始した順に取り出す
+    let mut running = FuturesOrdered::new();
+    // 端末の場合は1行ずつ表示する代わりに、プログレスバーに処理中の変更を表示する
+    let mut progress = Progress::new(tr!("applying"), Some(total), is_tty, color);
+    let mut done = 0;
+    let started = Instant::now();
+    // 適用中のCtrl-Cでは新たな変更を開始せず、適用中の変更を待って結果を記録してから止まる
+    let deferred = interrupt::defer();
+    loop {
+        if interrupt::requested() && error.is_none() {
+            error = Some(Error::Interrupted);
+        }
+        // 同時に適用する数まで変更を開始する。失敗した後は新たに開始せず、適用中の変更を待つ
+        while error.is_none() && running.len() < jobs.max(1) {
+            let Some((i, diff)) = queue.next() else {
+                break;
+            };
+            let mut prompt = console::style(tr!("applying-prefix"));
+            if color {
+                prompt = prompt.blue().bold();
+            }
+            let applying = Applying {
+                i,
+                shown: format_diff(&diff, widths, color),
+                index: diff.index,
+                mapping: mapping_line(&diff),
+                id: diff.item.to_string(),
+                op: diff.op.clone(),
+                old: diff.old.clone(),
+                new: diff.new.clone(),
+                reverse: (diff.op == Op::Rename).then(|| {
+                    (
+                        diff.old.clone(),
+                        diff.new.clone(),
+                        keyed_comment(&diff.item),
+                    )
+                }),
+            };
+            if progress.enabled() {
+                progress.set_current(format_diff(&diff, (0, 0), false));
+            } else {
+                info!("{prompt} {}", applying.shown);
+            }
+            let mut fields = diff_json(&diff);
+            fields["index"] = i.into();
+            progress::event("apply_started", fields);
+            manifest.set_started(i);
+            let deadline = scheduler
+                .as_ref()
+                .filter(|_| diff.op == Op::Rename)
+                .and_then(|scheduler| scheduler.deadline(&applying.id));
+            running.push_back(async move {
+                if let Some(deadline) = deadline {
+                    // レートリミットを待つ間に中断された場合は、適用せずに止まる
+                    tokio::select! {
+                        () = schedule::wait_until(&applying.id, deadline, color) => {}
+                        () = interrupt::wait_requested() => {
+                            return (applying, Err(Error::Interrupted));
+                        }
+                    }
+                }
+                (applying, diff.apply().await)
+            });
+        }
+        let Some((applying, result)) = running.next().await else {
+            break;
+        };
+        // 中断により開始しなかった変更は、失敗ではなく未適用として数える
+        if let Err(Error::Interrupted) = result {
+            continue;
         }
-        .to_string();
-        let parent_name = self.parent_name.clone();
-        if let Some(parent_name) = parent_name {
-            comment.push_str(" in ");
-            comment.push_str(&parent_name);
+        done += 1;
+        progress.set_done(done);
+        let Applying {
+            i,
+            shown,
+            index,
+            mapping,
+            id,
+            op,
+            old,
+            new,
+            reverse,
+        } = applying;
+        match &result {
+            Ok(item) => progress::event(
+                "apply_ok",
+                json!({ "index": i, "id": id, "result_id": item.to_string() }),
+            ),
+            Err(e) => progress::event(
+                "apply_failed",
+                json!({ "index": i, "id": id, "error": Redacted(e).to_string() }),
+            ),
+        }
+        if let Ok(item) = &result {
+            history.record(&id, &item.to_string(), &op, &old, &new);
+        }
+        match (&result, &mut scheduler, &reverse) {
+            (Ok(_), Some(scheduler), Some(_)) => scheduler.record(&id),
+            (Err(Error::RateLimited(_)), Some(scheduler), Some(_)) => scheduler.record_limited(&id),
+            _ => {}
+        }
+        // 検証するのは値の変更のみ
+        let new = reverse.as_ref().map(|(_, new, _)| new.clone());
+        match (&result, &mut undo, reverse) {
+            (Ok(_), Some(_), Some(reverse)) if atomic => pending_undo.push((id, reverse)),
+            (Ok(_), Some(undo), Some((old, new, comment))) => {
+                undo.record(&id, &old, &new, &comment)
+            }
+            _ => {}
+        }
+        if let (Ok(_), Some(resume)) = (&result, &mut resume) {
+            resume.done(i);
+        }
+        if let Err(e) = &result {
+            annotator.error(Some(index), &Redacted(e).to_string());
+        }
+        manifest.set_result(
+            i,
+            result
+                .as_ref()
+                .map(|_| ())
+                .map_err(|e| Redacted(e).to_string()),
+        );
+        match result {
+            Ok(item) => {
+                succeeded += 1;
+                summary.push((shown, None));
+                if print_mapping {
+                    // 作成したアイテムのIDは適用後に決まるため、適用後のアイテムのIDを出力する
+                    let mut stdout = stdout().lock();
+                    writeln!(stdout, "{mapping}\t{item}")?;
+                    stdout.flush()?;
+                }
+                if atomic || verify {
+                    applied.push((item, old, new));
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                let mut prompt = console::style(tr!("failed-prefix"));
+                if color {
+                    prompt = prompt.red().bold();
+                }
+                if keep_going {
+                    // 失敗した変更を報告し、残りの変更の適用を続ける
+                    progress::println(format!("{prompt} {shown}: {}", Redacted(&e)));
+                    summary.push((shown, Some(Redacted(&e).to_string())));
+                } else if error.is_none() {
+                    error = Some(e);
+                } else {
+                    // 最初のエラー以外は、同時に適用していた変更のもの
+                    progress::println(format!("{prompt} {shown}: {}", Redacted(&e)));
+                }
+            }
         }
-        comment.push_str(" (");
-        comment.push_str(&self.channel_id.to_string());
-        comment.push(')');
-        comment
     }
-    fn validate(&self, new: &str) -> Result<()> {
-        let len = new.chars().count();
-        if !(2..=100).contains(&len) {
-            return Err(Error::InvalidChannelName {
-                name: new.to_string(),
-                message: "Channel name must be between 2 and 100 characters",
-            });
+    drop(progress);
+    drop(deferred);
+    let totals = Totals {
+        applied: succeeded,
+        unchanged,
+        skipped,
+        failed,
+        not_applied: total - succeeded - failed,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+    };
+    print_totals(&totals, color);
+    manifest.set_totals(totals);
+    if keep_going && matches!(error, Some(Error::Interrupted)) {
+        print_summary(&summary, color);
+    }
+    if let Some(e) = error {
+        // 適用済みの変更を元に戻してから中断する
+        if atomic {
+            let applied = applied.into_iter().map(|(item, old, _)| (item, old));
+            roll_back(applied.collect(), scheduler.as_mut(), color).await;
+        }
+        return Err(e);
+    }
+    let mismatches = if verify {
+        verify_applied(&applied).await
+    } else {
+        0
+    };
+    if keep_going {
+        print_summary(&summary, color);
+    }
+    if let Some(undo) = &mut undo {
+        for (id, (old, new, comment)) in pending_undo {
+            undo.record(&id, &old, &new, &comment);
         }
+    }
+    if failed > 0 {
+        return Err(Error::PartialFailure { failed, total });
+    }
+    recovery.disarm();
+    if mismatches > 0 {
+        manifest.finish(Outcome::Failed);
+        return Err(Error::VerificationFailed(mismatches));
+    }
 
-        let err = Err(Error::InvalidChannelName {
-            name: new.to_string(),
-            message: "Contains characters or patterns that cannot be used",
-        });
+    // 全て適用できた場合のみ、変更の一覧を投稿する
+    if let Some(channel_id) = notify_channel {
+        let lines: Vec<_> = summary
+            .iter()
+            .map(|(shown, _)| console::strip_ansi_codes(shown).trim_end().to_string())
+            .collect();
+        notify::post_summary(&discord, channel_id, &lines, discord_args.reason.as_deref()).await?;
+        info!(
+            "{}",
+            tr!("posted-summary", channel = channel_id.to_string())
+        );
+    }
 
-        // TODO: 文字種やルールの制限が不十分。
-        let re = if self.channel.kind == ChannelType::Category {
-            Regex::new(r"^[\-\w]*|[^\x00-\x7F ]*$").unwrap()
-        } else {
-            Regex::new(r"^[\-\w]*|[^\x00-\x7F]*$").unwrap()
+    manifest.finish(Outcome::Applied);
+    Ok(())
+}
+
+/// 編集するアイテムをチェックリストで選ばせる
+fn pick_items<T: TextEditableItem>(items: Vec<T>) -> Result<Vec<T>> {
+    let labels: Vec<_> = items
+        .iter()
+        .map(|item| format!("{}  {}", item.content().replace('\n', " "), item.comment()))
+        .collect();
+    let selected = MultiSelect::new()
+        .with_prompt(tr!("prompt-pick"))
+        .items(&labels)
+        .interact()?;
+    Ok(items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| selected.contains(i))
+        .map(|(_, item)| item)
+        .collect())
+}
+
+/// 同じ範囲 ([`TextEditableItem::duplicate_scope`]) で、変更後の名前が (大文字小文字を無視して) 重複するアイテムを探す。
+/// 警告するか、最初のアイテム以外の名前に `-2`, `-3`, … を付けて重複を解消する。名前を変えた場合は true を返す
+fn dedup_names<T: TextEditableItem<Error = Error> + Display>(
+    editor: &mut Editor<T>,
+    mode: DedupMode,
+) -> Result<bool> {
+    // 変更後の名前。削除・移動するアイテムは調べない
+    let mut names: Vec<_> = editor
+        .items()
+        .iter()
+        .map(|item| Some(item.content()))
+        .collect();
+    // 検証に失敗する場合は、重複を調べる前に報告させる
+    let Ok(diffs) = editor.diffs() else {
+        return Ok(false);
+    };
+    for diff in diffs {
+        match diff.op {
+            Op::Rename => names[diff.index] = Some(diff.new),
+            Op::Drop | Op::Move(_) => names[diff.index] = None,
+            Op::Create => {}
+        }
+    }
+    let mut groups: Vec<((String, String), Vec<usize>)> = Vec::new();
+    let mut renamed = false;
+    for (i, name) in names.iter().enumerate() {
+        let (Some(scope), Some(name)) = (editor.items()[i].duplicate_scope(), name) else {
+            continue;
         };
-        if !re.is_match(new) || new.contains("--") {
-            return err;
+        let key = (scope, name.to_lowercase());
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, indices)) => indices.push(i),
+            None => groups.push((key, vec![i])),
         }
+    }
+    for ((scope, _), indices) in groups.into_iter().filter(|(_, indices)| indices.len() > 1) {
+        let first = indices[0];
+        let name = names[first].clone().unwrap_or_default();
+        if mode == DedupMode::Warn {
+            let ids: Vec<_> = indices
+                .iter()
+                .map(|&i| editor.items()[i].to_string())
+                .collect();
+            let place = editor.items()[first]
+                .section()
+                .unwrap_or_else(|| tr!("no-category"));
+            warn!(
+                "{}",
+                tr!(
+                    "duplicate-names-found",
+                    count = indices.len(),
+                    name = format!("{name:?}"),
+                    place = place,
+                    items = ids.join(", ")
+                )
+            );
+            continue;
+        }
+        let mut n = 2;
+        for &i in &indices[1..] {
+            // 同じ範囲の他の名前と重ならない番号を選ぶ
+            let new = loop {
+                let new = format!("{name}-{n}");
+                n += 1;
+                let taken = names.iter().enumerate().any(|(j, other)| {
+                    other
+                        .as_ref()
+                        .is_some_and(|other| other.to_lowercase() == new.to_lowercase())
+                        && editor.items()[j].duplicate_scope().as_ref() == Some(&scope)
+                });
+                if !taken {
+                    break new;
+                }
+            };
+            editor.set_content(i, &new);
+            names[i] = Some(new);
+            renamed = true;
+        }
+    }
+    Ok(renamed)
+}
 
-        Ok(())
+/// 変更後の名前が、同じ範囲で他のアイテム (変更しないもの・作成するものを含む) と (大文字小文字を無視して) 重複する場合に、
+/// 全ての重複を報告してエラーとする。変更に関わらない既存の重複は報告しない
+fn check_duplicates<T: TextEditableItem + Display>(
+    editor: &Editor<T>,
+    diffs: &[Diff<&T>],
+) -> Result<()> {
+    /// 変更後の名前
+    struct Named {
+        scope: String,
+        name: String,
+        /// 報告に表示するアイテム
+        shown: String,
+        place: Option<String>,
+        changed: bool,
+    }
+    // 削除・移動するアイテムは調べない
+    let mut names: Vec<_> = editor
+        .items()
+        .iter()
+        .map(|item| {
+            Some(Named {
+                scope: item.duplicate_scope()?,
+                name: item.content(),
+                shown: item.to_string(),
+                place: item.section(),
+                changed: false,
+            })
+        })
+        .collect();
+    for diff in diffs {
+        match &diff.op {
+            Op::Rename => {
+                if let Some(named) = &mut names[diff.index] {
+                    named.name = diff.new.clone();
+                    named.changed = true;
+                }
+            }
+            Op::Drop | Op::Move(_) => names[diff.index] = None,
+            Op::Create => names.push(diff.item.created_duplicate_scope(&diff.new).map(
+                |(scope, name)| Named {
+                    scope,
+                    name,
+                    shown: "new".to_string(),
+                    place: diff.item.section(),
+                    changed: true,
+                },
+            )),
+        }
+    }
+    let mut groups: Vec<((&str, String), Vec<&Named>)> = Vec::new();
+    for named in names.iter().flatten() {
+        let key = (named.scope.as_str(), named.name.to_lowercase());
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(named),
+            None => groups.push((key, vec![named])),
+        }
+    }
+    let duplicates: Vec<_> = groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.len() > 1 && group.iter().any(|named| named.changed))
+        .collect();
+    for group in &duplicates {
+        let shown: Vec<_> = group.iter().map(|named| named.shown.as_str()).collect();
+        let place = group[0].place.clone().unwrap_or_else(|| tr!("no-category"));
+        warn!(
+            "{}",
+            tr!(
+                "duplicate-names-planned",
+                count = group.len(),
+                name = format!("{:?}", group[0].name),
+                place = place,
+                items = shown.join(", ")
+            )
+        );
+    }
+    if !duplicates.is_empty() {
+        return Err(Error::DuplicateNames(duplicates.len()));
     }
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() {
-    let is_tty = atty::is(Stream::Stderr);
+/// 適用はできるが意図と異なる結果になりうる変更 ([`TextEditableItem::lint`]) を警告し、警告の数を返す
+fn lint_diffs<T: TextEditableItem + Display>(diffs: &[Diff<&T>]) -> usize {
+    let mut count = 0;
+    for diff in diffs {
+        for warning in diff.item.lint(&diff.op, &diff.new) {
+            warn!("{:?} ({}): {warning}", diff.new, diff.item);
+            count += 1;
+        }
+    }
+    count
+}
 
-    if let Err(e) = run(is_tty).await {
-        let prompt = if e.unknown() {
-            let mut p = console::style("UNKNOWN ERROR");
-            if is_tty {
-                p = p.on_red().bold();
+/// 変更を一つずつ表示して適用するかを尋ね、適用しない変更をバッファから取り消す。
+/// `e` では改名後のテキストをその場で書き換えられる。取り消した変更の数を返す
+fn review_diffs<T: TextEditableItem<Error = Error> + Display>(
+    editor: &mut Editor<T>,
+    color: bool,
+) -> Result<usize> {
+    let diffs = editor.diffs()?;
+    let widths = column_widths(&diffs);
+    let changes: Vec<_> = diffs
+        .iter()
+        .map(|diff| {
+            let line = format_diff(diff, widths, color);
+            (diff.index, diff.op.clone(), diff.new.clone(), line)
+        })
+        .collect();
+    let total = changes.len();
+    let mut kept = 0;
+    // 残りの変更を全て適用する (true) か、全て取り消す (false) か
+    let mut rest = None;
+    for (n, (index, op, new, line)) in changes.into_iter().enumerate() {
+        let keep = loop {
+            if let Some(all) = rest {
+                break all;
             }
-            p
-        } else {
-            let mut p = console::style("error:");
-            if is_tty {
-                p = p.red().bold();
+            eprintln!("{line}");
+            let answer: String = Input::new()
+                .with_prompt(tr!("prompt-review", n = n + 1, total = total))
+                .validate_with(|answer: &String| match answer.as_str() {
+                    "y" | "n" | "a" | "q" | "e" => Ok(()),
+                    _ => Err(tr!("review-invalid-answer")),
+                })
+                .interact_text()?;
+            match answer.as_str() {
+                "y" => break true,
+                "n" => break false,
+                "a" => rest = Some(true),
+                "q" => rest = Some(false),
+                _ => {
+                    let item = &editor.items()[index];
+                    if op != Op::Rename || item.multiline() || item.columns() != 1 {
+                        eprintln!("{}", tr!("review-single-line-only"));
+                        continue;
+                    }
+                    let edited: String = Input::new()
+                        .with_prompt(tr!("prompt-new-value"))
+                        .with_initial_text(&new)
+                        .validate_with(|new: &String| item.validate(new).map_err(|e| e.to_string()))
+                        .interact_text()?;
+                    let changed = edited != item.content();
+                    editor.set_content(index, &edited);
+                    break changed;
+                }
             }
-            p
         };
-        eprint!("{} ", prompt);
-        eprintln!("{}", e);
-        std::process::exit(1);
+        if keep {
+            kept += 1;
+        } else {
+            editor.discard(index, &op, &new);
+        }
     }
+    eprintln!("{}", tr!("review-kept", kept = kept, total = total));
+    Ok(total - kept)
 }
 
-async fn run(is_tty: bool) -> Result<()> {
-    let work: Work = Args::parse().into();
-
-    let (discord, filter, io, apply) = match work {
-        Work::Completion(shell) => {
-            shell_completion(shell);
-            return Ok(());
-        }
-        Work::Edit {
-            discord,
-            filter,
-            io,
-            apply,
-        } => (discord, filter, io, apply),
+/// エディタで編集し、編集結果として設定する。
+/// 設定や検証に失敗した (行数が合わない・名前が不正など) 場合は、編集したテキストのままエディタを開き直す。
+/// 開き直したテキストを変更せずに閉じた場合は、そのエラーを返す。
+/// 途中で終了しても失われないよう、編集したバッファを記録しておく
+fn edit_buffer<T: TextEditableItem<Error = Error>>(
+    editor: &mut Editor<T>,
+    options: &EditOptions,
+    recovery: &mut Recovery,
+) -> Result<Edited> {
+    let Some(mut text) = editor.edit_text(options)? else {
+        return Ok(Edited::Unchanged);
     };
+    loop {
+        let trimmed = text.trim_end_matches('\n').to_string();
+        recovery.set(trimmed.clone());
+        let result = editor
+            .set_text(text)
+            .map_err(Error::from)
+            .and_then(|edited| {
+                if edited == Edited::Changed {
+                    editor.diffs()?;
+                }
+                Ok(edited)
+            });
+        let e = match result {
+            Ok(edited) => return Ok(edited),
+            Err(e) => e,
+        };
+        warn!("{}", Redacted(&e));
+        text = match edisch_core::edit_document(&trimmed, options)? {
+            Some(text) => text,
+            None => return Err(e),
+        };
+    }
+}
 
-    let (http, guild_id) = {
-        // 設定したいGuild ID
-        let guild_id = GuildId::new(discord.guild_id.unwrap_or({
-            let Ok(id) = env::var("GUILD_ID") else {
-                return Err(Error::MissingArgument("GUILD_ID".into()));
-            };
-            let Ok(id) = id.parse() else {
-                return Err(Error::ParseArgument("GUILD_ID".into()));
+/// 適用中の変更の、結果を処理するために必要な情報
+struct Applying {
+    /// 変更の番号
+    i: usize,
+    /// 変更の表示
+    shown: String,
+    /// 編集結果での行の位置
+    index: usize,
+    /// --print-mapping で出力する行
+    mapping: String,
+    /// アイテムのID
+    id: String,
+    /// 変更の操作
+    op: Op,
+    /// 変更前の値
+    old: String,
+    /// 変更後の値
+    new: String,
+    /// 値の変更の場合、取り消すための (変更前, 変更後, コメント)
+    reverse: Option<(String, String, String)>,
+}
+
+/// 書き出した後にサーバー上で値が変更されたアイテムを、上書きしないよう編集前の状態に戻す。
+/// strict の場合は戻さずにエラーとする。戻したアイテムの数を返す
+fn skip_conflicts<T: TextEditableItem + Display>(
+    editor: &mut Editor<T>,
+    bases: &[Option<String>],
+    strict: bool,
+) -> Result<usize> {
+    let conflicts: Vec<_> = (0..editor.items().len())
+        .filter(|&i| {
+            // 行の位置で対応づけた場合は、アイテムと同じ位置の行
+            let line = match editor.line_indices() {
+                Some(indices) => indices[i],
+                None => Some(i),
             };
-            id
-        }));
+            let base = line.and_then(|line| bases.get(line)?.as_deref());
+            base.is_some_and(|base| base != format::fingerprint(&editor.items()[i].content()))
+        })
+        .collect();
+    for &i in &conflicts {
+        let item = &editor.items()[i];
+        warn!(
+            "{}",
+            tr!(
+                "changed-since-export",
+                value = format!("{:?}", item.content()),
+                item = item.to_string(),
+                skipped = if strict { "no" } else { "yes" }
+            )
+        );
+    }
+    if strict && !conflicts.is_empty() {
+        return Err(Error::Conflicts(conflicts.len()));
+    }
+    let skipped = conflicts.len();
+    for i in conflicts {
+        editor.revert(i);
+    }
+    Ok(skipped)
+}
 
-        let token = discord
-            .token
-            .clone()
-            .unwrap_or(env::var("DISCORD_TOKEN").unwrap_or_default());
-        if token.is_empty() {
-            return Err(Error::MissingArgument("DISCORD_TOKEN".into()));
-        }
+/// Botが編集する権限を持たないアイテムへの変更を報告し、取り消す変更 (行番号, 操作, 変更後のテキスト) を返す。
+/// strict の場合は取り消さずにエラーとする
+fn uneditable_diffs<T: Display>(
+    diffs: &[Diff<&T>],
+    uneditable: &HashSet<String>,
+    strict: bool,
+) -> Result<Vec<(usize, Op, String)>> {
+    // 作成は直前の行のアイテムを基にするため、そのアイテムの権限とは関係しない
+    let denied: Vec<_> = diffs
+        .iter()
+        .filter(|diff| diff.op != Op::Create && uneditable.contains(&diff.item.to_string()))
+        .collect();
+    for diff in &denied {
+        warn!(
+            "{}",
+            tr!(
+                "missing-manage-channels",
+                old = diff.old.clone(),
+                new = diff.label().to_string(),
+                item = diff.item.to_string(),
+                skipped = if strict { "no" } else { "yes" }
+            )
+        );
+    }
+    if strict && !denied.is_empty() {
+        return Err(Error::MissingPermissions(denied.len()));
+    }
+    Ok(denied
+        .into_iter()
+        .map(|diff| (diff.index, diff.op.clone(), diff.new.clone()))
+        .collect())
+}
 
-        // 接続
-        let http = Arc::new(Http::new(&token));
-        (http, guild_id)
+/// OldとNewの表示文字列の幅を揃えるための計算
+fn column_widths<T>(diffs: &[Diff<T>]) -> (usize, usize) {
+    let shown: Vec<_> = diffs.iter().map(shown).collect();
+    let old_width = shown
+        .iter()
+        .map(|(old, _)| UnicodeWidthStr::width(old.as_str()))
+        .max()
+        .unwrap_or(0);
+    let new_width = shown
+        .iter()
+        .map(|(_, new)| UnicodeWidthStr::width(new.as_str()))
+        .max()
+        .unwrap_or(0);
+    (old_width, new_width)
+}
+
+/// 変更前後の表示。複数の列からなるテキストは、変更された列のみを ` | ` で区切って表示する
+fn shown<T>(diff: &Diff<T>) -> (String, String) {
+    if diff.op == Op::Rename && diff.old.contains('\t') {
+        let changes = diff.changed_columns();
+        let old: Vec<_> = changes.iter().map(|(_, old, _)| *old).collect();
+        let new: Vec<_> = changes.iter().map(|(_, _, new)| *new).collect();
+        return (old.join(" | "), new.join(" | "));
+    }
+    let old = match diff.op {
+        Op::Create => "(new)".into(),
+        _ => one_line(&diff.old),
     };
+    (old.into_owned(), one_line(&diff.label()).into_owned())
+}
 
-    // 指定したGuildのチャンネル一覧を取得
-    {
-        // チャンネル一覧取得中の表示
-        let mut msg = console::style("Fetching channels...");
-        if is_tty {
-            msg = msg.dim();
+/// 変更を `old -> new  (id)` の形式で表示する
+fn format_diff<T: Display>(
+    diff: &Diff<T>,
+    (old_width, new_width): (usize, usize),
+    color: bool,
+) -> String {
+    let (mut old, mut label) = shown(diff);
+    // 改名では、どの文字が変わったかを強調する
+    if color && diff.op == Op::Rename {
+        (old, label) = highlight_changes(&old, &label);
+    }
+    let mut old = console::style(pad_str(&old, old_width, console::Alignment::Left, None));
+    let mut new = console::style(pad_str(&label, new_width, console::Alignment::Left, None));
+    let mut id = console::style(format!("({})", diff.item));
+    let split = " -> ".to_string();
+    if color {
+        if diff.op != Op::Rename {
+            old = old.green();
+            new = new.green();
         }
-        eprintln!("{msg}");
-        stdout().flush().unwrap();
+        id = id.dim().italic();
     }
+    format!("{old}{split}{new}  {id}")
+}
 
-    let channels = {
-        defer! {
-            if is_tty {
-                // チャンネル一覧取得中の表示を消す
-                eprint!("\x1B[1A\x1B[2K");
-                stdout().flush().unwrap();
-            }
+/// 変更を unified diff 形式で表す。各変更を一つのハンクとし、見出しにアイテムの ID と移動先を書く。
+/// 行番号は編集前のアイテムの位置 (1始まり) で、作成・削除による行のずれを変更後の行番号に反映する
+fn unified_diff<T: Display>(diffs: &[Diff<T>], color: bool) -> String {
+    let mut lines = Vec::new();
+    let mut push = |line: String, style: fn(StyledObject<String>) -> StyledObject<String>| {
+        lines.push(match color {
+            true => style(console::style(line)).to_string(),
+            false => line,
+        });
+    };
+    push("--- a/current".to_string(), StyledObject::bold);
+    push("+++ b/edited".to_string(), StyledObject::bold);
+    // それまでの変更による、変更後の行番号のずれ
+    let mut offset = 0isize;
+    for diff in diffs {
+        let old: Vec<_> = diff.old.lines().collect();
+        let new: Vec<_> = diff.new.lines().collect();
+        let (removed, added, context, heading): (&[&str], &[&str], &[&str], _) = match &diff.op {
+            Op::Rename => (&old, &new, &[], String::new()),
+            Op::Drop => (&old, &[], &[], " delete".to_string()),
+            Op::Create => (&[], &new, &[], " create".to_string()),
+            Op::Move(target) => (&[], &[], &old, format!(" move to {target}")),
+        };
+        // 作成は、直前の行のアイテムの後ろへの追加として表す
+        let start = match diff.op {
+            Op::Create => diff.index as isize + 2,
+            _ => diff.index as isize + 1,
+        };
+        let old_count = removed.len() + context.len();
+        let new_count = added.len() + context.len();
+        // 行数が0の場合、開始位置はその直前の行を指す
+        let old_start = if old_count == 0 { start - 1 } else { start };
+        let new_start = if new_count == 0 { start - 1 } else { start } + offset;
+        offset += new_count as isize - old_count as isize;
+        push(
+            format!(
+                "@@ -{old_start},{old_count} +{new_start},{new_count} @@ {}{heading}",
+                diff.item
+            ),
+            StyledObject::cyan,
+        );
+        for line in context {
+            push(format!(" {line}"), |s| s);
         }
+        for line in removed {
+            push(format!("-{line}"), StyledObject::red);
+        }
+        for line in added {
+            push(format!("+{line}"), StyledObject::green);
+        }
+    }
+    lines.join("\n") + "\n"
+}
 
-        if filter.none() {
-            HashMap::new()
-        } else {
-            guild_id.channels(&http).await?
+/// 変更前後のテキストを文字単位で比較し、削除された文字を赤、追加された文字を緑で強調する
+fn highlight_changes(old: &str, new: &str) -> (String, String) {
+    let diff = TextDiff::from_chars(old, new);
+    let (mut old, mut new) = (String::new(), String::new());
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let removed = diff.old_slices()[old_range].concat();
+        let added = diff.new_slices()[new_range].concat();
+        if tag == DiffTag::Equal {
+            old.push_str(&removed);
+            new.push_str(&added);
+            continue;
         }
-    };
+        if !removed.is_empty() {
+            old.push_str(
+                &console::style(removed)
+                    .red()
+                    .bold()
+                    .underlined()
+                    .to_string(),
+            );
+        }
+        if !added.is_empty() {
+            new.push_str(
+                &console::style(added)
+                    .green()
+                    .bold()
+                    .underlined()
+                    .to_string(),
+            );
+        }
+    }
+    (old, new)
+}
 
-    // フィルタリングとパース、ソート
-    let items = {
-        let mut items: Vec<_> = channels
-            .clone()
-            .into_iter()
-            .filter_map(|(channel_id, channel)| {
-                let kind = channel.kind;
-                let parent_name = 'p: {
-                    let Some(id) = channel.parent_id else {
-                        break 'p None;
-                    };
-                    let Some(parent) = channels.get(&id) else {
-                        break 'p None;
-                    };
-                    Some(parent.name.clone())
-                };
-                let category_position = if let Some(parent_id) = channel.parent_id {
-                    channels
-                        .get(&parent_id)
-                        .map(|p| p.position)
-                        .unwrap_or(channel.position)
-                } else {
-                    channel.position
-                };
-                if (&filter) & kind {
-                    Some(ChannelItem {
-                        http: http.clone(),
-                        channel,
-                        channel_id,
-                        parent_name,
-                        category_position,
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect();
-        if items.is_empty() {
-            eprintln!("No channels found");
-            return Ok(());
+/// 変更予定表を表示する。端末の高さに収まらない場合は、確認の前に読み通せるようページャーで表示する。
+/// ページャーは $PAGER (空の場合は使わない)、未設定の場合は `less -R`
+fn show_preview(lines: &[String], is_tty: bool) {
+    let height = Term::stderr().size().0 as usize;
+    if !is_tty || !Term::stdout().is_term() || lines.len() + 2 <= height {
+        for line in lines {
+            eprintln!("{line}");
         }
-        items.sort();
-        items
-    };
+        return;
+    }
+    let pager = env::var("PAGER").unwrap_or("less -R".to_string());
+    // エディタのコマンドと同様に、引用符で囲んだ空白を含むパスも扱う
+    let paged = edisch_core::split_command(&pager).is_ok_and(|(program, args)| {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()
+        else {
+            return false;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            // ページャーを途中で閉じた場合の書き込みエラーは無視する
+            writeln!(stdin, "{}", lines.join("\n")).ok();
+        }
+        child.wait().is_ok()
+    });
+    if paged {
+        // ページャーを閉じると表は見えなくなるため、件数を残す
+        eprintln!("{}", tr!("changes-count", count = lines.len()));
+    } else {
+        for line in lines {
+            eprintln!("{line}");
+        }
+    }
+}
 
-    // チャンネル名の一括編集
-    let mut editor = Editor::new(items.into_iter())?;
+/// 複数行のテキストを、改行を `⏎` に置き換えて1行で表示する
+fn one_line(text: &str) -> Cow<'_, str> {
+    if text.contains('\n') {
+        Cow::Owned(text.replace('\n', "⏎"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
 
-    let diffs: Vec<_> = {
-        match io {
-            IOMode::Output(output) => {
-                match output {
-                    Some(file) => {
-                        let mut output = BufWriter::new(File::create(file)?);
-                        writeln!(output, "{}", editor)?;
-                    }
-                    None => {
-                        let mut output = BufWriter::new(stdout());
-                        writeln!(output, "{}", editor)?;
-                    }
+/// --atomic で、適用済みの変更を逆順に元に戻す
+async fn roll_back<T: TextEditableItem<Error = Error> + Display>(
+    applied: Vec<(T, String)>,
+    mut scheduler: Option<&mut RenameScheduler>,
+    color: bool,
+) {
+    for (mut item, old) in applied.into_iter().rev() {
+        let mut prompt = console::style(tr!("rolling-back-prefix"));
+        if color {
+            prompt = prompt.yellow().bold();
+        }
+        info!("{prompt} {} ({item})", one_line(&old));
+        match item.apply(old).await {
+            Ok(()) => {
+                if let Some(scheduler) = &mut scheduler {
+                    scheduler.record(&item.to_string());
                 }
-                return Ok(());
             }
-            IOMode::Editor => {
-                editor.edit()?;
+            Err(e) => error!(
+                "{}",
+                tr!(
+                    "roll-back-failed",
+                    item = item.to_string(),
+                    detail = Redacted(e).to_string()
+                )
+            ),
+        }
+    }
+}
+
+/// 適用した値の変更を取得し直し、変更後のテキストと一致しないものを報告する。一致しなかった数を返す
+async fn verify_applied<T: TextEditableItem<Error = Error> + Display>(
+    applied: &[(T, String, Option<String>)],
+) -> usize {
+    let mut mismatches = 0;
+    let mut verified = 0;
+    for (item, _, new) in applied {
+        let Some(new) = new else {
+            continue;
+        };
+        match item.fetch_content().await {
+            Ok(Some(actual)) if actual == *new => verified += 1,
+            Ok(Some(actual)) => {
+                warn!(
+                    "{}",
+                    tr!(
+                        "not-applied-as-written",
+                        value = format!("{new:?}"),
+                        item = item.to_string(),
+                        actual = format!("{actual:?}")
+                    )
+                );
+                mismatches += 1;
             }
-            IOMode::Input(input) => {
-                let text = {
-                    let mut text = String::new();
-                    match input {
-                        Some(ref p) => {
-                            BufReader::new(File::open(p)?).read_to_string(&mut text)?;
-                        }
-                        None => {
-                            BufReader::new(stdin()).read_to_string(&mut text)?;
-                        }
-                    }
-                    text
-                };
-                editor.set_text(text)?;
+            Ok(None) => {}
+            Err(e) => {
+                warn!(
+                    "{}",
+                    tr!(
+                        "not-verified",
+                        value = format!("{new:?}"),
+                        item = item.to_string(),
+                        detail = Redacted(e).to_string()
+                    )
+                );
+                mismatches += 1;
             }
         }
-        editor.try_into()?
-    };
+    }
+    if mismatches == 0 {
+        info!("{}", tr!("verified", count = verified));
+    }
+    mismatches
+}
 
-    if let Some(ApplyArgs { yes, .. }) = apply {
-        if diffs.is_empty() {
-            eprintln!("No changes to apply");
-            return Ok(());
+/// 各変更の適用結果を一覧で表示する
+fn print_summary(summary: &[(String, Option<String>)], color: bool) {
+    let mut heading = console::style(tr!("summary-heading"));
+    if color {
+        heading = heading.bold();
+    }
+    eprintln!("{heading}");
+    for (shown, error) in summary {
+        let status = match error {
+            Some(_) => tr!("summary-failed"),
+            None => tr!("summary-ok"),
+        };
+        // 日本語でも揃うよう、表示幅で埋める
+        let mut status = console::style(pad_str(&status, 6, console::Alignment::Left, None));
+        if color {
+            status = match error {
+                Some(_) => status.red().bold(),
+                None => status.green(),
+            };
+        }
+        match error {
+            Some(e) => eprintln!("  {status} {shown}: {e}"),
+            None => eprintln!("  {status} {shown}"),
         }
+    }
+}
 
-        // OldとNewの表示文字列の幅を揃えるための計算
-        let old_width = {
-            let max_strwidth = diffs
-                .iter()
-                .map(|diff| UnicodeWidthStr::width(diff.old.as_str()))
-                .max()
-                .unwrap_or(0);
-            max_strwidth
-        };
-        let new_width = {
-            let max_strwidth = diffs
-                .iter()
-                .map(|diff| UnicodeWidthStr::width(diff.new.as_str()))
-                .max()
-                .unwrap_or(0);
-            max_strwidth
-        };
+/// 適用した・変更のない・適用しなかった・失敗した変更の数と、適用にかかった時間を1行で表示する
+fn print_totals(totals: &Totals, color: bool) {
+    let mut line = console::style(tr!(
+        "run-totals",
+        applied = totals.applied,
+        unchanged = totals.unchanged,
+        skipped = totals.skipped,
+        failed = totals.failed,
+        pending = totals.not_applied,
+        elapsed = schedule::format_duration(Duration::from_secs_f64(totals.elapsed_secs))
+    ));
+    if color {
+        line = line.bold();
+    }
+    eprintln!("{line}");
+}
 
-        if !yes {
-            // 変更予定表の表示
-            for diff in &diffs {
-                let mut old = console::style(pad_str(
-                    &diff.old,
-                    old_width,
-                    console::Alignment::Left,
-                    None,
-                ));
-                let mut new = console::style(pad_str(
-                    &diff.new,
-                    new_width,
-                    console::Alignment::Left,
-                    None,
-                ));
-                let mut id = console::style(format!("({})", diff.item));
-                let split = " -> ".to_string();
-                if is_tty {
-                    old = old.green();
-                    new = new.green();
-                    id = id.dim().italic();
-                }
-                eprintln!("{old}{split}{new}  {id}");
-            }
+/// 進捗のイベントに含める変更の内容
+fn diff_json<T: Display>(diff: &Diff<T>) -> serde_json::Value {
+    json!({
+        "id": diff.item.to_string(),
+        "op": diff.op.to_string(),
+        "old": diff.old,
+        "new": diff.new,
+    })
+}
 
-            if !Confirm::new()
-                .with_prompt("Do you want to apply these changes?")
-                .default(false)
-                .interact()?
-            {
-                return Ok(());
-            }
-        }
+/// 変更を `apply -i` で再び適用するための行
+fn resume_line<T: TextEditableItem + Display>(diff: &Diff<T>) -> String {
+    let comment = keyed_comment(&diff.item);
+    match &diff.op {
+        Op::Rename => format!("{}\t{comment}", diff.new),
+        Op::Drop => format!("!drop {}\t{comment}", diff.old),
+        Op::Move(target) => format!("!move {target}\t{comment}"),
+        // 追加された行は --structural で作成する
+        Op::Create => diff.new.clone(),
+    }
+}
 
-        // 変更状況の表示と適用
-        for diff in diffs {
-            let mut prompt = console::style("Applying:");
-            let mut old = console::style(pad_str(
-                &diff.old,
-                old_width,
-                console::Alignment::Left,
-                None,
-            ));
-            let mut new = console::style(pad_str(
-                &diff.new,
-                new_width,
-                console::Alignment::Left,
-                None,
-            ));
-            let mut id = console::style(format!("({})", diff.item));
-            let split = " -> ".to_string();
-            if is_tty {
-                prompt = prompt.blue().bold();
-                old = old.green();
-                new = new.green();
-                id = id.dim().italic();
-            }
+/// 後で `apply -i` で読み込んだ際に行とアイテムを対応づけられる、IDをキーに持つコメント。
+/// --comment-format でIDを含めなかった場合は、末尾にIDを添える
+fn keyed_comment<T: TextEditableItem + Display>(item: &T) -> String {
+    let comment = item.comment();
+    let id = item.to_string();
+    if comment_key(&comment) == id {
+        return comment;
+    }
+    format!("{comment} ({id})").trim_start().to_string()
+}
 
-            eprintln!("{prompt} {old}{split}{new}  {id}");
-            diff.apply().await?;
+/// 適用した変更の `old<TAB>new` の部分を表す。削除した場合 new は空に、作成した場合 old は空になる
+fn mapping_line<T>(diff: &Diff<T>) -> String {
+    let new = match diff.op {
+        Op::Drop => "",
+        _ => &diff.new,
+    };
+    format!("{}\t{new}", diff.old)
+}
+
+/// 引数、$DISCORD_TOKEN または設定ファイルのトークンでDiscord APIクライアントを作成する
+fn connect(discord: &ConnectionArgs, config: &Config) -> Result<Arc<Discord>> {
+    let token = match &discord.token_file {
+        Some(path) => Some(auth::read_token_file(path)?),
+        None => discord.token.clone().or_else(|| config.token()),
+    };
+    // 再生時は通信しないためトークンは不要
+    let token = match token {
+        Some(token) => token,
+        None if discord.replay.is_some() => String::new(),
+        // キャッシュを使う場合も、キャッシュがなければ取得時に失敗する
+        None if discord.cached && !discord.refresh => String::new(),
+        None => {
+            auth::prompt_token()?.ok_or_else(|| Error::MissingArgument("DISCORD_TOKEN".into()))?
         }
+    };
+
+    // 接続
+    let retry_delay = Duration::try_from_secs_f64(discord.retry_delay)
+        .map_err(|e| Error::ParseArgument(format!("--retry-delay: {e}").into()))?;
+    let timeout = Duration::try_from_secs_f64(discord.timeout)
+        .map_err(|e| Error::ParseArgument(format!("--timeout: {e}").into()))?;
+    let mut client = Discord::new(&token, discord.token_type)
+        .with_retries(discord.retries, retry_delay)
+        .with_cache(discord.cached && !discord.refresh)
+        .with_endpoint(
+            discord.proxy.as_deref(),
+            discord.api_base.as_deref(),
+            Some(timeout).filter(|t| !t.is_zero()),
+        )?;
+    if let Some(reason) = &discord.reason {
+        client = client.with_reason(reason)?;
+    }
+    if let Some(path) = discord.record.clone() {
+        client = client.record(path);
+    } else if let Some(path) = discord.replay.clone() {
+        client = client.replay(path)?;
     }
+    Ok(Arc::new(client))
+}
 
-    Ok(())
+/// 引数、$GUILD_ID または設定ファイルから操作対象のGuild IDを得る。
+/// 引数と $GUILD_ID には設定ファイルで定義した別名も使える
+fn guild_id_from(discord: &ConnectionArgs, config: &Config) -> Result<GuildId> {
+    if discord.all_guilds || discord.guild_id.len() > 1 {
+        return Err(Error::UnsupportedOperation(
+            "this command works on a single guild; pass --guild-id only once".into(),
+        ));
+    }
+    match discord.guild_id.first() {
+        Some(guild) => config.resolve_guild(guild),
+        None => config.default_guild(),
+    }
+}
+
+/// 複数の --guild-id または --all-guilds で指定した、操作対象の全Guild IDを得る。
+/// 複数のGuildを操作する場合は、どのGuildのアイテムか表示できるようGuildの名前も得る
+async fn guild_ids_from(
+    discord: &Discord,
+    args: &ConnectionArgs,
+    config: &Config,
+) -> Result<(Vec<GuildId>, HashMap<GuildId, String>)> {
+    if !args.all_guilds && args.guild_id.len() <= 1 {
+        return Ok((vec![guild_id_from(args, config)?], HashMap::new()));
+    }
+    let names: HashMap<_, _> = discord
+        .current_user_guilds()
+        .await?
+        .into_iter()
+        .map(|guild| (guild.id, guild.name))
+        .collect();
+    let guilds = if args.all_guilds {
+        names.keys().copied().collect()
+    } else {
+        args.guild_id
+            .iter()
+            .map(|guild| config.resolve_guild(guild))
+            .collect::<Result<_>>()?
+    };
+    Ok((guilds, names))
+}
+
+/// --guild-id の候補として別名を補完できるよう、全サブコマンドの引数に設定する
+fn with_guild_aliases(cmd: clap::Command, aliases: &[&'static str]) -> clap::Command {
+    let names: Vec<_> = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    let mut cmd = if cmd.get_arguments().any(|arg| arg.get_id() == "guild_id") {
+        cmd.mut_arg("guild_id", |arg| {
+            arg.value_parser(clap::builder::PossibleValuesParser::new(
+                aliases.iter().copied(),
+            ))
+        })
+    } else {
+        cmd
+    };
+    for name in names {
+        cmd = cmd.mut_subcommand(name, |sub| with_guild_aliases(sub, aliases));
+    }
+    cmd
 }
 
 #[cold]
 fn shell_completion(shell: clap_complete::Shell) {
     let mut stdout = BufWriter::new(io::stdout());
     let mut cmd = Args::command();
+    // 補完スクリプトの生成時のみ別名を候補にする (IDも受け付けるため、解析時には制限しない)
+    let aliases: Vec<_> = Config::load()
+        .map(|config| {
+            // 生成後すぐに終了するため、clapに渡す文字列はリークさせてよい
+            config
+                .guilds
+                .into_keys()
+                .map(|alias| &*alias.leak())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !aliases.is_empty() {
+        cmd = with_guild_aliases(cmd, &aliases);
+    }
     let name = cmd.get_name().to_string();
     clap_complete::generate(shell, &mut cmd, name, &mut stdout);
 }
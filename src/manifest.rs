@@ -0,0 +1,204 @@
+use crate::args::ChannelFilterArgs;
+use edisch_core::{Diff, TextEditableItem};
+use serde::Serialize;
+use serenity::{all::GuildId, model::Timestamp};
+use sha2::{Digest, Sha256};
+use std::{fmt::Display, fs::File, io::BufWriter, path::PathBuf, time::Instant};
+use tracing::warn;
+
+/// 実行の結果
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// 途中でエラーが発生した
+    Failed,
+    /// 変更がなかった
+    NoChanges,
+    /// ユーザーにより中止された
+    Cancelled,
+    /// 変更を表示するのみで、適用しなかった
+    DryRun,
+    /// 全ての変更を適用した
+    Applied,
+}
+
+/// 個々の変更の適用状況
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// 未適用
+    Pending,
+    /// 適用に成功した
+    Applied,
+    /// 適用に失敗した
+    Failed,
+}
+
+/// 適用の結果の集計
+#[derive(Serialize, Debug, Clone, Copy, Default)]
+pub struct Totals {
+    /// 適用に成功した変更
+    pub applied: usize,
+    /// 変更のなかったアイテム
+    pub unchanged: usize,
+    /// 適用しなかった変更 (サーバー上で変更されていた・権限がない・確認で取り消した)
+    pub skipped: usize,
+    /// 適用に失敗した変更
+    pub failed: usize,
+    /// 先に失敗したため適用しなかった変更
+    pub not_applied: usize,
+    /// 適用にかかった秒数
+    pub elapsed_secs: f64,
+}
+
+/// 変更とその結果の記録
+#[derive(Serialize, Debug)]
+struct Entry {
+    id: String,
+    op: String,
+    old: String,
+    new: String,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// 適用を開始した時刻
+    #[serde(skip_serializing_if = "Option::is_none")]
+    started_at: Option<String>,
+    /// 適用が完了した時刻
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished_at: Option<String>,
+}
+
+/// 実行内容を1つのJSONにまとめた記録。
+/// 破棄される際 (エラーによる中断時も含む) にファイルへ書き出される
+#[derive(Serialize, Debug)]
+pub struct Manifest {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    version: &'static str,
+    started_at: String,
+    finished_at: Option<String>,
+    /// 開始から終了までの秒数
+    duration_secs: Option<f64>,
+    #[serde(skip)]
+    started: Instant,
+    mode: &'static str,
+    input: Option<String>,
+    filter: ChannelFilterArgs,
+    guilds: Vec<String>,
+    /// 取得したチャンネルの (ID, 名前) のSHA-256
+    state_hash: Option<String>,
+    diffs: Vec<Entry>,
+    /// 適用した場合の結果の集計
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<Totals>,
+    outcome: Outcome,
+}
+
+impl Manifest {
+    /// `path` が None の場合は何も書き出さない
+    pub fn new(
+        path: Option<PathBuf>,
+        mode: &'static str,
+        input: Option<String>,
+        filter: ChannelFilterArgs,
+    ) -> Self {
+        Self {
+            path,
+            version: env!("CARGO_PKG_VERSION"),
+            started_at: Timestamp::now().to_string(),
+            finished_at: None,
+            duration_secs: None,
+            started: Instant::now(),
+            mode,
+            input,
+            filter,
+            guilds: Vec::new(),
+            state_hash: None,
+            diffs: Vec::new(),
+            summary: None,
+            outcome: Outcome::Failed,
+        }
+    }
+
+    /// 対象のGuildを記録する
+    pub fn add_guild(&mut self, guild_id: GuildId) {
+        self.guilds.push(guild_id.to_string());
+    }
+
+    /// 取得したアイテムの状態のハッシュを記録する
+    pub fn set_state<T: TextEditableItem + Display>(&mut self, items: &[T]) {
+        let mut hasher = Sha256::new();
+        for item in items {
+            hasher.update(format!("{item}\t{}\n", item.content()));
+        }
+        let hash = hasher.finalize();
+        self.state_hash = Some(hash.iter().map(|b| format!("{b:02x}")).collect());
+    }
+
+    /// 計算された変更を記録する
+    pub fn set_diffs<T: Display>(&mut self, diffs: &[Diff<T>]) {
+        self.diffs = diffs
+            .iter()
+            .map(|diff| Entry {
+                id: diff.item.to_string(),
+                op: diff.op.to_string(),
+                old: diff.old.clone(),
+                new: diff.new.clone(),
+                status: Status::Pending,
+                error: None,
+                started_at: None,
+                finished_at: None,
+            })
+            .collect();
+    }
+
+    /// i番目の変更の適用を開始したことを記録する
+    pub fn set_started(&mut self, i: usize) {
+        if let Some(entry) = self.diffs.get_mut(i) {
+            entry.started_at = Some(Timestamp::now().to_string());
+        }
+    }
+
+    /// i番目の変更の適用結果を記録する
+    pub fn set_result(&mut self, i: usize, result: std::result::Result<(), String>) {
+        if let Some(entry) = self.diffs.get_mut(i) {
+            entry.finished_at = Some(Timestamp::now().to_string());
+            match result {
+                Ok(()) => entry.status = Status::Applied,
+                Err(e) => {
+                    entry.status = Status::Failed;
+                    entry.error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// 適用の結果の集計を記録する
+    pub fn set_totals(&mut self, totals: Totals) {
+        self.summary = Some(totals);
+    }
+
+    /// 実行結果を記録する
+    pub fn finish(&mut self, outcome: Outcome) {
+        self.outcome = outcome;
+    }
+}
+
+impl Drop for Manifest {
+    fn drop(&mut self) {
+        let Some(path) = self.path.take() else {
+            return;
+        };
+        self.finished_at = Some(Timestamp::now().to_string());
+        self.duration_secs = Some(self.started.elapsed().as_secs_f64());
+        let result = File::create(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                serde_json::to_writer_pretty(BufWriter::new(file), self).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            warn!("Failed to write manifest {}: {e}", path.display());
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{discord::Discord, error::Result};
+use serenity::all::ChannelId;
+
+/// Discordのメッセージの最大文字数
+const MAX_LENGTH: usize = 2000;
+
+/// 適用した変更の一覧をチャンネルに投稿する。
+/// 1通に収まらない場合は、コードブロックごと複数のメッセージに分ける
+pub async fn post_summary(
+    discord: &Discord,
+    channel_id: ChannelId,
+    lines: &[String],
+    reason: Option<&str>,
+) -> Result<()> {
+    let mut heading = format!("edisch applied {} change(s)", lines.len());
+    if let Some(reason) = reason {
+        heading.push_str(&format!(": {reason}"));
+    }
+    // コードブロックの開始・終了と改行の分を除いた長さ
+    let limit = MAX_LENGTH - "```\n```".len() - 1;
+    let mut messages = Vec::new();
+    let mut block = String::new();
+    for line in lines {
+        // 1行で上限を超える場合は切り詰める
+        let line: String = line.chars().take(limit - 1).collect();
+        let head = if messages.is_empty() {
+            heading.chars().count() + 1
+        } else {
+            0
+        };
+        if head + block.chars().count() + line.chars().count() + 1 > limit {
+            messages.push(std::mem::take(&mut block));
+        }
+        block.push_str(&line);
+        block.push('\n');
+    }
+    messages.push(block);
+    for (i, block) in messages.iter().enumerate() {
+        let content = if i == 0 {
+            format!("{heading}\n```\n{block}```")
+        } else {
+            format!("```\n{block}```")
+        };
+        discord.send_message(channel_id, &content).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serde_json::Value;
+use serenity::all::GuildId;
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+/// オンボーディングの質問または選択肢のタイトル
+pub struct OnboardingItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// オンボーディングの設定全体。更新は設定全体の置き換えになるため、全アイテムで共有する
+    onboarding: Arc<Mutex<Value>>,
+    /// 質問の位置
+    prompt: usize,
+    /// 選択肢の場合、その位置
+    option: Option<usize>,
+    /// 質問または選択肢のID
+    id: String,
+    /// 現在のタイトル
+    title: String,
+    /// 選択肢の場合、その質問のタイトル
+    prompt_title: String,
+}
+
+impl OnboardingItem {
+    /// タイトルの最大文字数
+    fn max_length(&self) -> usize {
+        if self.option.is_some() {
+            50
+        } else {
+            100
+        }
+    }
+}
+
+/// 取得した設定を、更新リクエストの形式に変換する。
+/// 選択肢の絵文字は取得時はオブジェクトだが、更新時は個別の項目で指定する
+fn to_payload(onboarding: &Value) -> Value {
+    let mut payload = onboarding.clone();
+    let options = payload["prompts"]
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(|prompt| prompt["options"].as_array_mut())
+        .flatten();
+    for option in options {
+        let Some(option) = option.as_object_mut() else {
+            continue;
+        };
+        if let Some(emoji) = option.remove("emoji") {
+            option.insert("emoji_id".into(), emoji["id"].clone());
+            option.insert("emoji_name".into(), emoji["name"].clone());
+            option.insert(
+                "emoji_animated".into(),
+                emoji.get("animated").cloned().unwrap_or(Value::Bool(false)),
+            );
+        }
+    }
+    payload
+}
+
+impl Display for OnboardingItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for OnboardingItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let payload = {
+            let mut onboarding = self.onboarding.lock().unwrap();
+            let prompt = &mut onboarding["prompts"][self.prompt];
+            let target = match self.option {
+                Some(option) => &mut prompt["options"][option],
+                None => prompt,
+            };
+            target["title"] = Value::String(content.clone());
+            to_payload(&onboarding)
+        };
+        let updated = self
+            .discord
+            .edit_guild_onboarding(self.guild_id, &payload)
+            .await?;
+        // IDが採番し直される場合があるため、以降の更新は応答の内容を基にする
+        *self.onboarding.lock().unwrap() = updated;
+        self.title = content;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.title.clone()
+    }
+    fn comment(&self) -> String {
+        match self.option {
+            Some(_) => format!("  option of {:?} ({})", self.prompt_title, self.id),
+            None => format!("prompt ({})", self.id),
+        }
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        let max = self.max_length();
+        if !(1..=max).contains(&new.chars().count()) {
+            return Err(Error::InvalidEditResult(
+                format!("title must be between 1 and {max} characters: {new:?}").into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのオンボーディングの質問と選択肢を、質問ごとに並べて取得する
+pub async fn fetch_items(discord: &Arc<Discord>, guild_id: GuildId) -> Result<Vec<OnboardingItem>> {
+    let onboarding = discord.guild_onboarding(guild_id).await?;
+    let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+    let mut items = Vec::new();
+    let prompts = onboarding["prompts"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let onboarding = Arc::new(Mutex::new(onboarding));
+    for (i, prompt) in prompts.iter().enumerate() {
+        let prompt_title = text(&prompt["title"]);
+        let item = |option, value: &Value| OnboardingItem {
+            discord: discord.clone(),
+            guild_id,
+            onboarding: onboarding.clone(),
+            prompt: i,
+            option,
+            id: text(&value["id"]),
+            title: text(&value["title"]),
+            prompt_title: prompt_title.clone(),
+        };
+        items.push(item(None, prompt));
+        for (j, option) in prompt["options"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            items.push(item(Some(j), option));
+        }
+    }
+    Ok(items)
+}
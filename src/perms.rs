@@ -0,0 +1,759 @@
+use crate::{
+    args::{DocumentFormat, PermsEditArgs},
+    discord::Discord,
+    error::{Error, Result},
+    i18n::tr,
+};
+use dialoguer::{Confirm, Select};
+use edisch_core::{edit_document, EditOptions};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    GuildChannel, GuildId, PermissionOverwrite, PermissionOverwriteType, Permissions, Role, UserId,
+};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::Arc,
+};
+use tracing::{error, info};
+
+/// 上書きがない状態の (許可, 拒否)
+const EMPTY: (Permissions, Permissions) = (Permissions::empty(), Permissions::empty());
+
+/// 権限テンプレートファイルの内容
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Template {
+    /// ロール (名前またはID、`@everyone`) ごとの上書き
+    roles: HashMap<String, OverwriteTemplate>,
+}
+
+/// テンプレートで宣言された1ロール分の上書き
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct OverwriteTemplate {
+    /// 許可する権限の名前 (`VIEW_CHANNEL` など)
+    allow: Vec<String>,
+    /// 拒否する権限の名前
+    deny: Vec<String>,
+}
+
+/// 一括編集する、チャンネルごとの上書きの文書
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Document {
+    channels: Vec<ChannelOverwrites>,
+}
+
+/// 文書に書き出す1チャンネル分の上書き
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ChannelOverwrites {
+    /// チャンネルのID
+    id: String,
+    /// チャンネルの名前。見分けるためのもので、変更しても反映しない
+    #[serde(default)]
+    name: String,
+    /// 上書きの一覧。文書から消した上書きは削除する
+    #[serde(default)]
+    overwrites: Vec<OverwriteEntry>,
+}
+
+/// 文書に書き出す1つの上書き。対象は role と member のいずれか
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct OverwriteEntry {
+    /// ロールの名前またはID (`@everyone` を含む)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    /// メンバーのユーザーID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    member: Option<String>,
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+    /// 名前の分からない許可のビット。書き込む際に `allow` に加える
+    #[serde(default, skip_serializing_if = "is_zero")]
+    allow_bits: u64,
+    /// 名前の分からない拒否のビット
+    #[serde(default, skip_serializing_if = "is_zero")]
+    deny_bits: u64,
+}
+
+fn is_zero(bits: &u64) -> bool {
+    *bits == 0
+}
+
+/// 上書きの変更
+struct Change<'a> {
+    channel: &'a GuildChannel,
+    kind: PermissionOverwriteType,
+    /// 変更前の (許可, 拒否)。上書きがなかった場合は None
+    old: Option<(Permissions, Permissions)>,
+    /// 変更後の (許可, 拒否)。上書きを削除する場合は None
+    new: Option<(Permissions, Permissions)>,
+}
+
+/// 権限の名前を読み取る。`source` はエラーに示す読み取り元
+fn parse_permissions(names: &[String], source: &str) -> Result<Permissions> {
+    names.iter().try_fold(
+        Permissions::empty(),
+        |acc, name| match Permissions::from_name(&name.to_uppercase().replace([' ', '-'], "_")) {
+            Some(permission) => Ok(acc | permission),
+            None => Err(Error::Config(
+                format!("{source}: unknown permission: {name:?}").into(),
+            )),
+        },
+    )
+}
+
+/// ロールを名前またはIDから探す。`@everyone` も名前として扱える
+fn find_role<'a>(roles: &'a [Role], target: &str, source: &str) -> Result<&'a Role> {
+    let found: Vec<_> = roles
+        .iter()
+        .filter(|role| role.name == target || role.id.to_string() == target)
+        .collect();
+    match found[..] {
+        [role] => Ok(role),
+        [] => Err(Error::Config(
+            format!("{source}: role not found: {target:?}").into(),
+        )),
+        _ => Err(Error::Config(
+            format!("{source}: ambiguous role name: {target:?}").into(),
+        )),
+    }
+}
+
+/// 上書きの対象の表示順 (ロールは上から順、メンバーはその後)
+fn target_order(kind: &PermissionOverwriteType, roles: &[Role]) -> (u8, Reverse<u16>, u64) {
+    match kind {
+        PermissionOverwriteType::Role(id) => (
+            0,
+            Reverse(
+                roles
+                    .iter()
+                    .find(|role| role.id == *id)
+                    .map_or(0, |role| role.position),
+            ),
+            id.get(),
+        ),
+        PermissionOverwriteType::Member(id) => (1, Reverse(0), id.get()),
+        _ => (2, Reverse(0), 0),
+    }
+}
+
+/// 権限の名前の一覧
+fn permission_names(permissions: Permissions) -> Vec<String> {
+    permissions
+        .iter_names()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// 名前の分からない権限のビット
+fn unknown_bits(permissions: Permissions) -> u64 {
+    permissions.bits() & !Permissions::all().bits()
+}
+
+/// 名前の一覧と名前の分からないビットから権限を求める
+fn permissions_from(names: &[String], bits: u64, source: &str) -> Result<Permissions> {
+    Ok(parse_permissions(names, source)? | Permissions::from_bits_retain(bits))
+}
+
+/// 許可・拒否の差分を `+NAME -NAME` の形式で表す
+fn format_bits(old: Permissions, new: Permissions, color: bool) -> String {
+    let mut parts = Vec::new();
+    let names = |permissions: Permissions| {
+        let mut names: Vec<_> = permissions
+            .iter_names()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        if unknown_bits(permissions) != 0 {
+            names.push(format!("{:#x}", unknown_bits(permissions)));
+        }
+        names
+    };
+    for name in names(new - old) {
+        let mut part = console::style(format!("+{name}"));
+        if color {
+            part = part.green();
+        }
+        parts.push(part.to_string());
+    }
+    for name in names(old - new) {
+        let mut part = console::style(format!("-{name}"));
+        if color {
+            part = part.red();
+        }
+        parts.push(part.to_string());
+    }
+    parts.join(" ")
+}
+
+/// 上書きの対象を表示用に整形する
+fn format_target(kind: PermissionOverwriteType, roles: &[Role]) -> String {
+    match kind {
+        PermissionOverwriteType::Role(id) => match roles.iter().find(|role| role.id == id) {
+            Some(role) if role.name.starts_with('@') => role.name.clone(),
+            Some(role) => format!("@{}", role.name),
+            None => format!("role {id}"),
+        },
+        PermissionOverwriteType::Member(id) => format!("member {id}"),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// (許可, 拒否) の差分を `allow: ... deny: ...` の形式で表す
+fn format_overwrite(
+    old: (Permissions, Permissions),
+    new: (Permissions, Permissions),
+    color: bool,
+) -> String {
+    let mut line = String::new();
+    if old.0 != new.0 {
+        line.push_str(&format!("  allow: {}", format_bits(old.0, new.0, color)));
+    }
+    if old.1 != new.1 {
+        line.push_str(&format!("  deny: {}", format_bits(old.1, new.1, color)));
+    }
+    line
+}
+
+impl Change<'_> {
+    fn format(&self, roles: &[Role], color: bool) -> String {
+        let note = match (self.old, self.new) {
+            (None, _) => "  (new)",
+            (_, None) => "  (removed)",
+            _ => "",
+        };
+        format!(
+            "#{}  {}{note}{}",
+            self.channel.name,
+            format_target(self.kind, roles),
+            format_overwrite(self.old.unwrap_or(EMPTY), self.new.unwrap_or(EMPTY), color)
+        )
+    }
+}
+
+/// 変更を表示して確認する。`yes` の場合は確認しない
+fn confirm(changes: &[Change], roles: &[Role], yes: bool, color: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    for change in changes {
+        eprintln!("{}", change.format(roles, color));
+    }
+    Ok(Confirm::new()
+        .with_prompt(tr!("prompt-apply"))
+        .default(false)
+        .interact()?)
+}
+
+/// 変更を順に適用する
+async fn apply_changes(
+    discord: &Arc<Discord>,
+    changes: &[Change<'_>],
+    roles: &[Role],
+    color: bool,
+) -> Result<()> {
+    for change in changes {
+        let mut prompt = console::style(tr!("applying-prefix"));
+        if color {
+            prompt = prompt.blue().bold();
+        }
+        info!("{prompt} {}", change.format(roles, color));
+        match change.new {
+            Some((allow, deny)) => {
+                let overwrite = PermissionOverwrite {
+                    allow,
+                    deny,
+                    kind: change.kind,
+                };
+                discord
+                    .edit_permission(change.channel.id, &overwrite)
+                    .await?
+            }
+            None => {
+                discord
+                    .delete_permission(change.channel.id, change.kind)
+                    .await?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// テンプレートで宣言されたロールの上書きを、名前が一致する全チャンネルに適用する
+pub async fn apply_template(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    template: &Path,
+    pattern: Option<&str>,
+    yes: bool,
+    color: bool,
+) -> Result<()> {
+    let text = fs::read_to_string(template)?;
+    let parsed: Template = toml::from_str(&text)
+        .map_err(|e| Error::Config(format!("{}: {}", template.display(), e.message()).into()))?;
+    let pattern = pattern
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| Error::ParseArgument(format!("--match: {e}").into()))?;
+
+    let source = template.display().to_string();
+    let roles = discord.guild_roles(guild_id).await?;
+    let mut overwrites = Vec::new();
+    for (name, overwrite) in &parsed.roles {
+        let role = find_role(&roles, name, &source)?;
+        let allow = parse_permissions(&overwrite.allow, &source)?;
+        let deny = parse_permissions(&overwrite.deny, &source)?;
+        overwrites.push((role, allow, deny));
+    }
+    // 表示順を安定させる
+    overwrites.sort_by_key(|(role, _, _)| Reverse(role.position));
+
+    let mut channels = discord.guild_channels(guild_id, |_| {}).await?;
+    channels.retain(|channel| pattern.as_ref().is_none_or(|re| re.is_match(&channel.name)));
+    channels.sort_by_key(|channel| channel.position);
+
+    // 変更の計算
+    let mut changes = Vec::new();
+    for channel in &channels {
+        for &(role, allow, deny) in &overwrites {
+            let kind = PermissionOverwriteType::Role(role.id);
+            let old = channel
+                .permission_overwrites
+                .iter()
+                .find(|o| o.kind == kind)
+                .map(|o| (o.allow, o.deny));
+            if old.unwrap_or(EMPTY) != (allow, deny) {
+                changes.push(Change {
+                    channel,
+                    kind,
+                    old,
+                    new: Some((allow, deny)),
+                });
+            }
+        }
+    }
+    if changes.is_empty() {
+        info!("{}", tr!("no-changes-to-apply"));
+        return Ok(());
+    }
+
+    if !confirm(&changes, &roles, yes, color)? {
+        return Ok(());
+    }
+    apply_changes(discord, &changes, &roles, color).await
+}
+
+/// 文書に書き出す上書きの対象。名前が一意なロールは名前、それ以外はIDで表す
+fn entry_target(kind: PermissionOverwriteType, roles: &[Role]) -> (Option<String>, Option<String>) {
+    match kind {
+        PermissionOverwriteType::Role(id) => {
+            let name = roles
+                .iter()
+                .find(|role| role.id == id)
+                .map(|role| role.name.clone())
+                .filter(|name| roles.iter().filter(|role| &role.name == name).count() == 1);
+            (Some(name.unwrap_or_else(|| id.to_string())), None)
+        }
+        PermissionOverwriteType::Member(id) => (None, Some(id.to_string())),
+        _ => (None, None),
+    }
+}
+
+/// チャンネルの上書きを文書にする
+fn document(channels: &[&GuildChannel], roles: &[Role]) -> Document {
+    let channels = channels
+        .iter()
+        .map(|channel| {
+            let mut overwrites: Vec<_> = channel.permission_overwrites.iter().collect();
+            overwrites.sort_by_key(|o| target_order(&o.kind, roles));
+            ChannelOverwrites {
+                id: channel.id.to_string(),
+                name: channel.name.clone(),
+                overwrites: overwrites
+                    .into_iter()
+                    .filter_map(|o| {
+                        let (role, member) = entry_target(o.kind, roles);
+                        (role.is_some() || member.is_some()).then(|| OverwriteEntry {
+                            role,
+                            member,
+                            allow: permission_names(o.allow),
+                            deny: permission_names(o.deny),
+                            allow_bits: unknown_bits(o.allow),
+                            deny_bits: unknown_bits(o.deny),
+                        })
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    Document { channels }
+}
+
+/// 文書の先頭に付ける説明
+const DOCUMENT_HEADER: &str = "\
+# Permission overwrites of each channel. Edit the allow and deny lists, remove an overwrite to
+# delete it, or add one with role = \"NAME\" or member = \"USER ID\". Channels removed from the
+# document are left as they are.
+";
+
+/// 文書を指定の形式で書き出す
+fn render(document: &Document, format: DocumentFormat) -> Result<String> {
+    let body = match format {
+        DocumentFormat::Toml => {
+            toml::to_string(document).map_err(|e| Error::InvalidEditResult(e.to_string().into()))?
+        }
+        DocumentFormat::Yaml => serde_yaml::to_string(document)
+            .map_err(|e| Error::InvalidEditResult(e.to_string().into()))?,
+    };
+    let header = match format {
+        DocumentFormat::Toml => DOCUMENT_HEADER.to_string(),
+        // YAMLでは対象を `role: NAME` の形式で書く
+        DocumentFormat::Yaml => DOCUMENT_HEADER.replace(" = ", ": "),
+    };
+    Ok(format!("{header}\n{body}"))
+}
+
+/// 文書を読み取る
+fn parse(text: &str, format: DocumentFormat, source: &str) -> Result<Document> {
+    match format {
+        DocumentFormat::Toml => toml::from_str(text)
+            .map_err(|e| Error::InvalidEditResult(format!("{source}: {}", e.message()).into())),
+        DocumentFormat::Yaml => serde_yaml::from_str::<Option<Document>>(text)
+            .map(Option::unwrap_or_default)
+            .map_err(|e| Error::InvalidEditResult(format!("{source}: {e}").into())),
+    }
+}
+
+/// 編集した文書と現在の上書きを比べ、変更した上書きのみを求める。文書にないチャンネルは変更しない
+fn changes_from<'a>(
+    document: &Document,
+    channels: &[&'a GuildChannel],
+    roles: &[Role],
+    source: &str,
+) -> Result<Vec<Change<'a>>> {
+    let mut changes = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in &document.channels {
+        let channel = channels
+            .iter()
+            .find(|channel| channel.id.to_string() == entry.id)
+            .ok_or_else(|| {
+                Error::InvalidEditResult(
+                    format!("{source}: channel not found: {}", entry.id).into(),
+                )
+            })?;
+        if !seen.insert(channel.id) {
+            return Err(Error::InvalidEditResult(
+                format!("{source}: channel {} is listed twice", entry.id).into(),
+            ));
+        }
+        // 編集後の上書き
+        let mut wanted = Vec::new();
+        for overwrite in &entry.overwrites {
+            let kind = match (&overwrite.role, &overwrite.member) {
+                (Some(role), None) => {
+                    PermissionOverwriteType::Role(find_role(roles, role, source)?.id)
+                }
+                (None, Some(member)) => {
+                    let id = member
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|&id| id != 0)
+                        .ok_or_else(|| {
+                            Error::InvalidEditResult(
+                                format!("{source}: invalid member ID: {member:?}").into(),
+                            )
+                        })?;
+                    PermissionOverwriteType::Member(UserId::new(id))
+                }
+                _ => {
+                    return Err(Error::InvalidEditResult(
+                        format!(
+                            "{source}: each overwrite of #{} needs either role or member",
+                            channel.name
+                        )
+                        .into(),
+                    ))
+                }
+            };
+            if wanted.iter().any(|(other, _)| *other == kind) {
+                return Err(Error::InvalidEditResult(
+                    format!(
+                        "{source}: {} is listed twice in #{}",
+                        format_target(kind, roles),
+                        channel.name
+                    )
+                    .into(),
+                ));
+            }
+            let allow = permissions_from(&overwrite.allow, overwrite.allow_bits, source)?;
+            let deny = permissions_from(&overwrite.deny, overwrite.deny_bits, source)?;
+            wanted.push((kind, (allow, deny)));
+        }
+        let current = |kind| {
+            channel
+                .permission_overwrites
+                .iter()
+                .find(|o| o.kind == kind)
+                .map(|o| (o.allow, o.deny))
+        };
+        for &(kind, new) in &wanted {
+            let old = current(kind);
+            if old != Some(new) {
+                changes.push(Change {
+                    channel,
+                    kind,
+                    old,
+                    new: Some(new),
+                });
+            }
+        }
+        for o in &channel.permission_overwrites {
+            if !wanted.iter().any(|(kind, _)| *kind == o.kind) {
+                changes.push(Change {
+                    channel,
+                    kind: o.kind,
+                    old: Some((o.allow, o.deny)),
+                    new: None,
+                });
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// チャンネルの上書きを文書としてエディタで編集し、変更した上書きのみを適用する
+pub async fn edit(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    args: &PermsEditArgs,
+    mut options: EditOptions,
+    color: bool,
+) -> Result<()> {
+    let pattern = args
+        .pattern
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| Error::ParseArgument(format!("--match: {e}").into()))?;
+    let roles = discord.guild_roles(guild_id).await?;
+    let mut channels = discord.guild_channels(guild_id, |_| {}).await?;
+    // serenityが読み捨てた、名前の分からない権限のビットを戻す
+    let bits = discord.guild_overwrite_bits(guild_id).await?;
+    for channel in &mut channels {
+        for o in &mut channel.permission_overwrites {
+            let target = match o.kind {
+                PermissionOverwriteType::Role(id) => id.get(),
+                PermissionOverwriteType::Member(id) => id.get(),
+                _ => continue,
+            };
+            if let Some(&(allow, deny)) = bits.get(&(channel.id, target)) {
+                o.allow = Permissions::from_bits_retain(allow);
+                o.deny = Permissions::from_bits_retain(deny);
+            }
+        }
+    }
+    channels.sort_by_key(|channel| (channel.parent_id.is_some(), channel.position, channel.id));
+    let channels: Vec<_> = channels
+        .iter()
+        .filter(|channel| pattern.as_ref().is_none_or(|re| re.is_match(&channel.name)))
+        .collect();
+    if channels.is_empty() {
+        info!("{}", tr!("no-channels"));
+        return Ok(());
+    }
+
+    // 書き出しのみ
+    if let Some(output) = &args.output {
+        fs::write(output, render(&document(&channels, &roles), args.format)?)?;
+        info!("{}", tr!("wrote-file", path = output.display().to_string()));
+        return Ok(());
+    }
+
+    // 編集済みの文書を適用する
+    if let Some(input) = &args.input {
+        let format = match input.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => DocumentFormat::Yaml,
+            Some("toml") => DocumentFormat::Toml,
+            _ => args.format,
+        };
+        let source = input.display().to_string();
+        let text = fs::read_to_string(input)?;
+        let changes = changes_from(&parse(&text, format, &source)?, &channels, &roles, &source)?;
+        if changes.is_empty() {
+            info!("{}", tr!("no-changes-to-apply"));
+            return Ok(());
+        }
+        if args.dry_run {
+            for change in &changes {
+                eprintln!("{}", change.format(&roles, color));
+            }
+            return Ok(());
+        }
+        if !confirm(&changes, &roles, args.yes, color)? {
+            return Ok(());
+        }
+        return apply_changes(discord, &changes, &roles, color).await;
+    }
+
+    // エディタで編集し、確認時には再編集を選べる
+    options.suffix = Some(
+        match args.format {
+            DocumentFormat::Toml => "toml",
+            DocumentFormat::Yaml => "yaml",
+        }
+        .to_string(),
+    );
+    let original = render(&document(&channels, &roles), args.format)?;
+    let mut text = original.clone();
+    loop {
+        text = match edit_document(text.trim_end(), &options)? {
+            Some(edited) => edited,
+            None if text == original => {
+                info!("{}", tr!("no-changes"));
+                return Ok(());
+            }
+            None => text,
+        };
+        let changes = match parse(&text, args.format, "edited document")
+            .and_then(|document| changes_from(&document, &channels, &roles, "edited document"))
+        {
+            Ok(changes) => changes,
+            Err(e) => {
+                error!("{e}");
+                if Confirm::new()
+                    .with_prompt(tr!("prompt-edit-again"))
+                    .default(true)
+                    .interact()?
+                {
+                    continue;
+                }
+                return Err(e);
+            }
+        };
+        if changes.is_empty() {
+            info!("{}", tr!("no-changes-to-apply"));
+            return Ok(());
+        }
+        for change in &changes {
+            eprintln!("{}", change.format(&roles, color));
+        }
+        if args.dry_run {
+            return Ok(());
+        }
+        if !args.yes {
+            match Select::new()
+                .with_prompt(tr!("prompt-apply"))
+                .items(&[
+                    tr!("choice-no"),
+                    tr!("choice-yes"),
+                    tr!("choice-edit-again"),
+                ])
+                .default(0)
+                .interact()?
+            {
+                1 => {}
+                2 => continue,
+                _ => return Ok(()),
+            }
+        }
+        return apply_changes(discord, &changes, &roles, color).await;
+    }
+}
+
+/// チャンネルを名前またはIDから探す
+fn find_channel<'a>(channels: &'a [GuildChannel], target: &str) -> Result<&'a GuildChannel> {
+    let found: Vec<_> = channels
+        .iter()
+        .filter(|channel| channel.name == target || channel.id.to_string() == target)
+        .collect();
+    match found[..] {
+        [channel] => Ok(channel),
+        [] => Err(Error::ParseArgument(
+            format!("channel not found: {target:?}").into(),
+        )),
+        _ => Err(Error::ParseArgument(
+            format!("ambiguous channel name: {target:?} (use the ID instead)").into(),
+        )),
+    }
+}
+
+/// 2つのチャンネルの上書きの違いを表示する。
+/// `other` が `None` の場合は `channel` の親カテゴリと比較する
+pub async fn diff(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    channel: &str,
+    other: Option<&str>,
+    color: bool,
+) -> Result<()> {
+    let roles = discord.guild_roles(guild_id).await?;
+    let channels = discord.guild_channels(guild_id, |_| {}).await?;
+    let a = find_channel(&channels, channel)?;
+    let b = match other {
+        Some(other) => find_channel(&channels, other)?,
+        None => {
+            let parent = a.parent_id.ok_or_else(|| {
+                Error::ParseArgument(format!("#{} is not in a category", a.name).into())
+            })?;
+            channels
+                .iter()
+                .find(|channel| channel.id == parent)
+                .ok_or_else(|| Error::ParseArgument("category not found".into()))?
+        }
+    };
+
+    // 上書きの対象の一覧
+    let mut targets: Vec<_> = a
+        .permission_overwrites
+        .iter()
+        .chain(&b.permission_overwrites)
+        .map(|o| o.kind)
+        .collect();
+    targets.sort_by_key(|kind| target_order(kind, &roles));
+    targets.dedup();
+
+    let overwrite_of = |channel: &GuildChannel, kind| {
+        channel
+            .permission_overwrites
+            .iter()
+            .find(|o| o.kind == kind)
+            .map(|o| (o.allow, o.deny))
+    };
+    let mut header = console::style(format!("--- #{}\n+++ #{}", a.name, b.name));
+    if color {
+        header = header.bold();
+    }
+    println!("{header}");
+    let mut same = true;
+    for kind in targets {
+        let old = overwrite_of(a, kind);
+        let new = overwrite_of(b, kind);
+        if old.unwrap_or(EMPTY) == new.unwrap_or(EMPTY) {
+            continue;
+        }
+        same = false;
+        let note = match (old, new) {
+            (None, _) => format!("  (only in #{})", b.name),
+            (_, None) => format!("  (only in #{})", a.name),
+            _ => String::new(),
+        };
+        println!(
+            "{}{note}{}",
+            format_target(kind, &roles),
+            format_overwrite(old.unwrap_or(EMPTY), new.unwrap_or(EMPTY), color)
+        );
+    }
+    if same {
+        println!("No differences in permission overwrites");
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use crate::error::{Error, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io, path::Path};
+
+/// カレントディレクトリから探すポリシーファイルの名前
+pub const POLICY_FILE: &str = ".edisch-policy.toml";
+
+/// リポジトリで管理する命名ポリシー
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// カテゴリ以外のチャンネル名が一致すべき正規表現
+    pattern: Option<String>,
+    /// 改名・移動・削除を禁止するチャンネルの名前またはID
+    protected: Vec<String>,
+    /// カテゴリ以外のチャンネル名が始まるべき接頭辞のいずれか
+    required_prefixes: Vec<String>,
+    /// カテゴリ以外のチャンネル名を小文字に限る
+    lowercase: bool,
+    /// カテゴリ以外のチャンネル名の単語の区切り。空白・`_`・`-` のうちこれ以外を禁止する
+    separator: Option<char>,
+    /// カテゴリ以外のチャンネル名の最大文字数
+    max_length: Option<usize>,
+    /// カテゴリ (名前またはID) ごとのポリシー
+    categories: HashMap<String, CategoryPolicy>,
+    /// コンパイル済みの `pattern`
+    #[serde(skip)]
+    regex: Option<Regex>,
+}
+
+/// カテゴリごとの命名ポリシー
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CategoryPolicy {
+    /// カテゴリ内のチャンネル名が始まるべき接頭辞 (絵文字など)
+    prefix: Option<String>,
+}
+
+impl Policy {
+    /// カレントディレクトリのポリシーファイルを読み込む。存在しない場合は何も制限しない
+    pub fn load() -> Result<Self> {
+        let path = Path::new(POLICY_FILE);
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut policy: Self = toml::from_str(&text)
+            .map_err(|e| Error::Config(format!("{POLICY_FILE}: {}", e.message()).into()))?;
+        if let Some(pattern) = &policy.pattern {
+            policy.regex = Some(
+                Regex::new(pattern)
+                    .map_err(|e| Error::Config(format!("{POLICY_FILE}: pattern: {e}").into()))?,
+            );
+        }
+        Ok(policy)
+    }
+
+    /// 保護されたチャンネルでないか検査する
+    pub fn check_protected(&self, name: &str, id: &str) -> Result<()> {
+        if self.protected.iter().any(|p| p == name || p == id) {
+            return Err(Error::PolicyViolation(
+                format!("{name:?} is a protected channel").into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 親カテゴリ (名前, ID) に要求される接頭辞
+    fn category_prefix(&self, parent: Option<(&str, u64)>) -> Option<&str> {
+        let (name, id) = parent?;
+        self.categories
+            .get(name)
+            .or_else(|| self.categories.get(&id.to_string()))?
+            .prefix
+            .as_deref()
+    }
+
+    /// 新しい名前がポリシーに従っているか検査する。カテゴリ名は検査しない
+    pub fn check_name(
+        &self,
+        name: &str,
+        is_category: bool,
+        parent: Option<(&str, u64)>,
+    ) -> Result<()> {
+        if is_category {
+            return Ok(());
+        }
+        if self.lowercase && name.chars().any(char::is_uppercase) {
+            return Err(Error::PolicyViolation(
+                format!("{name:?} must be lowercase").into(),
+            ));
+        }
+        if let Some(sep) = self.separator {
+            if name.chars().any(|c| is_separator(c) && c != sep) {
+                return Err(Error::PolicyViolation(
+                    format!("{name:?} must separate words with {sep:?}").into(),
+                ));
+            }
+        }
+        if let Some(max) = self.max_length {
+            if name.chars().count() > max {
+                return Err(Error::PolicyViolation(
+                    format!("{name:?} must be at most {max} characters").into(),
+                ));
+            }
+        }
+        if let Some(re) = &self.regex {
+            if !re.is_match(name) {
+                return Err(Error::PolicyViolation(
+                    format!("{name:?} does not match the pattern {}", re.as_str()).into(),
+                ));
+            }
+        }
+        // カテゴリごとの接頭辞は全体の接頭辞より優先する
+        if let Some(prefix) = self.category_prefix(parent) {
+            if !name.starts_with(prefix) {
+                return Err(Error::PolicyViolation(
+                    format!(
+                        "{name:?} must start with {prefix:?} in category {:?}",
+                        parent.unwrap().0
+                    )
+                    .into(),
+                ));
+            }
+        } else if !self.required_prefixes.is_empty()
+            && !self.required_prefixes.iter().any(|p| name.starts_with(p))
+        {
+            return Err(Error::PolicyViolation(
+                format!(
+                    "{name:?} must start with one of {:?}",
+                    self.required_prefixes
+                )
+                .into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 機械的に直せる違反 (区切り・大文字・接頭辞・長さ) を直し、ポリシーに従うよう書き換える。
+    /// `pattern` や保護されたチャンネルなど、機械的に直せない違反はそのまま残る
+    pub fn fix_name(&self, name: &str, is_category: bool, parent: Option<(&str, u64)>) -> String {
+        if is_category {
+            return name.to_string();
+        }
+        let mut name = name.to_string();
+        if let Some(sep) = self.separator {
+            name = name
+                .chars()
+                .map(|c| if is_separator(c) { sep } else { c })
+                .collect();
+        }
+        if self.lowercase {
+            name = name.to_lowercase();
+        }
+        let prefix = match self.category_prefix(parent) {
+            Some(prefix) => Some(prefix),
+            None if !self.required_prefixes.iter().any(|p| name.starts_with(p)) => {
+                self.required_prefixes.first().map(String::as_str)
+            }
+            None => None,
+        };
+        if let Some(prefix) = prefix.filter(|prefix| !name.starts_with(prefix)) {
+            name.insert_str(0, prefix);
+        }
+        if let Some(max) = self.max_length {
+            name = name.chars().take(max).collect();
+        }
+        name
+    }
+}
+
+/// 単語の区切りとみなす文字
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == '_' || c == '-'
+}
@@ -0,0 +1,161 @@
+use crate::schedule::format_duration;
+use console::Term;
+use serde_json::{json, Value};
+use std::{
+    io::{stdout, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+/// プログレスバーの幅
+const WIDTH: usize = 24;
+
+/// 現在 stderr の最終行に表示しているプログレスバー
+static SHOWN: Mutex<Option<String>> = Mutex::new(None);
+
+/// 進捗をJSON Linesのイベントとして stdout に出力するか
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// 以降の進捗をプログレスバーの代わりにJSON Linesのイベントで出力する
+pub fn set_json() {
+    JSON.store(true, Ordering::Relaxed);
+}
+
+/// 進捗をJSON Linesで出力している場合に、`{"event": kind, ...fields}` を1行で出力する
+pub fn event(kind: &str, fields: Value) {
+    if !JSON.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut line = json!({ "event": kind });
+    if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+    let mut stdout = stdout().lock();
+    writeln!(stdout, "{line}").ok();
+    stdout.flush().ok();
+}
+
+/// プログレスバーを表示している場合は一旦消してからメッセージを出力し、バーを表示し直す
+pub fn println(msg: impl std::fmt::Display) {
+    let shown = SHOWN.lock().unwrap();
+    let term = Term::stderr();
+    if shown.is_some() {
+        term.clear_line().ok();
+    }
+    eprintln!("{msg}");
+    if let Some(line) = &*shown {
+        term.write_str(line).ok();
+    }
+}
+
+/// 最終行のプログレスバーを書き換える
+fn draw(line: String) {
+    let mut shown = SHOWN.lock().unwrap();
+    let term = Term::stderr();
+    term.clear_line().ok();
+    term.write_str(&line).ok();
+    *shown = Some(line);
+}
+
+/// プログレスバーを消す
+fn clear() {
+    let mut shown = SHOWN.lock().unwrap();
+    if shown.take().is_some() {
+        Term::stderr().clear_line().ok();
+    }
+}
+
+/// 件数・残り時間・処理中の項目を1行で表示するプログレスバー。
+/// stderr が端末でない場合は何も表示しない。破棄される際に消える
+pub struct Progress {
+    enabled: bool,
+    color: bool,
+    label: String,
+    /// 全体の件数。分からない場合は受信済みの件数のみ表示する
+    total: Option<usize>,
+    done: usize,
+    /// 処理中の項目
+    current: String,
+    started: Instant,
+}
+
+impl Progress {
+    pub fn new(label: impl Into<String>, total: Option<usize>, enabled: bool, color: bool) -> Self {
+        let progress = Self {
+            // JSON Linesで出力する場合はバーを表示しない
+            enabled: enabled && !JSON.load(Ordering::Relaxed),
+            color,
+            label: label.into(),
+            total,
+            done: 0,
+            current: String::new(),
+            started: Instant::now(),
+        };
+        progress.render();
+        progress
+    }
+
+    /// 表示するか
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 完了した件数を設定する
+    pub fn set_done(&mut self, done: usize) {
+        self.done = done;
+        self.render();
+    }
+
+    /// 処理中の項目を設定する
+    pub fn set_current(&mut self, current: impl Into<String>) {
+        self.current = current.into();
+        self.render();
+    }
+
+    fn render(&self) {
+        if !self.enabled {
+            return;
+        }
+        let mut label = console::style(&self.label);
+        if self.color {
+            label = match self.total {
+                Some(_) => label.blue().bold(),
+                None => label.dim(),
+            };
+        }
+        let line = match self.total {
+            Some(total) => {
+                let filled = (WIDTH * self.done).checked_div(total).unwrap_or(WIDTH);
+                let bar = format!("[{}{}]", "=".repeat(filled), " ".repeat(WIDTH - filled));
+                // 1件も完了していない間は残り時間を見積もれない
+                let eta = if self.done > 0 && self.done < total {
+                    let rest =
+                        self.started.elapsed() / self.done as u32 * (total - self.done) as u32;
+                    format!(" ETA {}", format_duration(rest))
+                } else {
+                    String::new()
+                };
+                let mut current = console::style(&self.current);
+                if self.color {
+                    current = current.dim();
+                }
+                format!("{label} {bar} {}/{total}{eta} {current}", self.done)
+            }
+            None => format!("{label} {}", self.done),
+        };
+        // 折り返すと書き換えられないため、端末の幅に収める
+        let (_, columns) = Term::stderr().size();
+        draw(console::truncate_str(&line, (columns as usize).saturating_sub(1), "…").into_owned());
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.enabled {
+            clear();
+        }
+    }
+}
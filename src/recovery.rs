@@ -0,0 +1,52 @@
+use std::{env, fs, path::PathBuf};
+use tracing::{info, warn};
+
+/// 編集したバッファを失わないよう保存するファイルのパス。$EDISCH_RECOVERY_FILE で上書きできる
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("EDISCH_RECOVERY_FILE") {
+        return Some(path.into());
+    }
+    dirs::data_local_dir().map(|dir| dir.join("edisch").join("recovery.txt"))
+}
+
+/// エディタで編集したバッファを保持し、適用せずに終了した場合にファイルへ書き出す。
+/// 破棄される際 (エラーによる中断時も含む) に、解除されていなければ `apply --input` で読み込める形式で書き出される
+#[derive(Default)]
+pub struct Recovery {
+    /// 最後に編集したバッファ
+    text: Option<String>,
+}
+
+impl Recovery {
+    /// 編集したバッファを記録する
+    pub fn set(&mut self, text: String) {
+        self.text = Some(text);
+    }
+
+    /// 保存する必要がなくなったバッファを破棄する
+    pub fn disarm(&mut self) {
+        self.text = None;
+    }
+}
+
+impl Drop for Recovery {
+    fn drop(&mut self) {
+        let Some(text) = self.text.take() else {
+            return;
+        };
+        let Some(path) = path() else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, format!("{text}\n")));
+        match result {
+            Ok(()) => info!(
+                "Saved the edited buffer; apply it with `edisch apply --input {}`",
+                path.display()
+            ),
+            Err(e) => warn!("Failed to write recovery file {}: {e}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use regex::Regex;
+use std::{
+    borrow::Cow,
+    fmt::{self, Display},
+    sync::{LazyLock, Mutex},
+};
+
+/// 伏せ字
+const MASK: &str = "[REDACTED]";
+
+/// 伏せる文字列として登録するのに必要な長さ。短い文字列を登録すると無関係な部分まで伏せてしまう
+const MIN_SECRET_LEN: usize = 16;
+
+/// 表示から取り除く秘密の文字列 (使用中のトークンなど)
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Discordのトークンの形式 (ユーザーID.タイムスタンプ.署名)。登録されていないもの
+/// (誤って別の引数に貼り付けられたものなど) も伏せる
+static TOKEN_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w-]{20,}\.[\w-]{6}\.[\w-]{27,}").unwrap());
+
+/// 以降の表示で伏せる文字列を登録する。`Bot ` や `Bearer ` の接頭辞は取り除く
+pub fn register(secret: &str) {
+    let secret = secret
+        .strip_prefix("Bot ")
+        .or(secret.strip_prefix("Bearer "))
+        .unwrap_or(secret)
+        .trim();
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.lock().unwrap();
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// 登録された文字列とトークンの形式の文字列を伏せ字にする
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let mut text = Cow::Borrowed(text);
+    for secret in SECRETS.lock().unwrap().iter() {
+        if text.contains(secret.as_str()) {
+            text = Cow::Owned(text.replace(secret.as_str(), MASK));
+        }
+    }
+    if TOKEN_RE.is_match(&text) {
+        text = Cow::Owned(TOKEN_RE.replace_all(&text, MASK).into_owned());
+    }
+    text
+}
+
+/// 表示する際にトークンを伏せ字にするラッパー。エラーを表示する箇所で使う
+pub struct Redacted<T>(pub T);
+
+impl<T: Display> Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact(&self.0.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_secrets_are_masked() {
+        register("Bot registered-secret-0123456789");
+        assert_eq!(
+            redact("request failed: registered-secret-0123456789 rejected"),
+            "request failed: [REDACTED] rejected"
+        );
+    }
+
+    #[test]
+    fn short_secrets_are_not_registered() {
+        register("x");
+        assert_eq!(redact("x marks the spot"), "x marks the spot");
+    }
+
+    #[test]
+    fn token_shaped_strings_are_masked() {
+        let token = "MTIzNDU2Nzg5MDEyMzQ1Njc4OQ.GaBcDe.abcdefghijklmnopqrstuvwxyz0123456";
+        let text = format!("unknown guild alias or ID: {token:?}");
+        assert_eq!(redact(&text), "unknown guild alias or ID: \"[REDACTED]\"");
+    }
+
+    #[test]
+    fn other_text_is_borrowed() {
+        assert!(matches!(
+            redact("channel general (10) renamed"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn redacted_wraps_display() {
+        register("wrapped-secret-abcdefghijkl");
+        let error = std::io::Error::other("denied for wrapped-secret-abcdefghijkl");
+        assert_eq!(Redacted(&error).to_string(), "denied for [REDACTED]");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    channel::kind_emoji,
+    discord::Discord,
+    error::{Error, Result},
+    i18n::tr,
+};
+use dialoguer::Confirm;
+use serenity::all::{ChannelId, ChannelType, GuildChannel, GuildId};
+use std::sync::Arc;
+use tracing::info;
+
+/// ボイスチャンネルの一覧に並ぶ種類か。Discordではテキストチャンネルの後に表示される
+fn is_voice(kind: ChannelType) -> bool {
+    matches!(kind, ChannelType::Voice | ChannelType::Stage)
+}
+
+/// 同じ親を持つチャンネルの並び
+struct Group<'a> {
+    /// 親カテゴリの名前 (カテゴリに属さない場合は None)
+    parent: Option<&'a str>,
+    /// 現在の順序
+    current: Vec<&'a GuildChannel>,
+    /// 並び替えた後の順序
+    sorted: Vec<&'a GuildChannel>,
+}
+
+/// 各カテゴリ (とカテゴリに属さないチャンネル) の中で、テキストチャンネルの後にボイスチャンネルが続くよう、
+/// それぞれを名前 (大文字小文字を無視) の順に並べ替える。カテゴリ自体の順序は変えない。
+/// 並びの変わるカテゴリのみ、そのチャンネルの position をまとめて変更する
+pub async fn sort_channels(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    yes: bool,
+    dry_run: bool,
+    color: bool,
+) -> Result<()> {
+    let channels = discord.guild_channels(guild_id, |_| {}).await?;
+    let mut categories: Vec<_> = channels
+        .iter()
+        .filter(|c| c.kind == ChannelType::Category)
+        .collect();
+    categories.sort_by_key(|c| (c.position, c.id));
+    let parents = std::iter::once(None).chain(categories.iter().map(|c| Some(c.id)));
+
+    let mut groups = Vec::new();
+    for parent in parents {
+        let mut current: Vec<_> = channels
+            .iter()
+            .filter(|c| c.kind != ChannelType::Category && c.parent_id == parent)
+            .collect();
+        current.sort_by_key(|c| (is_voice(c.kind), c.position, c.id));
+        let mut sorted = current.clone();
+        sorted.sort_by_key(|c| (is_voice(c.kind), c.name.to_lowercase(), c.id));
+        if current.iter().map(|c| c.id).eq(sorted.iter().map(|c| c.id)) {
+            continue;
+        }
+        let parent = parent.and_then(|id| categories.iter().find(|c| c.id == id));
+        groups.push(Group {
+            parent: parent.map(|c| c.name.as_str()),
+            current,
+            sorted,
+        });
+    }
+    if groups.is_empty() {
+        info!("The channels are already sorted");
+        return Ok(());
+    }
+
+    // 移動するチャンネルを、カテゴリごとに変更前後の順番とともに表示する
+    let mut moves = 0;
+    for group in &groups {
+        let mut heading = console::style(group.parent.unwrap_or("(no category)").to_string());
+        if color {
+            heading = heading.bold();
+        }
+        println!("{heading}");
+        for (new, channel) in group.sorted.iter().enumerate() {
+            let old = group
+                .current
+                .iter()
+                .position(|c| c.id == channel.id)
+                .unwrap_or(new);
+            if old == new {
+                continue;
+            }
+            moves += 1;
+            let mut order = console::style(format!("{} -> {}", old + 1, new + 1));
+            if color {
+                order = order.green();
+            }
+            println!(
+                "  {} {}  {order}  ({})",
+                kind_emoji(channel.kind),
+                channel.name,
+                channel.id
+            );
+        }
+    }
+    if dry_run {
+        return Err(Error::PendingChanges(moves));
+    }
+    if !yes
+        && !Confirm::new()
+            .with_prompt(tr!("prompt-move", count = moves))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    // 並びの変わるカテゴリのチャンネルに、0 から順に position を振り直す
+    let positions: Vec<(ChannelId, u16)> = groups
+        .iter()
+        .flat_map(|group| {
+            group
+                .sorted
+                .iter()
+                .enumerate()
+                .map(|(position, channel)| (channel.id, position as u16))
+        })
+        .collect();
+    discord.edit_channel_positions(guild_id, &positions).await?;
+    info!("Moved {moves} channel(s)");
+    Ok(())
+}
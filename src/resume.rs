@@ -0,0 +1,88 @@
+use serenity::all::GuildId;
+use std::{collections::HashMap, env, fs, path::PathBuf};
+use tracing::{info, warn};
+
+/// 適用を中断した場合に、残りの変更を `apply --resume` で再開するためのファイルのパス。
+/// $EDISCH_RESUME_FILE で上書きできる
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("EDISCH_RESUME_FILE") {
+        return Some(path.into());
+    }
+    dirs::data_local_dir().map(|dir| dir.join("edisch").join("resume.txt"))
+}
+
+/// まだ適用していない変更を、`apply -i` で読み込める形式で記録する。
+/// Ctrl-C などで中断された場合にも残るよう、変更を適用するたびにファイルを書き換える
+pub struct ResumeState {
+    path: Option<PathBuf>,
+    /// チャンネルIDと所属するGuildの対応
+    guilds: HashMap<String, GuildId>,
+    /// 各変更の行。適用済みの変更は None
+    lines: Vec<Option<String>>,
+}
+
+impl ResumeState {
+    /// `guilds` はチャンネルID (アイテムの表示) と所属するGuildの対応
+    pub fn new(guilds: HashMap<String, GuildId>) -> Self {
+        Self {
+            path: path(),
+            guilds,
+            lines: Vec::new(),
+        }
+    }
+
+    /// 適用する変更の (ID, 行) を記録し、ファイルに書き出す
+    pub fn start(&mut self, lines: impl IntoIterator<Item = (String, String)>) {
+        self.lines = lines
+            .into_iter()
+            .map(|(id, line)| match self.guilds.get(&id) {
+                Some(guild_id) => Some(format!("{line}\t{guild_id}")),
+                None => Some(line),
+            })
+            .collect();
+        self.write();
+    }
+
+    /// i番目の変更を適用済みとして記録する
+    pub fn done(&mut self, i: usize) {
+        if let Some(line) = self.lines.get_mut(i) {
+            *line = None;
+        }
+        self.write();
+    }
+
+    /// 残りの変更をファイルに書き出す。全て適用した場合はファイルを削除する
+    fn write(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let lines: Vec<_> = self.lines.iter().flatten().cloned().collect();
+        let result = if lines.is_empty() {
+            fs::remove_file(path).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            })
+        } else {
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::write(path, format!("{}\n", lines.join("\n"))))
+        };
+        if let Err(e) = result {
+            warn!("Failed to write resume file {}: {e}", path.display());
+        }
+    }
+}
+
+impl Drop for ResumeState {
+    fn drop(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if self.lines.iter().any(Option::is_some) {
+            info!(
+                "Resume the remaining changes with `edisch apply --resume {}`",
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::{CreateAttachment, EditRole, GuildId, ImageHash, RoleId};
+use std::{fmt::Display, fs, path::Path, sync::Arc};
+
+/// 画像のアイコンを表す値の接頭辞
+const ICON_PREFIX: &str = "icon:";
+
+/// ロールアイコンとして使える画像の拡張子
+const ICON_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// ロールアイコンの画像の最大サイズ (バイト)
+const ICON_MAX_SIZE: u64 = 256 * 1024;
+
+/// ロール名の最大長
+const NAME_MAX_LENGTH: usize = 100;
+
+/// ロール (名前)
+pub struct RoleItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// ロールID
+    id: RoleId,
+    /// ロール名
+    name: String,
+    /// Botや連携サービスによって管理されているか
+    managed: bool,
+}
+
+impl Display for RoleItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for RoleItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let role = self
+            .discord
+            .edit_role(self.guild_id, self.id, &EditRole::new().name(content))
+            .await?;
+        self.name = role.name;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.name.clone()
+    }
+    fn comment(&self) -> String {
+        if self.managed {
+            format!("🤖 managed ({})", self.id)
+        } else {
+            format!("({})", self.id)
+        }
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if !(1..=NAME_MAX_LENGTH).contains(&new.chars().count()) {
+            return Err(Error::InvalidEditResult(
+                format!("role name must be between 1 and {NAME_MAX_LENGTH} characters: {new:?}")
+                    .into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのロールを上位から順に取得する。
+/// `@everyone` は改名できないため除き、`include_managed` でなければBotなどの管理するロールも除く
+pub async fn fetch_items(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    include_managed: bool,
+) -> Result<Vec<RoleItem>> {
+    let mut roles = discord.guild_roles(guild_id).await?;
+    roles.retain(|role| role.id.get() != guild_id.get() && (include_managed || !role.managed));
+    roles.sort_by_key(|role| std::cmp::Reverse(role.position));
+    Ok(roles
+        .into_iter()
+        .map(|role| RoleItem {
+            discord: discord.clone(),
+            guild_id,
+            id: role.id,
+            name: role.name,
+            managed: role.managed,
+        })
+        .collect())
+}
+
+/// ロールのアイコン (Unicode絵文字または画像)
+pub struct RoleIconItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// ロールID
+    id: RoleId,
+    /// ロール名
+    name: String,
+    /// 現在の画像のアイコン
+    icon: Option<ImageHash>,
+    /// 現在のUnicode絵文字のアイコン
+    unicode_emoji: Option<String>,
+    /// Guildがロールアイコンを使えるか (ブーストレベル2以上)
+    icons_allowed: bool,
+}
+
+/// 編集後のアイコンの値
+enum IconValue<'a> {
+    /// アイコンを外す
+    Clear,
+    /// 画像ファイルをアイコンにする
+    File(&'a Path),
+    /// Unicode絵文字をアイコンにする
+    Emoji(&'a str),
+}
+
+impl RoleIconItem {
+    /// 編集後の値を読み取る
+    fn parse<'a>(&self, new: &'a str) -> Result<IconValue<'a>> {
+        let new = new.trim();
+        if new.is_empty() {
+            return Ok(IconValue::Clear);
+        }
+        if !self.icons_allowed {
+            return Err(Error::InvalidEditResult(
+                "role icons require the guild to be at boost level 2 (empty to clear)".into(),
+            ));
+        }
+        if new.starts_with(ICON_PREFIX) {
+            return Err(Error::InvalidEditResult(
+                format!("cannot set an image icon by hash, give a path to the image: {new:?}")
+                    .into(),
+            ));
+        }
+        let path = Path::new(new);
+        if path.is_file() {
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_lowercase)
+                .unwrap_or_default();
+            if !ICON_EXTENSIONS.contains(&extension.as_str()) {
+                return Err(Error::InvalidEditResult(
+                    format!("role icon must be a PNG or JPEG image: {new:?}").into(),
+                ));
+            }
+            if fs::metadata(path)?.len() > ICON_MAX_SIZE {
+                return Err(Error::InvalidEditResult(
+                    format!("role icon must be at most 256 KiB: {new:?}").into(),
+                ));
+            }
+            return Ok(IconValue::File(path));
+        }
+        if new.chars().any(|c| c.is_ascii() || c.is_whitespace()) {
+            return Err(Error::InvalidEditResult(
+                format!("not a unicode emoji or an image file: {new:?}").into(),
+            ));
+        }
+        Ok(IconValue::Emoji(new))
+    }
+}
+
+impl Display for RoleIconItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for RoleIconItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let edit = match self.parse(&content)? {
+            IconValue::Clear => EditRole::new().unicode_emoji(None),
+            IconValue::Emoji(emoji) => EditRole::new().unicode_emoji(Some(emoji.to_string())),
+            IconValue::File(path) => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let attachment = CreateAttachment::bytes(fs::read(path)?, name);
+                EditRole::new().icon(Some(&attachment))
+            }
+        };
+        let role = self
+            .discord
+            .edit_role(self.guild_id, self.id, &edit)
+            .await?;
+        self.icon = role.icon;
+        self.unicode_emoji = role.unicode_emoji;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        match (&self.unicode_emoji, &self.icon) {
+            (Some(emoji), _) => emoji.clone(),
+            (None, Some(icon)) => format!("{ICON_PREFIX}{icon}"),
+            (None, None) => String::new(),
+        }
+    }
+    fn comment(&self) -> String {
+        format!("@{} ({})", self.name, self.id)
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        self.parse(new).map(|_| ())
+    }
+}
+
+/// 指定したGuildのロールのアイコンを取得する。`@everyone` はアイコンを持てないため除く
+pub async fn fetch_icon_items(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+) -> Result<Vec<RoleIconItem>> {
+    let guild = discord.guild(guild_id).await?;
+    let icons_allowed = guild.features.iter().any(|f| f == "ROLE_ICONS");
+    let mut roles = discord.guild_roles(guild_id).await?;
+    roles.retain(|role| role.id.get() != guild_id.get());
+    roles.sort_by_key(|role| std::cmp::Reverse(role.position));
+    Ok(roles
+        .into_iter()
+        .map(|role| RoleIconItem {
+            discord: discord.clone(),
+            guild_id,
+            id: role.id,
+            name: role.name,
+            icon: role.icon,
+            unicode_emoji: role.unicode_emoji,
+            icons_allowed,
+        })
+        .collect())
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Discordがチャンネルごとに許す名前・トピックの変更回数
+const LIMIT: usize = 2;
+/// 変更回数を数える期間
+const WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// 直近の名前の変更の記録ファイルのパス。$EDISCH_RENAME_HISTORY で上書きできる
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("EDISCH_RENAME_HISTORY") {
+        return Some(path.into());
+    }
+    dirs::data_local_dir().map(|dir| dir.join("edisch").join("renames.txt"))
+}
+
+/// 待ち時間を `1m 30s` のように表示する
+pub fn format_duration(duration: Duration) -> String {
+    // 短い待ち時間は小数点以下まで表示する
+    if duration < Duration::from_secs(10) {
+        return format!("{:.1}s", duration.as_secs_f64());
+    }
+    let secs = duration.as_secs_f64().ceil() as u64;
+    match (secs / 60, secs % 60) {
+        (0, s) => format!("{s}s"),
+        (m, 0) => format!("{m}m"),
+        (m, s) => format!("{m}m {s}s"),
+    }
+}
+
+/// チャンネルの名前の変更のレートリミット (10分間に2回) を超えないよう、変更の間隔を空ける。
+/// 直前の実行での変更も数えられるよう、変更した時刻をファイルに記録する
+pub struct RenameScheduler {
+    path: Option<PathBuf>,
+    /// チャンネルIDごとの、期間内に変更した時刻 (UNIX時間)
+    history: HashMap<String, Vec<Duration>>,
+}
+
+impl RenameScheduler {
+    /// 記録ファイルから期間内の変更を読み込む
+    pub fn load() -> Self {
+        let path = path();
+        let now = now();
+        let mut history: HashMap<String, Vec<Duration>> = HashMap::new();
+        let text = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        for line in text.lines() {
+            let Some((id, secs)) = line.split_once('\t') else {
+                continue;
+            };
+            let Ok(secs) = secs.parse::<f64>() else {
+                continue;
+            };
+            let at = Duration::from_secs_f64(secs);
+            if now.saturating_sub(at) < WINDOW {
+                history.entry(id.to_string()).or_default().push(at);
+            }
+        }
+        Self { path, history }
+    }
+
+    /// `now` から数えて、次にチャンネルを変更できるまでの待ち時間
+    fn wait_at(&self, id: &str, now: Duration) -> Duration {
+        let Some(times) = self.history.get(id) else {
+            return Duration::ZERO;
+        };
+        if times.len() < LIMIT {
+            return Duration::ZERO;
+        }
+        // 期間内の変更が上限未満になるまで待つ
+        let mut times = times.clone();
+        times.sort();
+        (times[times.len() - LIMIT] + WINDOW).saturating_sub(now)
+    }
+
+    /// 全ての変更を適用し終えるまでに見込まれる待ち時間。
+    /// 待っている間も時間は進むため、チャンネルごとの待ち時間の最大値とする
+    pub fn estimate<'a>(&self, ids: impl IntoIterator<Item = &'a str>) -> Duration {
+        let now = now();
+        ids.into_iter()
+            .map(|id| self.wait_at(id, now))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// 期間内の変更が上限に達しているチャンネルの数と、最も長い待ち時間
+    pub fn blocked(&self) -> (usize, Duration) {
+        let now = now();
+        self.history
+            .keys()
+            .map(|id| self.wait_at(id, now))
+            .filter(|wait| !wait.is_zero())
+            .fold((0, Duration::ZERO), |(count, max), wait| {
+                (count + 1, max.max(wait))
+            })
+    }
+
+    /// チャンネルを変更できるようになる時刻。待つ必要がなければ None
+    pub fn deadline(&self, id: &str) -> Option<Instant> {
+        let wait = self.wait_at(id, now());
+        (!wait.is_zero()).then(|| Instant::now() + wait)
+    }
+
+    /// チャンネルを変更したことを記録し、ファイルに書き出す
+    pub fn record(&mut self, id: &str) {
+        let now = now();
+        self.history.entry(id.to_string()).or_default().push(now);
+        self.save(now);
+    }
+
+    /// Discordがレートリミットで変更を拒否したことを記録する。
+    /// edisch以外での変更で上限に達しているため、今から期間いっぱい変更できないものとする
+    pub fn record_limited(&mut self, id: &str) {
+        let now = now();
+        self.history
+            .entry(id.to_string())
+            .or_default()
+            .extend([now; LIMIT]);
+        self.save(now);
+    }
+
+    /// 期間を過ぎた記録を捨て、ファイルに書き出す
+    fn save(&mut self, now: Duration) {
+        for times in self.history.values_mut() {
+            times.retain(|&at| now.saturating_sub(at) < WINDOW);
+        }
+        self.history.retain(|_, times| !times.is_empty());
+        let Some(path) = &self.path else {
+            return;
+        };
+        let text: String = self
+            .history
+            .iter()
+            .flat_map(|(id, times)| {
+                times
+                    .iter()
+                    .map(move |at| format!("{id}\t{:.3}\n", at.as_secs_f64()))
+            })
+            .collect();
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, text));
+        if let Err(e) = result {
+            warn!("Failed to write rename history {}: {e}", path.display());
+        }
+    }
+}
+
+/// チャンネルを変更できるようになる時刻まで待つ
+pub async fn wait_until(id: &str, deadline: Instant, color: bool) {
+    let mut prompt = console::style("Waiting:");
+    if color {
+        prompt = prompt.yellow().bold();
+    }
+    info!(
+        "{prompt} {} for the rename limit of channel {id} (2 per 10 minutes)",
+        format_duration(deadline.saturating_duration_since(Instant::now()))
+    );
+    tokio::time::sleep_until(deadline).await;
+}
+
+/// 現在時刻 (UNIX時間)
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
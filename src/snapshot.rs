@@ -0,0 +1,167 @@
+use crate::{
+    args::ChannelField,
+    compress,
+    error::{Error, Result},
+};
+use serenity::{all::GuildId, model::Timestamp};
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::info;
+
+/// 保存されたスナップショット
+pub struct Snapshot {
+    /// 保存した日時から作るID (`20261016T153000.123` の形式)
+    pub id: String,
+    /// 保存した項目
+    pub field: ChannelField,
+    /// ファイルのパス
+    pub path: PathBuf,
+}
+
+/// Guildのスナップショットを保存するディレクトリ。$EDISCH_SNAPSHOT_DIR で上書きできる
+fn dir(guild_id: GuildId) -> Result<PathBuf> {
+    let base = match env::var_os("EDISCH_SNAPSHOT_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_local_dir()
+            .map(|dir| dir.join("edisch").join("snapshots"))
+            .ok_or_else(|| Error::MissingArgument("EDISCH_SNAPSHOT_DIR".into()))?,
+    };
+    Ok(base.join(guild_id.to_string()))
+}
+
+/// 現在の日時から作るID。同じ秒に続けて保存しても重ならないよう、ミリ秒まで含める
+fn new_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64);
+    let now = Timestamp::from_millis(millis).unwrap_or_else(|_| Timestamp::now());
+    // RFC 3339 の日時から、ファイル名に使える秒までの部分を取り出す
+    let seconds: String = now
+        .to_string()
+        .chars()
+        .take(19)
+        .filter(|c| !matches!(c, '-' | ':'))
+        .collect();
+    format!("{seconds}.{:03}", millis % 1000)
+}
+
+/// 新しいスナップショットを保存するファイルを、ディレクトリとともに作成してIDとパスを返す。
+/// 同時に保存した別のスナップショットを上書きしないよう、既にあるファイルは使わない
+pub fn new_path(guild_id: GuildId, field: ChannelField) -> Result<(String, PathBuf)> {
+    let dir = dir(guild_id)?;
+    fs::create_dir_all(&dir)?;
+    loop {
+        let id = new_id();
+        let path = dir.join(format!("{id}-{field}.txt.gz"));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok((id, path)),
+            // 同じミリ秒に作られていた場合は、時刻が進んでから作り直す
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Guildのスナップショットを古い順に列挙する
+pub fn list(guild_id: GuildId) -> Result<Vec<Snapshot>> {
+    let dir = dir(guild_id)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        // 保存に失敗して空のまま残ったファイルは、スナップショットとして扱わない
+        if entry.metadata()?.len() == 0 {
+            continue;
+        }
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some((id, field)) = name
+            .strip_suffix(".txt.gz")
+            .and_then(|stem| stem.split_once('-'))
+        else {
+            continue;
+        };
+        let Ok(field) = clap::ValueEnum::from_str(field, false) else {
+            continue;
+        };
+        snapshots.push(Snapshot {
+            id: id.to_string(),
+            field,
+            path,
+        });
+    }
+    snapshots.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(snapshots)
+}
+
+/// IDが一致するスナップショット、指定しない場合は最新のスナップショットを探す
+pub fn find(guild_id: GuildId, id: Option<&str>) -> Result<Snapshot> {
+    let snapshots = list(guild_id)?;
+    let found = match id {
+        Some(id) => snapshots.into_iter().find(|snapshot| snapshot.id == id),
+        None => snapshots.into_iter().last(),
+    };
+    found.ok_or_else(|| {
+        let message = match id {
+            Some(id) => format!("snapshot not found: {id}"),
+            None => format!("no snapshots saved for guild {guild_id}"),
+        };
+        io::Error::new(io::ErrorKind::NotFound, message).into()
+    })
+}
+
+/// Guildのスナップショットを、ID・項目・行数とともに一覧表示する
+pub fn print_list(guild_id: GuildId) -> Result<()> {
+    let snapshots = list(guild_id)?;
+    if snapshots.is_empty() {
+        info!("No snapshots saved for guild {guild_id}");
+        return Ok(());
+    }
+    for snapshot in snapshots {
+        let lines = compress::read_to_string(&snapshot.path)?.lines().count();
+        println!("{}\t{}\t{lines} channel(s)", snapshot.id, snapshot.field);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_saved_in_the_same_second_get_distinct_files() {
+        let dir = tempfile::tempdir().unwrap();
+        env::set_var("EDISCH_SNAPSHOT_DIR", dir.path());
+        let guild_id = GuildId::new(1);
+        let saved: Vec<_> = (0..3)
+            .map(|_| new_path(guild_id, ChannelField::Name).unwrap())
+            .collect();
+        for (_, path) in &saved {
+            compress::write(path, "general\t(10)\n").unwrap();
+        }
+        // 作成しただけで書き込まなかったファイルは一覧に出さない
+        new_path(guild_id, ChannelField::Name).unwrap();
+        let ids: Vec<_> = list(guild_id)
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        let expected: Vec<_> = saved.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, expected);
+        assert!(find(guild_id, Some(&ids[1])).is_ok());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    discord::{Discord, SoundboardSound},
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::GuildId;
+use std::{fmt::Display, sync::Arc};
+
+/// Guildのサウンドボードのサウンド
+pub struct SoundItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// サウンドの情報
+    sound: SoundboardSound,
+}
+
+impl Display for SoundItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.sound.sound_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for SoundItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        self.sound = self
+            .discord
+            .rename_soundboard_sound(self.guild_id, self.sound.sound_id, &content)
+            .await?;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.sound.name.clone()
+    }
+    fn comment(&self) -> String {
+        let emoji = match (&self.sound.emoji_id, &self.sound.emoji_name) {
+            (Some(id), Some(name)) => format!("<:{name}:{id}> "),
+            (Some(id), None) => format!("<:_:{id}> "),
+            (None, Some(emoji)) => format!("{emoji} "),
+            (None, None) => String::new(),
+        };
+        format!("🔊 {emoji}({})", self.sound.sound_id)
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if !(2..=32).contains(&new.chars().count()) {
+            return Err(Error::InvalidEditResult(
+                format!("sound name must be between 2 and 32 characters: {new:?}").into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのサウンドボードのサウンドを取得する
+pub async fn fetch_items(discord: &Arc<Discord>, guild_id: GuildId) -> Result<Vec<SoundItem>> {
+    Ok(discord
+        .guild_soundboard_sounds(guild_id)
+        .await?
+        .into_iter()
+        .map(|sound| SoundItem {
+            discord: discord.clone(),
+            guild_id,
+            sound,
+        })
+        .collect())
+}
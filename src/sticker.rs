@@ -0,0 +1,94 @@
+use crate::{
+    args::StickerField,
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serenity::all::{EditSticker, GuildId, Sticker};
+use std::{fmt::Display, sync::Arc};
+
+/// Guildのスタンプの、編集対象の項目
+pub struct StickerItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// スタンプ
+    sticker: Sticker,
+    /// 編集する項目
+    field: StickerField,
+}
+
+impl Display for StickerItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.sticker.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for StickerItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let edit = match self.field {
+            StickerField::Name => EditSticker::new().name(content),
+            StickerField::Tags => EditSticker::new().tags(content),
+            StickerField::Description => EditSticker::new().description(content),
+        };
+        self.sticker = self
+            .discord
+            .edit_sticker(self.guild_id, self.sticker.id, &edit)
+            .await?;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        match self.field {
+            StickerField::Name => self.sticker.name.clone(),
+            StickerField::Tags => self.sticker.tags.join(","),
+            StickerField::Description => self.sticker.description.clone().unwrap_or_default(),
+        }
+    }
+    fn comment(&self) -> String {
+        match self.field {
+            StickerField::Name => format!("({})", self.sticker.id),
+            _ => format!("{} ({})", self.sticker.name, self.sticker.id),
+        }
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        let length = new.chars().count();
+        let message = match self.field {
+            StickerField::Name if !(2..=30).contains(&length) => {
+                "sticker name must be between 2 and 30 characters"
+            }
+            StickerField::Tags if !(1..=200).contains(&length) => {
+                "sticker tags must be between 1 and 200 characters"
+            }
+            StickerField::Description if length != 0 && !(2..=100).contains(&length) => {
+                "sticker description must be empty or between 2 and 100 characters"
+            }
+            _ => return Ok(()),
+        };
+        Err(Error::InvalidEditResult(
+            format!("{message}: {new:?}").into(),
+        ))
+    }
+}
+
+/// 指定したGuildのスタンプを取得する
+pub async fn fetch_items(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    field: StickerField,
+) -> Result<Vec<StickerItem>> {
+    Ok(discord
+        .guild_stickers(guild_id)
+        .await?
+        .into_iter()
+        .map(|sticker| StickerItem {
+            discord: discord.clone(),
+            guild_id,
+            sticker,
+            field,
+        })
+        .collect())
+}
@@ -0,0 +1,437 @@
+use crate::{
+    channel::{kind_emoji, kind_from_emoji},
+    discord::Discord,
+    error::{Error, Result},
+    i18n::tr,
+};
+use dialoguer::Confirm;
+use serenity::all::{ChannelId, ChannelType, CreateChannel, EditChannel, GuildChannel, GuildId};
+use std::{collections::HashMap, sync::Arc};
+use tracing::info;
+
+/// エクスポートされたファイルの1行が表すチャンネル
+struct Entry {
+    /// 入力上の行番号 (1始まり)
+    line: usize,
+    /// チャンネル名
+    name: String,
+    /// チャンネルの種類
+    kind: ChannelType,
+    /// 親カテゴリの名前
+    parent: Option<String>,
+    /// エクスポート時のチャンネルID
+    id: Option<ChannelId>,
+}
+
+/// `📝 in Parent (123)` の形式のコメント列を読み取る
+fn parse_entry(line: usize, text: &str) -> Result<Entry> {
+    let invalid = |message: &str| {
+        Error::InvalidEditResult(format!("line {line}: {message}: {text:?}").into())
+    };
+    let mut columns = text.split('\t');
+    let name = columns.next().unwrap_or_default().to_string();
+    let comment = columns
+        .next()
+        .ok_or_else(|| invalid("missing the comment column written by export"))?
+        .trim();
+    let mut chars = comment.chars();
+    let kind = chars
+        .next()
+        .and_then(kind_from_emoji)
+        .ok_or_else(|| invalid("unknown channel type"))?;
+    let mut rest = chars.as_str().trim();
+    let mut id = None;
+    if let Some((head, tail)) = rest.rsplit_once(" (") {
+        if let Some(Ok(parsed)) = tail.strip_suffix(')').map(str::parse::<u64>) {
+            id = Some(ChannelId::new(parsed));
+            rest = head;
+        }
+    } else if let Some(Ok(parsed)) = rest
+        .strip_prefix('(')
+        .and_then(|r| r.strip_suffix(')'))
+        .map(str::parse::<u64>)
+    {
+        id = Some(ChannelId::new(parsed));
+        rest = "";
+    }
+    let parent = rest.trim().strip_prefix("in ").map(str::to_string);
+    if name.chars().count() < 2 {
+        return Err(invalid("channel name must be at least 2 characters"));
+    }
+    Ok(Entry {
+        line,
+        name,
+        kind,
+        parent,
+        id,
+    })
+}
+
+/// エクスポートされたファイルの空行以外の各行を読み取る
+fn parse_entries(text: &str) -> Result<Vec<Entry>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| parse_entry(n + 1, line))
+        .collect()
+}
+
+/// 構造を揃えるための操作
+enum Action<'a> {
+    /// 既存のチャンネルの名前・親カテゴリを変更する
+    Update {
+        channel: &'a GuildChannel,
+        name: Option<&'a str>,
+        parent: Option<Option<&'a str>>,
+    },
+    /// チャンネルを作成する
+    Create(&'a Entry),
+}
+
+impl Action<'_> {
+    fn is_category(&self) -> bool {
+        match self {
+            Action::Update { channel, .. } => channel.kind == ChannelType::Category,
+            Action::Create(entry) => entry.kind == ChannelType::Category,
+        }
+    }
+
+    fn format(&self) -> String {
+        match self {
+            Action::Update {
+                channel,
+                name,
+                parent,
+            } => {
+                let mut line = format!("update {} {}", kind_emoji(channel.kind), channel.name);
+                if let Some(name) = name {
+                    line.push_str(&format!(" -> {name}"));
+                }
+                match parent {
+                    Some(Some(parent)) => line.push_str(&format!(", move into {parent}")),
+                    Some(None) => line.push_str(", move out of its category"),
+                    None => {}
+                }
+                line.push_str(&format!(" ({})", channel.id));
+                line
+            }
+            Action::Create(entry) => {
+                let mut line = format!("create {} {}", kind_emoji(entry.kind), entry.name);
+                if let Some(parent) = &entry.parent {
+                    line.push_str(&format!(" in {parent}"));
+                }
+                line
+            }
+        }
+    }
+}
+
+/// エクスポートされたファイルの構造にGuildを揃える。
+/// 足りないカテゴリ・チャンネルを作成し、名前や親カテゴリの異なるものを変更する。
+/// ファイルにないチャンネルは報告のみ行い、削除しない
+pub async fn apply_structure(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    text: &str,
+    yes: bool,
+    color: bool,
+) -> Result<()> {
+    let entries = parse_entries(text)?;
+    let channels = discord.guild_channels(guild_id, |_| {}).await?;
+    let name_of = |id: Option<ChannelId>| {
+        id.and_then(|id| channels.iter().find(|c| c.id == id))
+            .map(|c| c.name.as_str())
+    };
+
+    // ファイルの各行に既存のチャンネルを対応付ける。IDが一致するものを優先し、
+    // 次に種類・名前・親カテゴリが一致するもの (別のGuildのファイルの場合) を探す
+    let mut matched: Vec<Option<&GuildChannel>> = entries
+        .iter()
+        .map(|entry| {
+            entry
+                .id
+                .and_then(|id| channels.iter().find(|c| c.id == id && c.kind == entry.kind))
+        })
+        .collect();
+    for (i, entry) in entries.iter().enumerate() {
+        if matched[i].is_some() {
+            continue;
+        }
+        matched[i] = channels.iter().find(|c| {
+            c.kind == entry.kind
+                && c.name == entry.name
+                && name_of(c.parent_id) == entry.parent.as_deref()
+                && !matched.iter().flatten().any(|m| m.id == c.id)
+        });
+    }
+    for entry in &entries {
+        let duplicates = entries
+            .iter()
+            .filter(|e| e.id.is_some() && e.id == entry.id);
+        if duplicates.count() > 1 {
+            return Err(Error::InvalidEditResult(
+                format!("line {}: duplicate channel ID", entry.line).into(),
+            ));
+        }
+    }
+
+    let mut actions: Vec<_> = entries
+        .iter()
+        .zip(&matched)
+        .filter_map(|(entry, channel)| match channel {
+            Some(channel) => {
+                let name = (channel.name != entry.name).then_some(entry.name.as_str());
+                let parent = (channel.kind != ChannelType::Category
+                    && name_of(channel.parent_id) != entry.parent.as_deref())
+                .then_some(entry.parent.as_deref());
+                (name.is_some() || parent.is_some()).then_some(Action::Update {
+                    channel,
+                    name,
+                    parent,
+                })
+            }
+            None => Some(Action::Create(entry)),
+        })
+        .collect();
+    // カテゴリを先に処理し、チャンネルの親として名前で参照できるようにする
+    actions.sort_by_key(|action| !action.is_category());
+    let extras: Vec<_> = channels
+        .iter()
+        .filter(|c| !matched.iter().flatten().any(|m| m.id == c.id))
+        .collect();
+
+    for action in &actions {
+        let mut line = console::style(action.format());
+        if color {
+            line = match action {
+                Action::Update { .. } => line.yellow(),
+                Action::Create(_) => line.green(),
+            };
+        }
+        eprintln!("{line}");
+    }
+    for channel in &extras {
+        let mut line = console::style(format!(
+            "extra {} {} ({}), not in the file and left as is",
+            kind_emoji(channel.kind),
+            channel.name,
+            channel.id
+        ));
+        if color {
+            line = line.dim();
+        }
+        eprintln!("{line}");
+    }
+    if actions.is_empty() {
+        info!("No changes");
+        return Ok(());
+    }
+    if !yes
+        && !Confirm::new()
+            .with_prompt(tr!("prompt-apply"))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    // カテゴリ名からIDへの索引。変更・作成したカテゴリも反映する
+    let mut categories: HashMap<String, ChannelId> = channels
+        .iter()
+        .filter(|c| c.kind == ChannelType::Category)
+        .map(|c| (c.name.clone(), c.id))
+        .collect();
+    let find_category = |categories: &HashMap<String, ChannelId>, name: &str| {
+        categories
+            .get(name)
+            .copied()
+            .ok_or_else(|| Error::InvalidEditResult(format!("category not found: {name:?}").into()))
+    };
+    for action in &actions {
+        let mut prompt = console::style("Applying:");
+        if color {
+            prompt = prompt.blue().bold();
+        }
+        info!("{prompt} {}", action.format());
+        match action {
+            Action::Update {
+                channel,
+                name,
+                parent,
+            } => {
+                let mut edit = EditChannel::new();
+                if let Some(name) = name {
+                    edit = edit.name(*name);
+                }
+                if let Some(parent) = parent {
+                    let parent = match parent {
+                        Some(parent) => Some(find_category(&categories, parent)?),
+                        None => None,
+                    };
+                    edit = edit.category(parent);
+                }
+                let edited = discord.edit_channel(channel.id, &edit).await?;
+                if edited.kind == ChannelType::Category {
+                    categories.retain(|_, id| *id != edited.id);
+                    categories.insert(edited.name, edited.id);
+                }
+            }
+            Action::Create(entry) => {
+                let mut create = CreateChannel::new(&entry.name).kind(entry.kind);
+                if let Some(parent) = &entry.parent {
+                    create = create.category(find_category(&categories, parent)?);
+                }
+                let created = discord.create_channel(guild_id, &create).await?;
+                if created.kind == ChannelType::Category {
+                    categories.insert(created.name, created.id);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// コピー元のGuildのカテゴリ・チャンネルを、IDを除いたエクスポートの形式で書き出す。
+/// Discordでの表示順 (カテゴリに属さないもの、各カテゴリとその中のもの) に並べる
+fn layout_text(channels: &[GuildChannel]) -> String {
+    let is_voice = |kind| matches!(kind, ChannelType::Voice | ChannelType::Stage);
+    // エクスポートの形式で表せない種類は除く
+    let mut channels: Vec<_> = channels
+        .iter()
+        .filter(|c| kind_from_emoji(kind_emoji(c.kind)).is_some())
+        .collect();
+    channels.sort_by_key(|c| (is_voice(c.kind), c.position, c.id));
+    let mut categories: Vec<_> = channels
+        .iter()
+        .filter(|c| c.kind == ChannelType::Category)
+        .collect();
+    categories.sort_by_key(|c| (c.position, c.id));
+
+    let mut lines = Vec::new();
+    for channel in channels
+        .iter()
+        .filter(|c| c.kind != ChannelType::Category && c.parent_id.is_none())
+    {
+        lines.push(format!("{}\t{}", channel.name, kind_emoji(channel.kind)));
+    }
+    for category in categories {
+        lines.push(format!("{}\t{}", category.name, kind_emoji(category.kind)));
+        for channel in channels.iter().filter(|c| c.parent_id == Some(category.id)) {
+            lines.push(format!(
+                "{}\t{} in {}",
+                channel.name,
+                kind_emoji(channel.kind),
+                category.name
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// コピー元のGuildのカテゴリ・チャンネルの構造を、コピー先のGuildに作成する。
+/// 種類・名前・親カテゴリで対応付け、[`apply_structure`] と同じく変更を表示して確認してから適用する
+pub async fn copy_structure(
+    discord: &Arc<Discord>,
+    from: GuildId,
+    to: GuildId,
+    yes: bool,
+    color: bool,
+) -> Result<()> {
+    if from == to {
+        return Err(Error::ParseArgument(
+            "--from and --to are the same guild".into(),
+        ));
+    }
+    let channels = discord.guild_channels(from, |_| {}).await?;
+    apply_structure(discord, to, &layout_text(&channels), yes, color).await
+}
+
+/// エクスポートされたファイルと現在のGuildを比べ、ファイルの書き出し後に変更・削除・作成された
+/// チャンネルを表示する。何も変更しない
+pub async fn status(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    text: &str,
+    color: bool,
+) -> Result<()> {
+    let entries = parse_entries(text)?;
+    let channels = discord.guild_channels(guild_id, |_| {}).await?;
+    let name_of = |id: Option<ChannelId>| {
+        id.and_then(|id| channels.iter().find(|c| c.id == id))
+            .map(|c| c.name.as_str())
+    };
+
+    let mut drifts = Vec::new();
+    let mut seen = Vec::new();
+    for entry in &entries {
+        // IDのない行は、種類・名前・親カテゴリが一致するチャンネルと対応付ける
+        let channel = match entry.id {
+            Some(id) => channels.iter().find(|c| c.id == id),
+            None => channels.iter().find(|c| {
+                c.kind == entry.kind
+                    && c.name == entry.name
+                    && name_of(c.parent_id) == entry.parent.as_deref()
+            }),
+        };
+        let Some(channel) = channel else {
+            let id = entry.id.map(|id| format!(" ({id})")).unwrap_or_default();
+            drifts.push((
+                "deleted",
+                format!("{} {}{id}", kind_emoji(entry.kind), entry.name),
+            ));
+            continue;
+        };
+        seen.push(channel.id);
+        let emoji = kind_emoji(channel.kind);
+        if channel.name != entry.name {
+            drifts.push((
+                "renamed",
+                format!(
+                    "{emoji} {} -> {} ({})",
+                    entry.name, channel.name, channel.id
+                ),
+            ));
+        }
+        let parent = name_of(channel.parent_id);
+        if channel.kind != ChannelType::Category && parent != entry.parent.as_deref() {
+            drifts.push((
+                "moved",
+                format!(
+                    "{emoji} {}: {} -> {} ({})",
+                    channel.name,
+                    entry.parent.as_deref().unwrap_or("(no category)"),
+                    parent.unwrap_or("(no category)"),
+                    channel.id
+                ),
+            ));
+        }
+    }
+    for channel in channels.iter().filter(|c| !seen.contains(&c.id)) {
+        drifts.push((
+            "created",
+            format!(
+                "{} {} ({})",
+                kind_emoji(channel.kind),
+                channel.name,
+                channel.id
+            ),
+        ));
+    }
+
+    if drifts.is_empty() {
+        info!("The server matches the file");
+        return Ok(());
+    }
+    for (kind, message) in &drifts {
+        let mut styled = console::style(format!("{kind:<8}"));
+        if color {
+            styled = match *kind {
+                "created" => styled.green(),
+                "deleted" => styled.red(),
+                _ => styled.yellow(),
+            };
+        }
+        println!("{styled} {message}");
+    }
+    Ok(())
+}
@@ -0,0 +1,582 @@
+use crate::{
+    channel::kind_emoji,
+    discord::Discord,
+    error::{Error, Result},
+    i18n::tr,
+};
+use dialoguer::Confirm;
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, ChannelType, CreateChannel, EditChannel, GuildChannel, GuildId};
+use std::{collections::HashSet, sync::Arc};
+use tracing::info;
+
+/// 望ましいGuildの構成 (`structure.toml`)。カテゴリ・チャンネルは記述した順に並べる
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Layout {
+    /// カテゴリに属さないチャンネル
+    #[serde(default, rename = "channel")]
+    channels: Vec<ChannelSpec>,
+    /// カテゴリ
+    #[serde(default, rename = "category")]
+    categories: Vec<CategorySpec>,
+}
+
+/// カテゴリの記述
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct CategorySpec {
+    name: String,
+    /// 既存のカテゴリのID。省略した場合は名前で対応付ける
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    /// カテゴリに属するチャンネル
+    #[serde(default, rename = "channel")]
+    channels: Vec<ChannelSpec>,
+}
+
+/// チャンネルの記述
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ChannelSpec {
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: Kind,
+    /// トピック。省略した場合は変更しない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    topic: Option<String>,
+    /// 既存のチャンネルのID。省略した場合は種類と名前で対応付ける
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+}
+
+/// 記述できるチャンネルの種類
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    #[default]
+    Text,
+    Voice,
+    #[serde(alias = "announcement")]
+    News,
+    Forum,
+    Stage,
+}
+
+impl From<Kind> for ChannelType {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Text => ChannelType::Text,
+            Kind::Voice => ChannelType::Voice,
+            Kind::News => ChannelType::News,
+            Kind::Forum => ChannelType::Forum,
+            Kind::Stage => ChannelType::Stage,
+        }
+    }
+}
+
+impl Kind {
+    /// 記述できる種類であれば変換する
+    fn from_channel_type(kind: ChannelType) -> Option<Self> {
+        match kind {
+            ChannelType::Text => Some(Kind::Text),
+            ChannelType::Voice => Some(Kind::Voice),
+            ChannelType::News => Some(Kind::News),
+            ChannelType::Forum => Some(Kind::Forum),
+            ChannelType::Stage => Some(Kind::Stage),
+            _ => None,
+        }
+    }
+}
+
+/// ボイスチャンネルの一覧に並ぶ種類か。Discordではテキストチャンネルの後に表示される
+fn is_voice(kind: ChannelType) -> bool {
+    matches!(kind, ChannelType::Voice | ChannelType::Stage)
+}
+
+/// ファイルのチャンネルと、対応付けた既存のチャンネル
+struct Target<'a> {
+    spec: &'a ChannelSpec,
+    /// 親カテゴリ ([`Layout::categories`] の添字)
+    parent: Option<usize>,
+    channel: Option<&'a GuildChannel>,
+}
+
+impl Target<'_> {
+    fn kind(&self) -> ChannelType {
+        self.spec.kind.into()
+    }
+}
+
+/// 並び順を揃える単位
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Group {
+    /// カテゴリの並び
+    Categories,
+    /// カテゴリに属さないチャンネル、またはカテゴリ ([`Layout::categories`] の添字) 内のチャンネル
+    Channels(Option<usize>),
+}
+
+/// 現在のGuildをファイルの構成に揃えるための計画
+struct Plan<'a> {
+    layout: &'a Layout,
+    /// 各カテゴリに対応付けた既存のカテゴリ
+    categories: Vec<Option<&'a GuildChannel>>,
+    targets: Vec<Target<'a>>,
+    /// 並び順の変わるもの
+    reorder: Vec<Group>,
+    /// ファイルにない既存のカテゴリ・チャンネル
+    extras: Vec<&'a GuildChannel>,
+}
+
+impl<'a> Plan<'a> {
+    /// ファイルのカテゴリ・チャンネルに既存のものを対応付ける。
+    /// IDが指定されていればそれを、なければ同じ種類・名前のもの (同じ親カテゴリにあるものを優先) を使う
+    fn new(layout: &'a Layout, channels: &'a [GuildChannel]) -> Result<Self> {
+        let mut used = HashSet::new();
+        let mut by_id = |id: Option<u64>, kind: ChannelType, name: &str| -> Result<_> {
+            let Some(id) = id else {
+                return Ok(None);
+            };
+            let channel = channels
+                .iter()
+                .find(|c| c.id.get() == id)
+                .ok_or_else(|| invalid(format!("{name:?}: channel {id} not found")))?;
+            if channel.kind != kind {
+                return Err(invalid(format!(
+                    "{name:?}: channel {id} is a {} channel, and its type cannot be changed",
+                    kind_emoji(channel.kind)
+                )));
+            }
+            if !used.insert(channel.id) {
+                return Err(invalid(format!("{name:?}: duplicate ID {id}")));
+            }
+            Ok(Some(channel))
+        };
+
+        let mut categories = Vec::new();
+        for spec in &layout.categories {
+            categories.push(by_id(spec.id, ChannelType::Category, &spec.name)?);
+        }
+        let mut targets = Vec::new();
+        let specs = layout.channels.iter().map(|spec| (spec, None)).chain(
+            layout
+                .categories
+                .iter()
+                .enumerate()
+                .flat_map(|(i, category)| {
+                    category.channels.iter().map(move |spec| (spec, Some(i)))
+                }),
+        );
+        for (spec, parent) in specs {
+            let channel = by_id(spec.id, spec.kind.into(), &spec.name)?;
+            targets.push(Target {
+                spec,
+                parent,
+                channel,
+            });
+        }
+
+        let mut unused = |kind: ChannelType, name: &str, parent: Option<Option<ChannelId>>| {
+            let channel = channels.iter().find(|c| {
+                c.kind == kind
+                    && c.name == name
+                    && parent.is_none_or(|parent| c.parent_id == parent)
+                    && !used.contains(&c.id)
+            })?;
+            used.insert(channel.id);
+            Some(channel)
+        };
+        for (i, spec) in layout.categories.iter().enumerate() {
+            if categories[i].is_none() {
+                categories[i] = unused(ChannelType::Category, &spec.name, None);
+            }
+        }
+        // 移動を減らすため、先に同じ親カテゴリにあるものから探す
+        for same_parent in [true, false] {
+            for target in targets.iter_mut().filter(|t| t.channel.is_none()) {
+                let parent = match target.parent {
+                    Some(i) => categories[i].map(|c| Some(c.id)),
+                    None => Some(None),
+                };
+                let parent = match (same_parent, parent) {
+                    (true, Some(parent)) => Some(parent),
+                    (true, None) => continue,
+                    (false, _) => None,
+                };
+                target.channel = unused(target.kind(), &target.spec.name, parent);
+            }
+        }
+        for target in &targets {
+            if target.spec.topic.is_some() && is_voice(target.kind()) {
+                return Err(invalid(format!(
+                    "{:?}: voice and stage channels have no topic",
+                    target.spec.name
+                )));
+            }
+        }
+
+        let extras = channels.iter().filter(|c| !used.contains(&c.id)).collect();
+        let mut plan = Plan {
+            layout,
+            categories,
+            targets,
+            reorder: Vec::new(),
+            extras,
+        };
+        let groups = std::iter::once(Group::Categories)
+            .chain(std::iter::once(Group::Channels(None)))
+            .chain((0..layout.categories.len()).map(|i| Group::Channels(Some(i))));
+        plan.reorder = groups.filter(|&group| plan.needs_reorder(group)).collect();
+        Ok(plan)
+    }
+
+    /// 親カテゴリの現在のID。作成するカテゴリの場合は None
+    fn parent_id(&self, parent: Option<usize>) -> Option<Option<ChannelId>> {
+        match parent {
+            Some(i) => self.categories[i].map(|c| Some(c.id)),
+            None => Some(None),
+        }
+    }
+
+    /// 名前・親カテゴリ・トピックのうち変更するもの
+    fn changes(&self, target: &Target) -> (bool, bool, bool) {
+        let Some(channel) = target.channel else {
+            return (false, false, false);
+        };
+        let rename = channel.name != target.spec.name;
+        let moved = self.parent_id(target.parent) != Some(channel.parent_id);
+        let topic = target
+            .spec
+            .topic
+            .as_ref()
+            .is_some_and(|topic| channel.topic.as_deref().unwrap_or_default() != topic);
+        (rename, moved, topic)
+    }
+
+    /// 親カテゴリに属するチャンネル ([`Plan::targets`] の添字) の、ファイルでの並び。
+    /// Discordの表示に合わせ、ボイスチャンネルはテキストチャンネルの後にする
+    fn ordered(&self, parent: Option<usize>) -> Vec<usize> {
+        let mut indices: Vec<_> = (0..self.targets.len())
+            .filter(|&i| self.targets[i].parent == parent)
+            .collect();
+        indices.sort_by_key(|&i| is_voice(self.targets[i].kind()));
+        indices
+    }
+
+    /// グループに属するものの、ファイルでの並び。作成するものは None
+    fn members(&self, group: Group) -> Vec<Option<&'a GuildChannel>> {
+        match group {
+            Group::Categories => self.categories.clone(),
+            Group::Channels(parent) => self
+                .ordered(parent)
+                .into_iter()
+                .map(|i| self.targets[i].channel)
+                .collect(),
+        }
+    }
+
+    /// 作成・移動するものがあるか、既存のものの並びがファイルと異なるか
+    fn needs_reorder(&self, group: Group) -> bool {
+        let members = self.members(group);
+        let parent = match group {
+            Group::Categories => Some(None),
+            Group::Channels(parent) => self.parent_id(parent),
+        };
+        let Some(parent) = parent else {
+            return !members.is_empty();
+        };
+        if members
+            .iter()
+            .any(|m| m.is_none_or(|c| c.parent_id != parent))
+        {
+            return true;
+        }
+        let mut current: Vec<_> = members.iter().flatten().collect();
+        current.sort_by_key(|c| (is_voice(c.kind), c.position, c.id));
+        current
+            .iter()
+            .map(|c| c.id)
+            .ne(members.iter().flatten().map(|c| c.id))
+    }
+
+    /// グループの名前
+    fn group_name(&self, group: Group) -> &str {
+        match group {
+            Group::Categories => "(categories)",
+            Group::Channels(None) => "(no category)",
+            Group::Channels(Some(i)) => &self.layout.categories[i].name,
+        }
+    }
+
+    /// 計画の各行 (操作の種類と内容)
+    fn lines(&self, prune: bool) -> Vec<(&'static str, String)> {
+        let mut lines = Vec::new();
+        for (spec, category) in self.layout.categories.iter().zip(&self.categories) {
+            match category {
+                None => lines.push(("create", format!("📁 {}", spec.name))),
+                Some(c) if c.name != spec.name => lines.push((
+                    "update",
+                    format!("📁 {} -> {} ({})", c.name, spec.name, c.id),
+                )),
+                Some(_) => {}
+            }
+        }
+        for target in &self.targets {
+            let emoji = kind_emoji(target.kind());
+            let parent = target.parent.map(|i| &self.layout.categories[i].name);
+            let Some(channel) = target.channel else {
+                let mut line = format!("{emoji} {}", target.spec.name);
+                if let Some(parent) = parent {
+                    line.push_str(&format!(" in {parent}"));
+                }
+                if let Some(topic) = &target.spec.topic {
+                    line.push_str(&format!(", topic {topic:?}"));
+                }
+                lines.push(("create", line));
+                continue;
+            };
+            let (rename, moved, topic) = self.changes(target);
+            if !(rename || moved || topic) {
+                continue;
+            }
+            let mut line = format!("{emoji} {}", channel.name);
+            if rename {
+                line.push_str(&format!(" -> {}", target.spec.name));
+            }
+            match (moved, parent) {
+                (true, Some(parent)) => line.push_str(&format!(", move into {parent}")),
+                (true, None) => line.push_str(", move out of its category"),
+                (false, _) => {}
+            }
+            if let (true, Some(topic)) = (topic, &target.spec.topic) {
+                line.push_str(&format!(", topic {topic:?}"));
+            }
+            line.push_str(&format!(" ({})", channel.id));
+            lines.push(("update", line));
+        }
+        for &group in &self.reorder {
+            let names: Vec<_> = match group {
+                Group::Categories => self
+                    .layout
+                    .categories
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect(),
+                Group::Channels(parent) => self
+                    .ordered(parent)
+                    .into_iter()
+                    .map(|i| self.targets[i].spec.name.as_str())
+                    .collect(),
+            };
+            lines.push((
+                "reorder",
+                format!("{}: {}", self.group_name(group), names.join(", ")),
+            ));
+        }
+        for channel in &self.extras {
+            let line = format!(
+                "{} {} ({})",
+                kind_emoji(channel.kind),
+                channel.name,
+                channel.id
+            );
+            if prune {
+                lines.push(("delete", line));
+            } else {
+                lines.push((
+                    "extra",
+                    format!("{line}, not in the file (kept without --prune)"),
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// ファイルの誤り
+fn invalid(message: String) -> Error {
+    Error::InvalidEditResult(message.into())
+}
+
+/// 望ましい構成を記述したTOMLファイルにGuildを揃える。
+/// 足りないカテゴリ・チャンネルを作成し、名前・親カテゴリ・トピック・並び順の異なるものを変更する。
+/// ファイルにないものは、prune の場合のみ削除する
+pub async fn sync(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    text: &str,
+    prune: bool,
+    yes: bool,
+    dry_run: bool,
+    color: bool,
+) -> Result<()> {
+    let layout: Layout = toml::from_str(text).map_err(|e| match e.span() {
+        Some(span) => invalid(format!(
+            "line {}: {}",
+            text[..span.start].lines().count().max(1),
+            e.message()
+        )),
+        None => invalid(e.message().to_string()),
+    })?;
+    let channels = discord.guild_channels(guild_id, |_| {}).await?;
+    let plan = Plan::new(&layout, &channels)?;
+
+    let lines = plan.lines(prune);
+    for (kind, line) in &lines {
+        let mut styled = console::style(format!("{kind:<7}"));
+        if color {
+            styled = match *kind {
+                "create" => styled.green(),
+                "delete" => styled.red(),
+                "extra" => styled.dim(),
+                _ => styled.yellow(),
+            };
+        }
+        println!("{styled} {line}");
+    }
+    let changes = lines.iter().filter(|(kind, _)| *kind != "extra").count();
+    if changes == 0 {
+        info!("The server matches the file");
+        return Ok(());
+    }
+    if dry_run {
+        return Err(Error::PendingChanges(changes));
+    }
+    if !yes
+        && !Confirm::new()
+            .with_prompt(tr!("prompt-apply-count", count = changes))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    // カテゴリを先に作成・変更し、チャンネルの親として使えるようにする
+    let mut category_ids = Vec::new();
+    for (spec, category) in layout.categories.iter().zip(&plan.categories) {
+        let id = match category {
+            Some(c) if c.name == spec.name => c.id,
+            Some(c) => {
+                info!("Renaming 📁 {} -> {}", c.name, spec.name);
+                let edit = EditChannel::new().name(&spec.name);
+                discord.edit_channel(c.id, &edit).await?.id
+            }
+            None => {
+                info!("Creating 📁 {}", spec.name);
+                let create = CreateChannel::new(&spec.name).kind(ChannelType::Category);
+                discord.create_channel(guild_id, &create).await?.id
+            }
+        };
+        category_ids.push(id);
+    }
+    let mut target_ids = Vec::new();
+    for target in &plan.targets {
+        let parent = target.parent.map(|i| category_ids[i]);
+        let Some(channel) = target.channel else {
+            info!(
+                "Creating {} {}",
+                kind_emoji(target.kind()),
+                target.spec.name
+            );
+            let mut create = CreateChannel::new(&target.spec.name).kind(target.kind());
+            if let Some(parent) = parent {
+                create = create.category(parent);
+            }
+            if let Some(topic) = &target.spec.topic {
+                create = create.topic(topic);
+            }
+            target_ids.push(discord.create_channel(guild_id, &create).await?.id);
+            continue;
+        };
+        let (rename, moved, topic) = plan.changes(target);
+        if rename || moved || topic {
+            info!("Updating {} {}", kind_emoji(channel.kind), channel.name);
+            let mut edit = EditChannel::new();
+            if rename {
+                edit = edit.name(&target.spec.name);
+            }
+            if moved {
+                edit = edit.category(parent);
+            }
+            if let (true, Some(topic)) = (topic, &target.spec.topic) {
+                edit = edit.topic(topic);
+            }
+            discord.edit_channel(channel.id, &edit).await?;
+        }
+        target_ids.push(channel.id);
+    }
+
+    // 並び順の変わるものに、0 から順に position を振り直してまとめて送る
+    let mut positions = Vec::new();
+    for &group in &plan.reorder {
+        let ids: Vec<_> = match group {
+            Group::Categories => category_ids.clone(),
+            Group::Channels(parent) => plan
+                .ordered(parent)
+                .into_iter()
+                .map(|i| target_ids[i])
+                .collect(),
+        };
+        positions.extend(ids.into_iter().zip(0..));
+    }
+    if !positions.is_empty() {
+        info!("Reordering {} channel(s)", positions.len());
+        discord.edit_channel_positions(guild_id, &positions).await?;
+    }
+
+    // カテゴリは、属するチャンネルを移動・削除した後に削除する
+    if prune {
+        let mut extras = plan.extras.clone();
+        extras.sort_by_key(|c| c.kind == ChannelType::Category);
+        for channel in extras {
+            info!(
+                "Deleting {} {} ({})",
+                kind_emoji(channel.kind),
+                channel.name,
+                channel.id
+            );
+            discord.delete_channel(channel.id).await?;
+        }
+    }
+    info!("Applied {changes} change(s)");
+    Ok(())
+}
+
+/// Guildのカテゴリ・チャンネルの構成を、IDを含まない [`sync`] の形式のTOMLとして書き出す。
+/// Discordでの表示順に並べ、トピックも含める
+pub async fn export_template(discord: &Arc<Discord>, guild_id: GuildId) -> Result<String> {
+    let mut channels = discord.guild_channels(guild_id, |_| {}).await?;
+    channels.sort_by_key(|c| (is_voice(c.kind), c.position, c.id));
+    let specs = |parent: Option<ChannelId>| {
+        channels
+            .iter()
+            .filter(|c| c.parent_id == parent)
+            .filter_map(|c| {
+                Some(ChannelSpec {
+                    name: c.name.clone(),
+                    kind: Kind::from_channel_type(c.kind)?,
+                    topic: c.topic.clone().filter(|topic| !topic.is_empty()),
+                    id: None,
+                })
+            })
+            .collect()
+    };
+    let mut categories: Vec<_> = channels
+        .iter()
+        .filter(|c| c.kind == ChannelType::Category)
+        .collect();
+    categories.sort_by_key(|c| (c.position, c.id));
+    let layout = Layout {
+        channels: specs(None),
+        categories: categories
+            .iter()
+            .map(|c| CategorySpec {
+                name: c.name.clone(),
+                id: None,
+                channels: specs(Some(c.id)),
+            })
+            .collect(),
+    };
+    toml::to_string_pretty(&layout).map_err(|e| Error::InvalidEditResult(e.to_string().into()))
+}
@@ -0,0 +1,139 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use futures::{stream, try_join, StreamExt};
+use serenity::all::{ChannelType, EditThread, GuildChannel, GuildId};
+use std::{cell::Cell, collections::HashMap, fmt::Display, sync::Arc};
+use tracing::warn;
+
+/// アーカイブされたスレッドを同時に取得するチャンネルの数
+const CONCURRENT_FETCHES: usize = 8;
+
+/// スレッド
+pub struct ThreadItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// スレッド
+    thread: GuildChannel,
+    /// 親チャンネルの名前
+    parent: String,
+}
+
+impl ThreadItem {
+    /// アーカイブされているか
+    fn archived(&self) -> bool {
+        self.thread
+            .thread_metadata
+            .is_some_and(|metadata| metadata.archived)
+    }
+}
+
+impl Display for ThreadItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.thread.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for ThreadItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let archived = self.archived();
+        let mut edit = EditThread::new().name(content);
+        if archived {
+            // アーカイブされたスレッドは、同じリクエストでアーカイブを解除しないと編集できない
+            edit = edit.archived(false);
+        }
+        self.thread = self.discord.edit_thread(self.thread.id, &edit).await?;
+        if archived {
+            self.thread = self
+                .discord
+                .edit_thread(self.thread.id, &EditThread::new().archived(true))
+                .await?;
+        }
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.thread.name.clone()
+    }
+    fn comment(&self) -> String {
+        let kind = if self.thread.kind == ChannelType::PrivateThread {
+            "🔒"
+        } else {
+            "🧵"
+        };
+        let archived = if self.archived() { " archived" } else { "" };
+        format!("{kind}{archived} in #{} ({})", self.parent, self.thread.id)
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if !(1..=100).contains(&new.chars().count()) {
+            return Err(Error::InvalidEditResult(
+                format!("thread name must be between 1 and 100 characters: {new:?}").into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのアクティブなスレッドを取得する。
+/// `archived` の場合は、スレッドを持てる各チャンネルのアーカイブされたスレッドも取得する。
+/// 非公開スレッドは権限がなければ取得できないため、その場合は公開スレッドのみとする
+pub async fn fetch_items(
+    discord: &Arc<Discord>,
+    guild_id: GuildId,
+    archived: bool,
+) -> Result<Vec<ThreadItem>> {
+    let (channels, mut threads) = try_join!(
+        discord.guild_channels(guild_id, |_| {}),
+        discord.guild_active_threads(guild_id),
+    )?;
+    if archived {
+        // 権限がないと分かった後は、非公開スレッドを取得しない
+        let private_forbidden = Cell::new(false);
+        let mut fetched = stream::iter(channels.iter().filter(|channel| {
+            matches!(
+                channel.kind,
+                ChannelType::Text | ChannelType::News | ChannelType::Forum
+            )
+        }))
+        .map(|channel| {
+            let private_forbidden = &private_forbidden;
+            async move {
+                let mut threads = discord.archived_threads(channel.id, false).await?;
+                // 非公開スレッドはテキストチャンネルにのみ作られる
+                if channel.kind != ChannelType::Text || private_forbidden.get() {
+                    return Ok(threads);
+                }
+                match discord.archived_threads(channel.id, true).await {
+                    Ok(private) => threads.extend(private),
+                    Err(e) if e.is_forbidden() => {
+                        if !private_forbidden.replace(true) {
+                            warn!("Skipping archived private threads: missing permission");
+                        }
+                    }
+                    Err(e) => return Err(e),
+                }
+                Ok(threads)
+            }
+        })
+        .buffered(CONCURRENT_FETCHES);
+        while let Some(archived) = fetched.next().await {
+            threads.extend(archived?);
+        }
+    }
+    let names: HashMap<_, _> = channels.into_iter().map(|c| (c.id, c.name)).collect();
+    Ok(threads
+        .into_iter()
+        .map(|thread| ThreadItem {
+            discord: discord.clone(),
+            parent: thread
+                .parent_id
+                .and_then(|id| names.get(&id).cloned())
+                .unwrap_or_default(),
+            thread,
+        })
+        .collect())
+}
@@ -0,0 +1,334 @@
+use crate::{
+    args::{NameCase, TransformArgs},
+    error::{Error, Result},
+};
+use edisch_core::{Editor, TextEditableItem};
+use regex::{Regex, RegexBuilder};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// エディタを使わずに各アイテムのテキストを書き換える変換
+pub enum Transform {
+    /// 正規表現による置換 (`s/REGEX/REPLACEMENT/FLAGS`)
+    Replace {
+        regex: Regex,
+        /// `regex` クレートの記法に直した置換文字列
+        replacement: String,
+        /// 全ての一致を置換するか (g フラグ)
+        all: bool,
+    },
+    /// 接頭辞があれば取り除く
+    StripPrefix(String),
+    /// 大文字小文字と単語の区切りを変える
+    Case(NameCase),
+    /// 接頭辞がなければ付ける
+    Prefix(String),
+    /// 接尾辞がなければ付ける
+    Suffix(String),
+    /// 先頭の絵文字を置き換える (絵文字がない場合は付ける)
+    SetEmoji { emoji: String, separator: String },
+    /// 先頭に絵文字がなければ付ける
+    AddEmoji { emoji: String, separator: String },
+    /// 先頭の絵文字を取り除く
+    StripEmoji,
+}
+
+impl Transform {
+    /// sed の `s/REGEX/REPLACEMENT/FLAGS` 形式の置換をパースする。
+    /// 区切り文字は `s` の直後の文字で、`\` でエスケープできる。
+    /// 置換文字列では `\1`〜`\9` がグループ、`&` が一致全体を表す。フラグは g (全て置換) と i (大文字小文字を無視)
+    pub fn parse_replace(expr: &str) -> std::result::Result<Self, String> {
+        let mut chars = expr.chars();
+        if chars.next() != Some('s') {
+            return Err("expected s/REGEX/REPLACEMENT/FLAGS".to_string());
+        }
+        let delimiter = chars
+            .next()
+            .filter(|c| !c.is_alphanumeric() && *c != '\\')
+            .ok_or_else(|| "expected a delimiter after s".to_string())?;
+        // 区切り文字で分割する。区切り文字のエスケープのみ解除し、他のエスケープはそのまま残す
+        let mut parts = vec![String::new()];
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(c) if c == delimiter => parts.last_mut().unwrap().push(c),
+                    Some(c) => {
+                        parts.last_mut().unwrap().push('\\');
+                        parts.last_mut().unwrap().push(c);
+                    }
+                    None => return Err("trailing backslash".to_string()),
+                },
+                c if c == delimiter => parts.push(String::new()),
+                c => parts.last_mut().unwrap().push(c),
+            }
+        }
+        let [pattern, replacement, flags] = <[String; 3]>::try_from(parts)
+            .map_err(|_| "expected s/REGEX/REPLACEMENT/FLAGS".to_string())?;
+        let (mut all, mut ignore_case) = (false, false);
+        for flag in flags.chars() {
+            match flag {
+                'g' => all = true,
+                'i' => ignore_case = true,
+                _ => return Err(format!("unknown flag {flag:?}")),
+            }
+        }
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Transform::Replace {
+            regex,
+            replacement: sed_replacement(&replacement),
+            all,
+        })
+    }
+
+    /// テキストを変換する
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Transform::Replace {
+                regex,
+                replacement,
+                all: true,
+            } => regex.replace_all(text, replacement.as_str()).into_owned(),
+            Transform::Replace {
+                regex,
+                replacement,
+                all: false,
+            } => regex.replace(text, replacement.as_str()).into_owned(),
+            Transform::StripPrefix(prefix) => text
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(text)
+                .to_string(),
+            Transform::Case(NameCase::Lower) => text.to_lowercase(),
+            Transform::Case(NameCase::Kebab) => words(text).join("-").to_lowercase(),
+            Transform::Case(NameCase::Title) => words(text)
+                .iter()
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map_or(String::new(), |first| {
+                        first
+                            .to_uppercase()
+                            .chain(chars.flat_map(char::to_lowercase))
+                            .collect()
+                    })
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            Transform::Prefix(prefix) if text.starts_with(prefix.as_str()) => text.to_string(),
+            Transform::Prefix(prefix) => format!("{prefix}{text}"),
+            Transform::Suffix(suffix) if text.ends_with(suffix.as_str()) => text.to_string(),
+            Transform::Suffix(suffix) => format!("{text}{suffix}"),
+            Transform::SetEmoji { emoji, separator } => {
+                format!("{emoji}{separator}{}", strip_emoji(text))
+            }
+            Transform::AddEmoji { .. } if strip_emoji(text).len() != text.len() => text.to_string(),
+            Transform::AddEmoji { emoji, separator } => format!("{emoji}{separator}{text}"),
+            Transform::StripEmoji => strip_emoji(text).to_string(),
+        }
+    }
+}
+
+/// 絵文字の後ろに置かれることの多い区切り文字
+const EMOJI_SEPARATORS: &[char] = &['-', '_', '|', '｜', '┃', '│', '・', '･', '︱'];
+
+/// 書記素クラスタ (国旗や肌の色などの複数のコードポイントからなる絵文字も一つの単位になる) が絵文字か。
+/// 絵文字の表を持たないため、絵文字の主なブロックの文字、異体字セレクタ16、キーキャップを含むかで判定する
+fn is_emoji(grapheme: &str) -> bool {
+    grapheme.chars().any(|c| {
+        matches!(c,
+            '\u{1F000}'..='\u{1FAFF}'
+            | '\u{2300}'..='\u{23FF}'
+            | '\u{2600}'..='\u{27BF}'
+            | '\u{2B00}'..='\u{2BFF}'
+            | '\u{FE0F}'
+            | '\u{20E3}')
+    })
+}
+
+/// 先頭の絵文字 (複数でもよい) と、その後ろの区切り文字・空白を取り除く
+fn strip_emoji(text: &str) -> &str {
+    let emoji_len: usize = text
+        .graphemes(true)
+        .take_while(|grapheme| is_emoji(grapheme))
+        .map(str::len)
+        .sum();
+    if emoji_len == 0 {
+        return text;
+    }
+    text[emoji_len..]
+        .trim_start_matches(|c: char| c.is_whitespace() || EMOJI_SEPARATORS.contains(&c))
+}
+
+/// テキストを単語に分ける。空白・`-`・`_`・`.` と、小文字から大文字に変わる位置 (camelCase) で区切る
+fn words(text: &str) -> Vec<String> {
+    let mut words = vec![String::new()];
+    let mut lower = false;
+    for c in text.chars() {
+        if c.is_whitespace() || matches!(c, '-' | '_' | '.') {
+            words.push(String::new());
+            lower = false;
+            continue;
+        }
+        if lower && c.is_uppercase() {
+            words.push(String::new());
+        }
+        lower = c.is_lowercase();
+        words.last_mut().unwrap().push(c);
+    }
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+/// sed の置換文字列を `regex` クレートの記法に直す
+fn sed_replacement(replacement: &str) -> String {
+    let mut result = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(n @ '0'..='9') => result.push_str(&format!("${{{n}}}")),
+                Some(c) => result.push(c),
+                None => {}
+            },
+            '&' => result.push_str("${0}"),
+            '$' => result.push_str("$$"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// 引数で指定された変換を、適用する順に並べる。
+/// --replace (指定された順)、--strip-prefix、--case、--prefix、--suffix、--emoji-prefix の順に適用する
+pub fn transforms(args: &TransformArgs) -> Result<Vec<Transform>> {
+    let mut transforms = args
+        .replace
+        .iter()
+        .map(|expr| {
+            Transform::parse_replace(expr).map_err(|e| {
+                Error::ParseArgument(format!("invalid --replace {expr:?}: {e}").into())
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    transforms.extend(args.strip_prefix.clone().map(Transform::StripPrefix));
+    transforms.extend(args.case.map(Transform::Case));
+    transforms.extend(args.prefix.clone().map(Transform::Prefix));
+    transforms.extend(args.suffix.clone().map(Transform::Suffix));
+    let separator = args.emoji_separator.clone();
+    match args.emoji_prefix.as_slice() {
+        [] => {}
+        [action] if action == "strip" => transforms.push(Transform::StripEmoji),
+        [action, emoji] if action == "set" || action == "add" => {
+            let mut graphemes = emoji.graphemes(true);
+            if !graphemes.next().is_some_and(is_emoji) || graphemes.next().is_some() {
+                return Err(Error::ParseArgument(
+                    format!("--emoji-prefix expects a single emoji, got {emoji:?}").into(),
+                ));
+            }
+            let emoji = emoji.clone();
+            transforms.push(match action.as_str() {
+                "set" => Transform::SetEmoji { emoji, separator },
+                _ => Transform::AddEmoji { emoji, separator },
+            });
+        }
+        _ => {
+            return Err(Error::ParseArgument(
+                "--emoji-prefix expects `set EMOJI`, `add EMOJI` or `strip`".into(),
+            ))
+        }
+    }
+    Ok(transforms)
+}
+
+/// 全てのアイテムのテキストに変換を順に適用し、編集結果として設定する
+pub fn transform_items<T: TextEditableItem>(
+    editor: &mut Editor<T>,
+    transforms: &[Transform],
+) -> Result<()> {
+    if editor.items().iter().any(|item| item.columns() != 1) {
+        return Err(Error::UnsupportedOperation(
+            "transforms are not supported for fields with several columns".into(),
+        ));
+    }
+    for i in 0..editor.items().len() {
+        let content = editor.items()[i].content();
+        let new = transforms
+            .iter()
+            .fold(content.clone(), |text, transform| transform.apply(&text));
+        if new != content {
+            editor.set_content(i, &new);
+        }
+    }
+    Ok(())
+}
+
+/// シェルでコマンドを実行する
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// 全てのアイテムのテキストを1行ずつコマンドの標準入力に渡し、標準出力の各行を編集結果として設定する。
+/// コマンドが失敗した場合や、出力の行数が入力と異なる場合はエラーにする
+pub fn pipe_items<T: TextEditableItem>(editor: &mut Editor<T>, command: &str) -> Result<()> {
+    if editor
+        .items()
+        .iter()
+        .any(|item| item.multiline() || item.columns() != 1)
+    {
+        return Err(Error::UnsupportedOperation(
+            "--pipe only supports single-line fields with one column".into(),
+        ));
+    }
+    let input: String = editor
+        .items()
+        .iter()
+        .map(|item| item.content() + "\n")
+        .collect();
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    // 出力を読みながら書き込まないと、パイプが詰まって止まることがある
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    // 入力を読まずに終了するコマンドもあるため、書き込みのエラーは無視する
+    writer.join().ok();
+    if !output.status.success() {
+        return Err(Error::InvalidEditResult(
+            format!("`{command}` failed ({})", output.status).into(),
+        ));
+    }
+    let output = String::from_utf8(output.stdout).map_err(|_| {
+        Error::InvalidEditResult(format!("`{command}` printed invalid UTF-8").into())
+    })?;
+    let lines: Vec<_> = output.lines().collect();
+    if lines.len() != editor.items().len() {
+        return Err(Error::InvalidEditResult(
+            format!(
+                "`{command}` printed {} line(s) for {} name(s)",
+                lines.len(),
+                editor.items().len()
+            )
+            .into(),
+        ));
+    }
+    for (i, line) in lines.into_iter().enumerate() {
+        if line != editor.items()[i].content() {
+            editor.set_content(i, line);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,330 @@
+use crate::error::{Error, Result};
+use console::{measure_text_width, pad_str, style, Alignment, Key, Term};
+use edisch_core::{Edited, Editor, Op, TextEditableItem};
+
+/// 代替スクリーンに切り替え、破棄される際 (エラーによる中断時も含む) に元の画面に戻す
+struct Screen(Term);
+
+impl Screen {
+    fn enter(term: Term) -> Result<Self> {
+        term.write_str("\x1b[?1049h")?;
+        term.hide_cursor()?;
+        Ok(Self(term))
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        self.0.show_cursor().ok();
+        self.0.write_str("\x1b[?1049l").ok();
+    }
+}
+
+/// 画面の状態
+struct State<'a, T> {
+    editor: &'a mut Editor<T>,
+    /// 各アイテムの変更後のテキスト
+    values: Vec<String>,
+    /// 各アイテムの検証エラー
+    errors: Vec<Option<String>>,
+    cursor: usize,
+    /// 一覧の表示を始める位置
+    offset: usize,
+    /// 名前を入力中のテキスト
+    input: Option<String>,
+    /// 最下部の直前の行に表示するメッセージ
+    message: String,
+    color: bool,
+}
+
+impl<T: TextEditableItem<Error = Error>> State<'_, T> {
+    /// 変更後のテキストをバッファとして設定し、全体を検証し直す
+    fn sync(&mut self) -> Result<()> {
+        let text: Vec<_> = self
+            .editor
+            .items()
+            .iter()
+            .zip(&self.values)
+            .map(|(item, value)| match item.comment() {
+                comment if comment.is_empty() => value.clone(),
+                comment => format!("{value}\t{comment}"),
+            })
+            .collect();
+        self.editor.set_text(text.join("\n"))?;
+        self.errors = vec![None; self.values.len()];
+        for (i, e) in self.editor.violations() {
+            self.errors[i] = Some(e.to_string());
+        }
+        Ok(())
+    }
+
+    /// 変更されたアイテムの番号
+    fn changed(&self) -> Vec<usize> {
+        let items = self.editor.items();
+        (0..items.len())
+            .filter(|&i| items[i].content() != self.values[i])
+            .collect()
+    }
+
+    /// 一覧の各行。セクション ([`TextEditableItem::section`]) の2番目以降のアイテムは字下げして木のように表示する
+    fn rows(&self) -> Vec<String> {
+        let items = self.editor.items();
+        let sections: Vec<_> = items.iter().map(TextEditableItem::section).collect();
+        (0..items.len())
+            .map(|i| {
+                let head = sections[i].is_none() || i == 0 || sections[i - 1] != sections[i];
+                let branch = match (head, sections.get(i + 1) == Some(&sections[i])) {
+                    (true, _) => "",
+                    (false, true) => "├─ ",
+                    (false, false) => "└─ ",
+                };
+                let mut value = style(self.values[i].clone());
+                let mut comment = style(items[i].comment());
+                if self.color {
+                    comment = comment.dim();
+                    if self.errors[i].is_some() {
+                        value = value.red();
+                    } else if items[i].content() != self.values[i] {
+                        value = value.green();
+                    }
+                }
+                let mark = if self.errors[i].is_some() {
+                    "!"
+                } else if items[i].content() != self.values[i] {
+                    "*"
+                } else {
+                    " "
+                };
+                let cursor = if i == self.cursor { ">" } else { " " };
+                format!("{cursor}{mark} {branch}{value}  {comment}")
+            })
+            .collect()
+    }
+
+    /// 変更の一覧
+    fn diff_rows(&self) -> Vec<String> {
+        let items = self.editor.items();
+        let mut rows = vec![format!("{} change(s)", self.changed().len())];
+        for i in self.changed() {
+            let mut old = style(items[i].content());
+            let mut new = style(self.values[i].clone());
+            if self.color {
+                old = old.red();
+                new = if self.errors[i].is_some() {
+                    new.red().bold()
+                } else {
+                    new.green()
+                };
+            }
+            rows.push(format!("{old} → {new}"));
+        }
+        rows
+    }
+
+    fn draw(&mut self, term: &Term) -> Result<()> {
+        let (height, width) = term.size();
+        let (height, width) = (height as usize, width as usize);
+        // 一覧に使える行数。下の2行はメッセージと操作の説明
+        let list_height = height.saturating_sub(3).max(1);
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + list_height {
+            self.offset = self.cursor + 1 - list_height;
+        }
+        // 十分な幅があれば、右側に変更の一覧を表示する
+        let (list_width, diff_width) = if width >= 80 {
+            (width * 3 / 5, width - width * 3 / 5 - 3)
+        } else {
+            (width, 0)
+        };
+        let rows = self.rows();
+        let diffs = self.diff_rows();
+        let mut title = style("edisch tui");
+        if self.color {
+            title = title.bold();
+        }
+        let mut screen = vec![title.to_string()];
+        for n in 0..list_height {
+            let row = rows.get(self.offset + n).map_or("", String::as_str);
+            let row = pad_str(row, list_width, Alignment::Left, Some("…"));
+            if diff_width == 0 {
+                screen.push(row.into_owned());
+                continue;
+            }
+            let diff = diffs.get(n).map_or("", String::as_str);
+            let diff = pad_str(diff, diff_width, Alignment::Left, Some("…"));
+            screen.push(format!("{row} │ {diff}"));
+        }
+        // 入力中のテキストは、その場で検証した結果とともに表示する
+        let status = match &self.input {
+            Some(input) => {
+                let error = self.editor.items()[self.cursor]
+                    .validate(input)
+                    .err()
+                    .map(|e| format!("  {e}"))
+                    .unwrap_or_default();
+                let mut error = style(error);
+                if self.color {
+                    error = error.red();
+                }
+                format!("Rename: {input}▏{error}")
+            }
+            None if !self.message.is_empty() => self.message.clone(),
+            None => match &self.errors[self.cursor] {
+                Some(e) => {
+                    let mut e = style(e.clone());
+                    if self.color {
+                        e = e.red();
+                    }
+                    e.to_string()
+                }
+                None => String::new(),
+            },
+        };
+        screen.push(status);
+        let mut help = style(match self.input {
+            Some(_) => "enter: done  esc: cancel",
+            None => "↑↓: move  enter/r: rename  u: revert  a: apply  q: quit",
+        });
+        if self.color {
+            help = help.dim();
+        }
+        screen.push(help.to_string());
+
+        let mut out = String::from("\x1b[H");
+        for (n, line) in screen.iter().enumerate() {
+            if n > 0 {
+                out.push_str("\r\n");
+            }
+            if measure_text_width(line) > width {
+                out.push_str(&console::truncate_str(line, width, "…"));
+            } else {
+                out.push_str(line);
+            }
+            out.push_str("\x1b[K");
+        }
+        out.push_str("\x1b[J");
+        term.write_str(&out)?;
+        Ok(())
+    }
+
+    /// 最下部の直前の行で y/N を尋ねる
+    fn ask(&mut self, term: &Term, question: String) -> Result<bool> {
+        self.message = format!("{question} [y/N]");
+        self.draw(term)?;
+        let yes = matches!(term.read_key()?, Key::Char('y' | 'Y'));
+        self.message.clear();
+        Ok(yes)
+    }
+}
+
+/// 全画面でアイテムの一覧を表示し、名前をその場で編集させる。
+/// 編集の内容は確定するたびにバッファとして設定され、各行の検証結果が表示される。
+/// 適用すると答えた場合は編集結果をバッファに設定して [`Edited::Changed`] を、
+/// 変更せずに終了した場合は [`Edited::Unchanged`] を、変更を破棄した場合は [`Edited::Aborted`] を返す
+pub fn run<T: TextEditableItem<Error = Error>>(
+    editor: &mut Editor<T>,
+    color: bool,
+) -> Result<Edited> {
+    let term = Term::stderr();
+    if !term.is_term() {
+        return Err(Error::UnsupportedOperation(
+            "the tui needs a terminal".into(),
+        ));
+    }
+    if editor
+        .items()
+        .iter()
+        .any(|item| item.multiline() || item.columns() != 1)
+    {
+        return Err(Error::UnsupportedOperation(
+            "the tui only supports single-line fields with one column".into(),
+        ));
+    }
+    // --fix などで既に書き換えられた名前も編集中の値として表示する
+    let mut values: Vec<_> = editor
+        .items()
+        .iter()
+        .map(TextEditableItem::content)
+        .collect();
+    for diff in editor.diffs()? {
+        if diff.op == Op::Rename {
+            values[diff.index] = diff.new;
+        }
+    }
+    let mut state = State {
+        errors: vec![None; values.len()],
+        values,
+        editor,
+        cursor: 0,
+        offset: 0,
+        input: None,
+        message: String::new(),
+        color,
+    };
+    state.sync()?;
+    let _screen = Screen::enter(term.clone())?;
+    loop {
+        state.draw(&term)?;
+        let key = term.read_key()?;
+        state.message.clear();
+        if let Some(input) = &mut state.input {
+            match key {
+                Key::Enter => {
+                    let input = state.input.take().unwrap_or_default();
+                    let old = std::mem::replace(&mut state.values[state.cursor], input);
+                    // バッファとして解釈できない名前 (コメント行と見なされるものなど) は受け付けない
+                    if let Err(e) = state.sync() {
+                        state.values[state.cursor] = old;
+                        state.sync()?;
+                        state.message = e.to_string();
+                    }
+                }
+                Key::Escape => state.input = None,
+                Key::Backspace => {
+                    input.pop();
+                }
+                Key::Char(c) if !c.is_control() => input.push(c),
+                _ => {}
+            }
+            continue;
+        }
+        let last = state.values.len() - 1;
+        let page = term.size().0.saturating_sub(3).max(1) as usize;
+        match key {
+            Key::ArrowUp | Key::Char('k') => state.cursor = state.cursor.saturating_sub(1),
+            Key::ArrowDown | Key::Char('j') => state.cursor = (state.cursor + 1).min(last),
+            Key::PageUp => state.cursor = state.cursor.saturating_sub(page),
+            Key::PageDown => state.cursor = (state.cursor + page).min(last),
+            Key::Home | Key::Char('g') => state.cursor = 0,
+            Key::End | Key::Char('G') => state.cursor = last,
+            Key::Enter | Key::Char('r') => {
+                state.input = Some(state.values[state.cursor].clone());
+            }
+            Key::Char('u') => {
+                state.values[state.cursor] = state.editor.items()[state.cursor].content();
+                state.sync()?;
+            }
+            Key::Char('a') => {
+                let changed = state.changed().len();
+                if changed == 0 {
+                    state.message = "No changes to apply".to_string();
+                } else if state.errors.iter().any(Option::is_some) {
+                    state.message = "Fix the invalid names (marked with !) first".to_string();
+                } else if state.ask(&term, format!("Apply {changed} change(s)?"))? {
+                    return Ok(Edited::Changed);
+                }
+            }
+            Key::Char('q') | Key::Escape | Key::CtrlC => {
+                let changed = state.changed().len();
+                if changed == 0 {
+                    return Ok(Edited::Unchanged);
+                }
+                if state.ask(&term, format!("Discard {changed} change(s)?"))? {
+                    return Ok(Edited::Aborted);
+                }
+            }
+            _ => {}
+        }
+    }
+}
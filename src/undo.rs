@@ -0,0 +1,66 @@
+use crate::format;
+use serenity::all::GuildId;
+use std::{collections::HashMap, env, fs, path::PathBuf};
+use tracing::{info, warn};
+
+/// 直前に適用した名前の変更を元に戻すためのファイルのパス。$EDISCH_UNDO_FILE で上書きできる
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("EDISCH_UNDO_FILE") {
+        return Some(path.into());
+    }
+    dirs::data_local_dir().map(|dir| dir.join("edisch").join("undo.txt"))
+}
+
+/// 適用した名前の変更を逆向きに記録し、`apply -i` で読み込める形式で書き出す。
+/// 破棄される際 (エラーによる中断時も含む) に、変更を1つ以上適用していればファイルへ書き出される
+pub struct UndoLog {
+    path: Option<PathBuf>,
+    /// チャンネルIDと所属するGuildの対応
+    guilds: HashMap<String, GuildId>,
+    /// 変更前の名前に戻す行
+    lines: Vec<String>,
+}
+
+impl UndoLog {
+    /// `guilds` はチャンネルID (アイテムの表示) と所属するGuildの対応
+    pub fn new(guilds: HashMap<String, GuildId>) -> Self {
+        Self {
+            path: path(),
+            guilds,
+            lines: Vec::new(),
+        }
+    }
+
+    /// 適用した名前の変更を記録する。
+    /// 元に戻す前に再び変更されていないか確かめられるよう、変更後の名前のハッシュも記録する
+    pub fn record(&mut self, id: &str, old: &str, new: &str, comment: &str) {
+        let guild = self
+            .guilds
+            .get(id)
+            .map(|guild_id| format!("\t{guild_id}"))
+            .unwrap_or_default();
+        self.lines.push(format!(
+            "{old}\t{comment}\tbase:{}{guild}",
+            format::fingerprint(new)
+        ));
+    }
+}
+
+impl Drop for UndoLog {
+    fn drop(&mut self) {
+        if self.lines.is_empty() {
+            return;
+        }
+        let Some(path) = self.path.take() else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, format!("{}\n", self.lines.join("\n"))));
+        match result {
+            Ok(()) => info!("Run `edisch undo` to revert these renames"),
+            Err(e) => warn!("Failed to write undo file {}: {e}", path.display()),
+        }
+    }
+}
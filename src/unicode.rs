@@ -0,0 +1,109 @@
+use crate::args::Normalization;
+use unicode_normalization::UnicodeNormalization;
+use unicode_script::{Script, UnicodeScript};
+
+/// 表示されない (または空白に見える) 文字と、その名前。
+/// 絵文字の組み合わせに使うゼロ幅接合子と異体字セレクタは [`invisible`] で別に扱う
+const INVISIBLE: [(char, &str); 16] = [
+    ('\u{00AD}', "SOFT HYPHEN"),
+    ('\u{034F}', "COMBINING GRAPHEME JOINER"),
+    ('\u{061C}', "ARABIC LETTER MARK"),
+    ('\u{115F}', "HANGUL CHOSEONG FILLER"),
+    ('\u{1160}', "HANGUL JUNGSEONG FILLER"),
+    ('\u{180E}', "MONGOLIAN VOWEL SEPARATOR"),
+    ('\u{200B}', "ZERO WIDTH SPACE"),
+    ('\u{200C}', "ZERO WIDTH NON-JOINER"),
+    ('\u{200E}', "LEFT-TO-RIGHT MARK"),
+    ('\u{200F}', "RIGHT-TO-LEFT MARK"),
+    ('\u{2060}', "WORD JOINER"),
+    ('\u{2061}', "FUNCTION APPLICATION"),
+    ('\u{3164}', "HANGUL FILLER"),
+    ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+    ('\u{FFA0}', "HALFWIDTH HANGUL FILLER"),
+    ('\u{2800}', "BRAILLE PATTERN BLANK"),
+];
+
+/// 互いに見分けにくい文字を含む文字体系
+const LOOKALIKE_SCRIPTS: [Script; 3] = [Script::Latin, Script::Cyrillic, Script::Greek];
+
+/// テキストを指定された形式に正規化する
+pub fn normalize(text: &str, form: Normalization) -> String {
+    match form {
+        Normalization::Nfc => text.nfc().collect(),
+        Normalization::Nfkc => text.nfkc().collect(),
+    }
+}
+
+/// コピー&ペーストで紛れ込みやすく、同じに見える別の名前の原因になる文字を探し、その説明を返す。
+/// 見えない文字と、1つの単語に混ざった別の文字体系の似た文字 (ラテン文字の中のキリル文字など) を報告する
+pub fn suspicious(text: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let invisible: Vec<_> = chars
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &c)| invisible(&chars, i).map(|name| format!("U+{:04X} {name}", c as u32)))
+        .collect();
+    if !invisible.is_empty() {
+        problems.push(format!(
+            "contains invisible characters: {}",
+            invisible.join(", ")
+        ));
+    }
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut scripts: Vec<(Script, Vec<char>)> = Vec::new();
+        for c in word.chars() {
+            let script = c.script();
+            if !LOOKALIKE_SCRIPTS.contains(&script) {
+                continue;
+            }
+            match scripts.iter_mut().find(|(s, _)| *s == script) {
+                Some((_, chars)) => chars.push(c),
+                None => scripts.push((script, vec![c])),
+            }
+        }
+        if scripts.len() < 2 {
+            continue;
+        }
+        // 少ない方の文字体系の文字を、紛れ込んだものとして示す
+        scripts.sort_by_key(|(_, chars)| std::cmp::Reverse(chars.len()));
+        let names: Vec<_> = scripts.iter().map(|(s, _)| s.full_name()).collect();
+        let odd: Vec<_> = scripts[1..]
+            .iter()
+            .flat_map(|(_, chars)| chars)
+            .map(|&c| format!("{c:?} U+{:04X}", c as u32))
+            .collect();
+        let place = if word == text {
+            String::new()
+        } else {
+            format!(" in {word:?}")
+        };
+        problems.push(format!(
+            "mixes {} letters{place}: {}",
+            names.join(" and "),
+            odd.join(", ")
+        ));
+    }
+    problems
+}
+
+/// `chars[i]` が見えない文字であれば、その名前を返す。
+/// ゼロ幅接合子と異体字セレクタは、絵文字の一部ではない (文字や数字に隣接する) 場合のみ見えない文字とみなす
+fn invisible(chars: &[char], i: usize) -> Option<&'static str> {
+    let c = chars[i];
+    let alphanumeric = |j: Option<usize>| {
+        j.and_then(|j| chars.get(j))
+            .is_some_and(|c| c.is_alphanumeric())
+    };
+    match c {
+        '\u{200D}' if alphanumeric(i.checked_sub(1)) || alphanumeric(Some(i + 1)) => {
+            Some("ZERO WIDTH JOINER")
+        }
+        '\u{FE00}'..='\u{FE0F}' if alphanumeric(i.checked_sub(1)) => Some("VARIATION SELECTOR"),
+        '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => Some("BIDIRECTIONAL CONTROL"),
+        _ => INVISIBLE
+            .iter()
+            .find(|(invisible, _)| *invisible == c)
+            .map(|(_, name)| *name),
+    }
+}
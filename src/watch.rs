@@ -0,0 +1,144 @@
+use crate::i18n::tr;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tracing::warn;
+
+/// 通知を使わない場合にファイルを調べる間隔
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// 書き込みが終わったとみなすまでに、変化がないことを確かめる時間
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// ファイルの更新日時と大きさ。ファイルがない場合 (エディタが置き換えている途中など) は None
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// ファイルの保存を待つ。ファイルシステムの通知を使い、使えない場合は定期的に調べる
+pub struct FileWatcher {
+    path: PathBuf,
+    /// 最後に知らせた時点の更新日時と大きさ
+    last: Option<(SystemTime, u64)>,
+    /// 通知の受け口。None なら定期的に調べる
+    events: Option<(RecommendedWatcher, UnboundedReceiver<()>)>,
+}
+
+impl FileWatcher {
+    /// `poll` の場合は通知を使わず、定期的に調べる
+    pub fn new(path: &Path, poll: bool) -> Self {
+        let events = if poll {
+            None
+        } else {
+            notifications(path)
+                .inspect_err(|e| {
+                    warn!(
+                        "{}",
+                        tr!(
+                            "watch-polling",
+                            path = path.display().to_string(),
+                            detail = e.to_string()
+                        )
+                    );
+                })
+                .ok()
+        };
+        Self {
+            path: path.to_path_buf(),
+            last: stamp(path),
+            events,
+        }
+    }
+
+    /// ファイルが保存されるまで待つ。
+    /// 書き込みの途中で読まないよう、変化が止まるまで待つ
+    pub async fn changed(&mut self) {
+        loop {
+            // 前回の適用中に保存された場合は、通知を待たずに読む
+            let current = stamp(&self.path);
+            if current.is_some() && current != self.last {
+                tokio::time::sleep(SETTLE).await;
+                if stamp(&self.path) == current {
+                    self.last = current;
+                    return;
+                }
+                continue;
+            }
+            match &mut self.events {
+                Some((_, rx)) => {
+                    if rx.recv().await.is_none() {
+                        // 通知が途絶えた場合は、定期的に調べる
+                        self.events = None;
+                    }
+                }
+                None => tokio::time::sleep(INTERVAL).await,
+            }
+        }
+    }
+}
+
+/// ファイルのあるディレクトリを監視し、そのファイルへの変更を知らせる。
+/// エディタは別のファイルに書いてから置き換えることが多いため、ファイルそのものは監視しない
+fn notifications(path: &Path) -> notify::Result<(RecommendedWatcher, UnboundedReceiver<()>)> {
+    let name = path.file_name().map(ToOwned::to_owned);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if event.kind.is_access() {
+            return;
+        }
+        if event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == name.as_deref())
+        {
+            tx.send(()).ok();
+        }
+    })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok((watcher, rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// エディタのように別のファイルに書いてから置き換えても、保存を検知する
+    async fn detects_a_save(poll: bool) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("channels.tsv");
+        fs::write(&path, "general\t(10)\n").unwrap();
+        let mut watcher = FileWatcher::new(&path, poll);
+        let saved = path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let temp = saved.with_extension("tmp");
+            fs::write(&temp, "general-chat\t(10)\n").unwrap();
+            fs::rename(temp, saved).unwrap();
+        });
+        tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+            .await
+            .expect("the save was not detected");
+    }
+
+    #[tokio::test]
+    async fn notifications_detect_a_save() {
+        detects_a_save(false).await;
+    }
+
+    #[tokio::test]
+    async fn polling_detects_a_save() {
+        detects_a_save(true).await;
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use futures::try_join;
+use serenity::all::{EditWebhook, GuildId, Webhook, WebhookType};
+use std::{collections::HashMap, fmt::Display, sync::Arc};
+
+/// Webhook名に含められない語 (大文字小文字を区別しない)
+const RESERVED_WORDS: [&str; 2] = ["clyde", "discord"];
+
+/// GuildのWebhook
+pub struct WebhookItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Webhook
+    webhook: Webhook,
+    /// 投稿先のチャンネルの名前
+    channel: String,
+}
+
+impl Display for WebhookItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.webhook.id)
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for WebhookItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        self.webhook = self
+            .discord
+            .edit_webhook(self.webhook.id, &EditWebhook::new().name(content))
+            .await?;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.webhook.name.clone().unwrap_or_default()
+    }
+    fn comment(&self) -> String {
+        format!("in #{} ({})", self.channel, self.webhook.id)
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        if !(1..=80).contains(&new.chars().count()) {
+            return Err(Error::InvalidEditResult(
+                format!("webhook name must be between 1 and 80 characters: {new:?}").into(),
+            ));
+        }
+        let lower = new.to_lowercase();
+        if let Some(word) = RESERVED_WORDS.iter().find(|word| lower.contains(*word)) {
+            return Err(Error::InvalidEditResult(
+                format!("webhook name must not contain {word:?}: {new:?}").into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのWebhookを取得する。
+/// アプリケーションのWebhookは編集できないため除く
+pub async fn fetch_items(discord: &Arc<Discord>, guild_id: GuildId) -> Result<Vec<WebhookItem>> {
+    let (channels, webhooks) = try_join!(
+        discord.guild_channels(guild_id, |_| {}),
+        discord.guild_webhooks(guild_id),
+    )?;
+    let names: HashMap<_, _> = channels
+        .into_iter()
+        .map(|channel| (channel.id, channel.name))
+        .collect();
+    Ok(webhooks
+        .into_iter()
+        .filter(|webhook| webhook.kind != WebhookType::Application)
+        .map(|webhook| WebhookItem {
+            discord: discord.clone(),
+            channel: webhook
+                .channel_id
+                .and_then(|id| names.get(&id).cloned())
+                .unwrap_or_default(),
+            webhook,
+        })
+        .collect())
+}
@@ -0,0 +1,209 @@
+use crate::{
+    discord::Discord,
+    error::{Error, Result},
+};
+use edisch_core::{async_trait, TextEditableItem};
+use serde_json::Value;
+use serenity::all::GuildId;
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+/// ウェルカム画面の編集できる項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WelcomeField {
+    /// サーバーの説明
+    Description,
+    /// おすすめチャンネルの説明
+    ChannelDescription(usize),
+    /// おすすめチャンネルの絵文字
+    ChannelEmoji(usize),
+}
+
+/// ウェルカム画面の項目の1つ
+pub struct WelcomeItem {
+    /// Discord APIクライアント
+    discord: Arc<Discord>,
+    /// Guild ID
+    guild_id: GuildId,
+    /// おすすめチャンネルの一覧。更新は一覧全体の置き換えになるため、全アイテムで共有する
+    channels: Arc<Mutex<Vec<Value>>>,
+    /// 項目
+    field: WelcomeField,
+    /// 現在の値
+    value: String,
+    /// おすすめチャンネルの項目の場合、そのチャンネル名
+    channel_name: String,
+}
+
+/// 絵文字の指定
+enum Emoji<'a> {
+    /// 絵文字なし
+    None,
+    /// Unicode絵文字
+    Unicode(&'a str),
+    /// カスタム絵文字 (名前, ID)
+    Custom(&'a str, &'a str),
+}
+
+/// `<:name:id>` 形式のカスタム絵文字またはUnicode絵文字を読み取る
+fn parse_emoji(text: &str) -> Result<Emoji<'_>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(Emoji::None);
+    }
+    if let Some(inner) = text
+        .strip_prefix("<a:")
+        .or_else(|| text.strip_prefix("<:"))
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        if let Some((name, id)) = inner.split_once(':') {
+            if !name.is_empty() && !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) {
+                return Ok(Emoji::Custom(name, id));
+            }
+        }
+    } else if !text.chars().any(|c| c.is_ascii() || c.is_whitespace()) {
+        return Ok(Emoji::Unicode(text));
+    }
+    Err(Error::InvalidEditResult(
+        format!("expected a unicode emoji or <:name:id>: {text:?}").into(),
+    ))
+}
+
+/// おすすめチャンネルの絵文字を表示用に整形する
+fn format_emoji(channel: &Value) -> String {
+    let name = channel["emoji_name"].as_str().unwrap_or_default();
+    match channel["emoji_id"].as_str() {
+        Some(id) => format!("<:{name}:{id}>"),
+        None => name.to_string(),
+    }
+}
+
+impl Display for WelcomeItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.field {
+            WelcomeField::Description => write!(f, "description"),
+            WelcomeField::ChannelDescription(i) => write!(f, "channel {}", i + 1),
+            WelcomeField::ChannelEmoji(i) => write!(f, "emoji {}", i + 1),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl TextEditableItem for WelcomeItem {
+    type Error = Error;
+
+    async fn apply(&mut self, content: String) -> Result<()> {
+        let edit = match self.field {
+            WelcomeField::Description => serde_json::json!({ "description": content }),
+            WelcomeField::ChannelDescription(i) | WelcomeField::ChannelEmoji(i) => {
+                let mut channels = self.channels.lock().unwrap().clone();
+                let channel = &mut channels[i];
+                if let WelcomeField::ChannelDescription(_) = self.field {
+                    channel["description"] = Value::String(content.clone());
+                } else {
+                    let (id, name) = match parse_emoji(&content)? {
+                        Emoji::None => (Value::Null, Value::Null),
+                        Emoji::Unicode(emoji) => (Value::Null, emoji.into()),
+                        Emoji::Custom(name, id) => (id.into(), name.into()),
+                    };
+                    channel["emoji_id"] = id;
+                    channel["emoji_name"] = name;
+                }
+                serde_json::json!({ "welcome_channels": channels })
+            }
+        };
+        let updated = self
+            .discord
+            .edit_guild_welcome_screen(self.guild_id, &edit)
+            .await?;
+        if let Some(channels) = updated["welcome_channels"].as_array() {
+            *self.channels.lock().unwrap() = channels.clone();
+        }
+        self.value = content;
+        Ok(())
+    }
+    fn content(&self) -> String {
+        self.value.clone()
+    }
+    fn comment(&self) -> String {
+        match self.field {
+            WelcomeField::Description => "server description".to_string(),
+            WelcomeField::ChannelDescription(_) => format!("description of #{}", self.channel_name),
+            WelcomeField::ChannelEmoji(_) => format!(
+                "emoji of #{} (unicode emoji or <:name:id>, empty for none)",
+                self.channel_name
+            ),
+        }
+    }
+    fn validate(&self, new: &str) -> Result<()> {
+        match self.field {
+            WelcomeField::Description => {
+                if new.chars().count() > 140 {
+                    return Err(Error::InvalidEditResult(
+                        "welcome screen description must be at most 140 characters".into(),
+                    ));
+                }
+            }
+            WelcomeField::ChannelDescription(_) => {
+                if !(1..=50).contains(&new.chars().count()) {
+                    return Err(Error::InvalidEditResult(
+                        format!("channel description must be between 1 and 50 characters: {new:?}")
+                            .into(),
+                    ));
+                }
+            }
+            WelcomeField::ChannelEmoji(_) => {
+                parse_emoji(new)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 指定したGuildのウェルカム画面の説明と、おすすめチャンネルごとの説明・絵文字を取得する
+pub async fn fetch_items(discord: &Arc<Discord>, guild_id: GuildId) -> Result<Vec<WelcomeItem>> {
+    let screen = discord.guild_welcome_screen(guild_id).await?;
+    let guild_channels = discord.guild_channels(guild_id, |_| {}).await?;
+    let welcome_channels = screen["welcome_channels"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let shared = Arc::new(Mutex::new(welcome_channels.clone()));
+    let item = |field, value: String, channel_name: String| WelcomeItem {
+        discord: discord.clone(),
+        guild_id,
+        channels: shared.clone(),
+        field,
+        value,
+        channel_name,
+    };
+    let mut items = vec![item(
+        WelcomeField::Description,
+        screen["description"]
+            .as_str()
+            .unwrap_or_default()
+            .replace('\n', " "),
+        String::new(),
+    )];
+    for (i, channel) in welcome_channels.iter().enumerate() {
+        let id = channel["channel_id"].as_str().unwrap_or_default();
+        let name = guild_channels
+            .iter()
+            .find(|c| c.id.to_string() == id)
+            .map_or_else(|| id.to_string(), |c| c.name.clone());
+        let description = channel["description"].as_str().unwrap_or_default();
+        items.push(item(
+            WelcomeField::ChannelDescription(i),
+            description.to_string(),
+            name.clone(),
+        ));
+        items.push(item(
+            WelcomeField::ChannelEmoji(i),
+            format_emoji(channel),
+            name,
+        ));
+    }
+    Ok(items)
+}
@@ -0,0 +1,792 @@
+//! Discord互換のモックサーバーに対して `edisch apply` を実行し、送られるリクエストと終了コードを確かめる
+
+use serde_json::{json, Value};
+use std::{path::Path, process::Output};
+use tempfile::TempDir;
+use tokio::process::Command;
+use wiremock::{
+    matchers::{body_json, header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// チャンネル一覧。Botは全てのチャンネルを編集できる
+fn channels() -> Value {
+    json!([
+        { "id": "10", "type": 0, "guild_id": "1", "name": "general", "position": 0, "permission_overwrites": [], "parent_id": null },
+        { "id": "11", "type": 4, "guild_id": "1", "name": "Stuff", "position": 1, "permission_overwrites": [], "parent_id": null },
+        { "id": "12", "type": 0, "guild_id": "1", "name": "chat", "position": 0, "permission_overwrites": [], "parent_id": "11" },
+    ])
+}
+
+/// Manage Channels の権限を持つBotのいるGuild
+async fn guild() -> MockServer {
+    guild_with(channels()).await
+}
+
+/// `channels` のチャンネルを持ち、Manage Channels の権限を持つBotのいるGuild
+async fn guild_with(channels: Value) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v10/users/@me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "id": "5", "username": "edisch-bot", "discriminator": "0", "avatar": null, "bot": true }),
+        ))
+        .mount(&server)
+        .await;
+    mount_guild(&server, 1, channels).await;
+    server
+}
+
+/// ID が `guild_id` で `channels` のチャンネルを持ち、Manage Channels の権限を持つBotのいるGuildを加える
+async fn mount_guild(server: &MockServer, guild_id: u64, channels: Value) {
+    let get = |route: &str, body: Value| {
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v10/guilds/{guild_id}{route}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+    };
+    get("/channels", channels).mount(server).await;
+    get(
+        "",
+        json!({
+            "id": guild_id.to_string(), "name": format!("Server {guild_id}"), "icon": null, "splash": null, "discovery_splash": null,
+            "owner_id": "999", "afk_channel_id": null, "afk_timeout": 300, "widget_enabled": false,
+            "widget_channel_id": null, "verification_level": 0, "default_message_notifications": 0,
+            "explicit_content_filter": 0,
+            "roles": [
+                { "id": "1", "name": "@everyone", "color": 0, "hoist": false, "managed": false, "mentionable": false, "permissions": "0", "position": 0, "flags": 0 },
+                { "id": "2", "name": "Bot", "color": 0, "hoist": false, "managed": false, "mentionable": false, "permissions": "16", "position": 1, "flags": 0 },
+            ],
+            "emojis": [], "stickers": [], "features": [], "mfa_level": 0, "application_id": null,
+            "system_channel_id": null, "system_channel_flags": 0, "rules_channel_id": null,
+            "max_members": 100, "vanity_url_code": null, "description": null, "banner": null,
+            "premium_tier": 0, "premium_subscription_count": 0, "preferred_locale": "en-US",
+            "public_updates_channel_id": null, "nsfw_level": 0, "premium_progress_bar_enabled": false,
+        }),
+    )
+    .mount(server)
+    .await;
+    get(
+        "/members/5",
+        json!({ "roles": ["2"], "joined_at": "2020-01-01T00:00:00Z", "deaf": false, "mute": false, "flags": 0 }),
+    )
+    .mount(server)
+    .await;
+}
+
+/// 設定ファイルや記録ファイルが `dir` の外に影響しないようにして、入力を適用する
+async fn apply(server: &MockServer, dir: &Path, input: &str, args: &[&str]) -> Output {
+    let file = dir.join("input.tsv");
+    std::fs::write(&file, input).unwrap();
+    let file = file.to_str().unwrap();
+    edisch(server, dir, "apply", &[&["--input", file], args].concat()).await
+}
+
+/// 設定ファイルや記録ファイルが `dir` の外に影響しないようにして、サブコマンドを実行する
+async fn edisch(server: &MockServer, dir: &Path, command: &str, args: &[&str]) -> Output {
+    edisch_command(server, dir, command, args)
+        .output()
+        .await
+        .expect("failed to run edisch")
+}
+
+/// 設定ファイルや記録ファイルが `dir` の外に影響しないようにした、サブコマンドを実行するコマンド
+fn edisch_command(server: &MockServer, dir: &Path, command: &str, args: &[&str]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_edisch"));
+    cmd.args([command, "--token", "test-token", "--guild-id", "1"])
+        .args(["--api-base", &server.uri()]);
+    // 再送を確かめる場合を除き、失敗したリクエストはすぐに諦める
+    if !args.contains(&"--retries") {
+        cmd.args(["--retries", "0"]);
+    }
+    cmd
+        // メッセージを照合するため、ロケールによらず英語で表示する
+        .args(["--lang", "en"])
+        .args(args)
+        .env_remove("DISCORD_TOKEN")
+        .env_remove("GUILD_ID")
+        .env_remove("EDISCH_PROFILE")
+        .env("EDISCH_CONFIG", dir.join("config.toml"))
+        .env("EDISCH_HISTORY_FILE", dir.join("history.jsonl"))
+        .env("EDISCH_CACHE_DIR", dir.join("cache"))
+        .env("EDISCH_RECOVERY_FILE", dir.join("recovery.txt"))
+        .env("EDISCH_RENAME_HISTORY", dir.join("renames.txt"))
+        .env("EDISCH_RESUME_FILE", dir.join("resume.txt"))
+        .env("EDISCH_UNDO_FILE", dir.join("undo.txt"));
+    cmd
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// #general (10) の名前を lobby に変えるリクエスト
+fn rename_general() -> Mock {
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .and(body_json(json!({ "name": "lobby" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "id": "10", "type": 0, "guild_id": "1", "name": "lobby", "position": 0, "permission_overwrites": [] }),
+        ))
+}
+
+/// 権限がなく失敗する、#chat (12) の名前の変更
+fn rename_chat_forbidden() -> Mock {
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/12"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .set_body_json(json!({ "message": "Missing Permissions", "code": 50013 })),
+        )
+}
+
+/// 履歴ファイルの各行
+fn history(dir: &Path) -> Vec<Value> {
+    std::fs::read_to_string(dir.join("history.jsonl"))
+        .unwrap_or_default()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn changed_lines_are_applied() {
+    let server = guild().await;
+    rename_general().expect(1).mount(&server).await;
+    let dir = TempDir::new().unwrap();
+    let output = apply(
+        &server,
+        dir.path(),
+        "lobby\t📝 (10)\nchat\t📝 in Stuff (12)\n",
+        &["--yes"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    // 元に戻すための記録は、変更前の名前を適用する行になる
+    let undo = std::fs::read_to_string(dir.path().join("undo.txt")).unwrap();
+    assert!(undo.starts_with("general\t📝 (10)"), "{undo}");
+}
+
+#[tokio::test]
+async fn summary_counts_applied_unchanged_and_failed_items() {
+    let server = guild().await;
+    rename_general().expect(1).mount(&server).await;
+    rename_chat_forbidden().expect(1).mount(&server).await;
+    let dir = TempDir::new().unwrap();
+    let report = dir.path().join("report.json");
+    let output = apply(
+        &server,
+        dir.path(),
+        "lobby\t📝 (10)\ntalk\t📝 in Stuff (12)\n",
+        &[
+            "--yes",
+            "--keep-going",
+            "--report",
+            report.to_str().unwrap(),
+        ],
+    )
+    .await;
+    assert_ne!(output.status.code(), Some(0), "{}", stderr(&output));
+    // 行のないカテゴリも、変更のないアイテムとして数える
+    assert!(
+        stderr(&output).contains("1 applied, 1 unchanged, 0 skipped, 1 failed in "),
+        "{}",
+        stderr(&output)
+    );
+    let report: Value = serde_json::from_str(&std::fs::read_to_string(report).unwrap()).unwrap();
+    assert_eq!(report["summary"]["applied"], 1);
+    assert_eq!(report["summary"]["unchanged"], 1);
+    assert_eq!(report["summary"]["skipped"], 0);
+    assert_eq!(report["summary"]["failed"], 1);
+}
+
+#[tokio::test]
+async fn history_is_appended() {
+    let server = guild().await;
+    rename_general().expect(2).mount(&server).await;
+    let dir = TempDir::new().unwrap();
+    for _ in 0..2 {
+        let output = apply(&server, dir.path(), "lobby\t📝 (10)\n", &["--yes"]).await;
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    }
+    // 実行ごとに追記し、Guildと変更したBotとともに残す
+    let records = history(dir.path());
+    assert_eq!(records.len(), 2, "{records:?}");
+    for record in &records {
+        assert_eq!(record["guild"], "1");
+        assert_eq!(record["id"], "10");
+        assert_eq!(record["op"], "rename");
+        assert_eq!(record["old"], "general");
+        assert_eq!(record["new"], "lobby");
+        assert_eq!(record["actor"], "edisch-bot (5)");
+    }
+}
+
+#[tokio::test]
+async fn history_keeps_only_the_changes_applied_before_a_failure() {
+    let server = guild().await;
+    rename_general().expect(1).mount(&server).await;
+    rename_chat_forbidden().expect(1).mount(&server).await;
+    let dir = TempDir::new().unwrap();
+    let output = apply(
+        &server,
+        dir.path(),
+        "lobby\t📝 (10)\ntalk\t📝 in Stuff (12)\n",
+        &["--yes"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(6), "{}", stderr(&output));
+    let records = history(dir.path());
+    assert_eq!(records.len(), 1, "{records:?}");
+    assert_eq!(records[0]["id"], "10");
+    assert_eq!(records[0]["new"], "lobby");
+}
+
+#[tokio::test]
+async fn rate_limited_renames_raise_the_estimate() {
+    let server = guild().await;
+    // Discordの外で名前を変えられ、チャンネルの名前の変更が上限に達している
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("retry-after", "512")
+                .insert_header("x-ratelimit-limit", "2")
+                .insert_header("x-ratelimit-remaining", "0")
+                .insert_header("x-ratelimit-reset-after", "512.3")
+                .insert_header("x-ratelimit-scope", "shared")
+                .set_body_json(json!({
+                    "message": "You are being rate limited.", "retry_after": 512.3, "global": false,
+                })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let input = "lobby\t📝 (10)\n";
+    // 最近名前を変えていなければ、待つ見込みはない
+    let output = apply(&server, dir.path(), input, &["--dry-run"]).await;
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("1 rename, no wait for rate limits expected"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = apply(&server, dir.path(), input, &["--yes"]).await;
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+
+    // 拒否されたチャンネルは、次の実行で期間いっぱい待つと見込む
+    let output = apply(&server, dir.path(), input, &["--dry-run"]).await;
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("1 rename, estimated ~"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(
+        stderr(&output).contains("due to rate limits"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[tokio::test]
+async fn dry_run_sends_no_changes() {
+    let server = guild().await;
+    Mock::given(method("PATCH"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let output = apply(&server, dir.path(), "lobby\t📝 (10)\n", &["--dry-run"]).await;
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("general -> lobby"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[tokio::test]
+async fn limit_and_offset_select_a_chunk_of_channels() {
+    let server = guild().await;
+    let dir = TempDir::new().unwrap();
+    let input = "lobby\t📝 (10)\nStuff\t📁 (11)\ntalk\t📝 in Stuff (12)\n";
+    let output = apply(&server, dir.path(), input, &["--dry-run", "--limit", "1"]).await;
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("general -> lobby"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(
+        !stdout(&output).contains("chat -> talk"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(
+        stderr(&output).contains("Pass --offset 1 for the next ones"),
+        "{}",
+        stderr(&output)
+    );
+    let output = apply(&server, dir.path(), input, &["--dry-run", "--offset", "2"]).await;
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(
+        !stdout(&output).contains("general -> lobby"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(
+        stdout(&output).contains("chat -> talk"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[tokio::test]
+async fn unchanged_input_sends_nothing() {
+    let server = guild().await;
+    Mock::given(method("PATCH"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let output = apply(&server, dir.path(), "general\t📝 (10)\n", &["--yes"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("No changes to apply"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[tokio::test]
+async fn exported_names_that_look_like_directives_are_not_changed() {
+    let server = guild_with(json!([
+        { "id": "10", "type": 4, "guild_id": "1", "name": "move night", "position": 0, "permission_overwrites": [], "parent_id": null },
+        { "id": "11", "type": 2, "guild_id": "1", "name": "drop zone", "position": 0, "permission_overwrites": [], "parent_id": "10", "bitrate": 64000 },
+        { "id": "12", "type": 2, "guild_id": "1", "name": "d lounge", "position": 1, "permission_overwrites": [], "parent_id": "10", "bitrate": 64000 },
+        { "id": "13", "type": 2, "guild_id": "1", "name": "D 1", "position": 2, "permission_overwrites": [], "parent_id": "10", "bitrate": 64000 },
+        { "id": "14", "type": 4, "guild_id": "1", "name": "r and d", "position": 1, "permission_overwrites": [], "parent_id": null },
+        { "id": "15", "type": 4, "guild_id": "1", "name": "keep out", "position": 2, "permission_overwrites": [], "parent_id": null },
+        { "id": "16", "type": 2, "guild_id": "1", "name": "!drop it", "position": 0, "permission_overwrites": [], "parent_id": "15", "bitrate": 64000 },
+    ]))
+    .await;
+    for verb in ["PATCH", "DELETE"] {
+        Mock::given(method(verb))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+    }
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("channels.tsv");
+    let output = edisch(
+        &server,
+        dir.path(),
+        "export",
+        &["--output", file.to_str().unwrap()],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let exported = std::fs::read_to_string(&file).unwrap();
+    assert!(exported.contains("drop zone"), "{exported}");
+
+    let output = apply(&server, dir.path(), &exported, &["--yes"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("No changes to apply"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[tokio::test]
+async fn guild_column_routes_lines_to_their_guild() {
+    let server = guild().await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .and(body_json(json!({ "name": "lobby" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "id": "10", "type": 0, "guild_id": "1", "name": "lobby", "position": 0, "permission_overwrites": [] }),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("channels.tsv");
+    let output = edisch(
+        &server,
+        dir.path(),
+        "export",
+        &["--guild-column", "--output", file.to_str().unwrap()],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let exported = std::fs::read_to_string(&file).unwrap();
+    assert!(
+        exported.lines().all(|line| line.ends_with("\tguild:1")),
+        "{exported}"
+    );
+
+    let input = exported.replace("general\t", "lobby\t");
+    let output = apply(&server, dir.path(), &input, &["--yes"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn guild_column_records_the_guild_of_each_item() {
+    let server = guild().await;
+    mount_guild(
+        &server,
+        2,
+        json!([
+            { "id": "20", "type": 0, "guild_id": "2", "name": "general", "position": 0, "permission_overwrites": [], "parent_id": null },
+        ]),
+    )
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v10/users/@me/guilds"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": "1", "name": "Server 1", "icon": null, "owner": false, "permissions": "16", "features": [] },
+            { "id": "2", "name": "Server 2", "icon": null, "owner": false, "permissions": "16", "features": [] },
+        ])))
+        .mount(&server)
+        .await;
+    for (id, guild_id) in [("10", "1"), ("20", "2")] {
+        Mock::given(method("PATCH"))
+            .and(path(format!("/api/v10/channels/{id}")))
+            .and(body_json(json!({ "name": "lobby" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({ "id": id, "type": 0, "guild_id": guild_id, "name": "lobby", "position": 0, "permission_overwrites": [] }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("channels.tsv");
+    let output = edisch(
+        &server,
+        dir.path(),
+        "export",
+        &[
+            "--guild-id",
+            "2",
+            "--guild-column",
+            "--output",
+            file.to_str().unwrap(),
+        ],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let exported = std::fs::read_to_string(&file).unwrap();
+    for (id, guild_id) in [("10", "1"), ("11", "1"), ("12", "1"), ("20", "2")] {
+        let line = exported
+            .lines()
+            .find(|line| line.contains(&format!("({id})")))
+            .unwrap_or_else(|| panic!("no line for {id}: {exported}"));
+        assert!(line.ends_with(&format!("\tguild:{guild_id}")), "{exported}");
+    }
+
+    // 両方のGuildの #general を、書き出したファイルから名前を変える
+    let input = exported.replace("general\t", "lobby\t");
+    let output = apply(&server, dir.path(), &input, &["--yes"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn numeric_last_columns_are_not_taken_for_a_guild() {
+    let server = guild().await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "id": "10", "type": 0, "guild_id": "1", "name": "lobby", "position": 0, "permission_overwrites": [] }),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    // 複数の列と、IDのみのコメントを持つ行
+    let output = apply(
+        &server,
+        dir.path(),
+        "lobby\t\t0\tfalse\t10\n",
+        &["--yes", "--field", "columns", "--comment-format", "{id}"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn invalid_names_are_rejected_before_any_request() {
+    let server = guild().await;
+    Mock::given(method("PATCH"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let input = format!("{}\t📝 (10)\n", "x".repeat(101));
+    let output = apply(&server, dir.path(), &input, &["--yes"]).await;
+    assert_eq!(output.status.code(), Some(5), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn missing_permission_exits_with_its_own_code() {
+    let server = guild().await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .set_body_json(json!({ "message": "Missing Permissions", "code": 50013 })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let output = apply(&server, dir.path(), "lobby\t📝 (10)\n", &["--yes"]).await;
+    assert_eq!(output.status.code(), Some(6), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("PATCH /channels/10"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[tokio::test]
+async fn bearer_tokens_check_the_permissions_of_the_user() {
+    let server = guild().await;
+    // ユーザーのトークンでは、Guildのメンバーのエンドポイントの代わりに自身のメンバー情報を取得する
+    Mock::given(method("GET"))
+        .and(path("/api/v10/users/@me/guilds/1/member"))
+        .and(header("authorization", "Bearer test-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "roles": ["2"], "joined_at": "2020-01-01T00:00:00Z", "deaf": false, "mute": false, "flags": 0 }),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .and(header("authorization", "Bearer test-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "id": "10", "type": 0, "guild_id": "1", "name": "lobby", "position": 0, "permission_overwrites": [] }),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let output = apply(
+        &server,
+        dir.path(),
+        "lobby\t📝 (10)\n",
+        &["--yes", "--token-type", "bearer"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn invalid_retry_after_headers_do_not_abort_the_run() {
+    for retry_after in ["inf", "NaN", "-1"] {
+        let server = guild().await;
+        // ユーザーのトークンのメンバー情報は、serenityのルートを使わずに取得する
+        let member = || Mock::given(method("GET")).and(path("/api/v10/users/@me/guilds/1/member"));
+        member()
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", retry_after)
+                    .set_body_json(json!({ "message": "You are being rate limited.", "retry_after": 0, "global": false })),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        member()
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({ "roles": ["2"], "joined_at": "2020-01-01T00:00:00Z", "deaf": false, "mute": false, "flags": 0 }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v10/channels/10"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({ "id": "10", "type": 0, "guild_id": "1", "name": "lobby", "position": 0, "permission_overwrites": [] }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dir = TempDir::new().unwrap();
+        let output = apply(
+            &server,
+            dir.path(),
+            "lobby\t📝 (10)\n",
+            &[
+                "--yes",
+                "--token-type",
+                "bearer",
+                "--retries",
+                "1",
+                "--retry-delay",
+                "0",
+            ],
+        )
+        .await;
+        assert_eq!(
+            output.status.code(),
+            Some(0),
+            "{retry_after}: {}",
+            stderr(&output)
+        );
+    }
+}
+
+#[tokio::test]
+async fn rate_limits_give_up_after_the_retries() {
+    let server = guild().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v10/users/@me/guilds/1/member"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("retry-after", "0")
+                .set_body_json(json!({ "message": "You are being rate limited.", "retry_after": 0, "global": false })),
+        )
+        // 最初の1回と、--retries の2回
+        .expect(3)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let output = apply(
+        &server,
+        dir.path(),
+        "lobby\t📝 (10)\n",
+        &["--yes", "--token-type", "bearer", "--retries", "2"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn ctrl_c_stops_between_changes_and_records_the_applied_ones() {
+    let server = guild().await;
+    // 1つ目の変更の適用中にCtrl-Cを送る
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "id": "10", "type": 0, "guild_id": "1", "name": "lobby", "position": 0, "permission_overwrites": [] }))
+                .set_delay(std::time::Duration::from_secs(1)),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/12"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("input.tsv");
+    std::fs::write(&file, "lobby\t📝 (10)\ntalk\t📝 in Stuff (12)\n").unwrap();
+    let child = edisch_command(
+        &server,
+        dir.path(),
+        "apply",
+        &["--input", file.to_str().unwrap(), "--yes"],
+    )
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+    while !server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .any(|req| req.method.as_str() == "PATCH")
+    {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().unwrap().to_string()])
+        .status()
+        .await
+        .unwrap();
+    assert!(status.success());
+    let output = child.wait_with_output().await.unwrap();
+    assert_eq!(output.status.code(), Some(130), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("1 applied, 1 unchanged, 0 skipped, 0 failed, 1 not applied"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(
+        stderr(&output).contains("Interrupted by Ctrl-C"),
+        "{}",
+        stderr(&output)
+    );
+    // 中断までに適用した変更は、履歴に残る
+    let history = std::fs::read_to_string(dir.path().join("history.jsonl")).unwrap();
+    assert_eq!(history.lines().count(), 1, "{history}");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn invalid_edits_reopen_the_editor_with_the_edited_text() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let server = guild().await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v10/channels/10"))
+        .and(body_json(json!({ "name": "lobby" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "id": "10", "type": 0, "guild_id": "1", "name": "lobby", "position": 0, "permission_overwrites": [] }),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    // 1回目は不正な名前を書き、2回目はその名前が残っていることを確かめてから直す
+    let invalid = "x".repeat(101);
+    let editor = dir.path().join("editor.sh");
+    std::fs::write(
+        &editor,
+        format!(
+            "#!/bin/sh
+if [ -e \"$0.done\" ]; then
+  grep -q {invalid} \"$1\" || exit 1
+  name=lobby
+else
+  touch \"$0.done\"
+  name={invalid}
+fi
+printf '%s\\t📝 (10)\\nStuff\\t📁 (11)\\nchat\\t📝 in Stuff (12)\\n' \"$name\" > \"$1\"
+"
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+    // サブコマンドを付けずに、全てのチャンネルをエディタで編集する
+    let output = edisch(
+        &server,
+        dir.path(),
+        "--all",
+        &["--editor", editor.to_str().unwrap(), "--yes"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("Invalid channel name"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,126 @@
+//! Discord互換のモックサーバーに対して `edisch follows` を実行し、答えに応じてフォローが送られることを確かめる
+#![cfg(unix)]
+
+use serde_json::json;
+use std::{os::unix::fs::PermissionsExt, path::Path, process::Output};
+use tempfile::TempDir;
+use tokio::process::Command;
+use wiremock::{
+    matchers::{body_json, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// アナウンスチャンネル #news と #updates があり、#updates だけが #inbox にフォローされているGuild
+async fn guild() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v10/guilds/1/channels"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": "20", "type": 5, "guild_id": "1", "name": "news", "position": 0, "permission_overwrites": [] },
+            { "id": "21", "type": 5, "guild_id": "1", "name": "updates", "position": 1, "permission_overwrites": [] },
+            { "id": "30", "type": 0, "guild_id": "1", "name": "inbox", "position": 2, "permission_overwrites": [] },
+        ])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v10/channels/30/webhooks"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "id": "40", "type": 2, "guild_id": "1", "channel_id": "30", "name": "updates", "avatar": null,
+                "source_channel": { "id": "21", "name": "updates" },
+                "source_guild": { "id": "1", "name": "Guild", "icon": null },
+            },
+        ])))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// 各行の答えを `sed` の式で書き換えるエディタを用意し、`follows` を実行する
+async fn follows(server: &MockServer, dir: &Path, script: &str) -> Output {
+    let editor = dir.join("editor.sh");
+    std::fs::write(&editor, format!("#!/bin/sh\nsed -i {script} \"$1\"\n")).unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_edisch"))
+        .args(["follows", "--target", "30", "--token", "test-token"])
+        .args([
+            "--guild-id",
+            "1",
+            "--api-base",
+            &server.uri(),
+            "--retries",
+            "0",
+        ])
+        .args([
+            "--lang",
+            "en",
+            "--yes",
+            "--editor",
+            editor.to_str().unwrap(),
+        ])
+        .env_remove("DISCORD_TOKEN")
+        .env_remove("GUILD_ID")
+        .env_remove("EDISCH_PROFILE")
+        .env("EDISCH_CONFIG", dir.join("config.toml"))
+        .env("EDISCH_CACHE_DIR", dir.join("cache"))
+        .output()
+        .await
+        .expect("failed to run edisch")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[tokio::test]
+async fn answering_yes_follows_the_channel() {
+    let server = guild().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v10/channels/20/followers"))
+        .and(body_json(json!({ "webhook_channel_id": "30" })))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "channel_id": "20", "webhook_id": "41" })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    // 短縮形や大文字で答えても、フォローしていない #news だけが変更になる
+    let output = follows(&server, dir.path(), "-e 's/^no/Y/' -e 's/^yes/y/'").await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("1 applied, 1 unchanged"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[tokio::test]
+async fn answering_no_unfollows_the_channel() {
+    let server = guild().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v10/webhooks/40"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let output = follows(&server, dir.path(), "-e 's/^no/n/' -e 's/^yes/no/'").await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("1 applied, 1 unchanged"),
+        "{}",
+        stderr(&output)
+    );
+}
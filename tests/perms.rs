@@ -0,0 +1,222 @@
+//! Discord互換のモックサーバーに対して `edisch perms edit` を実行し、変更した上書きのみが送られることを確かめる
+
+use serde_json::{json, Value};
+use std::{path::Path, process::Output};
+use tempfile::TempDir;
+use tokio::process::Command;
+use wiremock::{
+    matchers::{body_json, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// @everyone と Mods のロールがあり、#general に2つの上書きがあるGuild
+async fn guild() -> MockServer {
+    guild_with(json!([
+        { "id": "1", "type": 0, "allow": "0", "deny": "1024" },
+        { "id": "2", "type": 0, "allow": "1024", "deny": "0" },
+    ]))
+    .await
+}
+
+/// @everyone と Mods のロールがあり、#general に `overwrites` の上書きがあるGuild
+async fn guild_with(overwrites: Value) -> MockServer {
+    let server = MockServer::start().await;
+    let get = |route: &str, body: Value| {
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v10{route}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+    };
+    get(
+        "/guilds/1/roles",
+        json!([
+            { "id": "1", "name": "@everyone", "color": 0, "hoist": false, "managed": false, "mentionable": false, "permissions": "0", "position": 0, "flags": 0 },
+            { "id": "2", "name": "Mods", "color": 0, "hoist": false, "managed": false, "mentionable": false, "permissions": "0", "position": 1, "flags": 0 },
+        ]),
+    )
+    .mount(&server)
+    .await;
+    get(
+        "/guilds/1/channels",
+        json!([
+            {
+                "id": "10", "type": 0, "guild_id": "1", "name": "general", "position": 0, "parent_id": null,
+                "permission_overwrites": overwrites,
+            },
+            { "id": "11", "type": 0, "guild_id": "1", "name": "chat", "position": 1, "parent_id": null, "permission_overwrites": [] },
+        ]),
+    )
+    .mount(&server)
+    .await;
+    server
+}
+
+/// 設定ファイルが `dir` の外に影響しないようにして、`perms edit` を実行する
+async fn perms_edit(server: &MockServer, dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_edisch"))
+        .args(["perms", "edit", "--token", "test-token", "--guild-id", "1"])
+        .args(["--api-base", &server.uri(), "--retries", "0"])
+        .args(["--lang", "en"])
+        .args(args)
+        .env_remove("DISCORD_TOKEN")
+        .env_remove("GUILD_ID")
+        .env_remove("EDISCH_PROFILE")
+        .env("EDISCH_CONFIG", dir.join("config.toml"))
+        .env("EDISCH_CACHE_DIR", dir.join("cache"))
+        .output()
+        .await
+        .expect("failed to run edisch")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[tokio::test]
+async fn exported_document_applies_nothing_until_edited() {
+    let server = guild().await;
+    Mock::given(method("PUT"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    for file in ["overwrites.toml", "overwrites.yaml"] {
+        let document = dir.path().join(file);
+        let format = if file.ends_with("yaml") {
+            "yaml"
+        } else {
+            "toml"
+        };
+        let output = perms_edit(
+            &server,
+            dir.path(),
+            &["--format", format, "--output", document.to_str().unwrap()],
+        )
+        .await;
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+        let text = std::fs::read_to_string(&document).unwrap();
+        assert!(text.contains("VIEW_CHANNEL"), "{text}");
+        assert!(text.contains("Mods"), "{text}");
+
+        let output = perms_edit(
+            &server,
+            dir.path(),
+            &["--input", document.to_str().unwrap(), "--yes"],
+        )
+        .await;
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+        assert!(
+            stderr(&output).contains("No changes to apply"),
+            "{}",
+            stderr(&output)
+        );
+    }
+}
+
+#[tokio::test]
+async fn only_changed_overwrites_are_applied() {
+    let server = guild().await;
+    // @everyone の拒否を変え、Mods の上書きを消し、#chat に上書きを加える
+    Mock::given(method("PUT"))
+        .and(path("/api/v10/channels/10/permissions/1"))
+        .and(body_json(
+            json!({ "id": "1", "type": 0, "allow": "0", "deny": "3072" }),
+        ))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v10/channels/10/permissions/2"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/api/v10/channels/11/permissions/5"))
+        .and(body_json(
+            json!({ "id": "5", "type": 1, "allow": "1024", "deny": "0" }),
+        ))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let document = dir.path().join("overwrites.yaml");
+    std::fs::write(
+        &document,
+        "channels:
+- id: '10'
+  name: general
+  overwrites:
+  - role: '@everyone'
+    deny: [VIEW_CHANNEL, SEND_MESSAGES]
+- id: '11'
+  name: chat
+  overwrites:
+  - member: '5'
+    allow: [VIEW_CHANNEL]
+",
+    )
+    .unwrap();
+    let output = perms_edit(
+        &server,
+        dir.path(),
+        &["--input", document.to_str().unwrap(), "--yes"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
+
+#[tokio::test]
+async fn unknown_permission_bits_survive_a_round_trip() {
+    // 1 << 60 はserenityが名前を知らない権限
+    let unknown: u64 = 1 << 60;
+    let server = guild_with(json!([
+        { "id": "1", "type": 0, "allow": "0", "deny": (1024 + unknown).to_string() },
+        { "id": "2", "type": 0, "allow": (1024 + unknown).to_string(), "deny": "0" },
+    ]))
+    .await;
+    // @everyone の拒否に SEND_MESSAGES を加えても、名前の分からないビットは残る
+    Mock::given(method("PUT"))
+        .and(path("/api/v10/channels/10/permissions/1"))
+        .and(body_json(
+            json!({ "id": "1", "type": 0, "allow": "0", "deny": (3072 + unknown).to_string() }),
+        ))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/api/v10/channels/10/permissions/2"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let dir = TempDir::new().unwrap();
+    let document = dir.path().join("overwrites.toml");
+    let output = perms_edit(
+        &server,
+        dir.path(),
+        &["--output", document.to_str().unwrap()],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let text = std::fs::read_to_string(&document).unwrap();
+    assert!(text.contains(&format!("deny_bits = {unknown}")), "{text}");
+    assert!(text.contains(&format!("allow_bits = {unknown}")), "{text}");
+
+    let text = text.replacen(
+        "deny = [\"VIEW_CHANNEL\"]",
+        "deny = [\"VIEW_CHANNEL\", \"SEND_MESSAGES\"]",
+        1,
+    );
+    std::fs::write(&document, text).unwrap();
+    let output = perms_edit(
+        &server,
+        dir.path(),
+        &["--input", document.to_str().unwrap(), "--yes"],
+    )
+    .await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
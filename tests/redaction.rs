@@ -0,0 +1,69 @@
+//! トークンが標準エラー出力に表示されないことを、実際にコマンドを実行して確かめる
+
+use std::process::{Command, Output};
+
+/// Discordのトークンの形式の文字列
+const TOKEN_SHAPED: &str = "MTIzNDU2Nzg5MDEyMzQ1Njc4OQ.GaBcDe.abcdefghijklmnopqrstuvwxyz0123456";
+
+/// 形式に合わない (登録によってのみ伏せられる) トークン
+const PLAIN_TOKEN: &str = "plain-test-token-0123456789abcdef";
+
+/// 設定ファイルや環境変数の影響を受けないようにして edisch を実行する
+fn edisch(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_edisch"))
+        .args(args)
+        .env_remove("DISCORD_TOKEN")
+        .env_remove("GUILD_ID")
+        .env_remove("EDISCH_PROFILE")
+        .env("EDISCH_CONFIG", "/nonexistent/edisch/config.toml")
+        .output()
+        .expect("failed to run edisch")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn token_pasted_as_guild_is_redacted() {
+    let output = edisch(&["list", "--token", PLAIN_TOKEN, "--guild-id", PLAIN_TOKEN]);
+    let stderr = stderr(&output);
+    assert!(!output.status.success());
+    assert!(!stderr.contains(PLAIN_TOKEN), "{stderr}");
+    assert!(stderr.contains("[REDACTED]"), "{stderr}");
+}
+
+#[test]
+fn token_from_environment_is_redacted() {
+    let output = Command::new(env!("CARGO_BIN_EXE_edisch"))
+        .args(["list", "--guild-id", PLAIN_TOKEN])
+        .env("DISCORD_TOKEN", PLAIN_TOKEN)
+        .env_remove("GUILD_ID")
+        .env_remove("EDISCH_PROFILE")
+        .env("EDISCH_CONFIG", "/nonexistent/edisch/config.toml")
+        .output()
+        .expect("failed to run edisch");
+    let stderr = stderr(&output);
+    assert!(!output.status.success());
+    assert!(!stderr.contains(PLAIN_TOKEN), "{stderr}");
+    assert!(stderr.contains("[REDACTED]"), "{stderr}");
+}
+
+#[test]
+fn unregistered_token_shaped_value_is_redacted() {
+    // 短いトークンは登録されないため、形式による伏せ字のみが働く
+    let output = edisch(&["list", "--token", "short", "--guild-id", TOKEN_SHAPED]);
+    let stderr = stderr(&output);
+    assert!(!output.status.success());
+    assert!(!stderr.contains(TOKEN_SHAPED), "{stderr}");
+    assert!(stderr.contains("[REDACTED]"), "{stderr}");
+}
+
+#[test]
+fn clap_errors_are_redacted() {
+    let output = edisch(&["list", "--retries", TOKEN_SHAPED]);
+    let stderr = stderr(&output);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(!stderr.contains(TOKEN_SHAPED), "{stderr}");
+    assert!(stderr.contains("[REDACTED]"), "{stderr}");
+}